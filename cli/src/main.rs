@@ -1,6 +1,7 @@
 use clap::Parser;
+use scorelib::FileFormat;
 use scorelib::Song;
-use scorelib::Track;
+use scorelib::TabOptions;
 use std::fs;
 use std::io::Read;
 use std::path::Path;
@@ -10,13 +11,21 @@ const GUITAR_FILE_MAX_SIZE: usize = 16777216; // 16 MB
 #[derive(Parser, Debug)]
 #[clap(author="slundi", version, about="Guitar Pro File Parser CLI", long_about = None)]
 struct Args {
-    /// Input file path (.gp3, .gp4, .gp5)
+    /// Input file path (Guitar Pro, MIDI, MusicXML, MuseScore or text tab)
     #[clap(short, long)]
     input: String,
 
-    /// Show full tablature for the first track
+    /// Show the tablature of the tracks
     #[clap(short, long)]
     tab: bool,
+
+    /// Tracks to show in the tablature, numbered from 1 (default: the first track)
+    #[clap(long, value_delimiter = ',')]
+    tracks: Vec<usize>,
+
+    /// Maximum width of the tablature lines, 0 to disable wrapping
+    #[clap(short, long, default_value_t = 80)]
+    width: usize,
 }
 
 fn main() {
@@ -28,12 +37,6 @@ fn main() {
         std::process::exit(1);
     }
 
-    let ext = path
-        .extension()
-        .and_then(|e| e.to_str())
-        .map(|e| e.to_uppercase())
-        .unwrap_or_else(|| "UNKNOWN".to_string());
-
     let size = fs::metadata(&args.input)
         .map(|m| m.len() as usize)
         .unwrap_or(0);
@@ -47,32 +50,26 @@ fn main() {
     let mut data = Vec::with_capacity(size);
     file.read_to_end(&mut data).expect("Cannot read file");
 
-    let mut song = Song::default();
-    let result = match ext.as_str() {
-        "GP3" => song.read_gp3(&data),
-        "GP4" => song.read_gp4(&data),
-        "GP5" => song.read_gp5(&data),
-        "GP" => song.read_gp(&data),
-        "GPX" => song.read_gpx(&data),
-        _ => {
-            eprintln!(
-                "Error: Unsupported format '{}'. Supported: GP3, GP4, GP5, GP.",
-                ext
-            );
+    let (song, format) = match Song::from_bytes(&data) {
+        Ok(read) => read,
+        Err(e) => {
+            eprintln!("Error reading file: {}", e);
             std::process::exit(1);
         }
     };
-    if let Err(e) = result {
-        eprintln!("Error reading file: {}", e);
-        std::process::exit(1);
-    }
 
-    print_metadata(&song);
+    print_metadata(&song, format);
 
     if args.tab {
-        if let Some(track) = song.tracks.first() {
-            println!("\nGenerating Tablature for Track 1: {}", track.name);
-            print_ascii_tab(track);
+        let tracks: Vec<usize> = if args.tracks.is_empty() {
+            vec![0]
+        } else {
+            args.tracks.iter().map(|t| t.saturating_sub(1)).collect()
+        };
+        if tracks.iter().any(|t| *t < song.tracks.len()) {
+            let options = TabOptions { width: args.width };
+            println!();
+            print!("{}", song.to_ascii_tab(&tracks, &options));
         } else {
             println!("\nNo tracks found in the song.");
         }
@@ -81,8 +78,9 @@ fn main() {
     }
 }
 
-fn print_metadata(song: &Song) {
+fn print_metadata(song: &Song, format: FileFormat) {
     println!("=== Metadata ===");
+    println!("Format:      {}", format);
     println!("Title:       {}", song.name);
     println!("Artist:      {}", song.artist);
     println!("Album:       {}", song.album);
@@ -98,80 +96,3 @@ fn print_metadata(song: &Song) {
     println!("Tracks:      {}", song.tracks.len());
     println!("Tempos:      MixTable items (approx)");
 }
-
-fn print_ascii_tab(track: &Track) {
-    let num_strings = track.strings.len();
-    if num_strings == 0 {
-        return;
-    }
-
-    // Buffer for each string (reversed because string 1 is highest pitch = top line)
-    // Actually track.strings[0] is usually String 1 (High E).
-    // Tab lines: 0=High E, 1=B, 2=G ...
-    let mut lines: Vec<String> = vec![String::new(); num_strings];
-
-    // Tuning info
-    let tuning_names = ["E", "B", "G", "D", "A", "E", "B", "F#"]; // Simple approximation
-    for i in 0..num_strings {
-        let sc = if i < tuning_names.len() {
-            tuning_names[i]
-        } else {
-            "?"
-        };
-        lines[i].push_str(&format!("{} |", sc));
-    }
-
-    // Iterate measures
-    for measure in track.measures.iter() {
-        // Start of measure bar
-        for line in &mut lines {
-            line.push('|');
-        }
-
-        if measure.voices.is_empty() {
-            // Empty measure pad
-            for line in &mut lines {
-                line.push_str("----");
-            }
-            continue;
-        }
-
-        // We only verify Voice 0
-        let voice = &measure.voices[0];
-
-        for beat in &voice.beats {
-            // Determine columns needed for this beat (e.g., 3 chars: "12-" or "-")
-            // Check notes in this beat
-            let mut col_vals: Vec<String> = vec!["-".to_string(); num_strings];
-
-            for note in &beat.notes {
-                // Note string index (1-based usually)
-                // If note.string is 1, it corresponds to track.strings[0] (High E) -> lines[0]
-                let s_idx = (note.string - 1) as usize;
-                if s_idx < num_strings {
-                    col_vals[s_idx] = note.value.to_string();
-                }
-            }
-
-            // Find max width for this column (beat) to align vertical start
-            let max_width = col_vals.iter().map(|s| s.len()).max().unwrap_or(1);
-            let cell_width = max_width + 1; // +1 for spacing
-
-            for i in 0..num_strings {
-                let s = &col_vals[i];
-                lines[i].push_str(s);
-                // Padding
-                for _ in 0..(cell_width - s.len()) {
-                    lines[i].push('-');
-                }
-            }
-        }
-    }
-
-    // Print lines
-    println!();
-    for line in lines {
-        println!("{}", line);
-    }
-    println!();
-}
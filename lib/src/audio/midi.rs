@@ -1,6 +1,4 @@
-use fraction::ToPrimitive;
-
-use crate::{io::primitive::*, model::song::*, error::GpResult};
+use crate::{io::primitive::*, model::song::*, error::{GpError, GpResult, ScorePart}};
 
 //MIDI channels
 
@@ -194,7 +192,10 @@ impl SongMidiOps for Song {
     /// Read all the MIDI channels
     fn read_midi_channels(&mut self, data: &[u8], seek: &mut usize) -> GpResult<()> {
         for i in 0u8..64u8 {
-            self.channels.push(self.read_midi_channel(data, seek, i)?);
+            let channel = self
+                .read_midi_channel(data, seek, i)
+                .map_err(|e| e.within(ScorePart::Channel(usize::from(i)), *seek))?;
+            self.channels.push(channel);
         }
         Ok(())
     }
@@ -234,23 +235,28 @@ impl SongMidiOps for Song {
     /// Read MIDI channel. MIDI channel in Guitar Pro is represented by two integers. First is zero-based number of channel, second is zero-based number of channel used for effects.
     fn read_channel(&mut self, data: &[u8], seek: &mut usize) -> GpResult<usize> {
         //TODO: fixme for writing
-        let index = read_int(data, seek)? - 1;
-        let effect_channel = read_int(data, seek)? - 1;
-        if 0 <= index && index < self.channels.len().to_i32().unwrap() {
-            if self.channels[index.to_usize().unwrap()].instrument < 0 {
-                self.channels[index.to_usize().unwrap()].instrument = 0;
-            }
-            if !self.channels[index.to_usize().unwrap()].is_percussion_channel() {
-                self.channels[index.to_usize().unwrap()].effect_channel =
-                    effect_channel.to_u8().unwrap();
-            }
+        let number = read_int(data, seek)?;
+        let effect_channel = i64::from(read_int(data, seek)?) - 1;
+        let index = usize::try_from(i64::from(number) - 1)
+            .ok()
+            .filter(|&index| index < self.channels.len())
+            .ok_or(GpError::InvalidRange {
+                context: "MIDI channel",
+                value: number.into(),
+                min: 1,
+                max: self.channels.len() as i64,
+            })?;
+        if self.channels[index].instrument < 0 {
+            self.channels[index].instrument = 0;
+        }
+        if !self.channels[index].is_percussion_channel() {
+            self.channels[index].effect_channel = convert(effect_channel, "MIDI effect channel")?;
         }
-        Ok(index.to_usize().unwrap())
+        Ok(index)
     }
 
     fn write_midi_channels(&self, data: &mut Vec<u8>) {
         for i in 0..self.channels.len() {
-            println!("writing channel: {:?}", self.channels[i]);
             if self.channels[i].is_percussion_channel() && self.channels[i].instrument == 0 {
                 write_i32(data, -1);
             } else {
@@ -1,2 +1,6 @@
 pub mod midi;
+pub mod playback;
+pub mod smf;
+pub mod tempo;
+pub mod timeline;
 
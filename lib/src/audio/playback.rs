@@ -0,0 +1,197 @@
+// Playback order of the measures: repeats, alternate endings and directions
+use std::collections::{HashMap, HashSet};
+
+use crate::model::song::Song;
+use crate::types::enums::DirectionSign;
+use crate::types::measure::MeasureHeader;
+
+/// Upper bound of the played measures, against scores whose repeat counts multiply.
+const MAX_PLAYED_MEASURES: usize = 100_000;
+
+/// A repeated section being played.
+struct Section {
+    /// Header index the closing bars jump back to.
+    start: usize,
+    /// Pass through the section, from 1: selects the alternate endings.
+    pass: u32,
+    /// Header index of the closing bar left once its repeats were played.
+    end: Option<usize>,
+}
+
+/// Sign to look for after a *al Coda* or *al Fine* jump.
+#[derive(Clone, Copy, PartialEq)]
+enum Pending {
+    Coda,
+    DoubleCoda,
+    Fine,
+}
+
+/// Target sign and pending sign of a jump direction.
+fn jump(sign: &DirectionSign) -> Option<(Option<DirectionSign>, Option<Pending>)> {
+    use DirectionSign::*;
+    match sign {
+        DaCapo => Some((None, None)),
+        DaCapoAlCoda => Some((None, Some(Pending::Coda))),
+        DaCapoAlDoubleCoda => Some((None, Some(Pending::DoubleCoda))),
+        DaCapoAlFine => Some((None, Some(Pending::Fine))),
+        DaSegno => Some((Some(Segno), None)),
+        DaSegnoAlCoda => Some((Some(Segno), Some(Pending::Coda))),
+        DaSegnoAlDoubleCoda => Some((Some(Segno), Some(Pending::DoubleCoda))),
+        DaSegnoAlFine => Some((Some(Segno), Some(Pending::Fine))),
+        DaSegnoSegno => Some((Some(SegnoSegno), None)),
+        DaSegnoSegnoAlCoda => Some((Some(SegnoSegno), Some(Pending::Coda))),
+        DaSegnoSegnoAlDoubleCoda => Some((Some(SegnoSegno), Some(Pending::DoubleCoda))),
+        DaSegnoSegnoAlFine => Some((Some(SegnoSegno), Some(Pending::Fine))),
+        _ => None,
+    }
+}
+
+/// Whether a measure is played on a pass through its section: measures without alternate
+/// ending are always played, the others when their ending number is the pass.
+fn in_ending(header: &MeasureHeader, pass: u32) -> bool {
+    header.repeat_alternative == 0
+        || (pass <= 8 && header.repeat_alternative & (1 << (pass - 1)) != 0)
+}
+
+/// Header indexes in the order they are played.
+///
+/// A closing bar jumps back `repeat_close` times (at least once) to the opening bar of
+/// its section, or to the end of the previous repeat (the song start) when there is none;
+/// an opening bar inside a section that is still repeated starts a nested section. On
+/// each pass only the measures of the matching alternate ending are played.
+///
+/// D.C. and D.S. directions are followed once, at the end of their measure, after the
+/// repeats of that measure. The repeats are not played again after such a jump and the
+/// last alternate ending is taken. Following a *al Coda* jump, *Da Coda* jumps to the
+/// Coda (*Da Double Coda* to the Double Coda after a *al Double Coda* jump); following
+/// a *al Fine* jump, the song stops at the end of the Fine measure. Jumps to a missing
+/// sign are ignored.
+pub fn playback_order(song: &Song) -> Vec<usize> {
+    let headers = &song.measure_headers;
+    let find = |sign: &DirectionSign| {
+        headers
+            .iter()
+            .position(|h| h.direction.as_ref() == Some(sign))
+    };
+
+    let mut order = Vec::with_capacity(headers.len());
+    let mut sections = vec![Section {
+        start: 0,
+        pass: 1,
+        end: None,
+    }];
+    // Repeats played by each closing bar, last pass of each repeated section
+    let mut repeats: HashMap<usize, i32> = HashMap::new();
+    let mut last_pass: HashMap<usize, u32> = HashMap::new();
+    let mut taken: HashSet<usize> = HashSet::new();
+    let mut pending: Option<Pending> = None;
+    let mut jumped_to = true;
+    let mut index = 0;
+
+    while index < headers.len() && order.len() < MAX_PLAYED_MEASURES {
+        let header = &headers[index];
+        if header.repeat_open && !jumped_to {
+            let top = sections.last().unwrap();
+            if top.end.is_some() && sections.len() > 1 {
+                sections.pop();
+            }
+            let top = sections.last_mut().unwrap();
+            if top.end.is_some() {
+                // First section of the song, replaced by the next one
+                *top = Section {
+                    start: index,
+                    pass: 1,
+                    end: None,
+                };
+            } else if top.start != index {
+                sections.push(Section {
+                    start: index,
+                    pass: 1,
+                    end: None,
+                });
+            }
+        }
+        jumped_to = false;
+
+        if !in_ending(header, sections.last().unwrap().pass) {
+            index += 1;
+            continue;
+        }
+        order.push(index);
+
+        if header.repeat_close >= 0 {
+            if let Some(end) = sections.last().unwrap().end {
+                // Closing bar of a section without opening bar
+                if sections.len() > 1 {
+                    sections.pop();
+                } else {
+                    sections[0] = Section {
+                        start: end + 1,
+                        pass: 1,
+                        end: None,
+                    };
+                }
+            }
+            let section = sections.last_mut().unwrap();
+            let count = repeats.entry(index).or_insert(0);
+            if *count < i32::from(header.repeat_close).max(1) {
+                *count += 1;
+                section.pass += 1;
+                last_pass.insert(section.start, section.pass);
+                let start = section.start;
+                // Nested sections are repeated again
+                repeats.retain(|&closing, _| closing <= start || closing >= index);
+                index = start;
+                jumped_to = true;
+                continue;
+            }
+            section.end = Some(index);
+        }
+
+        match (pending, &header.direction) {
+            (Some(Pending::Fine), Some(DirectionSign::Fine)) => break,
+            (Some(Pending::Coda), Some(DirectionSign::DaCoda))
+            | (Some(Pending::DoubleCoda), Some(DirectionSign::DaDoubleCoda)) => {
+                let target = if pending == Some(Pending::Coda) {
+                    DirectionSign::Coda
+                } else {
+                    DirectionSign::DoubleCoda
+                };
+                if let Some(coda) = find(&target) {
+                    pending = None;
+                    index = coda;
+                    sections = vec![Section {
+                        start: coda,
+                        pass: last_pass.get(&coda).copied().unwrap_or(1),
+                        end: None,
+                    }];
+                    jumped_to = true;
+                    continue;
+                }
+            }
+            (None, Some(sign)) if !taken.contains(&index) => {
+                if let Some((target, then)) = jump(sign) {
+                    let target = match target {
+                        Some(segno) => find(&segno),
+                        None => Some(0),
+                    };
+                    if let Some(target) = target {
+                        taken.insert(index);
+                        pending = then;
+                        index = target;
+                        sections = vec![Section {
+                            start: target,
+                            pass: last_pass.get(&target).copied().unwrap_or(1),
+                            end: None,
+                        }];
+                        jumped_to = true;
+                        continue;
+                    }
+                }
+            }
+            _ => {}
+        }
+        index += 1;
+    }
+    order
+}
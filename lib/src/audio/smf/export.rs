@@ -0,0 +1,308 @@
+// Standard MIDI File (SMF) export
+use std::collections::{BTreeMap, HashMap};
+
+use super::*;
+use crate::audio::midi::{MidiChannel, DEFAULT_PERCUSSION_CHANNEL};
+use crate::model::key_signature::KeySignature;
+use crate::model::song::Song;
+use crate::model::track::Track;
+use crate::types::enums::{BeatStatus, NoteType};
+
+/// A timed MIDI event of a track chunk. Events are sorted by tick, then by `order`
+/// so that note-offs are written before note-ons sharing the same tick.
+struct TimedEvent {
+    tick: u32,
+    order: u8,
+    bytes: Vec<u8>,
+}
+
+/// A sounding note: pitch, start and end ticks, velocity.
+struct PlayedNote {
+    pitch: u8,
+    start: u32,
+    end: u32,
+    velocity: u8,
+}
+
+/// MIDI settings used to play a track.
+pub(crate) struct TrackOutput {
+    pub(crate) channel: u8,
+    pub(crate) program: u8,
+    pub(crate) bank: u8,
+    pub(crate) mixer: Option<MidiChannel>,
+}
+
+fn meta_event(kind: u8, payload: &[u8]) -> Vec<u8> {
+    let mut bytes = vec![0xFF, kind];
+    write_vlq(&mut bytes, payload.len() as u32);
+    bytes.extend_from_slice(payload);
+    bytes
+}
+
+fn key_signature_event(key: &KeySignature) -> Vec<u8> {
+    meta_event(
+        META_KEY_SIGNATURE,
+        &[key.key.clamp(-7, 7) as u8, key.is_minor as u8],
+    )
+}
+
+/// Serialize events to an `MTrk` chunk, closed by an end-of-track meta event.
+fn write_track_chunk(data: &mut Vec<u8>, mut events: Vec<TimedEvent>) {
+    events.sort_by_key(|e| (e.tick, e.order));
+    let end = events.last().map_or(0, |e| e.tick);
+    events.push(TimedEvent {
+        tick: end,
+        order: u8::MAX,
+        bytes: meta_event(META_END_OF_TRACK, &[]),
+    });
+
+    let mut chunk = Vec::new();
+    let mut last_tick = 0;
+    for event in events {
+        write_vlq(&mut chunk, event.tick - last_tick);
+        chunk.extend(event.bytes);
+        last_tick = event.tick;
+    }
+    data.extend_from_slice(b"MTrk");
+    data.extend_from_slice(&(chunk.len() as u32).to_be_bytes());
+    data.extend(chunk);
+}
+
+/// Start tick of every measure, followed by the end tick of the song.
+fn measure_starts(song: &Song) -> Vec<u32> {
+    let mut starts = Vec::with_capacity(song.measure_headers.len() + 1);
+    let mut tick = 0u32;
+    starts.push(tick);
+    for header in &song.measure_headers {
+        tick += header.length().max(0) as u32;
+        starts.push(tick);
+    }
+    starts
+}
+
+/// Tempo at the start of the song and every tempo change, in BPM keyed by tick.
+/// Changes come from GPIF measure headers and from GP3-5 mix table changes.
+fn tempo_map(song: &Song, starts: &[u32]) -> BTreeMap<u32, u32> {
+    let mut tempos = BTreeMap::new();
+    tempos.insert(0, song.tempo.max(1) as u32);
+    for (m_idx, header) in song.measure_headers.iter().enumerate() {
+        if header.tempo > 0 {
+            tempos.insert(starts[m_idx], header.tempo as u32);
+        }
+    }
+    for track in &song.tracks {
+        for (m_idx, measure) in track.measures.iter().enumerate().take(starts.len() - 1) {
+            for voice in &measure.voices {
+                let mut tick = starts[m_idx];
+                for beat in &voice.beats {
+                    let tempo = beat
+                        .effect
+                        .mix_table_change
+                        .as_ref()
+                        .and_then(|mtc| mtc.tempo.as_ref());
+                    if let Some(tempo) = tempo.filter(|t| t.value > 0) {
+                        tempos.insert(tick, tempo.value as u32);
+                    }
+                    tick += beat.duration.time();
+                }
+            }
+        }
+    }
+    tempos
+}
+
+/// The conductor track: song name, tempo map, time and key signatures.
+fn conductor_events(song: &Song, starts: &[u32]) -> Vec<TimedEvent> {
+    let mut events = vec![TimedEvent {
+        tick: 0,
+        order: 0,
+        bytes: meta_event(META_TRACK_NAME, song.name.as_bytes()),
+    }];
+
+    for (tick, bpm) in tempo_map(song, starts) {
+        let micros = 60_000_000 / bpm;
+        events.push(TimedEvent {
+            tick,
+            order: 1,
+            bytes: meta_event(META_TEMPO, &micros.to_be_bytes()[1..]),
+        });
+    }
+
+    let mut previous_time = None;
+    let mut previous_key = None;
+    for (m_idx, header) in song.measure_headers.iter().enumerate() {
+        let ts = &header.time_signature;
+        let time = (ts.numerator, ts.denominator.value);
+        if previous_time != Some(time) {
+            // Denominator as a power of two, metronome click every quarter, 8 32nds per quarter
+            let denominator = 15u16.saturating_sub(time.1.max(1).leading_zeros() as u16) as u8;
+            events.push(TimedEvent {
+                tick: starts[m_idx],
+                order: 1,
+                bytes: meta_event(
+                    META_TIME_SIGNATURE,
+                    &[time.0.max(1) as u8, denominator, 24, 8],
+                ),
+            });
+            previous_time = Some(time);
+        }
+        if previous_key.as_ref() != Some(&header.key_signature) {
+            events.push(TimedEvent {
+                tick: starts[m_idx],
+                order: 1,
+                bytes: key_signature_event(&header.key_signature),
+            });
+            previous_key = Some(header.key_signature.clone());
+        }
+    }
+    if song.measure_headers.is_empty() {
+        events.push(TimedEvent {
+            tick: 0,
+            order: 1,
+            bytes: key_signature_event(&song.key),
+        });
+    }
+    events
+}
+
+/// Resolve the MIDI channel, program and bank of a track.
+///
+/// GP3-5 tracks point into the song's channel table. Tracks read from GPIF only carry
+/// their channel number and program, so the table is used only when it has the entry.
+pub(crate) fn track_output(song: &Song, track: &Track) -> TrackOutput {
+    match song.channels.get(track.channel_index) {
+        Some(c) => TrackOutput {
+            channel: if c.is_percussion_channel() || track.percussion_track {
+                DEFAULT_PERCUSSION_CHANNEL
+            } else {
+                c.channel % 16
+            },
+            program: c.instrument.clamp(0, 127) as u8,
+            bank: c.bank.min(127),
+            mixer: Some(*c),
+        },
+        None => TrackOutput {
+            channel: if track.percussion_track {
+                DEFAULT_PERCUSSION_CHANNEL
+            } else {
+                (track.channel_index % 16) as u8
+            },
+            program: track.midi_program_gpif.unwrap_or(0).clamp(0, 127) as u8,
+            bank: 0,
+            mixer: None,
+        },
+    }
+}
+
+/// Collect the notes played by a track. Tied notes extend the note they continue.
+fn played_notes(track: &Track, starts: &[u32]) -> Vec<PlayedNote> {
+    let mut notes: Vec<PlayedNote> = Vec::new();
+    let offset = if track.percussion_track {
+        0
+    } else {
+        track.offset
+    };
+    // Index in `notes` of the last note played on each (voice, string)
+    let mut sounding: HashMap<(usize, i8), usize> = HashMap::new();
+
+    for (m_idx, measure) in track.measures.iter().enumerate().take(starts.len() - 1) {
+        for (v_idx, voice) in measure.voices.iter().enumerate() {
+            let mut tick = starts[m_idx];
+            for beat in &voice.beats {
+                let length = beat.duration.time();
+                if beat.status == BeatStatus::Normal {
+                    for note in &beat.notes {
+                        match note.kind {
+                            NoteType::Rest => {}
+                            NoteType::Tie => {
+                                if let Some(&idx) = sounding.get(&(v_idx, note.string)) {
+                                    notes[idx].end = tick + length;
+                                }
+                            }
+                            _ => {
+                                let Ok(value) = note.real_value(&track.strings) else {
+                                    continue;
+                                };
+                                let pitch = value as i32 + offset;
+                                if !(0..=127).contains(&pitch) {
+                                    continue;
+                                }
+                                sounding.insert((v_idx, note.string), notes.len());
+                                notes.push(PlayedNote {
+                                    pitch: pitch as u8,
+                                    start: tick,
+                                    end: tick + length,
+                                    velocity: note.velocity.clamp(1, 127) as u8,
+                                });
+                            }
+                        }
+                    }
+                }
+                tick += length;
+            }
+        }
+    }
+    notes
+}
+
+fn track_events(song: &Song, track: &Track, starts: &[u32]) -> Vec<TimedEvent> {
+    let output = track_output(song, track);
+    let channel = output.channel;
+    let mut events = vec![TimedEvent {
+        tick: 0,
+        order: 0,
+        bytes: meta_event(META_TRACK_NAME, track.name.as_bytes()),
+    }];
+
+    let mut setup = vec![
+        vec![0xB0 | channel, CONTROLLER_BANK_SELECT, output.bank],
+        vec![0xC0 | channel, output.program],
+    ];
+    if let Some(mixer) = output.mixer {
+        for (controller, value) in [
+            (CONTROLLER_VOLUME, mixer.volume),
+            (CONTROLLER_PAN, mixer.balance),
+            (CONTROLLER_REVERB, mixer.reverb),
+            (CONTROLLER_CHORUS, mixer.chorus),
+        ] {
+            setup.push(vec![0xB0 | channel, controller, value.clamp(0, 127) as u8]);
+        }
+    }
+    events.extend(setup.into_iter().map(|bytes| TimedEvent {
+        tick: 0,
+        order: 1,
+        bytes,
+    }));
+
+    for note in played_notes(track, starts) {
+        events.push(TimedEvent {
+            tick: note.start,
+            order: 3,
+            bytes: vec![0x90 | channel, note.pitch, note.velocity],
+        });
+        events.push(TimedEvent {
+            tick: note.end,
+            order: 2,
+            bytes: vec![0x80 | channel, note.pitch, 0],
+        });
+    }
+    events
+}
+
+/// Build a Type-1 Standard MIDI File: a conductor track holding the tempo map, time and
+/// key signatures, followed by one track per song track.
+pub fn write_smf(song: &Song) -> Vec<u8> {
+    let starts = measure_starts(song);
+    let mut data = Vec::new();
+    data.extend_from_slice(b"MThd");
+    data.extend_from_slice(&6u32.to_be_bytes());
+    data.extend_from_slice(&1u16.to_be_bytes());
+    data.extend_from_slice(&(song.tracks.len() as u16 + 1).to_be_bytes());
+    data.extend_from_slice(&SMF_DIVISION.to_be_bytes());
+
+    write_track_chunk(&mut data, conductor_events(song, &starts));
+    for track in &song.tracks {
+        write_track_chunk(&mut data, track_events(song, track, &starts));
+    }
+    data
+}
@@ -0,0 +1,902 @@
+// Standard MIDI File (SMF) import
+use std::collections::{BTreeSet, HashMap, VecDeque};
+
+use super::*;
+use crate::audio::midi::{MidiChannel, CHANNEL_DEFAULT_NAMES, DEFAULT_PERCUSSION_CHANNEL};
+use crate::error::{GpError, GpResult};
+use crate::io::limits::{check, ReadLimits};
+use crate::model::beat::{Beat, Voice};
+use crate::model::key_signature::{Duration, KeySignature, TimeSignature, SUPPORTED_TUPLETS};
+use crate::model::measure::Measure;
+use crate::model::note::Note;
+use crate::model::song::Song;
+use crate::model::track::Track;
+use crate::types::enums::{BeatStatus, NoteType};
+use crate::types::measure::MeasureHeader;
+
+/// Time unit used while quantizing, per quarter note. It divides evenly by every straight
+/// subdivision down to 128th notes and by every tuplet of `SUPPORTED_TUPLETS`.
+const UNITS_PER_QUARTER: u64 = 2_882_880;
+/// Song ticks (`DURATION_QUARTER_TIME` per quarter) to quantization units.
+const UNITS_PER_TICK: u64 = UNITS_PER_QUARTER / DURATION_QUARTER_TIME as u64;
+/// Largest distance to the grid, in units, for a grid to be accepted without comparison.
+const QUANTIZE_TOLERANCE: u64 = UNITS_PER_QUARTER / 32;
+/// Straight subdivisions of a quarter note, simplest first.
+const STRAIGHT_DIVISIONS: [u64; 5] = [1, 2, 4, 8, 16];
+
+const CONTROLLER_PAN_CENTER: u8 = 64;
+
+/// Tuplet of a duration: (enters, times).
+type Tuplet = (u8, u8);
+/// Place of a note on the instrument: (string, fret).
+pub(crate) type Position = (i8, i16);
+
+/// A note read from the file, in MIDI ticks.
+struct SmfNote {
+    start: u64,
+    end: u64,
+    pitch: u8,
+    velocity: u8,
+}
+
+/// The notes one track chunk plays on one channel, with the channel settings.
+struct SmfPart {
+    name: String,
+    channel: u8,
+    program: Option<u8>,
+    bank: u8,
+    volume: Option<u8>,
+    pan: Option<u8>,
+    notes: Vec<SmfNote>,
+}
+
+/// Content of a Standard MIDI File, events in MIDI ticks.
+#[derive(Default)]
+struct SmfFile {
+    division: u64,
+    first_track_name: String,
+    /// (tick, microseconds per quarter)
+    tempos: Vec<(u64, u32)>,
+    /// (tick, numerator, denominator)
+    time_signatures: Vec<(u64, u8, u16)>,
+    /// (tick, key)
+    key_signatures: Vec<(u64, KeySignature)>,
+    /// Programs set on each channel, used by parts that do not set their own
+    channel_programs: HashMap<u8, u8>,
+    parts: Vec<SmfPart>,
+}
+
+/// Big-endian reader over the file, failing with `GpError::UnexpectedEof`.
+struct SmfReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> SmfReader<'a> {
+    fn bytes(&mut self, count: usize) -> GpResult<&'a [u8]> {
+        if self.data.len() - self.pos < count {
+            return Err(GpError::UnexpectedEof {
+                offset: self.pos,
+                needed: count,
+            });
+        }
+        let bytes = &self.data[self.pos..self.pos + count];
+        self.pos += count;
+        Ok(bytes)
+    }
+
+    fn byte(&mut self) -> GpResult<u8> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u32(&mut self) -> GpResult<u32> {
+        let b = self.bytes(4)?;
+        Ok(u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    /// Read a variable-length quantity (at most 4 bytes).
+    fn vlq(&mut self) -> GpResult<u32> {
+        let mut value = 0u32;
+        for _ in 0..4 {
+            let byte = self.byte()?;
+            value = (value << 7) | (byte & 0x7F) as u32;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(GpError::FormatError(format!(
+            "MIDI variable-length quantity too long at offset {}",
+            self.pos
+        )))
+    }
+
+    fn is_eof(&self) -> bool {
+        self.pos >= self.data.len()
+    }
+}
+
+fn parse_smf(data: &[u8]) -> GpResult<SmfFile> {
+    let mut reader = SmfReader { data, pos: 0 };
+    if reader.bytes(4)? != b"MThd" {
+        return Err(GpError::FormatError(
+            "Not a Standard MIDI File: missing MThd header".to_string(),
+        ));
+    }
+    let header_len = reader.u32()? as usize;
+    let header = reader.bytes(header_len)?;
+    if header.len() < 6 {
+        return Err(GpError::FormatError("MIDI header too short".to_string()));
+    }
+    let track_count = u16::from_be_bytes([header[2], header[3]]);
+    let division = u16::from_be_bytes([header[4], header[5]]);
+    if division & 0x8000 != 0 || division == 0 {
+        return Err(GpError::FormatError(format!(
+            "Unsupported MIDI time division 0x{:04X}: only ticks per quarter note are supported",
+            division
+        )));
+    }
+
+    let mut smf = SmfFile {
+        division: division as u64,
+        ..Default::default()
+    };
+    let mut track_index = 0;
+    while track_index < track_count && !reader.is_eof() {
+        let kind = reader.bytes(4)?;
+        let len = reader.u32()? as usize;
+        let chunk = reader.bytes(len)?;
+        // Unknown chunks must be skipped
+        if kind == b"MTrk" {
+            parse_track(&mut smf, chunk, track_index == 0)?;
+            track_index += 1;
+        }
+    }
+    // Format 1 files may spread the meta events over several tracks
+    smf.tempos.sort_by_key(|e| e.0);
+    smf.time_signatures.sort_by_key(|e| e.0);
+    smf.key_signatures.sort_by_key(|e| e.0);
+    Ok(smf)
+}
+
+/// Read the events of a `MTrk` chunk. Notes are split into one part per channel.
+fn parse_track(smf: &mut SmfFile, chunk: &[u8], first: bool) -> GpResult<()> {
+    let mut reader = SmfReader {
+        data: chunk,
+        pos: 0,
+    };
+    let mut tick = 0u64;
+    let mut running_status = None;
+    let mut name = String::new();
+    let mut parts: Vec<SmfPart> = Vec::new();
+    // Started notes per (channel, pitch), closed first-in first-out
+    let mut open: HashMap<(u8, u8), VecDeque<(u64, u8)>> = HashMap::new();
+
+    fn part(parts: &mut Vec<SmfPart>, channel: u8) -> &mut SmfPart {
+        let idx = match parts.iter().position(|p| p.channel == channel) {
+            Some(idx) => idx,
+            None => {
+                parts.push(SmfPart {
+                    name: String::new(),
+                    channel,
+                    program: None,
+                    bank: 0,
+                    volume: None,
+                    pan: None,
+                    notes: Vec::new(),
+                });
+                parts.len() - 1
+            }
+        };
+        &mut parts[idx]
+    }
+
+    while !reader.is_eof() {
+        tick += reader.vlq()? as u64;
+        let mut status = reader.byte()?;
+        if status < 0x80 {
+            // Running status: the byte read is the first data byte
+            status = running_status.ok_or_else(|| {
+                GpError::FormatError(format!(
+                    "MIDI data byte without status at offset {}",
+                    reader.pos - 1
+                ))
+            })?;
+            reader.pos -= 1;
+        }
+
+        match status {
+            0xFF => {
+                let kind = reader.byte()?;
+                let len = reader.vlq()? as usize;
+                let payload = reader.bytes(len)?;
+                match (kind, payload) {
+                    (META_TRACK_NAME, _) => name = String::from_utf8_lossy(payload).to_string(),
+                    (META_TEMPO, [a, b, c]) => {
+                        let micros = u32::from_be_bytes([0, *a, *b, *c]);
+                        if micros > 0 {
+                            smf.tempos.push((tick, micros));
+                        }
+                    }
+                    (META_TIME_SIGNATURE, [numerator, power, ..])
+                        if *numerator > 0 && *power <= 6 =>
+                    {
+                        smf.time_signatures.push((tick, *numerator, 1 << power));
+                    }
+                    (META_KEY_SIGNATURE, [key, mode]) => smf.key_signatures.push((
+                        tick,
+                        KeySignature {
+                            key: (*key as i8).clamp(-7, 7),
+                            is_minor: *mode == 1,
+                        },
+                    )),
+                    (META_END_OF_TRACK, _) => break,
+                    _ => {}
+                }
+                running_status = None;
+            }
+            0xF0 | 0xF7 => {
+                let len = reader.vlq()? as usize;
+                reader.bytes(len)?;
+                running_status = None;
+            }
+            0xF1..=0xFE => {
+                return Err(GpError::FormatError(format!(
+                    "Unexpected MIDI system message 0x{:02X} in track",
+                    status
+                )));
+            }
+            _ => {
+                running_status = Some(status);
+                let channel = status & 0x0F;
+                match status & 0xF0 {
+                    0x80 | 0x90 => {
+                        let pitch = reader.byte()? & 0x7F;
+                        let velocity = reader.byte()? & 0x7F;
+                        let queue = open.entry((channel, pitch)).or_default();
+                        if status & 0xF0 == 0x90 && velocity > 0 {
+                            queue.push_back((tick, velocity));
+                        } else if let Some((start, velocity)) = queue.pop_front() {
+                            part(&mut parts, channel).notes.push(SmfNote {
+                                start,
+                                end: tick,
+                                pitch,
+                                velocity,
+                            });
+                        }
+                    }
+                    0xB0 => {
+                        let controller = reader.byte()?;
+                        let value = reader.byte()? & 0x7F;
+                        let p = part(&mut parts, channel);
+                        match controller {
+                            CONTROLLER_BANK_SELECT => p.bank = value,
+                            CONTROLLER_VOLUME => p.volume = p.volume.or(Some(value)),
+                            CONTROLLER_PAN => p.pan = p.pan.or(Some(value)),
+                            _ => {}
+                        }
+                    }
+                    0xC0 => {
+                        let program = reader.byte()? & 0x7F;
+                        smf.channel_programs.entry(channel).or_insert(program);
+                        let p = part(&mut parts, channel);
+                        p.program = p.program.or(Some(program));
+                    }
+                    0xD0 => {
+                        reader.byte()?;
+                    }
+                    _ => {
+                        reader.bytes(2)?;
+                    }
+                }
+            }
+        }
+    }
+
+    // Notes still held at the end of the track stop there
+    for ((channel, pitch), queue) in open {
+        for (start, velocity) in queue {
+            part(&mut parts, channel).notes.push(SmfNote {
+                start,
+                end: tick,
+                pitch,
+                velocity,
+            });
+        }
+    }
+
+    if first {
+        smf.first_track_name = name.clone();
+    }
+    for mut p in parts.into_iter().filter(|p| !p.notes.is_empty()) {
+        p.notes.sort_by_key(|n| (n.start, n.pitch));
+        p.name = name.clone();
+        smf.parts.push(p);
+    }
+    Ok(())
+}
+
+/// Last value of a list of timed events at or before `tick`.
+fn value_at<T: Clone>(events: &[(u64, T)], tick: u64) -> Option<T> {
+    events
+        .iter()
+        .rev()
+        .find(|(t, _)| *t <= tick)
+        .map(|(_, v)| v.clone())
+}
+
+/// A quantization window: one quarter note (or what remains of the measure) with the grid
+/// its notes are snapped to. Tuplet windows keep their own beats.
+#[derive(Clone)]
+struct Window {
+    start: u64,
+    end: u64,
+    grid: u64,
+    tuplet: Tuplet,
+}
+
+/// Grids that can be used for a window of `len` units, simplest first: (grid, tuplet).
+///
+/// Tuplets are only used on whole quarter notes. Triplets and sextuplets are tried before
+/// straight 32nd notes, the other tuplets after them.
+fn window_grids(len: u64) -> Vec<(u64, Tuplet)> {
+    let straight = |division: u64| (UNITS_PER_QUARTER / division, (1, 1));
+    let tuplet = |&(enters, times): &Tuplet| (UNITS_PER_QUARTER / enters as u64, (enters, times));
+    let mut grids: Vec<(u64, Tuplet)> = STRAIGHT_DIVISIONS[..3]
+        .iter()
+        .map(|&d| straight(d))
+        .collect();
+    if len == UNITS_PER_QUARTER {
+        let (simple, complex): (Vec<Tuplet>, Vec<Tuplet>) = SUPPORTED_TUPLETS
+            .iter()
+            .filter(|(enters, times)| enters != times)
+            .partition(|(enters, _)| *enters <= 6);
+        grids.extend(simple.iter().map(tuplet));
+        grids.push(straight(STRAIGHT_DIVISIONS[3]));
+        grids.extend(complex.iter().map(tuplet));
+    } else {
+        grids.push(straight(STRAIGHT_DIVISIONS[3]));
+    }
+    grids.push(straight(STRAIGHT_DIVISIONS[4]));
+    grids.retain(|(grid, _)| len.is_multiple_of(*grid));
+    if grids.is_empty() {
+        grids.push((len, (1, 1)));
+    }
+    grids
+}
+
+/// Distance from `pos` to the closest point of a grid starting at `origin`.
+fn grid_error(pos: u64, origin: u64, grid: u64) -> u64 {
+    let offset = (pos - origin) % grid;
+    offset.min(grid - offset)
+}
+
+/// Split the measures into windows and pick the grid of each one from the note starts.
+fn quantization_windows(measure_bounds: &[(u64, u64)], starts: &[u64]) -> Vec<Window> {
+    let mut starts = starts.to_vec();
+    starts.sort_unstable();
+    let mut windows = Vec::new();
+    for &(measure_start, measure_end) in measure_bounds {
+        let mut start = measure_start;
+        while start < measure_end {
+            let end = (start + UNITS_PER_QUARTER).min(measure_end);
+            // The note starts in the window, the starts being sorted
+            let first = starts.partition_point(|&s| s < start);
+            let last = starts.partition_point(|&s| s < end);
+            let points = &starts[first..last];
+            let grids = window_grids(end - start);
+            let errors = |grid: u64| points.iter().map(move |&p| grid_error(p, start, grid));
+            let (grid, tuplet) = grids
+                .iter()
+                .copied()
+                .find(|&(grid, _)| errors(grid).max().unwrap_or(0) <= QUANTIZE_TOLERANCE)
+                .or_else(|| {
+                    grids
+                        .iter()
+                        .copied()
+                        .min_by_key(|&(grid, _)| errors(grid).sum::<u64>())
+                })
+                .unwrap_or((end - start, (1, 1)));
+            windows.push(Window {
+                start,
+                end,
+                grid,
+                tuplet,
+            });
+            start = end;
+        }
+    }
+    windows
+}
+
+/// Snap `pos` to the grid of the window containing it.
+fn snap(windows: &[Window], pos: u64) -> u64 {
+    let idx = windows.partition_point(|w| w.end <= pos);
+    match windows.get(idx) {
+        Some(w) => {
+            let offset = (pos - w.start) % w.grid;
+            let down = pos - offset;
+            if offset * 2 < w.grid {
+                down
+            } else {
+                (down + w.grid).min(w.end)
+            }
+        }
+        None => windows.last().map_or(pos, |w| w.end),
+    }
+}
+
+/// Length in units of a duration, if it is a whole number of units.
+fn duration_units(value: u16, dotted: bool, tuplet: Tuplet) -> Option<u64> {
+    let mut len = UNITS_PER_QUARTER * 4 / value as u64;
+    if dotted {
+        len += len / 2;
+    }
+    let scaled = len * tuplet.1 as u64;
+    scaled
+        .is_multiple_of(tuplet.0 as u64)
+        .then(|| scaled / tuplet.0 as u64)
+}
+
+/// Decompose a length into note durations, longest first.
+fn split_length(mut len: u64, tuplet: Tuplet) -> Vec<Duration> {
+    let mut candidates: Vec<(u64, Duration)> = Vec::new();
+    for value in [1u16, 2, 4, 8, 16, 32, 64] {
+        for dotted in [true, false] {
+            if let Some(units) = duration_units(value, dotted, tuplet) {
+                candidates.push((
+                    units,
+                    Duration {
+                        value,
+                        dotted,
+                        tuplet_enters: tuplet.0,
+                        tuplet_times: tuplet.1,
+                        ..Default::default()
+                    },
+                ));
+            }
+        }
+    }
+    candidates.sort_by_key(|(units, _)| std::cmp::Reverse(*units));
+
+    let mut durations = Vec::new();
+    while len > 0 {
+        match candidates.iter().find(|(units, _)| *units <= len) {
+            Some((units, duration)) => {
+                durations.push(duration.clone());
+                len -= units;
+            }
+            None => break,
+        }
+    }
+    durations
+}
+
+/// A quantized note of a part, in units.
+struct QuantizedNote {
+    start: u64,
+    end: u64,
+    pitch: u8,
+    velocity: u8,
+    /// (string, fret) once placed on the instrument, `None` when it does not fit
+    position: Option<Position>,
+}
+
+/// A pitch moved by octaves into the range of the instrument, when it is out of it.
+fn fold_pitch(pitch: u8, strings: &[(i8, i8)], fret_count: u8) -> i16 {
+    let lowest = strings.iter().map(|s| s.1 as i16).min().unwrap_or(0);
+    let highest = strings.iter().map(|s| s.1 as i16).max().unwrap_or(0) + fret_count as i16;
+    let mut p = pitch as i16;
+    while p < lowest && p + 12 <= highest {
+        p += 12;
+    }
+    while p > highest && p - 12 >= lowest {
+        p -= 12;
+    }
+    p
+}
+
+/// Place new notes on the free strings, minimizing the number of dropped notes then
+/// the sum of the frets. Pitches out of the instrument's range are moved by octaves.
+pub(crate) fn assign_strings(
+    pitches: &[u8],
+    strings: &[(i8, i8)],
+    busy: &[i8],
+    fret_count: u8,
+) -> Vec<Option<Position>> {
+    let pitches: Vec<i16> = pitches
+        .iter()
+        .map(|&p| fold_pitch(p, strings, fret_count))
+        .collect();
+
+    struct Search<'a> {
+        pitches: &'a [i16],
+        strings: &'a [(i8, i8)],
+        fret_count: i16,
+        current: Vec<Option<Position>>,
+        /// (dropped notes, fret sum) of `best`
+        best_cost: Option<(usize, i32)>,
+        best: Vec<Option<Position>>,
+    }
+    fn search(s: &mut Search, idx: usize, used: &mut Vec<i8>, cost: (usize, i32)) {
+        if s.best_cost.is_some_and(|best| cost >= best) {
+            return;
+        }
+        if idx == s.pitches.len() {
+            s.best_cost = Some(cost);
+            s.best = s.current.clone();
+            return;
+        }
+        for &(string, tuning) in s.strings {
+            let fret = s.pitches[idx] - tuning as i16;
+            if used.contains(&string) || fret < 0 || fret > s.fret_count {
+                continue;
+            }
+            used.push(string);
+            s.current.push(Some((string, fret)));
+            search(s, idx + 1, used, (cost.0, cost.1 + fret as i32));
+            s.current.pop();
+            used.pop();
+        }
+        s.current.push(None);
+        search(s, idx + 1, used, (cost.0 + 1, cost.1));
+        s.current.pop();
+    }
+
+    let mut s = Search {
+        pitches: &pitches,
+        strings,
+        fret_count: fret_count as i16,
+        current: Vec::new(),
+        best_cost: None,
+        best: Vec::new(),
+    };
+    search(&mut s, 0, &mut busy.to_vec(), (0, 0));
+    // Dropping every note is always possible, so the search always ends with a result
+    s.best
+}
+
+/// Place new drum notes: the fret is the MIDI note, strings are used in order.
+pub(crate) fn assign_drums(pitches: &[u8], strings: &[(i8, i8)], busy: &[i8]) -> Vec<Option<Position>> {
+    let mut free = strings.iter().map(|s| s.0).filter(|s| !busy.contains(s));
+    pitches
+        .iter()
+        .map(|&p| free.next().map(|string| (string, p as i16)))
+        .collect()
+}
+
+fn new_track(song: &Song, part: &SmfPart, program: u8) -> Track {
+    let percussion = part.channel == DEFAULT_PERCUSSION_CHANNEL;
+    let name = if !part.name.is_empty() {
+        part.name.clone()
+    } else if percussion {
+        String::from("Drums")
+    } else {
+        CHANNEL_DEFAULT_NAMES[program as usize].to_string()
+    };
+    let mut track = Track {
+        number: song.tracks.len() as i32 + 1,
+        name,
+        channel_index: part.channel as usize,
+        percussion_track: percussion,
+        ..Default::default()
+    };
+    if percussion {
+        track.strings = (1..=6).map(|s| (s, 0)).collect();
+        track.fret_count = 127;
+    } else if (32..40).contains(&program) {
+        // Basses: standard 4-string tuning
+        track.strings = vec![(1, 43), (2, 38), (3, 33), (4, 28)];
+    }
+    track
+}
+
+/// Build the measures of a track from the notes of a part.
+fn build_measures(
+    track: &mut Track,
+    headers: &[MeasureHeader],
+    measure_bounds: &[(u64, u64)],
+    notes: &[SmfNote],
+    division: u64,
+) {
+    let to_units = |tick: u64| (tick * UNITS_PER_QUARTER + division / 2) / division;
+    let starts: Vec<u64> = notes.iter().map(|n| to_units(n.start)).collect();
+    let windows = quantization_windows(measure_bounds, &starts);
+    let song_end = measure_bounds.last().map_or(0, |b| b.1);
+
+    let mut quantized: Vec<QuantizedNote> = notes
+        .iter()
+        .map(|n| {
+            let start = snap(&windows, to_units(n.start)).min(song_end);
+            let mut end = snap(&windows, to_units(n.end)).min(song_end);
+            if end <= start {
+                let idx = windows.partition_point(|w| w.end <= start);
+                end = windows.get(idx).map_or(start, |w| start + w.grid);
+            }
+            QuantizedNote {
+                start,
+                end,
+                pitch: n.pitch,
+                velocity: n.velocity,
+                position: None,
+            }
+        })
+        .filter(|n| n.start < n.end)
+        .collect();
+    quantized.sort_by_key(|n| (n.start, std::cmp::Reverse(n.pitch)));
+    // A repeated pitch stops the previous note; notes collapsed on the same start are merged
+    let mut last_by_pitch: HashMap<u8, usize> = HashMap::new();
+    for idx in 0..quantized.len() {
+        if let Some(&prev) = last_by_pitch.get(&quantized[idx].pitch) {
+            if quantized[prev].end > quantized[idx].start {
+                quantized[prev].end = quantized[idx].start;
+            }
+        }
+        last_by_pitch.insert(quantized[idx].pitch, idx);
+    }
+    quantized.retain(|n| n.start < n.end);
+
+    // Fingering of the whole part, the notes starting together as chords
+    let mut planned: Vec<Option<Position>> = vec![None; quantized.len()];
+    if !track.percussion_track {
+        let mut onsets: Vec<Vec<usize>> = Vec::new();
+        for (i, n) in quantized.iter().enumerate() {
+            match onsets.last_mut() {
+                Some(group) if quantized[group[0]].start == n.start => group.push(i),
+                _ => onsets.push(vec![i]),
+            }
+        }
+        let chords: Vec<Vec<u8>> = onsets
+            .iter()
+            .map(|group| {
+                group
+                    .iter()
+                    .map(|&i| {
+                        fold_pitch(quantized[i].pitch, &track.strings, track.fret_count)
+                            .clamp(0, 127) as u8
+                    })
+                    .collect()
+            })
+            .collect();
+        for (group, positions) in onsets.iter().zip(track.fingering(&chords)) {
+            for (&i, position) in group.iter().zip(positions) {
+                planned[i] = position;
+            }
+        }
+    }
+
+    for (m_idx, (header, &(measure_start, measure_end))) in
+        headers.iter().zip(measure_bounds).enumerate()
+    {
+        let in_measure: Vec<usize> = (0..quantized.len())
+            .filter(|&i| quantized[i].start < measure_end && quantized[i].end > measure_start)
+            .collect();
+        // Segment boundaries: measure, tuplet windows, note starts and ends
+        let mut bounds = BTreeSet::from([measure_start, measure_end]);
+        for w in windows
+            .iter()
+            .filter(|w| w.start >= measure_start && w.end <= measure_end && w.tuplet != (1, 1))
+        {
+            bounds.insert(w.start);
+            bounds.insert(w.end);
+        }
+        for n in in_measure.iter().map(|&i| &quantized[i]) {
+            for pos in [n.start, n.end] {
+                if pos > measure_start && pos < measure_end {
+                    bounds.insert(pos);
+                }
+            }
+        }
+
+        let mut voice = Voice {
+            measure_index: m_idx as i16,
+            ..Default::default()
+        };
+        let bounds: Vec<u64> = bounds.into_iter().collect();
+        for pair in bounds.windows(2) {
+            let (a, b) = (pair[0], pair[1]);
+            let tuplet = windows
+                .get(windows.partition_point(|w| w.end <= a))
+                .map_or((1, 1), |w| w.tuplet);
+
+            // Place the notes starting here on the strings left by the sustained ones
+            let active: Vec<usize> = in_measure
+                .iter()
+                .copied()
+                .filter(|&i| quantized[i].start <= a && a < quantized[i].end)
+                .collect();
+            let busy: Vec<i8> = active
+                .iter()
+                .filter(|&&i| quantized[i].start < a)
+                .filter_map(|&i| quantized[i].position.map(|p| p.0))
+                .collect();
+            let new_notes: Vec<usize> = active
+                .iter()
+                .copied()
+                .filter(|&i| quantized[i].start == a)
+                .collect();
+            let pitches: Vec<u8> = new_notes.iter().map(|&i| quantized[i].pitch).collect();
+            let positions = if track.percussion_track {
+                assign_drums(&pitches, &track.strings, &busy)
+            } else {
+                // The planned fingering, unless a sustained note holds the string
+                let mut positions: Vec<Option<Position>> = new_notes
+                    .iter()
+                    .map(|&i| planned[i].filter(|p| !busy.contains(&p.0)))
+                    .collect();
+                let mut busy = busy.clone();
+                busy.extend(positions.iter().flatten().map(|p| p.0));
+                let missing: Vec<usize> = (0..positions.len())
+                    .filter(|&k| positions[k].is_none())
+                    .collect();
+                let pitches: Vec<u8> = missing.iter().map(|&k| pitches[k]).collect();
+                let assigned = assign_strings(&pitches, &track.strings, &busy, track.fret_count);
+                for (&k, position) in missing.iter().zip(assigned) {
+                    positions[k] = position;
+                }
+                positions
+            };
+            for (&i, position) in new_notes.iter().zip(positions) {
+                quantized[i].position = position;
+            }
+
+            let mut position = a;
+            for (piece, duration) in split_length(b - a, tuplet).into_iter().enumerate() {
+                let mut beat_notes: Vec<Note> = active
+                    .iter()
+                    .filter_map(|&i| {
+                        let n = &quantized[i];
+                        n.position.map(|(string, fret)| Note {
+                            value: fret,
+                            velocity: n.velocity as i16,
+                            string,
+                            kind: if n.start < a || piece > 0 {
+                                NoteType::Tie
+                            } else {
+                                NoteType::Normal
+                            },
+                            ..Default::default()
+                        })
+                    })
+                    .collect();
+                beat_notes.sort_by_key(|n| n.string);
+                let units = duration_units(duration.value, duration.dotted, tuplet).unwrap_or(0);
+                voice.beats.push(Beat {
+                    status: if beat_notes.is_empty() {
+                        BeatStatus::Rest
+                    } else {
+                        BeatStatus::Normal
+                    },
+                    notes: beat_notes,
+                    duration,
+                    start: Some(
+                        header.start + ((position - measure_start) / UNITS_PER_TICK) as i64,
+                    ),
+                    ..Default::default()
+                });
+                position += units;
+            }
+        }
+
+        // GP3-5 measures hold two voices: the second one is left empty
+        let empty_voice = Voice {
+            measure_index: m_idx as i16,
+            beats: vec![Beat {
+                status: BeatStatus::Empty,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        track.measures.push(Measure {
+            number: m_idx + 1,
+            start: header.start,
+            track_index: track.number as usize - 1,
+            header_index: m_idx,
+            time_signature: header.time_signature.clone(),
+            key_signature: header.key_signature.clone(),
+            voices: vec![voice, empty_voice],
+            ..Default::default()
+        });
+    }
+}
+
+/// Read a Standard MIDI File (format 0 or 1) into `song`.
+///
+/// Measures follow the time signature events, tempo events set the tempo of the measure
+/// they fall in. Every (track chunk, channel) pair playing notes becomes a track.
+pub fn read_smf(song: &mut Song, data: &[u8], limits: &ReadLimits) -> GpResult<()> {
+    let smf = parse_smf(data)?;
+    check("tracks", smf.parts.len(), limits.max_tracks)?;
+    let notes = smf.parts.iter().map(|p| p.notes.len()).sum();
+    check("notes", notes, limits.max_notes)?;
+    let division = smf.division;
+    let to_song_ticks = |tick: u64| tick * DURATION_QUARTER_TIME as u64 / division;
+    let last_tick = smf
+        .parts
+        .iter()
+        .flat_map(|p| &p.notes)
+        .map(|n| n.end)
+        .max()
+        .unwrap_or(0);
+    let bpm =
+        |micros: u32| ((60_000_000.0 / micros as f64).round() as i32).clamp(1, i16::MAX as i32);
+
+    // Measure headers, in song ticks (`start` keeps the GP offset of one quarter)
+    song.name = smf.first_track_name.clone();
+    song.tempo = value_at(&smf.tempos, 0).map_or(120, |micros| bpm(micros) as i16);
+    song.key = value_at(&smf.key_signatures, 0).unwrap_or_default();
+    song.measure_headers.clear();
+    let mut measure_bounds: Vec<(u64, u64)> = Vec::new();
+    let mut tick = 0u64;
+    let mut previous_tempo = None;
+    while measure_bounds.is_empty() || tick * division < last_tick * DURATION_QUARTER_TIME as u64 {
+        let midi_tick = tick * division / DURATION_QUARTER_TIME as u64;
+        let (_, numerator, denominator) = smf
+            .time_signatures
+            .iter()
+            .rev()
+            .find(|(t, _, _)| to_song_ticks(*t) <= tick)
+            .copied()
+            .unwrap_or((0, 4, 4));
+        let mut header = MeasureHeader {
+            number: measure_bounds.len() as u16 + 1,
+            start: DURATION_QUARTER_TIME + tick as i64,
+            time_signature: TimeSignature {
+                numerator: numerator.min(i8::MAX as u8) as i8,
+                denominator: Duration {
+                    value: denominator,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            key_signature: value_at(&smf.key_signatures, midi_tick).unwrap_or_default(),
+            ..Default::default()
+        };
+        let length = header.length() as u64;
+        let end_tick = (tick + length) * division / DURATION_QUARTER_TIME as u64;
+        // The last tempo event of the measure is the one kept for it
+        let tempo = value_at(&smf.tempos, end_tick.saturating_sub(1)).map(bpm);
+        if tempo.is_some() && tempo != previous_tempo {
+            header.tempo = tempo.unwrap_or(0);
+            previous_tempo = tempo;
+        }
+        check("measures", measure_bounds.len() + 1, limits.max_measures)?;
+        measure_bounds.push((tick * UNITS_PER_TICK, (tick + length) * UNITS_PER_TICK));
+        song.measure_headers.push(header);
+        tick += length;
+    }
+
+    // Channels: the 64 GP channels, with the settings of the parts
+    song.channels = (0..64u8)
+        .map(|channel| MidiChannel {
+            channel,
+            effect_channel: channel,
+            ..Default::default()
+        })
+        .collect();
+    song.tracks.clear();
+    for part in &smf.parts {
+        let program = part
+            .program
+            .or(smf.channel_programs.get(&part.channel).copied())
+            .unwrap_or(0);
+        let channel = &mut song.channels[part.channel as usize];
+        channel.set_instrument(program as i32);
+        channel.bank = part.bank;
+        channel.volume = part.volume.unwrap_or(channel.volume as u8) as i8;
+        channel.balance = part.pan.unwrap_or(CONTROLLER_PAN_CENTER) as i8;
+        if channel.is_percussion_channel() {
+            channel.instrument = 0;
+        }
+
+        let mut track = new_track(song, part, program);
+        build_measures(
+            &mut track,
+            &song.measure_headers,
+            &measure_bounds,
+            &part.notes,
+            division,
+        );
+        song.tracks.push(track);
+    }
+    Ok(())
+}
@@ -0,0 +1,38 @@
+// Standard MIDI File (SMF) support
+pub mod export;
+pub mod import;
+
+use crate::model::key_signature::DURATION_QUARTER_TIME;
+
+/// Ticks per quarter note written in the SMF header: the song's own time base.
+pub const SMF_DIVISION: u16 = DURATION_QUARTER_TIME as u16;
+
+const META_TRACK_NAME: u8 = 0x03;
+const META_END_OF_TRACK: u8 = 0x2F;
+const META_TEMPO: u8 = 0x51;
+const META_TIME_SIGNATURE: u8 = 0x58;
+const META_KEY_SIGNATURE: u8 = 0x59;
+
+const CONTROLLER_BANK_SELECT: u8 = 0x00;
+const CONTROLLER_VOLUME: u8 = 0x07;
+const CONTROLLER_PAN: u8 = 0x0A;
+const CONTROLLER_REVERB: u8 = 0x5B;
+const CONTROLLER_CHORUS: u8 = 0x5D;
+
+/// Write a variable-length quantity (7 bits per byte, MSB first).
+pub(crate) fn write_vlq(data: &mut Vec<u8>, value: u32) {
+    let mut buffer = [0u8; 5];
+    let mut count = 0;
+    let mut value = value;
+    loop {
+        buffer[count] = (value & 0x7F) as u8;
+        count += 1;
+        value >>= 7;
+        if value == 0 {
+            break;
+        }
+    }
+    for i in (0..count).rev() {
+        data.push(if i > 0 { buffer[i] | 0x80 } else { buffer[i] });
+    }
+}
@@ -0,0 +1,281 @@
+// Tempo map: conversion between ticks and seconds
+use crate::model::key_signature::DURATION_QUARTER_TIME;
+use crate::model::song::Song;
+use crate::types::enums::BeatStatus;
+
+/// A part of the tempo map: from `tick` the tempo goes linearly from `bpm` to `end_bpm`
+/// in `ramp` ticks, then stays at `end_bpm` until the next segment.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TempoSegment {
+    /// Start of the segment in ticks
+    pub tick: i64,
+    /// Start of the segment in seconds
+    pub seconds: f64,
+    /// Tempo at the start of the segment, in quarter notes per minute
+    pub bpm: f64,
+    /// Tempo at the end of the ramp
+    pub end_bpm: f64,
+    /// Length of the ramp in ticks, 0 for a constant tempo
+    pub ramp: i64,
+}
+
+impl TempoSegment {
+    /// Tempo `offset` ticks after the start of the segment.
+    fn bpm_at(&self, offset: i64) -> f64 {
+        if offset <= 0 {
+            self.bpm
+        } else if offset < self.ramp {
+            self.bpm + (self.end_bpm - self.bpm) * offset as f64 / self.ramp as f64
+        } else {
+            self.end_bpm
+        }
+    }
+
+    /// Seconds from the start of the segment to `offset` ticks after it.
+    fn seconds_to(&self, offset: f64) -> f64 {
+        let quarter = DURATION_QUARTER_TIME as f64;
+        if offset <= 0.0 || self.ramp == 0 {
+            return offset * 60.0 / (quarter * self.bpm);
+        }
+        let ramp = offset.min(self.ramp as f64);
+        let slope = (self.end_bpm - self.bpm) / self.ramp as f64;
+        // Integral of 60 / (quarter * (bpm + slope * x)) over the ramp
+        let seconds = if slope.abs() < f64::EPSILON {
+            ramp * 60.0 / (quarter * self.bpm)
+        } else {
+            60.0 / (quarter * slope) * ((self.bpm + slope * ramp) / self.bpm).ln()
+        };
+        seconds + (offset - ramp) * 60.0 / (quarter * self.end_bpm)
+    }
+
+    /// Ticks from the start of the segment to `seconds` after it.
+    fn ticks_to(&self, seconds: f64) -> f64 {
+        let quarter = DURATION_QUARTER_TIME as f64;
+        let ramp_seconds = self.seconds_to(self.ramp as f64);
+        if seconds <= 0.0 || self.ramp == 0 {
+            return seconds * quarter * self.bpm / 60.0;
+        }
+        if seconds >= ramp_seconds {
+            return self.ramp as f64 + (seconds - ramp_seconds) * quarter * self.end_bpm / 60.0;
+        }
+        let slope = (self.end_bpm - self.bpm) / self.ramp as f64;
+        if slope.abs() < f64::EPSILON {
+            seconds * quarter * self.bpm / 60.0
+        } else {
+            self.bpm * ((seconds * quarter * slope / 60.0).exp() - 1.0) / slope
+        }
+    }
+}
+
+/// A change read from the song, before the segments are built.
+enum TempoEvent {
+    /// New tempo, changing gradually up to the next event when linear
+    Set { bpm: f64, linear: bool },
+    /// Gradual change from the current tempo, reaching `bpm` after `ticks`
+    Ramp { bpm: f64, ticks: i64 },
+}
+
+/// Converts tick positions (`DURATION_QUARTER_TIME` per quarter note, 0 at the start of
+/// the first measure) to seconds and back, from the tempo changes of a song.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TempoMap {
+    segments: Vec<TempoSegment>,
+}
+
+impl TempoMap {
+    /// Tempo map of the song in written order: measures follow each other once, without
+    /// repeats. The tempo starts at `Song::tempo` and changes with `MeasureHeader::tempo`
+    /// (gradually up to the next change when `tempo_linear`) and with the tempo of mix
+    /// table changes, which moves gradually over its duration in beats.
+    pub fn new(song: &Song) -> TempoMap {
+        let starts = measure_starts(song);
+        let mut events: Vec<(i64, TempoEvent)> = vec![(
+            0,
+            TempoEvent::Set {
+                bpm: f64::from(song.tempo),
+                linear: false,
+            },
+        )];
+        for (m, header) in song.measure_headers.iter().enumerate() {
+            if header.tempo > 0 {
+                events.push((
+                    starts[m],
+                    TempoEvent::Set {
+                        bpm: f64::from(header.tempo),
+                        linear: header.tempo_linear,
+                    },
+                ));
+            }
+        }
+        for track in &song.tracks {
+            for (m, measure) in track.measures.iter().enumerate().take(starts.len() - 1) {
+                let beat_ticks = song.measure_headers[m].length()
+                    / i64::from(song.measure_headers[m].time_signature.numerator.max(1));
+                for voice in &measure.voices {
+                    let mut tick = starts[m];
+                    for beat in &voice.beats {
+                        let tempo = beat
+                            .effect
+                            .mix_table_change
+                            .as_ref()
+                            .and_then(|mtc| mtc.tempo.as_ref());
+                        if let Some(tempo) = tempo.filter(|t| t.value > 0) {
+                            let bpm = f64::from(tempo.value);
+                            let event = if tempo.duration > 0 {
+                                TempoEvent::Ramp {
+                                    bpm,
+                                    ticks: i64::from(tempo.duration) * beat_ticks,
+                                }
+                            } else {
+                                TempoEvent::Set { bpm, linear: false }
+                            };
+                            events.push((tick, event));
+                        }
+                        // Grace beats take no time, as in the note events
+                        if !(beat.status == BeatStatus::Normal && beat.is_grace_beat()) {
+                            tick += i64::from(beat.duration.time());
+                        }
+                    }
+                }
+            }
+        }
+        events.sort_by_key(|e| e.0);
+
+        let mut map = TempoMap {
+            segments: Vec::with_capacity(events.len()),
+        };
+        for (i, (tick, event)) in events.iter().enumerate() {
+            let (bpm, end_bpm, ramp) = match *event {
+                TempoEvent::Set { bpm, linear: true } => {
+                    // Up to the tempo of the next change
+                    match events[i + 1..].iter().find(|e| e.0 > *tick) {
+                        Some((next, TempoEvent::Set { bpm: end, .. }))
+                        | Some((next, TempoEvent::Ramp { bpm: end, .. })) => {
+                            (bpm, *end, next - tick)
+                        }
+                        None => (bpm, bpm, 0),
+                    }
+                }
+                TempoEvent::Set { bpm, linear: false } => (bpm, bpm, 0),
+                TempoEvent::Ramp { bpm, ticks } => (map.tempo_at(*tick), bpm, ticks),
+            };
+            map.push(*tick, bpm, end_bpm, ramp);
+        }
+        map
+    }
+
+    /// Tempo map of the song in playback order (see `Song::playback_order`): ticks run
+    /// through the measures as they are played, each measure keeping the tempo it has in
+    /// written order.
+    pub fn unrolled(song: &Song) -> TempoMap {
+        let written = TempoMap::new(song);
+        let starts = measure_starts(song);
+        let mut map = TempoMap {
+            segments: Vec::with_capacity(written.segments.len()),
+        };
+        let mut tick = 0;
+        let mut previous: Option<usize> = None;
+        for m in song.playback_order() {
+            let (start, end) = (starts[m], starts[m + 1]);
+            let follows = previous.is_some_and(|p| p + 1 == m);
+            if !follows {
+                // Tempo in effect at the start of the measure
+                let segment = written.segment_at(start);
+                let offset = start - segment.tick;
+                map.push(
+                    tick,
+                    segment.bpm_at(offset),
+                    segment.end_bpm,
+                    (segment.ramp - offset).max(0),
+                );
+            }
+            let first = written.segments.partition_point(|s| s.tick < start);
+            for segment in written.segments[first..]
+                .iter()
+                .take_while(|s| s.tick < end)
+            {
+                if follows || segment.tick > start {
+                    map.push(
+                        tick + segment.tick - start,
+                        segment.bpm,
+                        segment.end_bpm,
+                        segment.ramp,
+                    );
+                }
+            }
+            tick += end - start;
+            previous = Some(m);
+        }
+        if map.segments.is_empty() {
+            map.push(0, f64::from(song.tempo), f64::from(song.tempo), 0);
+        }
+        map
+    }
+
+    /// Segments of the map, sorted by tick.
+    pub fn segments(&self) -> &[TempoSegment] {
+        &self.segments
+    }
+
+    /// Tempo at a tick, in quarter notes per minute.
+    pub fn tempo_at(&self, tick: i64) -> f64 {
+        let segment = self.segment_at(tick);
+        segment.bpm_at(tick - segment.tick)
+    }
+
+    /// Time in seconds at a tick.
+    pub fn seconds_at(&self, tick: i64) -> f64 {
+        let segment = self.segment_at(tick);
+        segment.seconds + segment.seconds_to((tick - segment.tick) as f64)
+    }
+
+    /// Tick at a time in seconds, rounded to the nearest tick.
+    pub fn tick_at(&self, seconds: f64) -> i64 {
+        let index = self
+            .segments
+            .partition_point(|s| s.seconds <= seconds)
+            .saturating_sub(1);
+        let segment = &self.segments[index];
+        segment.tick + segment.ticks_to(seconds - segment.seconds).round() as i64
+    }
+
+    /// Last segment starting at or before a tick, the first one before the song.
+    fn segment_at(&self, tick: i64) -> &TempoSegment {
+        let index = self
+            .segments
+            .partition_point(|s| s.tick <= tick)
+            .saturating_sub(1);
+        &self.segments[index]
+    }
+
+    /// Add a segment after the others, replacing the last one when it starts on the same
+    /// tick.
+    fn push(&mut self, tick: i64, bpm: f64, end_bpm: f64, ramp: i64) {
+        if self.segments.last().is_some_and(|s| s.tick == tick) {
+            self.segments.pop();
+        }
+        let seconds = self
+            .segments
+            .last()
+            .map_or(0.0, |s| s.seconds + s.seconds_to((tick - s.tick) as f64));
+        self.segments.push(TempoSegment {
+            tick,
+            seconds,
+            bpm: bpm.max(1.0),
+            end_bpm: end_bpm.max(1.0),
+            ramp,
+        });
+    }
+}
+
+/// Start tick of every measure in written order, followed by the end tick of the song.
+pub(crate) fn measure_starts(song: &Song) -> Vec<i64> {
+    let mut starts = Vec::with_capacity(song.measure_headers.len() + 1);
+    let mut tick = 0;
+    starts.push(tick);
+    for header in &song.measure_headers {
+        tick += header.length().max(0);
+        starts.push(tick);
+    }
+    starts
+}
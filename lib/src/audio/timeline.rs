@@ -0,0 +1,231 @@
+// Note event timeline: the played notes of all the tracks, in time order
+use std::collections::HashMap;
+
+use crate::audio::tempo::{measure_starts, TempoMap};
+use crate::model::key_signature::DURATION_QUARTER_TIME;
+use crate::model::note::Note;
+use crate::model::song::Song;
+use crate::model::track::Track;
+use crate::types::enums::{BeatStatus, NoteType};
+use crate::types::note::NoteEffect;
+
+/// A note as it is played.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NoteEvent {
+    /// Start in ticks (`DURATION_QUARTER_TIME` per quarter note)
+    pub tick: i64,
+    /// Start in seconds
+    pub seconds: f64,
+    /// Length in ticks, tied notes included
+    pub duration: i64,
+    /// Length in seconds
+    pub duration_seconds: f64,
+    /// Sounding MIDI pitch
+    pub pitch: u8,
+    /// MIDI velocity
+    pub velocity: u8,
+    /// Index of the track in `Song::tracks`
+    pub track: usize,
+    /// Index of the measure in `Track::measures`
+    pub measure: usize,
+    /// Index of the voice in `Measure::voices`
+    pub voice: usize,
+    /// Index of the beat in `Voice::beats`
+    pub beat: usize,
+    /// String number, from 1 (the highest)
+    pub string: i8,
+    /// Fret, or the MIDI note of a percussion track
+    pub fret: i16,
+    /// The note is a grace note: a GP6/7 grace beat or the grace effect of a GP3-5 note
+    pub grace: bool,
+    /// Effects of the note
+    pub effect: NoteEffect,
+}
+
+/// Sounding pitch of a fret on a string of a track: tuning and capo. The GPIF transposition
+/// only changes the written notes.
+pub(crate) fn pitch(track: &Track, string: i8, fret: i16) -> Option<u8> {
+    let note = Note {
+        value: fret,
+        string,
+        ..Default::default()
+    };
+    let mut value = i32::from(note.real_value(&track.strings).ok()?);
+    if !track.percussion_track {
+        value += track.offset;
+    }
+    u8::try_from(value).ok().filter(|p| *p <= 127)
+}
+
+/// Collect the note events of a track, measures played in the given order.
+fn track_events(
+    song: &Song,
+    track_index: usize,
+    order: &[usize],
+    starts: &[i64],
+    events: &mut Vec<NoteEvent>,
+) {
+    let track = &song.tracks[track_index];
+    // Index in `events` of the last note played on each (voice, string)
+    let mut sounding: HashMap<(usize, i8), usize> = HashMap::new();
+    let mut measure_tick = 0;
+    for &m in order {
+        let Some(measure) = track.measures.get(m) else {
+            measure_tick += starts[m + 1] - starts[m];
+            continue;
+        };
+        for (v, voice) in measure.voices.iter().enumerate() {
+            let mut tick = measure_tick;
+            // Grace beats waiting for the beat they lead to
+            let mut graces: Vec<usize> = Vec::new();
+            for (b, beat) in voice.beats.iter().enumerate() {
+                if beat.status == BeatStatus::Normal && beat.is_grace_beat() {
+                    graces.push(b);
+                    continue;
+                }
+                let length = i64::from(beat.duration.time());
+                let mut start = tick;
+                if !graces.is_empty() {
+                    let beats: Vec<_> = graces.drain(..).map(|g| (g, &voice.beats[g])).collect();
+                    let total: i64 = beats.iter().map(|g| i64::from(g.1.duration.time())).sum();
+                    let on_beat = beats
+                        .iter()
+                        .flat_map(|g| &g.1.notes)
+                        .any(|n| n.effect.grace.as_ref().is_some_and(|g| g.is_on_beat));
+                    let total = if on_beat { total.min(length) } else { total };
+                    let mut grace_tick = if on_beat { tick } else { tick - total };
+                    for (g, grace_beat) in beats {
+                        let grace_length = i64::from(grace_beat.duration.time()).min(total);
+                        for note in &grace_beat.notes {
+                            if note.kind == NoteType::Rest {
+                                continue;
+                            }
+                            if let Some(pitch) = pitch(track, note.string, note.value) {
+                                sounding.insert((v, note.string), events.len());
+                                events.push(NoteEvent {
+                                    tick: grace_tick,
+                                    seconds: 0.0,
+                                    duration: grace_length,
+                                    duration_seconds: 0.0,
+                                    pitch,
+                                    velocity: note.velocity.clamp(1, 127) as u8,
+                                    track: track_index,
+                                    measure: m,
+                                    voice: v,
+                                    beat: g,
+                                    string: note.string,
+                                    fret: note.value,
+                                    grace: true,
+                                    effect: note.effect.clone(),
+                                });
+                            }
+                        }
+                        grace_tick += grace_length;
+                    }
+                    if on_beat {
+                        start += total;
+                    }
+                }
+                if beat.status == BeatStatus::Normal {
+                    for note in &beat.notes {
+                        let mut note_start = start;
+                        match note.kind {
+                            NoteType::Rest => continue,
+                            NoteType::Tie => {
+                                if let Some(&index) = sounding.get(&(v, note.string)) {
+                                    events[index].duration = tick + length - events[index].tick;
+                                    continue;
+                                }
+                            }
+                            _ => {}
+                        }
+                        // Grace effect of a GP3-5 note, before the beat or on it
+                        if let Some(grace) = &note.effect.grace {
+                            let grace_length =
+                                DURATION_QUARTER_TIME * 4 / i64::from(grace.duration.max(1));
+                            let grace_length = if grace.is_on_beat {
+                                grace_length.min(length / 2)
+                            } else {
+                                grace_length
+                            };
+                            let grace_tick = if grace.is_on_beat {
+                                note_start += grace_length;
+                                start
+                            } else {
+                                start - grace_length
+                            };
+                            if let Some(pitch) = pitch(track, note.string, grace.fret.into()) {
+                                events.push(NoteEvent {
+                                    tick: grace_tick,
+                                    seconds: 0.0,
+                                    duration: grace_length,
+                                    duration_seconds: 0.0,
+                                    pitch,
+                                    velocity: grace.velocity.clamp(1, 127) as u8,
+                                    track: track_index,
+                                    measure: m,
+                                    voice: v,
+                                    beat: b,
+                                    string: note.string,
+                                    fret: grace.fret.into(),
+                                    grace: true,
+                                    effect: NoteEffect::default(),
+                                });
+                            }
+                        }
+                        if let Some(pitch) = pitch(track, note.string, note.value) {
+                            sounding.insert((v, note.string), events.len());
+                            events.push(NoteEvent {
+                                tick: note_start,
+                                seconds: 0.0,
+                                duration: tick + length - note_start,
+                                duration_seconds: 0.0,
+                                pitch,
+                                velocity: note.velocity.clamp(1, 127) as u8,
+                                track: track_index,
+                                measure: m,
+                                voice: v,
+                                beat: b,
+                                string: note.string,
+                                fret: note.value,
+                                grace: false,
+                                effect: note.effect.clone(),
+                            });
+                        }
+                    }
+                }
+                tick += length;
+            }
+        }
+        measure_tick += starts[m + 1] - starts[m];
+    }
+}
+
+/// Note events of all the tracks, measures played in the given order, sorted by tick.
+fn collect(song: &Song, order: &[usize], tempo: &TempoMap) -> Vec<NoteEvent> {
+    let starts = measure_starts(song);
+    let mut events = Vec::new();
+    for t in 0..song.tracks.len() {
+        track_events(song, t, order, &starts, &mut events);
+    }
+    events.sort_by_key(|e| (e.tick, e.track, e.voice, e.string));
+    for event in &mut events {
+        event.seconds = tempo.seconds_at(event.tick);
+        event.duration_seconds = tempo.seconds_at(event.tick + event.duration) - event.seconds;
+    }
+    events
+}
+
+/// Note events of the song in written order, measures played once. Tied notes extend
+/// the note they continue; grace notes (GP6/7 grace beats and GP3-5 grace effects) are
+/// played before their beat, or on it and delaying the note when they are on the beat.
+pub fn events(song: &Song) -> Vec<NoteEvent> {
+    let order: Vec<usize> = (0..song.measure_headers.len()).collect();
+    collect(song, &order, &TempoMap::new(song))
+}
+
+/// Note events of the song in playback order (see `Song::playback_order`), ticks and
+/// seconds counted along the played measures.
+pub fn played_events(song: &Song) -> Vec<NoteEvent> {
+    collect(song, &song.playback_order(), &TempoMap::unrolled(song))
+}
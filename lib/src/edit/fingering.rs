@@ -0,0 +1,159 @@
+// Fingering optimizer: strings and frets for sequences of pitches
+/// Frets the hand covers without stretching.
+pub(crate) const HAND_SPAN: i32 = 4;
+/// Cost of each fret of stretch beyond the hand span.
+pub(super) const STRETCH_COST: i32 = 3;
+/// Cost of each fret the hand moves between two chords.
+const SHIFT_COST: i32 = 2;
+/// Cost of a note that cannot be placed, over any fingering of the other notes.
+const MISSING_COST: i32 = 10_000;
+/// Fingerings of a chord kept for the search, the cheapest ones.
+const MAX_CANDIDATES: usize = 64;
+
+/// A way to play a chord: (string, fret) of each of its pitches.
+struct Candidate {
+    positions: Vec<Option<(i8, i16)>>,
+    /// Fret of the index finger, None when only open strings are played
+    hand: Option<i32>,
+    /// Cost of the chord alone: stretch, height on the neck, missing notes
+    cost: i32,
+}
+
+/// Fingerings of a chord on the strings, each pitch on its own string or left out when
+/// it cannot be played, the cheapest first.
+fn candidates(pitches: &[u8], strings: &[(i8, i8)], capo: i32, max: i32) -> Vec<Candidate> {
+    fn search(
+        pitches: &[u8],
+        strings: &[(i8, i8)],
+        capo: i32,
+        max: i32,
+        current: &mut Vec<Option<(i8, i16)>>,
+        found: &mut Vec<Vec<Option<(i8, i16)>>>,
+    ) {
+        let Some(&pitch) = pitches.get(current.len()) else {
+            found.push(current.clone());
+            return;
+        };
+        let mut placed = false;
+        for &(string, tuning) in strings {
+            let fret = i32::from(pitch) - i32::from(tuning) - capo;
+            if (0..=max).contains(&fret) && current.iter().flatten().all(|p| p.0 != string) {
+                placed = true;
+                current.push(Some((string, fret as i16)));
+                search(pitches, strings, capo, max, current, found);
+                current.pop();
+            }
+        }
+        // Left out only when no free string can play it
+        if !placed {
+            current.push(None);
+            search(pitches, strings, capo, max, current, found);
+            current.pop();
+        }
+    }
+
+    let mut found = Vec::new();
+    search(pitches, strings, capo, max, &mut Vec::new(), &mut found);
+    let mut candidates: Vec<Candidate> = found
+        .into_iter()
+        .map(|positions| {
+            let frets: Vec<i32> = positions
+                .iter()
+                .flatten()
+                .map(|p| i32::from(p.1))
+                .filter(|f| *f > 0)
+                .collect();
+            let hand = frets.iter().min().copied();
+            let stretch = hand.map_or(0, |low| frets.iter().max().unwrap() - low - HAND_SPAN);
+            let missing = positions.iter().filter(|p| p.is_none()).count() as i32;
+            Candidate {
+                cost: STRETCH_COST * stretch.max(0) + hand.unwrap_or(0) + missing * MISSING_COST,
+                hand,
+                positions,
+            }
+        })
+        .collect();
+    candidates.sort_by_key(|c| c.cost);
+    candidates.truncate(MAX_CANDIDATES);
+    candidates
+}
+
+/// Strings and frets of a sequence of chords (sets of sounding MIDI pitches, one for a
+/// single note), on an instrument tuned to `strings` (string number from 1 and MIDI pitch
+/// of the open string) with `fret_count` frets and a capo on fret `capo`.
+///
+/// The fingerings minimize over the whole sequence the moves of the hand from a chord to
+/// the next, the stretch of each chord beyond four frets and, less, the height on the
+/// neck; open strings cost nothing. Two pitches of a chord are never on the same string.
+/// Each position is the (string, fret) of the pitch at the same index, ready for
+/// `Note::string` and `Note::value`, or None when no free string can play it.
+pub fn fingering(
+    chords: &[Vec<u8>],
+    strings: &[(i8, i8)],
+    fret_count: u8,
+    capo: i32,
+) -> Vec<Vec<Option<(i8, i16)>>> {
+    let max = i32::from(fret_count) - capo;
+    let steps: Vec<Vec<Candidate>> = chords
+        .iter()
+        .map(|pitches| candidates(pitches, strings, capo, max))
+        .collect();
+
+    // Cheapest total cost ending on each candidate, with the candidate it comes from
+    let mut costs: Vec<Vec<(i32, usize)>> = Vec::with_capacity(steps.len());
+    let mut previous: Option<usize> = None;
+    for (s, step) in steps.iter().enumerate() {
+        let row = step
+            .iter()
+            .map(|candidate| match previous {
+                None => (candidate.cost, 0),
+                Some(p) => steps[p]
+                    .iter()
+                    .zip(&costs[p])
+                    .enumerate()
+                    .map(|(i, (from, (total, _)))| {
+                        let shift = match (from.hand, candidate.hand) {
+                            (Some(a), Some(b)) => (a - b).abs(),
+                            _ => 0,
+                        };
+                        (total + SHIFT_COST * shift + candidate.cost, i)
+                    })
+                    .min()
+                    .unwrap_or((candidate.cost, 0)),
+            })
+            .collect();
+        costs.push(row);
+        // Empty chords leave the hand where it is
+        if !chords[s].is_empty() {
+            previous = Some(s);
+        }
+    }
+
+    // Back from the cheapest last candidate
+    let mut chosen = vec![0; steps.len()];
+    let mut next: Option<(usize, usize)> = None;
+    for s in (0..steps.len()).rev() {
+        if chords[s].is_empty() {
+            continue;
+        }
+        chosen[s] = match next {
+            None => (0..costs[s].len())
+                .min_by_key(|&i| costs[s][i].0)
+                .unwrap_or(0),
+            Some((n, c)) => costs[n][c].1,
+        };
+        next = Some((s, chosen[s]));
+    }
+    steps
+        .into_iter()
+        .zip(chosen)
+        .zip(chords)
+        .map(|((mut step, c), pitches)| {
+            if step.is_empty() {
+                vec![None; pitches.len()]
+            } else {
+                step.swap_remove(c).positions
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,3 @@
+pub mod fingering;
+pub mod retune;
+pub mod transpose;
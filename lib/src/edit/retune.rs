@@ -0,0 +1,291 @@
+// Re-tuning of tracks: the notes keep their pitch on the new strings
+use std::collections::HashMap;
+use std::fmt;
+
+use super::fingering::{HAND_SPAN, STRETCH_COST};
+use super::transpose::{follow, max_fret, tuning};
+use crate::model::track::Track;
+use crate::types::chord::Chord;
+use crate::types::enums::NoteType;
+
+/// Cost of a note that cannot be placed, over any position on the neck.
+const MISSING_COST: i32 = 1000;
+
+/// A note the new tuning cannot play: its string and fret are left unchanged.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnplacedNote {
+    /// Index of the measure in `Track::measures`
+    pub measure: usize,
+    /// Index of the voice in `Measure::voices`
+    pub voice: usize,
+    /// Index of the beat in `Voice::beats`
+    pub beat: usize,
+    /// Index of the note in `Beat::notes`
+    pub note: usize,
+    /// MIDI pitch of the note, without capo and transposition
+    pub pitch: i32,
+}
+
+impl fmt::Display for UnplacedNote {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "measure {}, voice {}, beat {}: pitch {} cannot be played",
+            self.measure + 1,
+            self.voice + 1,
+            self.beat + 1,
+            self.pitch
+        )
+    }
+}
+
+/// Cost of a set of positions for a hand around `hand`: the distance of each fretted note
+/// to the hand, and the stretch between the lowest and the highest fretted notes.
+fn cost(positions: &[(i8, i32)], hand: i32) -> i32 {
+    let frets = positions.iter().map(|p| p.1).filter(|f| *f > 0);
+    let distance: i32 = frets.clone().map(|f| (f - hand).abs()).sum();
+    let stretch = match (frets.clone().min(), frets.max()) {
+        (Some(low), Some(high)) => (high - low - HAND_SPAN).max(0),
+        _ => 0,
+    };
+    distance + STRETCH_COST * stretch
+}
+
+/// Search of the cheapest positions for the notes not placed yet.
+struct Search<'a> {
+    strings: &'a [(i8, i8)],
+    max: i32,
+    /// Pitch of each note to place, None for the others
+    pitches: &'a [Option<i32>],
+    hand: i32,
+    /// Positions taken, those of the notes placed before the search first
+    taken: Vec<(i8, i32)>,
+    current: Vec<Option<(i8, i32)>>,
+    missing: i32,
+    best: (i32, Vec<Option<(i8, i32)>>),
+}
+
+impl Search<'_> {
+    /// Try the positions of the notes from `index` on: each free string playing the
+    /// pitch, or none. Branches already costing more than the best are left.
+    fn run(&mut self, index: usize) {
+        let total = cost(&self.taken, self.hand) + self.missing * MISSING_COST;
+        if total >= self.best.0 {
+            return;
+        }
+        if index == self.pitches.len() {
+            self.best = (total, self.current.clone());
+            return;
+        }
+        let Some(pitch) = self.pitches[index] else {
+            return self.run(index + 1);
+        };
+        for (i, open) in self.strings.iter().enumerate() {
+            let (string, fret) = (i as i8 + 1, pitch - i32::from(open.1));
+            if (0..=self.max).contains(&fret) && self.taken.iter().all(|p| p.0 != string) {
+                self.taken.push((string, fret));
+                self.current[index] = Some((string, fret));
+                self.run(index + 1);
+                self.current[index] = None;
+                self.taken.pop();
+            }
+        }
+        self.missing += 1;
+        self.run(index + 1);
+        self.missing -= 1;
+    }
+}
+
+/// Positions on the `strings` of a set of notes, each given by the string it is written on
+/// and its pitch (None for the notes already placed in `fixed`). A note keeps its string
+/// when it can still be played there; the others take the positions closest to the hand
+/// with the least stretch. None for the notes that cannot be placed.
+fn place(
+    strings: &[(i8, i8)],
+    max: i32,
+    notes: &[(i8, Option<i32>)],
+    fixed: &[(i8, i32)],
+    hand: i32,
+) -> Vec<Option<(i8, i32)>> {
+    let mut placed: Vec<Option<(i8, i32)>> = vec![None; notes.len()];
+    let mut taken: Vec<(i8, i32)> = fixed.to_vec();
+    for (i, &(string, pitch)) in notes.iter().enumerate() {
+        let Some(pitch) = pitch else {
+            continue;
+        };
+        let Some(open) = usize::try_from(string)
+            .ok()
+            .and_then(|s| strings.get(s.checked_sub(1)?))
+        else {
+            continue;
+        };
+        let fret = pitch - i32::from(open.1);
+        if (0..=max).contains(&fret) && taken.iter().all(|p| p.0 != string) {
+            placed[i] = Some((string, fret));
+            taken.push((string, fret));
+        }
+    }
+    // The other notes, on the strings left
+    let pitches: Vec<Option<i32>> = notes
+        .iter()
+        .zip(&placed)
+        .map(|(n, p)| if p.is_none() { n.1 } else { None })
+        .collect();
+    if pitches.iter().all(Option::is_none) {
+        return placed;
+    }
+    let mut search = Search {
+        strings,
+        max,
+        pitches: &pitches,
+        hand,
+        taken,
+        current: vec![None; notes.len()],
+        missing: 0,
+        best: (i32::MAX, vec![None; notes.len()]),
+    };
+    search.run(0);
+    for (i, position) in search.best.1.into_iter().enumerate() {
+        if position.is_some() {
+            placed[i] = position;
+        }
+    }
+    placed
+}
+
+/// Hand position after a set of positions: the middle of the fretted notes, unchanged
+/// when all the strings are open.
+fn hand_after(positions: &[(i8, i32)], hand: i32) -> i32 {
+    let frets: Vec<i32> = positions.iter().map(|p| p.1).filter(|f| *f > 0).collect();
+    if frets.is_empty() {
+        hand
+    } else {
+        (frets.iter().min().unwrap() + frets.iter().max().unwrap()) / 2
+    }
+}
+
+/// Re-voice a chord diagram for the new strings, its notes at the same pitch.
+fn retune_chord(chord: &mut Chord, old: &[(i8, i8)], strings: &[(i8, i8)], max: i32) {
+    let fretted: Vec<(i8, i32)> = chord
+        .strings
+        .iter()
+        .enumerate()
+        .filter(|(i, fret)| **fret >= 0 && *i < old.len())
+        .map(|(i, fret)| (i as i8 + 1, i32::from(*fret)))
+        .collect();
+    let notes: Vec<(i8, Option<i32>)> = fretted
+        .iter()
+        .map(|&(string, fret)| (string, Some(i32::from(old[string as usize - 1].1) + fret)))
+        .collect();
+    let placed = place(strings, max, &notes, &[], hand_after(&fretted, 0));
+    let mut diagram = vec![-1i8; strings.len()];
+    for (string, fret) in placed.into_iter().flatten() {
+        diagram[string as usize - 1] = fret as i8;
+    }
+    if diagram != chord.strings {
+        chord.strings = diagram;
+        chord.barres.clear();
+        let frets = chord.strings.iter().filter(|f| **f > 0);
+        let (low, high) = (
+            frets.clone().min().copied().unwrap_or(1),
+            frets.max().copied().unwrap_or(1),
+        );
+        if chord.first_fret.is_some() {
+            chord.first_fret = Some(if i32::from(high) <= HAND_SPAN + 1 {
+                1
+            } else {
+                low as u8
+            });
+        }
+    }
+    chord.length = strings.len() as u8;
+}
+
+/// Change the tuning of a track to `strings` (string number from 1, the highest, and MIDI
+/// pitch of the open string), keeping the pitch of every note. A note stays on its string
+/// when the new fret is on the neck (up to `Track::fret_count` above the capo); otherwise
+/// it moves to the position closest to the hand, which follows the previous notes of the
+/// voice, with the least stretch, and never on a string already used by the beat. Tied
+/// notes follow the note they continue; grace, trill and tapped harmonic frets follow
+/// their note. Chord diagrams are re-voiced the same way, their barres cleared when they
+/// change. Returns the notes that cannot be played with the new tuning, left unchanged.
+/// Percussion tracks are left unchanged.
+pub fn retune(track: &mut Track, strings: Vec<(i8, i8)>) -> Vec<UnplacedNote> {
+    let mut unplaced = Vec::new();
+    if track.percussion_track {
+        return unplaced;
+    }
+    let old = std::mem::replace(&mut track.strings, strings);
+    let max = max_fret(track);
+    let mut measures = std::mem::take(&mut track.measures);
+    // Position of the last note on each (voice, written string), hand of each voice
+    let mut ties: HashMap<(usize, i8), (i8, i32)> = HashMap::new();
+    let mut hands: HashMap<usize, i32> = HashMap::new();
+    let old_pitch = |string: i8, fret: i16| {
+        let index = usize::try_from(string).ok()?.checked_sub(1)?;
+        old.get(index).map(|s| i32::from(s.1) + i32::from(fret))
+    };
+    for (m, measure) in measures.iter_mut().enumerate() {
+        for (v, voice) in measure.voices.iter_mut().enumerate() {
+            for (b, beat) in voice.beats.iter_mut().enumerate() {
+                let hand = hands.get(&v).copied().unwrap_or(0);
+                let mut fixed = Vec::new();
+                let mut notes = Vec::with_capacity(beat.notes.len());
+                let mut tied = vec![None; beat.notes.len()];
+                for (i, note) in beat.notes.iter().enumerate() {
+                    let pitch = old_pitch(note.string, note.value);
+                    if note.kind == NoteType::Tie {
+                        if let Some(&target) = ties.get(&(v, note.string)) {
+                            tied[i] = Some(target);
+                            fixed.push(target);
+                            notes.push((note.string, None));
+                            continue;
+                        }
+                    }
+                    let pitch = pitch.filter(|_| note.kind != NoteType::Rest);
+                    notes.push((note.string, pitch));
+                }
+                let placed = place(&track.strings, max, &notes, &fixed, hand);
+                let mut positions = fixed;
+                for (i, note) in beat.notes.iter_mut().enumerate() {
+                    let target = tied[i].or(placed[i]);
+                    let Some((string, fret)) = target else {
+                        if let Some(pitch) = notes[i].1 {
+                            unplaced.push(UnplacedNote {
+                                measure: m,
+                                voice: v,
+                                beat: b,
+                                note: i,
+                                pitch,
+                            });
+                        }
+                        continue;
+                    };
+                    let old_open = old_pitch(note.string, 0).unwrap_or(0);
+                    let new_open = tuning(track, string).unwrap_or(0);
+                    let shift = old_open - new_open;
+                    ties.insert((v, note.string), (string, fret));
+                    positions.push((string, fret));
+                    note.string = string;
+                    note.value = fret as i16;
+                    if let Some(grace) = &mut note.effect.grace {
+                        grace.fret = follow(grace.fret, shift, max);
+                    }
+                    if let Some(trill) = &mut note.effect.trill {
+                        trill.fret = follow(trill.fret, shift, max);
+                    }
+                    if let Some(fret) = note.effect.harmonic.as_mut().and_then(|h| h.fret.as_mut())
+                    {
+                        *fret = follow(*fret, shift, max);
+                    }
+                }
+                hands.insert(v, hand_after(&positions, hand));
+                if let Some(chord) = &mut beat.effect.chord {
+                    retune_chord(chord, &old, &track.strings, max);
+                }
+            }
+        }
+    }
+    track.measures = measures;
+    unplaced
+}
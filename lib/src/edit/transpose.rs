@@ -0,0 +1,260 @@
+// Chromatic transposition of songs and tracks
+use std::collections::HashMap;
+
+use crate::model::beat::Beat;
+use crate::model::song::Song;
+use crate::model::track::Track;
+use crate::types::chord::pitch_class::{FLAT_NOTES, SHARP_NOTES};
+use crate::types::chord::Chord;
+use crate::types::enums::NoteType;
+
+/// Highest fret that can be played on a track, above the capo.
+pub(super) fn max_fret(track: &Track) -> i32 {
+    (i32::from(track.fret_count) - track.offset).max(0)
+}
+
+/// Open string pitch of a string number, from 1 (the highest).
+pub(super) fn tuning(track: &Track, string: i8) -> Option<i32> {
+    let index = usize::try_from(string).ok()?.checked_sub(1)?;
+    track.strings.get(index).map(|s| i32::from(s.1))
+}
+
+/// Fret of `fret` moved by `shift`, clamped to the neck: used for the frets that come with
+/// a note (grace note, trill, tapped harmonic) so that they follow it.
+pub(super) fn follow(fret: i8, shift: i32, max: i32) -> i8 {
+    (i32::from(fret) + shift).clamp(0, max.min(i32::from(i8::MAX))) as i8
+}
+
+/// Transpose the notes of a beat on a track. `ties` maps (voice, string) of the last note
+/// played to its new (string, fret), for the tied notes that follow.
+fn transpose_beat(
+    track: &Track,
+    beat: &mut Beat,
+    voice: usize,
+    semitones: i32,
+    ties: &mut HashMap<(usize, i8), (i8, i32)>,
+) {
+    let max = max_fret(track);
+    // New (string, fret) of each note, None while it is not placed
+    let mut placed: Vec<Option<(i8, i32)>> = vec![None; beat.notes.len()];
+    // Tied notes stay with the note they continue
+    for (i, note) in beat.notes.iter().enumerate() {
+        if note.kind == NoteType::Tie {
+            placed[i] = ties.get(&(voice, note.string)).copied();
+        }
+    }
+    // Notes still on the neck keep their string
+    for (i, note) in beat.notes.iter().enumerate() {
+        if placed[i].is_some()
+            || note.kind == NoteType::Rest
+            || tuning(track, note.string).is_none()
+        {
+            continue;
+        }
+        let fret = i32::from(note.value) + semitones;
+        if (0..=max).contains(&fret) && placed.iter().flatten().all(|p| p.0 != note.string) {
+            placed[i] = Some((note.string, fret));
+        }
+    }
+    // The others: another free string, the fret closest to the written one
+    for (i, note) in beat.notes.iter().enumerate() {
+        if placed[i].is_some() || note.kind == NoteType::Rest {
+            continue;
+        }
+        let Some(open) = tuning(track, note.string) else {
+            continue;
+        };
+        let pitch = open + i32::from(note.value) + semitones;
+        let free = (1..=track.strings.len() as i8)
+            .filter(|s| placed.iter().flatten().all(|p| p.0 != *s))
+            .filter_map(|s| Some((s, pitch - tuning(track, s)?)))
+            .filter(|(_, fret)| (0..=max).contains(fret))
+            .min_by_key(|(s, fret)| {
+                (
+                    (fret - i32::from(note.value)).abs(),
+                    (s - note.string).abs(),
+                )
+            });
+        // Last resort: the same string, an octave up or down
+        placed[i] = Some(free.unwrap_or_else(|| {
+            let fret = i32::from(note.value) + semitones;
+            let octaves = if fret < 0 {
+                (-fret + 11) / 12
+            } else {
+                -((fret - max + 11) / 12)
+            };
+            (note.string, (fret + 12 * octaves).clamp(0, max))
+        }));
+    }
+    for (note, target) in beat.notes.iter_mut().zip(placed) {
+        let Some((string, fret)) = target else {
+            continue;
+        };
+        let shift = fret - i32::from(note.value);
+        ties.insert((voice, note.string), (string, fret));
+        note.string = string;
+        note.value = fret as i16;
+        if let Some(grace) = &mut note.effect.grace {
+            grace.fret = follow(grace.fret, shift, max);
+        }
+        if let Some(trill) = &mut note.effect.trill {
+            trill.fret = follow(trill.fret, shift, max);
+        }
+        if let Some(harmonic) = &mut note.effect.harmonic {
+            if let Some(fret) = &mut harmonic.fret {
+                *fret = follow(*fret, shift, max);
+            }
+            if let Some(pitch) = &harmonic.pitch {
+                harmonic.pitch = Some(pitch.transposed(semitones, pitch.sharp));
+            }
+        }
+    }
+}
+
+/// Transposed root note at the start of `name` (a letter and an optional `#` or `b`),
+/// with the length of the note it replaces.
+fn transpose_note_name(name: &str, semitones: i32, sharp: bool) -> Option<(String, usize)> {
+    let letter = name.get(..1)?;
+    let natural = SHARP_NOTES.iter().position(|n| *n == letter)? as i32;
+    let (accidental, length) = match name[1..].chars().next() {
+        Some('#') => (1, 2),
+        Some('b') => (-1, 2),
+        _ => (0, 1),
+    };
+    let value = (natural + accidental + semitones).rem_euclid(12) as usize;
+    let note = if sharp {
+        SHARP_NOTES[value]
+    } else {
+        FLAT_NOTES[value]
+    };
+    Some((note.to_string(), length))
+}
+
+/// Chord name with its root and its bass (after a `/`) transposed.
+fn transpose_chord_name(name: &str, semitones: i32, sharp: bool) -> String {
+    let mut result = String::with_capacity(name.len() + 2);
+    let mut rest = name;
+    if let Some((root, length)) = transpose_note_name(rest, semitones, sharp) {
+        result.push_str(&root);
+        rest = &rest[length..];
+    }
+    match rest.rfind('/') {
+        Some(slash) => {
+            result.push_str(&rest[..=slash]);
+            let bass = &rest[slash + 1..];
+            match transpose_note_name(bass, semitones, sharp) {
+                Some((note, length)) => {
+                    result.push_str(&note);
+                    result.push_str(&bass[length..]);
+                }
+                None => result.push_str(bass),
+            }
+        }
+        None => result.push_str(rest),
+    }
+    result
+}
+
+/// Transpose a chord: root, bass and name, and the diagram moved up or down the neck by
+/// the interval or by the interval and an octave, whichever stays on the neck. A diagram
+/// that cannot be moved is cleared.
+fn transpose_chord(chord: &mut Chord, semitones: i32, sharp: bool, max: i32) {
+    if let Some(root) = &chord.root {
+        chord.root = Some(root.transposed(semitones, sharp));
+    }
+    if let Some(bass) = &chord.bass {
+        chord.bass = Some(bass.transposed(semitones, sharp));
+    }
+    if chord.sharp.is_some() {
+        chord.sharp = Some(sharp);
+    }
+    chord.name = transpose_chord_name(&chord.name, semitones, sharp);
+
+    let played: Vec<i32> = chord
+        .strings
+        .iter()
+        .filter(|f| **f >= 0)
+        .map(|f| i32::from(*f))
+        .collect();
+    let interval = semitones % 12;
+    let shift = [semitones, interval, interval - 12, interval + 12]
+        .into_iter()
+        .find(|shift| {
+            played.iter().all(|f| (0..=max).contains(&(f + shift)))
+                && chord
+                    .barres
+                    .iter()
+                    .all(|b| (0..=max).contains(&(i32::from(b.fret) + shift)))
+        });
+    match shift {
+        Some(shift) => {
+            for fret in chord.strings.iter_mut().filter(|f| **f >= 0) {
+                *fret = (i32::from(*fret) + shift) as i8;
+            }
+            for barre in &mut chord.barres {
+                barre.fret = (i32::from(barre.fret) + shift) as i8;
+            }
+            if let Some(first) = chord.first_fret {
+                chord.first_fret = Some((i32::from(first) + shift).clamp(1, max.max(1)) as u8);
+            }
+        }
+        None => {
+            chord.strings.iter_mut().for_each(|f| *f = -1);
+            chord.barres.clear();
+        }
+    }
+}
+
+/// Transpose the notes, chords and key signatures of a track. Percussion tracks are left
+/// unchanged.
+fn transpose_track_notes(track: &mut Track, semitones: i32, sharp: bool) {
+    if track.percussion_track || semitones == 0 {
+        return;
+    }
+    let max = max_fret(track);
+    let mut measures = std::mem::take(&mut track.measures);
+    let mut ties: HashMap<(usize, i8), (i8, i32)> = HashMap::new();
+    for measure in &mut measures {
+        measure.key_signature = measure.key_signature.transposed(semitones);
+        for (v, voice) in measure.voices.iter_mut().enumerate() {
+            for beat in &mut voice.beats {
+                transpose_beat(track, beat, v, semitones, &mut ties);
+                if let Some(chord) = &mut beat.effect.chord {
+                    transpose_chord(chord, semitones, sharp, max);
+                }
+            }
+        }
+    }
+    track.measures = measures;
+}
+
+/// Transpose the whole song by `semitones`: the notes of every track except the
+/// percussion tracks (see `transpose_track`), the key of the song and the key signature of
+/// every measure header.
+pub fn transpose_song(song: &mut Song, semitones: i32) {
+    if semitones == 0 {
+        return;
+    }
+    song.key = song.key.transposed(semitones);
+    for header in &mut song.measure_headers {
+        header.key_signature = header.key_signature.transposed(semitones);
+    }
+    let sharp = song.key.key >= 0;
+    for track in &mut song.tracks {
+        transpose_track_notes(track, semitones, sharp);
+    }
+}
+
+/// Transpose one track by `semitones`, keeping the notes playable: a note whose fret goes
+/// off the neck (over `Track::fret_count` above the capo, or below the nut) moves to a free
+/// string, at the fret closest to the written one, or an octave up or down when no string
+/// can play it. Tied notes follow the note they continue. Chord roots, basses, names and
+/// diagrams are transposed, spelled with sharps or flats after the transposed song key.
+/// The key signatures of the track measures follow; those of the song and the measure
+/// headers are kept. Percussion tracks are left unchanged.
+pub fn transpose_track(song: &mut Song, track: usize, semitones: i32) {
+    let sharp = song.key.transposed(semitones).key >= 0;
+    if let Some(track) = song.tracks.get_mut(track) {
+        transpose_track_notes(track, semitones, sharp);
+    }
+}
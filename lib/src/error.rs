@@ -20,11 +20,125 @@ pub enum GpError {
         min: i64,
         max: i64,
     },
+    /// A limit of the reading was exceeded, see `ReadLimits`: more than `max` of `limit`
+    LimitExceeded { limit: &'static str, max: usize },
+    /// Error of a reader with where it happened: the parts of the score being read, from
+    /// the outermost, the offset in the file where the reading stopped and the version of
+    /// the file when known
+    Located {
+        error: Box<GpError>,
+        location: Vec<ScorePart>,
+        offset: usize,
+        version: Option<(u8, u8, u8)>,
+    },
 }
 
 /// Convenience type alias
 pub type GpResult<T> = Result<T, GpError>;
 
+/// A part of the score being read, by its index from 0. Displayed numbered from 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScorePart {
+    Channel(usize),
+    MeasureHeader(usize),
+    Track(usize),
+    Measure(usize),
+    Voice(usize),
+    Beat(usize),
+    Note(usize),
+}
+
+impl fmt::Display for ScorePart {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (name, index) = match self {
+            ScorePart::Channel(i) => ("MIDI channel", i),
+            ScorePart::MeasureHeader(i) => ("measure header", i),
+            ScorePart::Track(i) => ("track", i),
+            ScorePart::Measure(i) => ("measure", i),
+            ScorePart::Voice(i) => ("voice", i),
+            ScorePart::Beat(i) => ("beat", i),
+            ScorePart::Note(i) => ("note", i),
+        };
+        write!(f, "{} {}", name, index + 1)
+    }
+}
+
+impl GpError {
+    /// Add the part of the score being read to the location of the error. `seek` is the
+    /// offset of the reader, kept only by the innermost part.
+    pub(crate) fn within(self, part: ScorePart, seek: usize) -> GpError {
+        match self {
+            GpError::Located {
+                error,
+                mut location,
+                offset,
+                version,
+            } => {
+                location.insert(0, part);
+                GpError::Located {
+                    error,
+                    location,
+                    offset,
+                    version,
+                }
+            }
+            error => GpError::Located {
+                error: Box::new(error),
+                location: vec![part],
+                offset: seek,
+                version: None,
+            },
+        }
+    }
+
+    /// Set the version of the file read, locating the error at `seek` if it has no location.
+    pub(crate) fn with_version(self, number: (u8, u8, u8), seek: usize) -> GpError {
+        match self {
+            GpError::Located {
+                error,
+                location,
+                offset,
+                ..
+            } => GpError::Located {
+                error,
+                location,
+                offset,
+                version: Some(number),
+            },
+            error => GpError::Located {
+                error: Box::new(error),
+                location: Vec::new(),
+                offset: seek,
+                version: Some(number),
+            },
+        }
+    }
+
+    /// The error without its location.
+    pub fn cause(&self) -> &GpError {
+        match self {
+            GpError::Located { error, .. } => error.cause(),
+            error => error,
+        }
+    }
+
+    /// Parts of the score being read when the error happened, from the outermost.
+    pub fn location(&self) -> &[ScorePart] {
+        match self {
+            GpError::Located { location, .. } => location,
+            _ => &[],
+        }
+    }
+
+    /// Version of the file read, when known.
+    pub fn version(&self) -> Option<(u8, u8, u8)> {
+        match self {
+            GpError::Located { version, .. } => *version,
+            _ => None,
+        }
+    }
+}
+
 impl fmt::Display for GpError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -59,6 +173,25 @@ impl fmt::Display for GpError {
                     value, context, min, max
                 )
             }
+            GpError::LimitExceeded { limit, max } => {
+                write!(f, "Read limit exceeded: more than {} {}", max, limit)
+            }
+            GpError::Located {
+                error,
+                location,
+                offset,
+                version,
+            } => {
+                let path: Vec<String> = location.iter().map(|p| p.to_string()).collect();
+                if !path.is_empty() {
+                    write!(f, "{}: ", path.join(" \u{203a} "))?;
+                }
+                write!(f, "{} at 0x{:X}", error, offset)?;
+                if let Some((major, minor, patch)) = version {
+                    write!(f, " (version {}.{}.{})", major, minor, patch)?;
+                }
+                Ok(())
+            }
         }
     }
 }
@@ -67,6 +200,7 @@ impl std::error::Error for GpError {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         match self {
             GpError::Io(err) => Some(err),
+            GpError::Located { error, .. } => Some(error.as_ref()),
             _ => None,
         }
     }
@@ -0,0 +1,106 @@
+// ASCII tablature module
+
+pub mod parse;
+pub mod render;
+
+use crate::model::track::Track;
+use crate::types::chord::SHARP_NOTES;
+
+/// Line labels of the strings of a track, from string 1 (the top line): the note names of
+/// the open strings. The top string is written in lower case when another string has the
+/// same name (`e B G D A E`). Percussion tracks are labelled with string numbers.
+pub(crate) fn string_labels(track: &Track) -> Vec<String> {
+    if track.percussion_track {
+        return track.strings.iter().map(|s| s.0.to_string()).collect();
+    }
+    let names: Vec<&str> = track
+        .strings
+        .iter()
+        .map(|s| SHARP_NOTES[i32::from(s.1).rem_euclid(12) as usize])
+        .collect();
+    names
+        .iter()
+        .enumerate()
+        .map(|(i, name)| {
+            if i == 0 && names[1..].contains(name) {
+                name.to_lowercase()
+            } else {
+                name.to_string()
+            }
+        })
+        .collect()
+}
+
+/// Pitch class of a line label (`E`, `e`, `F#`, `Bb`), from 0 for C.
+pub(crate) fn label_pitch_class(label: &str) -> Option<i32> {
+    let mut chars = label.chars();
+    let natural: i32 = match chars.next()?.to_ascii_uppercase() {
+        'C' => 0,
+        'D' => 2,
+        'E' => 4,
+        'F' => 5,
+        'G' => 7,
+        'A' => 9,
+        'B' => 11,
+        _ => return None,
+    };
+    let alter = match chars.next() {
+        None => 0,
+        Some('#') => 1,
+        Some('b') => -1,
+        Some(_) => return None,
+    };
+    Some((natural + alter).rem_euclid(12))
+}
+
+/// Labels of the standard tuning of a tab with `count` strings, from the top line: a
+/// bass for 4 or 5 strings, a guitar otherwise.
+pub(crate) fn standard_labels(count: usize) -> Vec<String> {
+    let labels: &[&str] = match count {
+        4 => &["G", "D", "A", "E"],
+        5 => &["G", "D", "A", "E", "B"],
+        7 => &["E", "B", "G", "D", "A", "E", "B"],
+        8 => &["E", "B", "G", "D", "A", "E", "B", "F#"],
+        _ => &["E", "B", "G", "D", "A", "E"],
+    };
+    labels.iter().map(|l| l.to_string()).collect()
+}
+
+/// Open strings, from the lowest, of the lines labelled `labels` from the top line. The
+/// lowest string is the note of its name nearest to the lowest string of the standard
+/// tuning (E2 for 6 strings, B1 for 7, E1 for a 4-string bass...), every other string is
+/// the first note of its name above the string below it.
+pub(crate) fn tuning_from_labels(labels: &[String]) -> Option<Vec<i8>> {
+    let classes: Vec<i32> = labels
+        .iter()
+        .rev()
+        .map(|l| label_pitch_class(l))
+        .collect::<Option<_>>()?;
+    let lowest = match labels.len() {
+        4 => 28,
+        5 => 23,
+        7 => 35,
+        8 => 30,
+        _ => 40,
+    };
+    let mut tuning = Vec::with_capacity(classes.len());
+    let mut previous = 0;
+    for (i, class) in classes.into_iter().enumerate() {
+        let pitch = if i == 0 {
+            let above = lowest + (class - lowest).rem_euclid(12);
+            if above - lowest > 6 {
+                above - 12
+            } else {
+                above
+            }
+        } else {
+            match (class - previous).rem_euclid(12) {
+                0 => previous + 12,
+                interval => previous + interval,
+            }
+        };
+        tuning.push(pitch.clamp(0, 127) as i8);
+        previous = pitch;
+    }
+    Some(tuning)
+}
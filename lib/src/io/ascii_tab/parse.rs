@@ -0,0 +1,522 @@
+// ASCII tablature parser
+use std::fmt;
+
+use super::{standard_labels, tuning_from_labels};
+use crate::error::GpResult;
+use crate::io::limits::ReadLimits;
+use crate::io::musicxml::import::{
+    build_tracks, header_at, longest_duration, reset_song, NoteEvent, ReadPart,
+};
+use crate::model::note::Note;
+use crate::model::song::Song;
+use crate::types::effects::{BendEffect, BendPoint, HarmonicEffect, BEND_EFFECT_MAX_POSITION};
+use crate::types::enums::{BendType, HarmonicType, NoteType, SlideType};
+
+/// String lines in a block of a text tab.
+const STRING_COUNTS: std::ops::RangeInclusive<usize> = 4..=8;
+
+/// Something of a text tab the parser skipped: a character it does not know, a bar line
+/// out of place, a block with too few or too many strings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TabIssue {
+    /// Line, from 1
+    pub line: usize,
+    /// Character of the line, from 1
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for TabIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.line, self.column, self.message
+        )
+    }
+}
+
+/// A string line of a tab: `e|--0--|`, `D#:--3--`, `|--5--|`.
+struct StringLine {
+    /// Index of the line in the text
+    index: usize,
+    label: Option<String>,
+    /// Column of the first character of the content
+    offset: usize,
+    /// The line from its first `|` or `-`, until the first space
+    content: Vec<char>,
+}
+
+/// Read a line as a string line: an optional note name label, an optional `:` and the
+/// content, which starts with `|` or `-` and has at least three dashes.
+fn string_line(index: usize, line: &str) -> Option<StringLine> {
+    let chars: Vec<char> = line.chars().collect();
+    let mut i = chars.iter().take_while(|c| c.is_whitespace()).count();
+    let mut label = None;
+    if chars
+        .get(i)
+        .is_some_and(|c| matches!(c.to_ascii_uppercase(), 'A'..='G'))
+    {
+        let mut end = i + 1;
+        if matches!(chars.get(end), Some('#') | Some('b'))
+            && chars
+                .get(end + 1)
+                .is_some_and(|c| matches!(c, '|' | '-' | ':' | ' '))
+        {
+            end += 1;
+        }
+        label = Some(chars[i..end].iter().collect::<String>());
+        i = end;
+    }
+    while chars.get(i).is_some_and(|c| *c == ':' || *c == ' ') {
+        i += 1;
+    }
+    if !matches!(chars.get(i), Some('|') | Some('-')) {
+        return None;
+    }
+    let content: Vec<char> = chars[i..]
+        .iter()
+        .take_while(|c| !c.is_whitespace())
+        .copied()
+        .collect();
+    if content.iter().filter(|c| **c == '-').count() < 3 {
+        return None;
+    }
+    Some(StringLine {
+        index,
+        label,
+        offset: i,
+        content,
+    })
+}
+
+/// A note read on a string line, at the column of its first character in the measure.
+struct TabNote {
+    column: usize,
+    /// Characters of the fret and its prefix (`/5`, `<12>`), which place it in a beat
+    width: usize,
+    string: usize,
+    note: Note,
+}
+
+struct Parser {
+    issues: Vec<TabIssue>,
+    /// Open strings from the top line
+    tuning: Vec<i8>,
+    measures: Vec<Vec<NoteEvent>>,
+}
+
+impl Parser {
+    fn issue(&mut self, line: &StringLine, at: usize, message: String) {
+        self.issues.push(TabIssue {
+            line: line.index + 1,
+            column: line.offset + at + 1,
+            message,
+        });
+    }
+
+    fn block(&mut self, song: &mut Song, lines: &[StringLine]) {
+        // Bar lines are where the top line has them
+        let bars: Vec<usize> = lines[0]
+            .content
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| **c == '|')
+            .map(|(i, _)| i)
+            .collect();
+        for line in &lines[1..] {
+            for bar in &bars {
+                if line.content.get(*bar) != Some(&'|') {
+                    self.issue(
+                        line,
+                        *bar,
+                        String::from("bar line not aligned with the top line"),
+                    );
+                }
+            }
+        }
+        let width = lines[0].content.len();
+        let mut edges = vec![0];
+        for bar in &bars {
+            edges.push(*bar);
+            edges.push(bar + 1);
+        }
+        edges.push(width);
+        for segment in edges.chunks(2) {
+            let (start, end) = (segment[0], segment[1]);
+            if end > start {
+                self.measure(song, lines, start, end);
+            }
+        }
+    }
+
+    /// Read the measure between the columns `start` and `end` of the lines.
+    fn measure(&mut self, song: &mut Song, lines: &[StringLine], mut start: usize, mut end: usize) {
+        let m = self.measures.len();
+        let header = header_at(&mut song.measure_headers, m);
+        // Repeat dots next to the bar lines
+        if lines.iter().any(|l| l.content.get(start) == Some(&'*')) {
+            header.repeat_open = true;
+            start += 1;
+        }
+        if end > start && lines.iter().any(|l| l.content.get(end - 1) == Some(&'*')) {
+            header.repeat_close = 1;
+            end -= 1;
+        }
+        let length = header.length().max(0) as u64;
+
+        let mut notes = Vec::new();
+        for (string, line) in lines.iter().enumerate() {
+            notes.extend(self.line_notes(line, string, start, end));
+        }
+        notes.sort_by_key(|n| (n.column, n.string));
+        // Notes starting under the fret of the first note of a beat belong to that beat
+        let mut beats: Vec<Vec<TabNote>> = Vec::new();
+        for note in notes {
+            match beats.last_mut() {
+                Some(beat)
+                    if note.column < beat[0].column + beat[0].width
+                        && beat.iter().all(|n| n.string != note.string) =>
+                {
+                    beat.push(note)
+                }
+                _ => beats.push(vec![note]),
+            }
+        }
+
+        // Rhythm from the spacing, on a grid of sixteenths or thirty-seconds
+        let mut columns: Vec<usize> = beats.iter().map(|b| b[0].column - start).collect();
+        let mut span = end - start;
+        let gaps = columns
+            .windows(2)
+            .map(|w| w[1] - w[0])
+            .chain(columns.last().map(|c| span - c));
+        if let (Some(first), Some(gap)) = (columns.first().copied(), gaps.min()) {
+            // Dashes before the first note are padding when shorter than the spacing
+            if first < gap {
+                columns.iter_mut().for_each(|c| *c -= first);
+                span -= first;
+            }
+        }
+        let grid = if beats.len() > 16 { 120 } else { 240 };
+        let slots = (length / grid) as usize;
+        if beats.len() > slots {
+            let line = &lines[beats[slots][0].string];
+            let column = beats[slots][0].column;
+            self.issue(
+                line,
+                column,
+                format!("more than {} notes in the measure", slots),
+            );
+            beats.truncate(slots);
+            columns.truncate(slots);
+        }
+        let mut ticks: Vec<u64> = Vec::with_capacity(columns.len());
+        for (i, column) in columns.iter().enumerate() {
+            let exact = (*column as u64 * length) as f64 / span.max(1) as f64;
+            let mut tick = (exact / grid as f64).round() as u64 * grid;
+            if let Some(previous) = ticks.last() {
+                tick = tick.max(previous + grid);
+            }
+            ticks.push(tick.min(length - grid * (columns.len() - i) as u64));
+        }
+
+        let mut events = Vec::new();
+        for (i, beat) in beats.into_iter().enumerate() {
+            let next = ticks.get(i + 1).copied().unwrap_or(length);
+            let Some(duration) = longest_duration(next - ticks[i]) else {
+                continue;
+            };
+            for tab_note in beat {
+                let note = tab_note.note;
+                events.push(NoteEvent {
+                    voice: 0,
+                    start: ticks[i],
+                    length: duration.time() as u64,
+                    duration: duration.clone(),
+                    grace: false,
+                    grace_on_beat: false,
+                    hidden: false,
+                    pitch: Some(i32::from(self.tuning[tab_note.string]) + i32::from(note.value)),
+                    position: Some((note.string, note.value)),
+                    note,
+                    text: String::new(),
+                    lyrics: Vec::new(),
+                });
+            }
+        }
+        self.measures.push(events);
+    }
+
+    /// Notes of a string line between two columns, with their techniques.
+    fn line_notes(
+        &mut self,
+        line: &StringLine,
+        string: usize,
+        start: usize,
+        end: usize,
+    ) -> Vec<TabNote> {
+        let chars = &line.content[..end.min(line.content.len())];
+        let number = |i: &mut usize| -> Option<i16> {
+            let digits: String = chars[*i..]
+                .iter()
+                .take_while(|c| c.is_ascii_digit())
+                .take(2)
+                .collect();
+            *i += digits.len();
+            digits.parse().ok()
+        };
+        let mut notes: Vec<TabNote> = Vec::new();
+        let mut slide_in = None;
+        let mut i = start.min(chars.len());
+        while i < chars.len() {
+            let column = i;
+            let mut note = Note {
+                string: string as i8 + 1,
+                kind: NoteType::Normal,
+                ..Default::default()
+            };
+            match chars[i] {
+                '-' | '|' => {
+                    i += 1;
+                    continue;
+                }
+                c @ ('/' | '\\') if chars.get(i + 1).is_some_and(|c| c.is_ascii_digit()) => {
+                    slide_in = Some(if c == '/' {
+                        SlideType::IntoFromBelow
+                    } else {
+                        SlideType::IntoFromAbove
+                    });
+                    i += 1;
+                    continue;
+                }
+                'x' | 'X' => {
+                    note.kind = NoteType::Dead;
+                    i += 1;
+                }
+                c if c.is_ascii_digit() => note.value = number(&mut i).unwrap_or(0),
+                open @ ('<' | '(') => {
+                    let close = if open == '<' { '>' } else { ')' };
+                    i += 1;
+                    match number(&mut i) {
+                        Some(fret) if chars.get(i) == Some(&close) => {
+                            note.value = fret;
+                            i += 1;
+                        }
+                        _ => {
+                            self.issue(line, i, format!("expected a fret and '{}'", close));
+                            continue;
+                        }
+                    }
+                    if open == '<' {
+                        note.effect.harmonic = Some(HarmonicEffect {
+                            kind: HarmonicType::Natural,
+                            pitch: None,
+                            octave: None,
+                            fret: None,
+                        });
+                    } else {
+                        note.effect.ghost_note = true;
+                    }
+                }
+                c => {
+                    self.issue(line, i, format!("unexpected character '{}'", c));
+                    i += 1;
+                    continue;
+                }
+            }
+            let mut width = i - column;
+            let (mut column, fret) = (column, note.value);
+            if let Some(slide) = slide_in.take() {
+                note.effect.slides.push(slide);
+                column -= 1;
+                width += 1;
+            }
+            // Techniques after the fret
+            while i < chars.len() {
+                match chars[i] {
+                    'b' => {
+                        i += 1;
+                        let target = number(&mut i).unwrap_or(fret + 2);
+                        let release = if chars.get(i) == Some(&'r') {
+                            i += 1;
+                            Some(number(&mut i).unwrap_or(fret))
+                        } else {
+                            None
+                        };
+                        match bend(fret, target, release) {
+                            Some(bend) => note.effect.bend = Some(bend),
+                            None => self.issue(line, column, String::from("bend below the fret")),
+                        }
+                    }
+                    'h' | 'p' => {
+                        note.effect.hammer = true;
+                        i += 1;
+                        break;
+                    }
+                    c @ ('/' | '\\' | 's') => {
+                        let slide = match chars.get(i + 1) {
+                            Some(next) if next.is_ascii_digit() => SlideType::ShiftSlideTo,
+                            _ if c == '\\' => SlideType::OutDownwards,
+                            _ => SlideType::OutUpWards,
+                        };
+                        note.effect.slides.push(slide);
+                        i += 1;
+                        break;
+                    }
+                    '~' => {
+                        note.effect.vibrato = true;
+                        i += 1;
+                    }
+                    _ => break,
+                }
+            }
+            notes.push(TabNote {
+                column,
+                width,
+                string,
+                note,
+            });
+        }
+        notes
+    }
+}
+
+/// A bend from `fret` up to the `target` fret, released to the `release` fret.
+fn bend(fret: i16, target: i16, release: Option<i16>) -> Option<BendEffect> {
+    // Bend point values are in quarter tones
+    let value = ((target - fret) * 2).clamp(0, i16::from(i8::MAX)) as i8;
+    if value == 0 {
+        return None;
+    }
+    let max = BEND_EFFECT_MAX_POSITION;
+    let point = |position: u8, value: i8| BendPoint {
+        position,
+        value,
+        vibrato: false,
+    };
+    let (kind, points) = match release {
+        Some(release) => {
+            let back = ((release - fret) * 2).clamp(0, i16::from(value)) as i8;
+            (
+                BendType::BendRelease,
+                vec![
+                    point(0, 0),
+                    point(max / 4, value),
+                    point(max / 2, value),
+                    point(max * 3 / 4, back),
+                    point(max, back),
+                ],
+            )
+        }
+        None => (
+            BendType::Bend,
+            vec![point(0, 0), point(max / 2, value), point(max, value)],
+        ),
+    };
+    Some(BendEffect {
+        kind,
+        value: i16::from(value) * 25,
+        points,
+        ..Default::default()
+    })
+}
+
+/// Runs of consecutive string lines of a text.
+fn blocks(text: &str) -> Vec<Vec<StringLine>> {
+    let mut blocks: Vec<Vec<StringLine>> = Vec::new();
+    let mut in_block = false;
+    for (index, line) in text.lines().enumerate() {
+        match string_line(index, line) {
+            Some(line) if in_block => blocks.last_mut().unwrap().push(line),
+            Some(line) => {
+                blocks.push(vec![line]);
+                in_block = true;
+            }
+            None => in_block = false,
+        }
+    }
+    blocks
+}
+
+/// Whether a text has a block of 4 to 8 string lines.
+pub(crate) fn contains_tab(text: &str) -> bool {
+    blocks(text)
+        .iter()
+        .any(|block| STRING_COUNTS.contains(&block.len()))
+}
+
+/// Read a plain-text tab into a song of one track. Blocks of 4 to 8 string lines are read
+/// one after the other, their tuning from the labels of the first block (standard tuning
+/// when the lines have no labels); `|` separates the measures and the rhythm follows the
+/// spacing of the notes. Other lines are ignored; what could not be read in the string
+/// lines is returned.
+pub fn read_ascii_tab(
+    song: &mut Song,
+    data: &[u8],
+    limits: &ReadLimits,
+) -> GpResult<Vec<TabIssue>> {
+    let blocks = blocks(&String::from_utf8_lossy(data));
+    let mut parser = Parser {
+        issues: Vec::new(),
+        tuning: Vec::new(),
+        measures: Vec::new(),
+    };
+    reset_song(song);
+    for block in blocks {
+        if !STRING_COUNTS.contains(&block.len()) {
+            parser.issues.push(TabIssue {
+                line: block[0].index + 1,
+                column: 1,
+                message: format!(
+                    "expected {} to {} string lines, found {}",
+                    STRING_COUNTS.start(),
+                    STRING_COUNTS.end(),
+                    block.len()
+                ),
+            });
+            continue;
+        }
+        if parser.tuning.is_empty() {
+            let labels: Vec<String> = block
+                .iter()
+                .map(|l| l.label.clone())
+                .collect::<Option<_>>()
+                .unwrap_or_else(|| standard_labels(block.len()));
+            let mut tuning = tuning_from_labels(&labels).unwrap_or_else(|| {
+                tuning_from_labels(&standard_labels(block.len())).unwrap_or_default()
+            });
+            tuning.reverse();
+            parser.tuning = tuning;
+        } else if block.len() != parser.tuning.len() {
+            let message = format!(
+                "expected {} string lines as in the first block, found {}",
+                parser.tuning.len(),
+                block.len()
+            );
+            parser.issues.push(TabIssue {
+                line: block[0].index + 1,
+                column: 1,
+                message,
+            });
+            continue;
+        }
+        parser.block(song, &block);
+    }
+    if parser.tuning.is_empty() {
+        return Err(String::from("No tablature block found").into());
+    }
+
+    song.measure_headers.truncate(parser.measures.len());
+    let mut tuning = parser.tuning;
+    tuning.reverse();
+    let part = ReadPart {
+        tuning,
+        voice_count: 1,
+        measures: parser.measures,
+        ..Default::default()
+    };
+    build_tracks(song, vec![part], limits)?;
+    parser.issues.sort_by_key(|i| (i.line, i.column));
+    Ok(parser.issues)
+}
@@ -0,0 +1,423 @@
+// ASCII tablature renderer
+use std::collections::{BTreeSet, HashMap};
+
+use super::string_labels;
+use crate::model::beat::Beat;
+use crate::model::note::Note;
+use crate::model::song::Song;
+use crate::model::track::Track;
+use crate::types::enums::{BeatStatus, GraceEffectTransition, NoteType, SlideType};
+
+/// Options of the ASCII tablature renderer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TabOptions {
+    /// Maximum length of a line in characters, 0 to keep every measure on one line. A measure
+    /// longer than the limit gets a line of its own.
+    pub width: usize,
+}
+
+impl Default for TabOptions {
+    fn default() -> Self {
+        TabOptions { width: 80 }
+    }
+}
+
+/// Beats of every voice of a measure, with their tick from the measure start.
+fn measure_beats(track: &Track, m: usize) -> Vec<(i64, &Beat)> {
+    let mut beats = Vec::new();
+    if let Some(measure) = track.measures.get(m) {
+        for voice in &measure.voices {
+            let mut tick = 0;
+            for beat in &voice.beats {
+                if beat.status != BeatStatus::Empty {
+                    beats.push((tick, beat));
+                }
+                tick += i64::from(beat.duration.time());
+            }
+        }
+    }
+    beats
+}
+
+fn sounding(beat: &Beat) -> impl Iterator<Item = &Note> {
+    beat.notes.iter().filter(|n| n.kind != NoteType::Rest)
+}
+
+/// The fret of the next note on the same string, for each note of a track by
+/// (measure, tick, string). Hammer-ons, pull-offs and slides are written from it.
+fn next_frets(track: &Track, measure_count: usize) -> HashMap<(usize, i64, i8), i16> {
+    let mut notes = Vec::new();
+    for m in 0..measure_count {
+        for (tick, beat) in measure_beats(track, m) {
+            notes.extend(sounding(beat).map(|n| (m, tick, n.string, n.value)));
+        }
+    }
+    notes.sort();
+    let mut next = HashMap::new();
+    let mut following: HashMap<i8, i16> = HashMap::new();
+    for (m, tick, string, fret) in notes.into_iter().rev() {
+        if let Some(next_fret) = following.get(&string) {
+            next.insert((m, tick, string), *next_fret);
+        }
+        following.insert(string, fret);
+    }
+    next
+}
+
+/// How a note is written on its string line: fret and technique letters.
+fn note_text(note: &Note, next: Option<i16>, vibrato: bool) -> String {
+    let fret = note.value;
+    match note.kind {
+        NoteType::Dead => return String::from("x"),
+        NoteType::Tie => return String::new(),
+        _ => {}
+    }
+    let mut text = if note.effect.harmonic.is_some() {
+        format!("<{}>", fret)
+    } else {
+        fret.to_string()
+    };
+    if note.effect.ghost_note {
+        text = format!("({})", text);
+    }
+    let mut before = String::new();
+    if let Some(grace) = &note.effect.grace {
+        let grace_fret = i16::from(grace.fret);
+        let transition = match grace.transition {
+            GraceEffectTransition::Hammer if grace_fret > fret => "p",
+            GraceEffectTransition::Hammer => "h",
+            GraceEffectTransition::Slide if grace_fret > fret => "\\",
+            GraceEffectTransition::Slide => "/",
+            GraceEffectTransition::Bend => "b",
+            GraceEffectTransition::None => "",
+        };
+        if !transition.is_empty() {
+            before = format!("{}{}", grace_fret, transition);
+        }
+    }
+    for slide in &note.effect.slides {
+        match slide {
+            SlideType::IntoFromBelow => before.push('/'),
+            SlideType::IntoFromAbove => before.push('\\'),
+            _ => {}
+        }
+    }
+    text.insert_str(0, &before);
+
+    if let Some(bend) = &note.effect.bend {
+        // Bend values are in quarter tones
+        let highest = bend.points.iter().map(|p| p.value).max().unwrap_or(0);
+        if highest > 0 {
+            text.push_str(&format!("b{}", fret + i16::from((highest + 1) / 2)));
+            if bend.points.last().is_some_and(|p| p.value < highest) {
+                let last = bend.points.last().map_or(0, |p| p.value);
+                text.push_str(&format!("r{}", fret + i16::from((last + 1) / 2)));
+            }
+        }
+    }
+    if note.effect.hammer {
+        text.push(if next.is_some_and(|n| n < fret) {
+            'p'
+        } else {
+            'h'
+        });
+    }
+    for slide in &note.effect.slides {
+        match slide {
+            SlideType::ShiftSlideTo | SlideType::LegatoSlideTo => {
+                text.push(if next.is_some_and(|n| n < fret) {
+                    '\\'
+                } else {
+                    '/'
+                })
+            }
+            SlideType::OutDownwards => text.push('\\'),
+            SlideType::OutUpWards => text.push('/'),
+            _ => {}
+        }
+    }
+    if note.effect.vibrato || vibrato {
+        text.push('~');
+    }
+    text
+}
+
+/// A measure laid out in columns, shared by every rendered track so they stay aligned.
+struct Block {
+    /// Palm mute line and string lines of each track, without bar lines
+    tracks: Vec<(String, Vec<String>)>,
+    /// Voltas and marker at the start, repeat count at the end
+    text: String,
+    repeat_open: bool,
+    /// Closing bar line: `Some(true)` for a repeat end, `Some(false)` for a double bar
+    close: Option<bool>,
+}
+
+/// Whether line `i` of `n` string lines carries the repeat dots.
+fn dot(i: usize, n: usize) -> &'static str {
+    let dotted = if n >= 3 { i == 1 || i + 2 == n } else { i == 0 };
+    if dotted {
+        "*"
+    } else {
+        "-"
+    }
+}
+
+struct Renderer<'a> {
+    song: &'a Song,
+    tracks: Vec<&'a Track>,
+    next: Vec<HashMap<(usize, i64, i8), i16>>,
+}
+
+impl Renderer<'_> {
+    fn block(&self, m: usize) -> Block {
+        let header = &self.song.measure_headers[m];
+        let beats: Vec<Vec<(i64, &Beat)>> =
+            self.tracks.iter().map(|t| measure_beats(t, m)).collect();
+        let columns: Vec<i64> = beats
+            .iter()
+            .flatten()
+            .map(|(tick, _)| *tick)
+            .collect::<BTreeSet<i64>>()
+            .into_iter()
+            .collect();
+
+        // Text of every note, by track, column and string line
+        let mut grids = Vec::new();
+        let mut palm_mutes = Vec::new();
+        for (t, track) in self.tracks.iter().enumerate() {
+            let lines = track.strings.len();
+            let mut grid = vec![vec![String::new(); lines]; columns.len()];
+            let mut palm_mute = vec![false; columns.len()];
+            for (tick, beat) in &beats[t] {
+                let column = columns.binary_search(tick).unwrap_or(0);
+                for note in sounding(beat) {
+                    let Some(line) = usize::try_from(note.string)
+                        .ok()
+                        .and_then(|s| s.checked_sub(1))
+                    else {
+                        continue;
+                    };
+                    if line >= lines || !grid[column][line].is_empty() {
+                        continue;
+                    }
+                    let next = self.next[t].get(&(m, *tick, note.string)).copied();
+                    grid[column][line] = note_text(note, next, beat.effect.vibrato);
+                    palm_mute[column] |= note.effect.palm_mute;
+                }
+            }
+            grids.push(grid);
+            palm_mutes.push(palm_mute);
+        }
+        let widths: Vec<usize> = (0..columns.len())
+            .map(|c| {
+                let widest = grids
+                    .iter()
+                    .flat_map(|grid| grid[c].iter().map(|text| text.chars().count()))
+                    .max()
+                    .unwrap_or(0);
+                widest.max(1) + 1
+            })
+            .collect();
+
+        let previous = m.checked_sub(1).map(|p| &self.song.measure_headers[p]);
+        let mut start = String::new();
+        if header.repeat_alternative != 0
+            && previous.is_none_or(|p| p.repeat_alternative != header.repeat_alternative)
+        {
+            for bit in 0..8 {
+                if header.repeat_alternative & (1 << bit) != 0 {
+                    start.push_str(&format!("{}.", bit + 1));
+                }
+            }
+        }
+        if let Some(marker) = &header.marker {
+            if !start.is_empty() {
+                start.push(' ');
+            }
+            start.push_str(&marker.title);
+        }
+        let end = if header.repeat_close > 1 {
+            format!("x{}", header.repeat_close + 1)
+        } else {
+            String::new()
+        };
+        let needed = start.chars().count() + end.len() + usize::from(!end.is_empty());
+        let content: usize = widths.iter().sum();
+        let width = content
+            .max(needed)
+            .max(if columns.is_empty() { 4 } else { 1 });
+        let extra = width - content;
+
+        let text = format!(
+            "{}{}{}",
+            start,
+            " ".repeat(width - start.chars().count() - end.len()),
+            end
+        );
+        let tracks = grids
+            .iter()
+            .zip(&palm_mutes)
+            .zip(&self.tracks)
+            .map(|((grid, palm_mute), track)| {
+                let mut pm_line = String::new();
+                for (c, w) in widths.iter().enumerate() {
+                    pm_line.push_str(&match (palm_mute[c], c > 0 && palm_mute[c - 1]) {
+                        (true, true) => "-".repeat(*w),
+                        (true, false) => format!("PM{}", "-".repeat(w.saturating_sub(2))),
+                        _ => " ".repeat(*w),
+                    });
+                }
+                pm_line.push_str(&" ".repeat(extra));
+                let lines = (0..track.strings.len())
+                    .map(|line| {
+                        let mut text = String::new();
+                        for (c, w) in widths.iter().enumerate() {
+                            let note = &grid[c][line];
+                            text.push_str(note);
+                            text.push_str(&"-".repeat(w - note.chars().count()));
+                        }
+                        text.push_str(&"-".repeat(extra));
+                        text
+                    })
+                    .collect();
+                (pm_line, lines)
+            })
+            .collect();
+        Block {
+            tracks,
+            text,
+            repeat_open: header.repeat_open,
+            close: if header.repeat_close >= 0 {
+                Some(true)
+            } else if header.double_bar {
+                Some(false)
+            } else {
+                None
+            },
+        }
+    }
+}
+
+/// Bar lines of a block on line `i` of `n`, given whether the previous block of the line
+/// closed with its own bar line.
+fn bars(block: &Block, previous_closed: bool, i: usize, n: usize) -> (String, String) {
+    let left = match (block.repeat_open, previous_closed) {
+        (true, true) => dot(i, n).to_string(),
+        (true, false) => format!("||{}", dot(i, n)),
+        (false, true) => String::new(),
+        (false, false) => String::from("|"),
+    };
+    let right = match block.close {
+        Some(true) => format!("{}||", dot(i, n)),
+        Some(false) => String::from("||"),
+        None => String::new(),
+    };
+    (left, right)
+}
+
+/// Render tracks of a song as ASCII tablature, one block of lines per track, measures
+/// aligned across the tracks and wrapped at `options.width`.
+pub fn render_tab(song: &Song, tracks: &[usize], options: &TabOptions) -> String {
+    let renderer = Renderer {
+        song,
+        tracks: tracks.iter().filter_map(|t| song.tracks.get(*t)).collect(),
+        next: tracks
+            .iter()
+            .filter_map(|t| song.tracks.get(*t))
+            .map(|t| next_frets(t, song.measure_headers.len()))
+            .collect(),
+    };
+    if renderer.tracks.is_empty() {
+        return String::new();
+    }
+    let blocks: Vec<Block> = (0..song.measure_headers.len())
+        .map(|m| renderer.block(m))
+        .collect();
+    let labels: Vec<Vec<String>> = renderer.tracks.iter().map(|t| string_labels(t)).collect();
+    let label_width = labels
+        .iter()
+        .flatten()
+        .map(|l| l.chars().count())
+        .max()
+        .unwrap_or(0);
+
+    // Measures of each system, wrapped at the width
+    let mut systems: Vec<Vec<&Block>> = Vec::new();
+    let mut line_length = 0;
+    for block in &blocks {
+        let previous_closed = systems
+            .last()
+            .and_then(|s| s.last())
+            .is_some_and(|b| b.close.is_some());
+        let (left, right) = bars(block, previous_closed, 0, 1);
+        let length = left.len() + block.text.chars().count() + right.len();
+        // The closing bar line of a system takes one more character
+        let wrap = options.width > 0 && line_length + length + 1 > options.width;
+        if systems.last().is_none_or(|s| s.is_empty()) || wrap {
+            let (left, right) = bars(block, false, 0, 1);
+            line_length = label_width + left.len() + block.text.chars().count() + right.len();
+            systems.push(vec![block]);
+        } else {
+            line_length += length;
+            if let Some(system) = systems.last_mut() {
+                system.push(block);
+            }
+        }
+    }
+
+    let mut out = String::new();
+    for system in &systems {
+        for (t, track) in renderer.tracks.iter().enumerate() {
+            let n = labels[t].len();
+            let mut text = " ".repeat(label_width);
+            let mut pm = " ".repeat(label_width);
+            let mut strings: Vec<String> = labels[t]
+                .iter()
+                .map(|l| format!("{:<width$}", l, width = label_width))
+                .collect();
+            for (k, block) in system.iter().enumerate() {
+                let previous_closed = k > 0 && system[k - 1].close.is_some();
+                let (left, right) = bars(block, previous_closed, 0, n.max(1));
+                let blank_left = " ".repeat(left.len());
+                let blank_right = " ".repeat(right.len());
+                text.push_str(&format!("{}{}{}", blank_left, block.text, blank_right));
+                pm.push_str(&format!(
+                    "{}{}{}",
+                    blank_left, block.tracks[t].0, blank_right
+                ));
+                for (i, line) in strings.iter_mut().enumerate() {
+                    let (left, right) = bars(block, previous_closed, i, n);
+                    line.push_str(&left);
+                    line.push_str(&block.tracks[t].1[i]);
+                    line.push_str(&right);
+                }
+            }
+            if system.last().is_some_and(|b| b.close.is_none()) {
+                for line in strings.iter_mut() {
+                    line.push('|');
+                }
+            }
+            if !out.is_empty() {
+                out.push('\n');
+            }
+            if renderer.tracks.len() > 1 {
+                out.push_str(&track.name);
+                out.push('\n');
+            }
+            // Voltas, markers and repeat counts are written above the first track only
+            let annotations = if t == 0 { vec![text, pm] } else { vec![pm] };
+            for line in annotations {
+                if !line.trim().is_empty() {
+                    out.push_str(line.trim_end());
+                    out.push('\n');
+                }
+            }
+            for line in strings {
+                out.push_str(&line);
+                out.push('\n');
+            }
+        }
+    }
+    out
+}
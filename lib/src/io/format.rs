@@ -0,0 +1,121 @@
+// File format detection
+use std::fmt;
+use std::io::Cursor;
+
+use zip::ZipArchive;
+
+use crate::io::ascii_tab::parse::contains_tab;
+use crate::io::primitive::{read_version_string, VERSIONS};
+
+/// Formats `Song::from_bytes` can read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FileFormat {
+    /// Guitar Pro 3 (.gp3)
+    Gp3,
+    /// Guitar Pro 4 (.gp4)
+    Gp4,
+    /// Guitar Pro 5 (.gp5)
+    Gp5,
+    /// Guitar Pro 6 BCFZ/BCFS container (.gpx)
+    Gpx,
+    /// Guitar Pro 7+ ZIP archive (.gp)
+    Gp,
+    /// Standard MIDI File (.mid)
+    Midi,
+    /// MusicXML score, plain (.musicxml) or compressed (.mxl)
+    MusicXml,
+    /// Compressed MuseScore score (.mscz)
+    Mscz,
+    /// Uncompressed MuseScore score (.mscx)
+    Mscx,
+    /// Plain-text ASCII tablature
+    AsciiTab,
+}
+
+impl fmt::Display for FileFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            FileFormat::Gp3 => "Guitar Pro 3",
+            FileFormat::Gp4 => "Guitar Pro 4",
+            FileFormat::Gp5 => "Guitar Pro 5",
+            FileFormat::Gpx => "Guitar Pro 6",
+            FileFormat::Gp => "Guitar Pro 7",
+            FileFormat::Midi => "MIDI",
+            FileFormat::MusicXml => "MusicXML",
+            FileFormat::Mscz => "MuseScore",
+            FileFormat::Mscx => "MuseScore (uncompressed)",
+            FileFormat::AsciiTab => "ASCII tab",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Major version of a GP3-5 file from its version string: "FICHIER GUITAR PRO v5.10",
+/// "CLIPBOARD GP 5.1"...
+fn guitar_pro_version(data: &[u8]) -> Option<u8> {
+    let mut seek = 0;
+    let version = read_version_string(data, &mut seek).ok()?;
+    if let Some(known) = VERSIONS.iter().find(|v| v.2 == version.data) {
+        return Some(known.0 .0);
+    }
+    let number = version
+        .data
+        .strip_prefix("FICHIER GUITAR PRO v")
+        .or_else(|| version.data.strip_prefix("CLIPBOARD GUITAR PRO "))
+        .or_else(|| version.data.strip_prefix("CLIPBOARD GP "))?;
+    number.chars().next()?.to_digit(10).map(|d| d as u8)
+}
+
+/// Find the format of a file from its content: the version string of GP3-5 files, the
+/// BCFZ/BCFS magic of GPX files, the entries of ZIP archives (`Content/score.gpif` for GP7,
+/// a `.mscx` score for MuseScore, MusicXML otherwise), the MIDI header, the root element of
+/// XML scores and the string lines of text tabs.
+pub fn detect_format(data: &[u8]) -> Option<FileFormat> {
+    if let Some(major) = guitar_pro_version(data) {
+        return match major {
+            3 => Some(FileFormat::Gp3),
+            4 => Some(FileFormat::Gp4),
+            5 => Some(FileFormat::Gp5),
+            _ => None,
+        };
+    }
+    if data.starts_with(b"BCFZ") || data.starts_with(b"BCFS") {
+        return Some(FileFormat::Gpx);
+    }
+    if data.starts_with(b"MThd") {
+        return Some(FileFormat::Midi);
+    }
+    if data.starts_with(b"PK\x03\x04") {
+        let zip = ZipArchive::new(Cursor::new(data)).ok()?;
+        let names: Vec<&str> = zip.file_names().collect();
+        return if names.contains(&"Content/score.gpif") {
+            Some(FileFormat::Gp)
+        } else if names.iter().any(|n| n.ends_with(".mscx")) {
+            Some(FileFormat::Mscz)
+        } else if names.contains(&"META-INF/container.xml")
+            || names
+                .iter()
+                .any(|n| n.ends_with(".xml") || n.ends_with(".musicxml"))
+        {
+            Some(FileFormat::MusicXml)
+        } else {
+            None
+        };
+    }
+
+    let text = String::from_utf8_lossy(data);
+    let text = text.trim_start_matches('\u{feff}').trim_start();
+    if text.starts_with('<') {
+        return if text.contains("<museScore") {
+            Some(FileFormat::Mscx)
+        } else if text.contains("<score-partwise") {
+            Some(FileFormat::MusicXml)
+        } else {
+            None
+        };
+    }
+    if contains_tab(text) {
+        return Some(FileFormat::AsciiTab);
+    }
+    None
+}
@@ -93,7 +93,7 @@ pub fn read_beat_effects_v4(song: &Song, data: &[u8], seek: &mut usize) -> GpRes
     be.vibrato = (flags1 & 0x02) == 0x02 || be.vibrato;
     be.fade_in = (flags1 & 0x10) == 0x10;
     if (flags1 & 0x20) == 0x20 {
-        be.slap_effect = get_slap_effect(read_signed_byte(data, seek)?.to_u8().unwrap())?;
+        be.slap_effect = get_slap_effect(convert(read_signed_byte(data, seek)?, "slap effect")?)?;
     }
     if (flags2 & 0x04) == 0x04 {
         be.tremolo_bar = song.read_bend_effect(data, seek)?;
@@ -118,11 +118,11 @@ pub fn read_beat_stroke(song: &Song, data: &[u8], seek: &mut usize) -> GpResult<
     let up = read_signed_byte(data, seek)?;
     if up > 0 {
         bs.direction = BeatStrokeDirection::Up;
-        bs.value = stroke_value(up).to_u16().unwrap();
+        bs.value = u16::from(stroke_value(up));
     }
     if down > 0 {
         bs.direction = BeatStrokeDirection::Down;
-        bs.value = stroke_value(down).to_u16().unwrap();
+        bs.value = u16::from(stroke_value(down));
     }
     if song.version.number >= (5, 0, 0) {
         bs.swap_direction();
@@ -150,7 +150,7 @@ pub fn read_tremolo_bar(data: &[u8], seek: &mut usize) -> GpResult<BendEffect> {
         kind: BendType::Dip,
         ..Default::default()
     };
-    be.value = read_int(data, seek)?.to_i16().unwrap();
+    be.value = convert(read_int(data, seek)?, "tremolo bar value")?;
     be.points.push(BendPoint {
         position: 0,
         value: 0,
@@ -158,10 +158,10 @@ pub fn read_tremolo_bar(data: &[u8], seek: &mut usize) -> GpResult<BendEffect> {
     });
     be.points.push(BendPoint {
         position: BEND_EFFECT_MAX_POSITION / 2,
-        value: (-f32::from(be.value) / GP_BEND_SEMITONE)
-            .round()
-            .to_i8()
-            .unwrap(),
+        value: convert(
+            (-f32::from(be.value) / GP_BEND_SEMITONE).round() as i32,
+            "tremolo bar value",
+        )?,
         ..Default::default()
     });
     be.points.push(BendPoint {
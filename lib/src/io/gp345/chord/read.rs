@@ -1,6 +1,4 @@
-use fraction::ToPrimitive;
-
-use crate::error::GpResult;
+use crate::error::{GpError, GpResult};
 use crate::io::primitive::*;
 use crate::model::song::Song;
 use crate::types::chord::{Barre, Chord, PitchClass};
@@ -13,9 +11,6 @@ pub fn read_chord(song: &Song, data: &[u8], seek: &mut usize, string_count: u8)
         strings: vec![-1; string_count.into()],
         ..Default::default()
     };
-    for _ in 0..string_count {
-        c.strings.push(-1);
-    }
     c.new_format = Some(read_bool(data, seek)?);
     if c.new_format == Some(true) {
         if song.version.number.0 == 3 {
@@ -43,14 +38,28 @@ pub fn read_old_format_chord(
     if chord.first_fret.is_some() {
         for i in 0u8..6u8 {
             let fret = read_int(data, seek)? as i8;
-            if i < chord.strings.len().to_u8().unwrap() {
-                chord.strings.push(fret);
+            if let Some(string) = chord.strings.get_mut(usize::from(i)) {
+                *string = fret;
             }
         }
     }
     Ok(())
 }
 
+/// Number of barres of a chord, at most the number of barres stored.
+fn stored_barres<V: Copy + Into<i64>>(count: V, max: usize) -> GpResult<usize> {
+    let count = count.into();
+    if count < 0 || count > max as i64 {
+        return Err(GpError::InvalidRange {
+            context: "barre count",
+            value: count,
+            min: 0,
+            max: max as i64,
+        });
+    }
+    Ok(count as usize)
+}
+
 pub fn read_new_format_chord_v3(
     _song: &Song,
     data: &[u8],
@@ -60,40 +69,50 @@ pub fn read_new_format_chord_v3(
     chord.sharp = Some(read_bool(data, seek)?);
     *seek += 3;
     chord.root = Some(PitchClass::from(
-        read_int(data, seek)?.to_i8().unwrap(),
+        convert(read_int(data, seek)?, "chord root")?,
         None,
         chord.sharp,
     ));
-    chord.kind = Some(get_chord_type(read_int(data, seek)?.to_u8().unwrap()));
-    chord.extension = Some(get_chord_extension(read_int(data, seek)?.to_u8().unwrap()));
+    chord.kind = Some(get_chord_type(convert(
+        read_int(data, seek)?,
+        "chord type",
+    )?));
+    chord.extension = Some(get_chord_extension(convert(
+        read_int(data, seek)?,
+        "chord extension",
+    )?));
     chord.bass = Some(PitchClass::from(
-        read_int(data, seek)?.to_i8().unwrap(),
+        convert(read_int(data, seek)?, "chord bass")?,
         None,
         chord.sharp,
     ));
-    chord.tonality = Some(get_chord_alteration(
-        read_int(data, seek)?.to_u8().unwrap(),
-    )?);
+    chord.tonality = Some(get_chord_alteration(convert(
+        read_int(data, seek)?,
+        "chord tonality",
+    )?)?);
     chord.add = Some(read_bool(data, seek)?);
     chord.name = read_byte_size_string(data, seek, 22)?;
-    chord.fifth = Some(get_chord_alteration(
-        read_int(data, seek)?.to_u8().unwrap(),
-    )?);
-    chord.ninth = Some(get_chord_alteration(
-        read_int(data, seek)?.to_u8().unwrap(),
-    )?);
-    chord.eleventh = Some(get_chord_alteration(
-        read_int(data, seek)?.to_u8().unwrap(),
-    )?);
-    chord.first_fret = Some(read_int(data, seek)?.to_u8().unwrap());
+    chord.fifth = Some(get_chord_alteration(convert(
+        read_int(data, seek)?,
+        "chord fifth",
+    )?)?);
+    chord.ninth = Some(get_chord_alteration(convert(
+        read_int(data, seek)?,
+        "chord ninth",
+    )?)?);
+    chord.eleventh = Some(get_chord_alteration(convert(
+        read_int(data, seek)?,
+        "chord eleventh",
+    )?)?);
+    chord.first_fret = Some(convert(read_int(data, seek)?, "chord first fret")?);
     for i in 0u8..6u8 {
-        let fret = read_int(data, seek)?.to_i8().unwrap();
-        if i < chord.strings.len().to_u8().unwrap() {
-            chord.strings.push(fret);
+        let fret = convert(read_int(data, seek)?, "chord fret")?;
+        if let Some(string) = chord.strings.get_mut(usize::from(i)) {
+            *string = fret;
         }
     }
     //barre
-    let barre_count = read_int(data, seek)?.to_usize().unwrap();
+    let barre_count = read_int(data, seek)?;
     let mut barre_frets: Vec<i32> = Vec::with_capacity(2);
     let mut barre_starts: Vec<i32> = Vec::with_capacity(2);
     let mut barre_ends: Vec<i32> = Vec::with_capacity(2);
@@ -106,11 +125,11 @@ pub fn read_new_format_chord_v3(
     for _ in 0u8..2u8 {
         barre_ends.push(read_int(data, seek)?);
     }
-    for i in 0..barre_count {
+    for i in 0..stored_barres(barre_count, barre_frets.len())? {
         chord.barres.push(Barre {
-            fret: barre_frets[i].to_i8().unwrap(),
-            start: barre_starts[i].to_i8().unwrap(),
-            end: barre_ends[i].to_i8().unwrap(),
+            fret: convert(barre_frets[i], "barre fret")?,
+            start: convert(barre_starts[i], "barre start")?,
+            end: convert(barre_ends[i], "barre end")?,
         });
     }
 
@@ -130,31 +149,36 @@ pub fn read_new_format_chord_v4(
     chord.sharp = Some(read_bool(data, seek)?);
     *seek += 3;
     chord.root = Some(PitchClass::from(
-        read_byte(data, seek)?.to_i8().unwrap(),
+        convert(read_byte(data, seek)?, "chord root")?,
         None,
         chord.sharp,
     ));
     chord.kind = Some(get_chord_type(read_byte(data, seek)?));
     chord.extension = Some(get_chord_extension(read_byte(data, seek)?));
     let i = read_int(data, seek)?;
-    chord.bass = Some(PitchClass::from(i.to_i8().unwrap(), None, chord.sharp));
-    chord.tonality = Some(get_chord_alteration(
-        read_int(data, seek)?.to_u8().unwrap(),
-    )?);
+    chord.bass = Some(PitchClass::from(
+        convert(i, "chord bass")?,
+        None,
+        chord.sharp,
+    ));
+    chord.tonality = Some(get_chord_alteration(convert(
+        read_int(data, seek)?,
+        "chord tonality",
+    )?)?);
     chord.add = Some(read_bool(data, seek)?);
     chord.name = read_byte_size_string(data, seek, 22)?;
     chord.fifth = Some(get_chord_alteration(read_byte(data, seek)?)?);
     chord.ninth = Some(get_chord_alteration(read_byte(data, seek)?)?);
     chord.eleventh = Some(get_chord_alteration(read_byte(data, seek)?)?);
-    chord.first_fret = Some(read_int(data, seek)?.to_u8().unwrap());
+    chord.first_fret = Some(convert(read_int(data, seek)?, "chord first fret")?);
     for i in 0u8..7u8 {
-        let fret = read_int(data, seek)?.to_i8().unwrap();
-        if i < chord.strings.len().to_u8().unwrap() {
-            chord.strings.push(fret);
+        let fret = convert(read_int(data, seek)?, "chord fret")?;
+        if let Some(string) = chord.strings.get_mut(usize::from(i)) {
+            *string = fret;
         }
     }
     //barre
-    let barre_count = read_byte(data, seek)?.to_usize().unwrap();
+    let barre_count = read_byte(data, seek)?;
     let mut barre_frets: Vec<u8> = Vec::with_capacity(5);
     let mut barre_starts: Vec<u8> = Vec::with_capacity(5);
     let mut barre_ends: Vec<u8> = Vec::with_capacity(5);
@@ -167,11 +191,11 @@ pub fn read_new_format_chord_v4(
     for _ in 0u8..5u8 {
         barre_ends.push(read_byte(data, seek)?);
     }
-    for i in 0..barre_count {
+    for i in 0..stored_barres(barre_count, barre_frets.len())? {
         chord.barres.push(Barre {
-            fret: barre_frets[i].to_i8().unwrap(),
-            start: barre_starts[i].to_i8().unwrap(),
-            end: barre_ends[i].to_i8().unwrap(),
+            fret: convert(barre_frets[i], "barre fret")?,
+            start: convert(barre_starts[i], "barre start")?,
+            end: convert(barre_ends[i], "barre end")?,
         });
     }
     for _ in 0u8..7u8 {
@@ -76,8 +76,8 @@ impl SongEffectOps for Song {
             fret: read_signed_byte(data, seek)?,
             ..Default::default()
         };
-        g.velocity = unpack_velocity(read_byte(data, seek)?.to_i16().unwrap());
-        g.duration = 1 << (7 - read_byte(data, seek)?);
+        g.velocity = unpack_velocity(i16::from(read_byte(data, seek)?));
+        g.duration = grace_duration(read_byte(data, seek)?)?;
         //g.duration = 1 << (7 - read_byte(data, seek));
         g.is_dead = g.fret == -1;
         g.transition = get_grace_effect_transition(read_signed_byte(data, seek)?)?;
@@ -97,12 +97,15 @@ impl SongEffectOps for Song {
     ///   - *0x02*: grace note is on beat
     fn read_grace_effect_v5(&self, data: &[u8], seek: &mut usize) -> GpResult<GraceEffect> {
         let mut g = GraceEffect {
-            fret: read_byte(data, seek)?.to_i8().unwrap(),
+            fret: convert(read_byte(data, seek)?, "grace note fret")?,
             ..Default::default()
         };
-        g.velocity = unpack_velocity(read_byte(data, seek)?.to_i16().unwrap());
-        g.transition = get_grace_effect_transition(read_byte(data, seek)?.to_i8().unwrap())?;
-        g.duration = 1 << (7 - read_byte(data, seek)?);
+        g.velocity = unpack_velocity(i16::from(read_byte(data, seek)?));
+        g.transition = get_grace_effect_transition(convert(
+            read_byte(data, seek)?,
+            "grace note transition",
+        )?)?;
+        g.duration = grace_duration(read_byte(data, seek)?)?;
         let flags = read_byte(data, seek)?;
         g.is_dead = (flags & 0x01) == 0x01;
         g.is_on_beat = (flags & 0x02) == 0x02;
@@ -116,9 +119,7 @@ impl SongEffectOps for Song {
         seek: &mut usize,
     ) -> GpResult<TremoloPickingEffect> {
         let mut tp = TremoloPickingEffect::default();
-        tp.duration.value = from_tremolo_value(read_signed_byte(data, seek)?)?
-            .to_u16()
-            .unwrap();
+        tp.duration.value = u16::from(from_tremolo_value(read_signed_byte(data, seek)?)?);
         Ok(tp)
     }
 
@@ -239,14 +240,22 @@ impl SongEffectOps for Song {
                 // b = -1, # = 1
                 // loco = 0, 8va = 1, 15ma = 2
                 he.kind = HarmonicType::Artificial;
-                let semitone = read_byte(data, seek)?.to_i8().unwrap();
+                let semitone = convert(read_byte(data, seek)?, "harmonic semitone")?;
                 let accidental = read_signed_byte(data, seek)?;
+                if !(-2..=2).contains(&accidental) {
+                    return Err(crate::error::GpError::InvalidRange {
+                        context: "harmonic accidental",
+                        value: i64::from(accidental),
+                        min: -2,
+                        max: 2,
+                    });
+                }
                 he.pitch = Some(PitchClass::from(semitone, Some(accidental), None));
                 he.octave = Some(get_octave(read_byte(data, seek)?)?);
             }
             3 => {
                 he.kind = HarmonicType::Tapped;
-                he.fret = Some(read_byte(data, seek)?.to_i8().unwrap());
+                he.fret = Some(convert(read_byte(data, seek)?, "harmonic fret")?);
             }
             4 => he.kind = HarmonicType::Pinch,
             5 => he.kind = HarmonicType::Semi,
@@ -397,3 +406,15 @@ impl SongEffectOps for Song {
         write_byte(data, st);
     }
 }
+
+/// Grace note duration from its stored value: `1 << (7 - value)`.
+fn grace_duration(value: u8) -> GpResult<u8> {
+    7u8.checked_sub(value)
+        .map(|shift| 1 << shift)
+        .ok_or(crate::error::GpError::InvalidRange {
+            context: "grace note duration",
+            value: i64::from(value),
+            min: 0,
+            max: 7,
+        })
+}
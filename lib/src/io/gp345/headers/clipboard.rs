@@ -25,7 +25,6 @@ pub fn read_clipboard(
         c.stop_beat = read_int(data, seek)?;
         c.sub_bar_copy = read_int(data, seek)? != 0;
     }
-    println!("read_clipboard(): {:?}", c);
     Ok(Some(c))
 }
 
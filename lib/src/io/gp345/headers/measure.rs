@@ -1,7 +1,7 @@
 use fraction::ToPrimitive;
 use std::collections::HashMap;
 
-use crate::error::GpResult;
+use crate::error::{GpError, GpResult, ScorePart};
 use crate::io::primitive::*;
 use crate::model::song::Song;
 use crate::traits::header_ops::read_marker;
@@ -17,7 +17,8 @@ pub fn read_measure_headers(
     //println!("read_measure_headers()");
     let mut previous: Option<MeasureHeader> = None;
     for i in 1..measure_count + 1 {
-        let r: (MeasureHeader, u8) = read_measure_header(song, data, seek, i, previous)?;
+        let r: (MeasureHeader, u8) = read_measure_header(song, data, seek, i, previous)
+            .map_err(|e| e.within(ScorePart::MeasureHeader(i - 1), *seek))?;
         previous = Some(r.0.clone());
         song.measure_headers.push(r.0); //TODO: use add_measure_header
     }
@@ -34,18 +35,24 @@ pub fn read_measure_headers_v5(
     //println!("read_measure_headers_v5()");
     let mut previous: Option<MeasureHeader> = None;
     for i in 1..measure_count + 1 {
-        let r: (MeasureHeader, u8) = read_measure_header_v5(song, data, seek, i, previous)?;
+        let r: (MeasureHeader, u8) = read_measure_header_v5(song, data, seek, i, previous)
+            .map_err(|e| e.within(ScorePart::MeasureHeader(i - 1), *seek))?;
         previous = Some(r.0.clone());
         song.measure_headers.push(r.0); //TODO: use add_measure_header
     }
-    for s in &directions.0 {
+    for s in directions.0.iter().chain(&directions.1) {
         if s.1 > &-1 {
-            song.measure_headers[s.1.to_usize().unwrap() - 1].direction = Some(s.0.clone());
-        }
-    }
-    for s in &directions.1 {
-        if s.1 > &-1 {
-            song.measure_headers[s.1.to_usize().unwrap() - 1].direction = Some(s.0.clone());
+            let count = song.measure_headers.len();
+            let header = usize::try_from(*s.1 - 1)
+                .ok()
+                .and_then(|h| song.measure_headers.get_mut(h))
+                .ok_or(GpError::InvalidRange {
+                    context: "direction measure",
+                    value: i64::from(*s.1),
+                    min: 1,
+                    max: count as i64,
+                })?;
+            header.direction = Some(s.0.clone());
         }
     }
     Ok(())
@@ -61,7 +68,12 @@ pub fn read_measure_header(
     let flag = read_byte(data, seek)?;
     //println!("read_measure_header(), flags: {} \t N: {} \t Measure header count: {}", flag, number, song.measure_headers.len());
     let mut mh = MeasureHeader {
-        number: number.to_u16().unwrap(),
+        number: u16::try_from(number).map_err(|_| GpError::InvalidRange {
+            context: "measure count",
+            value: number as i64,
+            min: 1,
+            max: i64::from(u16::MAX),
+        })?,
         ..Default::default()
     };
     mh.start = 0;
@@ -75,7 +87,8 @@ pub fn read_measure_header(
     }
     //Denominator of the (key) signature
     if (flag & 0x02) == 0x02 {
-        mh.time_signature.denominator.value = read_signed_byte(data, seek)?.to_u16().unwrap();
+        mh.time_signature.denominator.value =
+            convert(read_signed_byte(data, seek)?, "time signature denominator")?;
     } else if number > 1 {
         mh.time_signature.denominator = previous.clone().unwrap().time_signature.denominator;
     }
@@ -126,30 +139,41 @@ pub fn read_measure_header_v5(
         for i in 0..4 {
             mh.time_signature.beams[i] = read_byte(data, seek)?;
         }
-    } else {
-        mh.time_signature.beams = previous.unwrap().time_signature.beams;
+    } else if let Some(previous) = previous {
+        mh.time_signature.beams = previous.time_signature.beams;
     };
     if (flags & 0x10) == 0 {
         *seek += 1;
     } //always 0
-    mh.triplet_feel = get_triplet_feel(read_byte(data, seek)?.to_i8().unwrap())?;
+    mh.triplet_feel = get_triplet_feel(convert(read_byte(data, seek)?, "triplet feel")?)?;
     //println!("################################### {:?}", mh.triplet_feel);
     Ok((mh, flags))
 }
 
 pub fn read_repeat_alternative(song: &mut Song, data: &[u8], seek: &mut usize) -> GpResult<u8> {
     //println!("read_repeat_alternative()");
-    let value = read_byte(data, seek)?.to_u16().unwrap();
+    let value = read_byte(data, seek)?;
+    if value > 8 {
+        return Err(GpError::InvalidRange {
+            context: "repeat alternative",
+            value: i64::from(value),
+            min: 0,
+            max: 8,
+        });
+    }
     let mut existing_alternative = 0u16;
     for i in (0..song.measure_headers.len()).rev() {
         if song.measure_headers[i].repeat_open {
             break;
         }
-        existing_alternative |= song.measure_headers[i].repeat_alternative.to_u16().unwrap();
+        existing_alternative |= u16::from(song.measure_headers[i].repeat_alternative);
     }
     //println!("read_repeat_alternative(), value:  {}, existing_alternative: {}", value, existing_alternative);
     //println!("read_repeat_alternative(), return: {}", ((1 << value) - 1) ^ existing_alternative);
-    Ok((((1 << value) - 1) ^ existing_alternative).to_u8().unwrap())
+    convert(
+        ((1u16 << value) - 1) ^ existing_alternative,
+        "repeat alternative",
+    )
 }
 
 pub fn read_repeat_alternative_v5(_song: &mut Song, data: &[u8], seek: &mut usize) -> GpResult<u8> {
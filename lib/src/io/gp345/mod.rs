@@ -7,3 +7,4 @@ pub mod effects;
 pub mod headers;
 pub mod mix_table;
 pub mod note;
+pub mod recovery;
@@ -1,6 +1,6 @@
 use fraction::ToPrimitive;
 
-use crate::error::GpResult;
+use crate::error::{GpResult, ScorePart};
 use crate::io::primitive::*;
 use crate::model::key_signature::*;
 use crate::model::song::Song;
@@ -37,7 +37,7 @@ pub fn read_notes(
                     &mut note,
                     song.tracks[track_index].strings[i],
                     track_index,
-                )?;
+                )
             } else {
                 read_note_v5(
                     song,
@@ -46,8 +46,9 @@ pub fn read_notes(
                     &mut note,
                     song.tracks[track_index].strings[i],
                     track_index,
-                )?;
+                )
             }
+            .map_err(|e| e.within(ScorePart::Note(beat.notes.len()), *seek))?;
             beat.notes.push(note);
         }
         beat.duration = duration.clone();
@@ -0,0 +1,177 @@
+// Lenient reading of damaged Guitar Pro 3, 4 and 5 files: keep what was read before the failure
+use std::fmt;
+
+use crate::error::{GpError, GpResult};
+use crate::io::format::{detect_format, FileFormat};
+use crate::io::limits::ReadLimits;
+use crate::model::beat::Voice;
+use crate::model::measure::Measure;
+use crate::model::song::Song;
+
+/// Part of a Guitar Pro 3, 4 or 5 file, in the order of the file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) enum ReadPart {
+    #[default]
+    Version,
+    /// Score information, tempo, key, RSE master effect and page setup
+    Info,
+    Lyrics,
+    Channels,
+    MeasureHeaders,
+    Tracks,
+    Measures,
+}
+
+/// Where the reading of a file is: the part being read and the byte offset.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ReadProgress {
+    pub(crate) part: ReadPart,
+    pub(crate) seek: usize,
+}
+
+impl ReadProgress {
+    /// Locate an error of the reading at the offset reached, with the version of the file
+    /// once it is read.
+    pub(crate) fn locate(&self, error: GpError, version: (u8, u8, u8)) -> GpError {
+        match self.part {
+            ReadPart::Version => error,
+            _ => error.with_version(version, self.seek),
+        }
+    }
+}
+
+/// A problem met while reading a damaged file, after which the song was closed off.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReadWarning {
+    /// Byte offset in the file where the reading stopped
+    pub offset: usize,
+    /// Structure being read, e.g. `track 2 › measure 37 › voice 1 › beat 4`
+    pub structure: String,
+    pub message: String,
+}
+
+impl fmt::Display for ReadWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} at 0x{:X}: {}",
+            self.structure, self.offset, self.message
+        )
+    }
+}
+
+/// Structure being read at the failure: the location of the error, numbered from 1, or
+/// the part of the file.
+fn structure(error: &GpError, part: ReadPart) -> String {
+    let location: Vec<String> = error.location().iter().map(|p| p.to_string()).collect();
+    if !location.is_empty() {
+        return location.join(" \u{203a} ");
+    }
+    String::from(match part {
+        ReadPart::Version => "version",
+        ReadPart::Info => "score information",
+        ReadPart::Lyrics => "lyrics",
+        ReadPart::Channels => "MIDI channels",
+        ReadPart::MeasureHeaders => "measure headers",
+        ReadPart::Tracks => "tracks",
+        ReadPart::Measures => "measures",
+    })
+}
+
+/// Close off a song whose reading stopped: drop the beat being read, give each measure
+/// its voices and each track a measure per measure header, the measure headers past the
+/// last measure read being dropped. Returns the number of measure headers dropped.
+fn close(song: &mut Song) -> usize {
+    if let (Some(t), Some(v), Some(b)) = (
+        song.current_track,
+        song.current_voice_number,
+        song.current_beat_number,
+    ) {
+        if let Some(voice) = song.tracks[t]
+            .measures
+            .last_mut()
+            .and_then(|m| m.voices.get_mut(v - 1))
+        {
+            voice.beats.truncate(b - 1);
+        }
+    }
+    song.current_track = None;
+    song.current_measure_number = None;
+    song.current_voice_number = None;
+    song.current_beat_number = None;
+
+    let voices = if song.version.number < (5, 0, 0) {
+        1
+    } else {
+        2
+    };
+    let count = match song.tracks.iter().map(|t| t.measures.len()).max() {
+        Some(read) if read > 0 => read,
+        _ => song.measure_headers.len(),
+    };
+    let dropped = song.measure_headers.len().saturating_sub(count);
+    song.measure_headers.truncate(count);
+    for (t, track) in song.tracks.iter_mut().enumerate() {
+        for measure in &mut track.measures {
+            measure.voices.resize_with(voices, Voice::default);
+        }
+        for h in track.measures.len()..count {
+            track.measures.push(Measure {
+                track_index: t,
+                header_index: h,
+                voices: vec![Voice::default(); voices],
+                ..Default::default()
+            });
+        }
+    }
+    dropped
+}
+
+/// Read a Guitar Pro 3, 4 or 5 file, keeping what was read when the file is truncated or
+/// damaged: the reading stops at the first error, the beat being read is dropped, the
+/// measures are given their voices and the tracks an empty measure for each measure read
+/// in another track, the measure headers past them being dropped. Tracks, measure headers
+/// and MIDI channels are kept up to the one being read.
+///
+/// Returns the warnings, empty when the file was read without error: the error with its
+/// byte offset and the structure being read, then the measures dropped. Fails when the
+/// data is not a Guitar Pro 3, 4 or 5 file, or when it exceeds `limits`.
+pub fn read_lenient(
+    song: &mut Song,
+    data: &[u8],
+    limits: &ReadLimits,
+) -> GpResult<Vec<ReadWarning>> {
+    let mut progress = ReadProgress::default();
+    let read = match detect_format(data) {
+        Some(FileFormat::Gp3) => song.read_gp3_parts(data, limits, &mut progress),
+        Some(FileFormat::Gp4) => song.read_gp4_parts(data, limits, &mut progress),
+        Some(FileFormat::Gp5) => song.read_gp5_parts(data, limits, &mut progress),
+        _ => {
+            return Err(GpError::FormatError(String::from(
+                "Not a Guitar Pro 3, 4 or 5 file",
+            )))
+        }
+    };
+    let Err(error) = read else {
+        return Ok(Vec::new());
+    };
+    let error = progress.locate(error, song.version.number);
+    if matches!(error.cause(), GpError::LimitExceeded { .. }) {
+        return Err(error);
+    }
+    let mut warnings = vec![ReadWarning {
+        offset: progress.seek,
+        structure: structure(&error, progress.part),
+        message: error.cause().to_string(),
+    }];
+    let headers = song.measure_headers.len();
+    let dropped = close(song);
+    if dropped > 0 {
+        warnings.push(ReadWarning {
+            offset: progress.seek,
+            structure: String::from("measures"),
+            message: format!("measures {} to {} not read", headers - dropped + 1, headers),
+        });
+    }
+    Ok(warnings)
+}
@@ -0,0 +1,257 @@
+// GPIF beat conversion
+use super::bend::bend_to_whammy;
+use super::helpers::*;
+use super::note::convert_note;
+use super::song::GpifWriter;
+use crate::io::gpif::model::{
+    Beat, BeatPropertiesWrapper, BeatProperty, EnableTag, Property, RhythmRef, TieInfo,
+};
+use crate::model::beat::Beat as SongBeat;
+use crate::model::key_signature::Duration;
+use crate::model::note::{Note as SongNote, NoteEffect};
+use crate::types::enums::*;
+use std::collections::HashMap;
+
+/// Per-track state carried from one beat to the next while exporting.
+pub(crate) struct TrackState {
+    /// Last written (fret, GPIF note index) per string, used to resolve ties.
+    last_notes: HashMap<i8, (i16, usize)>,
+    /// Dynamic of the previous beat; GPIF dynamics persist until changed.
+    dynamic: &'static str,
+}
+
+impl Default for TrackState {
+    fn default() -> Self {
+        TrackState {
+            last_notes: HashMap::new(),
+            dynamic: "F",
+        }
+    }
+}
+
+/// Convert a song beat to one or more GPIF beats and return their IDs.
+///
+/// GP3-5 store grace notes as an effect on the main note, GPIF stores them in a
+/// dedicated beat preceding it: such grace notes produce an extra beat.
+pub(crate) fn convert_beat(
+    w: &mut GpifWriter,
+    state: &mut TrackState,
+    s_beat: &SongBeat,
+    strings: &[(i8, i8)],
+) -> Vec<i32> {
+    let mut ids = Vec::with_capacity(1);
+    let has_notes = s_beat.status == BeatStatus::Normal && !s_beat.notes.is_empty();
+
+    if has_notes && s_beat.is_grace_beat() {
+        let on_beat = s_beat.notes[0]
+            .effect
+            .grace
+            .as_ref()
+            .is_some_and(|g| g.is_on_beat);
+        ids.push(write_beat(
+            w,
+            state,
+            s_beat,
+            strings,
+            Some(grace_position(on_beat)),
+        ));
+        return ids;
+    }
+
+    if has_notes {
+        let graced: Vec<SongNote> = s_beat
+            .notes
+            .iter()
+            .filter_map(|n| n.effect.grace.as_ref().map(|g| (n, g)))
+            .map(|(n, g)| SongNote {
+                value: g.fret as i16,
+                velocity: g.velocity,
+                string: n.string,
+                kind: if g.is_dead {
+                    NoteType::Dead
+                } else {
+                    NoteType::Normal
+                },
+                effect: NoteEffect {
+                    hammer: g.transition == GraceEffectTransition::Hammer,
+                    slides: if g.transition == GraceEffectTransition::Slide {
+                        vec![SlideType::LegatoSlideTo]
+                    } else {
+                        Vec::new()
+                    },
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .collect();
+        if let Some(grace) = s_beat.notes.iter().find_map(|n| n.effect.grace.as_ref()) {
+            let grace_beat = SongBeat {
+                notes: graced,
+                duration: Duration {
+                    value: match grace.duration {
+                        32 | 64 => grace.duration as u16,
+                        _ => 16,
+                    },
+                    ..Default::default()
+                },
+                status: BeatStatus::Normal,
+                ..Default::default()
+            };
+            let position = grace_position(grace.is_on_beat);
+            ids.push(write_beat(w, state, &grace_beat, strings, Some(position)));
+        }
+    }
+
+    ids.push(write_beat(w, state, s_beat, strings, None));
+    ids
+}
+
+fn grace_position(on_beat: bool) -> &'static str {
+    if on_beat {
+        "OnBeat"
+    } else {
+        "BeforeBeat"
+    }
+}
+
+fn beat_property(name: &str) -> BeatProperty {
+    BeatProperty {
+        name: name.to_string(),
+        ..Default::default()
+    }
+}
+
+fn write_beat(
+    w: &mut GpifWriter,
+    state: &mut TrackState,
+    s_beat: &SongBeat,
+    strings: &[(i8, i8)],
+    grace: Option<&str>,
+) -> i32 {
+    let id = w.gpif.beats.beats.len() as i32;
+    let mut g_beat = Beat {
+        id,
+        rhythm: Some(RhythmRef {
+            r#ref: w.rhythm_id(&s_beat.duration),
+        }),
+        grace_notes: grace.map(str::to_string),
+        ..Default::default()
+    };
+
+    // Notes
+    if s_beat.status == BeatStatus::Normal && !s_beat.notes.is_empty() {
+        let dynamic = velocity_to_dynamic(s_beat.notes[0].velocity);
+        if dynamic != state.dynamic {
+            g_beat.dynamic = Some(dynamic.to_string());
+            state.dynamic = dynamic;
+        }
+
+        let mut note_ids = Vec::with_capacity(s_beat.notes.len());
+        for s_note in &s_beat.notes {
+            let note_index = w.gpif.notes.notes.len();
+            let previous = state.last_notes.get(&s_note.string).copied();
+            let fret = match (&s_note.kind, previous) {
+                (NoteType::Tie, Some((fret, _))) => fret,
+                _ => s_note.value,
+            };
+
+            let mut g_note = convert_note(s_note, note_index as i32, fret, strings);
+            if s_beat.effect.slap_effect == SlapEffect::Tapping {
+                g_note.properties.properties.push(Property {
+                    name: "Tapped".to_string(),
+                    enable: Some(EnableTag),
+                    ..Default::default()
+                });
+            }
+            if let (NoteType::Tie, Some((_, origin))) = (&s_note.kind, previous) {
+                w.gpif.notes.notes[origin]
+                    .tie
+                    .get_or_insert_with(|| TieInfo {
+                        origin: "true".to_string(),
+                        destination: "false".to_string(),
+                    })
+                    .origin = "true".to_string();
+            }
+
+            w.gpif.notes.notes.push(g_note);
+            state.last_notes.insert(s_note.string, (fret, note_index));
+            note_ids.push(note_index as i32);
+        }
+        g_beat.notes = Some(join_ids(&note_ids));
+    }
+
+    // Text
+    if !s_beat.text.is_empty() {
+        g_beat.free_text = Some(s_beat.text.clone());
+    }
+
+    // Fade in
+    if s_beat.effect.fade_in {
+        g_beat.fadding = Some("FadeIn".to_string());
+    }
+
+    // Wah effect
+    if let Some(wah) = s_beat
+        .effect
+        .mix_table_change
+        .as_ref()
+        .and_then(|mtc| mtc.wah.as_ref())
+    {
+        g_beat.wah = Some(if wah.value > 0 { "Open" } else { "Closed" }.to_string());
+    }
+
+    // Whammy bar
+    if let Some(tremolo_bar) = &s_beat.effect.tremolo_bar {
+        g_beat.whammy = Some(bend_to_whammy(tremolo_bar));
+    }
+
+    // Ottavia
+    g_beat.ottavia = ottavia_name(&s_beat.octave).map(str::to_string);
+
+    // Beat properties
+    let mut props: Vec<BeatProperty> = Vec::new();
+    if let Some(dir) = stroke_direction_name(&s_beat.effect.stroke.direction) {
+        props.push(BeatProperty {
+            direction: Some(dir.to_string()),
+            ..beat_property("Brush")
+        });
+    }
+    if let Some(dir) = stroke_direction_name(&s_beat.effect.pick_stroke) {
+        props.push(BeatProperty {
+            direction: Some(dir.to_string()),
+            ..beat_property("PickStroke")
+        });
+    }
+    if s_beat.effect.has_rasgueado {
+        props.push(beat_property("Rasgueado"));
+    }
+    match s_beat.effect.slap_effect {
+        SlapEffect::Slapping => props.push(BeatProperty {
+            enable: Some(EnableTag),
+            ..beat_property("Slapped")
+        }),
+        SlapEffect::Popping => props.push(BeatProperty {
+            enable: Some(EnableTag),
+            ..beat_property("Popped")
+        }),
+        _ => {}
+    }
+    if s_beat.effect.vibrato {
+        props.push(BeatProperty {
+            strength: Some("Slight".to_string()),
+            ..beat_property("VibratoWTremBar")
+        });
+    }
+    if s_beat.effect.tremolo_bar.is_some() {
+        props.push(BeatProperty {
+            enable: Some(EnableTag),
+            ..beat_property("WhammyBar")
+        });
+    }
+    if !props.is_empty() {
+        g_beat.properties = Some(BeatPropertiesWrapper { properties: props });
+    }
+
+    w.gpif.beats.beats.push(g_beat);
+    id
+}
@@ -0,0 +1,63 @@
+// GPIF bend effect writers
+use crate::io::gpif::model::{Property, WhammyInfo};
+use crate::model::effects::{BendEffect, BEND_EFFECT_MAX_POSITION, GP_BEND_SEMITONE};
+
+/// Reduce a bend effect to the GPIF origin/middle/destination curve.
+/// Values are in 1/100 semitone, offsets are percentages (0-100).
+///
+/// The middle point is the inner point that deviates most from the origin, so that
+/// bend-release curves with more than three points keep their peak.
+pub(crate) fn bend_to_whammy(bend: &BendEffect) -> WhammyInfo {
+    let value = |v: i8| v as f64 * GP_BEND_SEMITONE as f64;
+    let offset = |p: u8| (p as f64 / BEND_EFFECT_MAX_POSITION as f64 * 100.0).round();
+
+    let (first, last) = match (bend.points.first(), bend.points.last()) {
+        (Some(first), Some(last)) => (first, last),
+        _ => return WhammyInfo::default(),
+    };
+    let middle = bend
+        .points
+        .iter()
+        .skip(1)
+        .take(bend.points.len().saturating_sub(2))
+        .max_by_key(|p| (p.value as i16 - first.value as i16).abs());
+
+    WhammyInfo {
+        origin_value: Some(value(first.value)),
+        middle_value: middle.map(|p| value(p.value)),
+        destination_value: Some(value(last.value)),
+        origin_offset: Some(offset(first.position)),
+        middle_offset1: middle.map(|p| offset(p.position)),
+        middle_offset2: middle.map(|p| offset(p.position)),
+        destination_offset: Some(offset(last.position)),
+    }
+}
+
+/// Build the GPIF note bend properties (`Bended`, `BendOriginValue`, ...) from a bend effect.
+pub(crate) fn bend_properties(bend: &BendEffect) -> Vec<Property> {
+    let w = bend_to_whammy(bend);
+    let mut props = vec![Property {
+        name: "Bended".to_string(),
+        enable: Some(Default::default()),
+        ..Default::default()
+    }];
+    let floats = [
+        ("BendOriginValue", w.origin_value),
+        ("BendOriginOffset", w.origin_offset),
+        ("BendMiddleValue", w.middle_value),
+        ("BendMiddleOffset1", w.middle_offset1),
+        ("BendMiddleOffset2", w.middle_offset2),
+        ("BendDestinationValue", w.destination_value),
+        ("BendDestinationOffset", w.destination_offset),
+    ];
+    for (name, value) in floats {
+        if let Some(v) = value {
+            props.push(Property {
+                name: name.to_string(),
+                float: Some(v),
+                ..Default::default()
+            });
+        }
+    }
+    props
+}
@@ -0,0 +1,147 @@
+// GPIF export helper functions
+use crate::model::effects::{MIN_VELOCITY, VELOCITY_INCREMENT};
+use crate::model::headers::FermataType;
+use crate::types::enums::*;
+
+/// Convert Duration.value to a GPIF note value string.
+/// Falls back to "Quarter" for unknown values.
+pub(crate) fn duration_to_note_value(value: u16) -> &'static str {
+    match value {
+        1 => "Whole",
+        2 => "Half",
+        4 => "Quarter",
+        8 => "Eighth",
+        16 => "16th",
+        32 => "32nd",
+        64 => "64th",
+        128 => "128th",
+        _ => "Quarter",
+    }
+}
+
+/// Convert a MIDI velocity to the nearest GPIF dynamic string.
+pub(crate) fn velocity_to_dynamic(velocity: i16) -> &'static str {
+    const DYNAMICS: [&str; 8] = ["PPP", "PP", "P", "MP", "MF", "F", "FF", "FFF"];
+    let step = ((velocity - MIN_VELOCITY) as f32 / VELOCITY_INCREMENT as f32).round();
+    DYNAMICS[step.clamp(0.0, 7.0) as usize]
+}
+
+/// Join integer IDs into a space-separated string.
+pub(crate) fn join_ids(ids: &[i32]) -> String {
+    ids.iter()
+        .map(|id| id.to_string())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Build a slide flags bitmask from a list of `SlideType` values.
+/// Inverse of `parse_slide_flags` in the importer.
+pub(crate) fn slide_flags(slides: &[SlideType]) -> i32 {
+    slides.iter().fold(0, |flags, slide| {
+        flags
+            | match slide {
+                SlideType::ShiftSlideTo => 0x01,
+                SlideType::LegatoSlideTo => 0x02,
+                SlideType::OutDownwards => 0x04,
+                SlideType::OutUpWards => 0x08,
+                SlideType::IntoFromBelow => 0x10,
+                SlideType::IntoFromAbove => 0x20,
+                SlideType::None => 0,
+            }
+    })
+}
+
+/// Convert a `HarmonicType` to its GPIF name.
+pub(crate) fn harmonic_type_name(kind: &HarmonicType) -> &'static str {
+    match kind {
+        HarmonicType::Natural => "Natural",
+        HarmonicType::Artificial => "Artificial",
+        HarmonicType::Tapped => "Tap",
+        HarmonicType::Pinch => "Pinch",
+        HarmonicType::Semi => "Semi",
+    }
+}
+
+/// Convert a `DirectionSign` to its GPIF name.
+/// Returns `true` as second element when the sign is a jump target (`<Target>`),
+/// `false` when it is a jump instruction (`<Jump>`).
+pub(crate) fn direction_sign_name(sign: &DirectionSign) -> (&'static str, bool) {
+    match sign {
+        DirectionSign::Coda => ("Coda", true),
+        DirectionSign::DoubleCoda => ("DoubleCoda", true),
+        DirectionSign::Segno => ("Segno", true),
+        DirectionSign::SegnoSegno => ("SegnoSegno", true),
+        DirectionSign::Fine => ("Fine", true),
+        DirectionSign::DaCapo => ("DaCapo", false),
+        DirectionSign::DaCapoAlCoda => ("DaCapoAlCoda", false),
+        DirectionSign::DaCapoAlDoubleCoda => ("DaCapoAlDoubleCoda", false),
+        DirectionSign::DaCapoAlFine => ("DaCapoAlFine", false),
+        DirectionSign::DaSegno => ("DaSegno", false),
+        DirectionSign::DaSegnoAlCoda => ("DaSegnoAlCoda", false),
+        DirectionSign::DaSegnoAlDoubleCoda => ("DaSegnoAlDoubleCoda", false),
+        DirectionSign::DaSegnoAlFine => ("DaSegnoAlFine", false),
+        DirectionSign::DaSegnoSegno => ("DaSegnoSegno", false),
+        DirectionSign::DaSegnoSegnoAlCoda => ("DaSegnoSegnoAlCoda", false),
+        DirectionSign::DaSegnoSegnoAlDoubleCoda => ("DaSegnoSegnoAlDoubleCoda", false),
+        DirectionSign::DaSegnoSegnoAlFine => ("DaSegnoSegnoAlFine", false),
+        DirectionSign::DaCoda => ("DaCoda", false),
+        DirectionSign::DaDoubleCoda => ("DaDoubleCoda", false),
+    }
+}
+
+/// Convert a `MeasureClef` to its GPIF name.
+pub(crate) fn clef_name(clef: &MeasureClef) -> &'static str {
+    match clef {
+        MeasureClef::Treble => "G2",
+        MeasureClef::Bass => "F4",
+        MeasureClef::Tenor => "C4",
+        MeasureClef::Alto => "C3",
+    }
+}
+
+/// Convert a `Fingering` to its GPIF name, `None` when no finger is set.
+pub(crate) fn fingering_name(finger: &Fingering) -> Option<&'static str> {
+    match finger {
+        Fingering::Thumb => Some("P"),
+        Fingering::Index => Some("I"),
+        Fingering::Middle => Some("M"),
+        Fingering::Annular => Some("A"),
+        Fingering::Little => Some("C"),
+        Fingering::Open | Fingering::Unknown(_) => None,
+    }
+}
+
+/// Convert a `FermataType` to its GPIF name.
+pub(crate) fn fermata_type_name(kind: &FermataType) -> &'static str {
+    match kind {
+        FermataType::Short => "Short",
+        FermataType::Medium => "Medium",
+        FermataType::Long => "Long",
+    }
+}
+
+/// Convert an `Octave` to its GPIF ottavia name, `None` when no octave sign is set.
+pub(crate) fn ottavia_name(octave: &Octave) -> Option<&'static str> {
+    match octave {
+        Octave::Ottava => Some("8va"),
+        Octave::OttavaBassa => Some("8vb"),
+        Octave::Quindicesima => Some("15ma"),
+        Octave::QuindicesimaBassa => Some("15mb"),
+        Octave::None => None,
+    }
+}
+
+/// Convert a stroke direction to its GPIF name, `None` when no direction is set.
+pub(crate) fn stroke_direction_name(direction: &BeatStrokeDirection) -> Option<&'static str> {
+    match direction {
+        BeatStrokeDirection::Down => Some("Down"),
+        BeatStrokeDirection::Up => Some("Up"),
+        BeatStrokeDirection::None => None,
+    }
+}
+
+/// Convert a song string number (1 = highest string) to a GPIF string index (0 = lowest string).
+/// Inverse of `gpif_string_to_song` in the importer.
+pub(crate) fn song_string_to_gpif(string: i8, string_count: usize) -> i32 {
+    string_count as i32 - string as i32
+}
@@ -0,0 +1,7 @@
+// GPIF export modules
+
+pub mod beat;
+pub mod bend;
+pub mod helpers;
+pub mod note;
+pub mod song;
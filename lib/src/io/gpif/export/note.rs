@@ -0,0 +1,103 @@
+// GPIF note conversion
+use super::bend::bend_properties;
+use super::helpers::*;
+use crate::io::gpif::model::{EnableTag, Note, NoteProperties, Property, TieInfo};
+use crate::model::note::Note as SongNote;
+use crate::types::enums::*;
+
+fn enabled(name: &str) -> Property {
+    Property {
+        name: name.to_string(),
+        enable: Some(EnableTag),
+        ..Default::default()
+    }
+}
+
+/// Convert a song note to a GPIF note.
+///
+/// `fret` is passed separately because tied notes read from GP3-5 files carry no fret,
+/// the caller resolves it from the previous note on the same string.
+pub(crate) fn convert_note(s_note: &SongNote, id: i32, fret: i16, strings: &[(i8, i8)]) -> Note {
+    let mut props: Vec<Property> = Vec::new();
+
+    props.push(Property {
+        name: "String".to_string(),
+        string: Some(song_string_to_gpif(s_note.string, strings.len())),
+        ..Default::default()
+    });
+    props.push(Property {
+        name: "Fret".to_string(),
+        fret: Some(fret as i32),
+        ..Default::default()
+    });
+    if let Some(&(_, tuning)) = strings.iter().find(|s| s.0 == s_note.string) {
+        props.push(Property {
+            name: "Midi".to_string(),
+            number: Some(tuning as i32 + fret as i32),
+            ..Default::default()
+        });
+    }
+
+    if s_note.effect.palm_mute {
+        props.push(enabled("PalmMuted"));
+    }
+    if s_note.kind == NoteType::Dead {
+        props.push(enabled("Muted"));
+    }
+    if s_note.effect.hammer {
+        props.push(enabled("HopoOrigin"));
+    }
+    let flags = slide_flags(&s_note.effect.slides);
+    if flags != 0 {
+        props.push(Property {
+            name: "Slide".to_string(),
+            flags: Some(flags),
+            ..Default::default()
+        });
+    }
+    if let Some(harmonic) = &s_note.effect.harmonic {
+        props.push(Property {
+            name: "HarmonicType".to_string(),
+            htype: Some(harmonic_type_name(&harmonic.kind).to_string()),
+            ..Default::default()
+        });
+        if let Some(hfret) = harmonic.fret {
+            props.push(Property {
+                name: "HarmonicFret".to_string(),
+                hfret: Some(hfret as f64),
+                ..Default::default()
+            });
+        }
+    }
+    if let Some(bend) = &s_note.effect.bend {
+        props.extend(bend_properties(bend));
+    }
+
+    let mut accent = 0;
+    if s_note.effect.staccato {
+        accent |= 0x01;
+    }
+    if s_note.effect.heavy_accentuated_note {
+        accent |= 0x04;
+    }
+    if s_note.effect.accentuated_note {
+        accent |= 0x08;
+    }
+
+    Note {
+        id,
+        properties: NoteProperties { properties: props },
+        tie: (s_note.kind == NoteType::Tie).then(|| TieInfo {
+            origin: "false".to_string(),
+            destination: "true".to_string(),
+        }),
+        vibrato: s_note.effect.vibrato.then(|| "Slight".to_string()),
+        let_ring: s_note.effect.let_ring.then_some(EnableTag),
+        anti_accent: s_note.effect.ghost_note.then(|| "Normal".to_string()),
+        accent: (accent != 0).then_some(accent),
+        trill: s_note.effect.trill.as_ref().map(|t| t.fret as i32),
+        ornament: s_note.effect.ornament.clone(),
+        left_fingering: fingering_name(&s_note.effect.left_hand_finger).map(str::to_string),
+        right_fingering: fingering_name(&s_note.effect.right_hand_finger).map(str::to_string),
+    }
+}
@@ -0,0 +1,385 @@
+// GPIF Song conversion - Main implementation
+use super::beat::{convert_beat, TrackState};
+use super::helpers::*;
+use crate::io::gpif::model::*;
+use crate::model::{
+    headers::MeasureHeader, key_signature::Duration, song::Song, track::Track as SongTrack,
+};
+use crate::types::enums::BeatStatus;
+use std::collections::{BTreeMap, HashMap};
+
+/// `GPRevision` written for GP6 documents, the revision of the Guitar Pro 6.1 files we read.
+const GP6_REVISION: &str = "11686";
+
+/// GPIF document under construction, with the lookup tables needed to share rhythms.
+pub(crate) struct GpifWriter {
+    pub(crate) gpif: Gpif,
+    rhythms: HashMap<(u16, bool, bool, u8, u8), i32>,
+}
+
+impl GpifWriter {
+    /// Return the ID of the rhythm matching `duration`, creating it if needed.
+    pub(crate) fn rhythm_id(&mut self, duration: &Duration) -> i32 {
+        let key = (
+            duration.value,
+            duration.dotted,
+            duration.double_dotted,
+            duration.tuplet_enters,
+            duration.tuplet_times,
+        );
+        if let Some(&id) = self.rhythms.get(&key) {
+            return id;
+        }
+        let id = self.gpif.rhythms.rhythms.len() as i32;
+        let dots = if duration.double_dotted {
+            2
+        } else if duration.dotted {
+            1
+        } else {
+            0
+        };
+        self.gpif.rhythms.rhythms.push(Rhythm {
+            id,
+            note_value: duration_to_note_value(duration.value).to_string(),
+            augmentation_dot: (dots > 0).then_some(AugmentationDot { count: dots }),
+            primary_tuplet: (duration.tuplet_enters != duration.tuplet_times).then_some(
+                PrimaryTuplet {
+                    num: duration.tuplet_enters as i32,
+                    den: duration.tuplet_times as i32,
+                },
+            ),
+        });
+        self.rhythms.insert(key, id);
+        id
+    }
+}
+
+/// Convert a song to a GPIF document.
+///
+/// `version` selects the layout of the track elements: GP6 keeps tuning and MIDI
+/// settings at the track level, GP7 moves them to staves, MIDI connection and sounds.
+pub(crate) fn write_gpif(song: &Song, version: (u8, u8, u8)) -> Gpif {
+    let mut w = GpifWriter {
+        gpif: Gpif::default(),
+        rhythms: HashMap::new(),
+    };
+
+    // 0. Version
+    if version.0 >= 7 {
+        w.gpif.version = Some(version.0.to_string());
+    } else {
+        w.gpif.revision = Some(GP6_REVISION.to_string());
+    }
+
+    // 1. Metadata
+    w.gpif.score = Score {
+        title: song.name.clone(),
+        sub_title: song.subtitle.clone(),
+        artist: song.artist.clone(),
+        album: song.album.clone(),
+        words: song.words.clone(),
+        music: song.author.clone(),
+        copyright: song.copyright.clone(),
+        tabber: song.transcriber.clone(),
+        instructions: song.comments.clone(),
+        notices: song.notice.join("\n"),
+    };
+
+    // 2. MasterTrack: tempo automations and master volume
+    w.gpif.master_track = MasterTrack {
+        tracks_count: (0..song.tracks.len())
+            .map(|t| t.to_string())
+            .collect::<Vec<_>>()
+            .join(" "),
+        automations: Some(AutomationsWrapper {
+            automations: tempo_changes(song)
+                .into_iter()
+                .map(|(bar, tempo)| Automation {
+                    automation_type: "Tempo".to_string(),
+                    linear: Some(
+                        song.measure_headers
+                            .get(bar)
+                            .is_some_and(|mh| mh.tempo_linear),
+                    ),
+                    bar: bar as i32,
+                    position: 0,
+                    visible: Some(true),
+                    value: format!("{} 2", tempo),
+                })
+                .collect(),
+        }),
+        rse: (song.master_effect.volume > 0.0).then(|| RseMasterWrapper {
+            master: Some(RseMaster {
+                volume: Some(song.master_effect.volume / 100.0),
+                effects: Vec::new(),
+            }),
+        }),
+    };
+
+    // 3. Tracks and their bars
+    let mut track_bar_ids: Vec<Vec<i32>> = Vec::with_capacity(song.tracks.len());
+    for (t_idx, track) in song.tracks.iter().enumerate() {
+        let g_track = convert_track(song, track, t_idx, version);
+        w.gpif.tracks.tracks.push(g_track);
+        track_bar_ids.push(write_bars(&mut w, song, track));
+    }
+
+    // 4. MasterBars
+    for (mh_idx, mh) in song.measure_headers.iter().enumerate() {
+        let bar_ids: Vec<i32> = track_bar_ids.iter().map(|ids| ids[mh_idx]).collect();
+        w.gpif
+            .master_bars
+            .master_bars
+            .push(convert_header(mh, &bar_ids));
+    }
+
+    w.gpif
+}
+
+/// Collect the tempo at the start of the song and every tempo change, keyed by bar index.
+/// Changes come from GPIF measure headers and from GP3-5 mix table changes.
+fn tempo_changes(song: &Song) -> BTreeMap<usize, i32> {
+    let mut tempos = BTreeMap::new();
+    tempos.insert(0, song.tempo as i32);
+    for (mh_idx, mh) in song.measure_headers.iter().enumerate() {
+        if mh.tempo > 0 {
+            tempos.insert(mh_idx, mh.tempo);
+        }
+    }
+    for track in &song.tracks {
+        for (m_idx, measure) in track.measures.iter().enumerate() {
+            let tempo = measure
+                .voices
+                .iter()
+                .flat_map(|v| &v.beats)
+                .filter_map(|b| b.effect.mix_table_change.as_ref())
+                .find_map(|mtc| mtc.tempo.as_ref());
+            if let Some(tempo) = tempo {
+                tempos.insert(m_idx, tempo.value as i32);
+            }
+        }
+    }
+    tempos
+}
+
+fn convert_track(song: &Song, track: &SongTrack, t_idx: usize, version: (u8, u8, u8)) -> Track {
+    let channel = song.channels.get(track.channel_index);
+    let primary_channel = channel.map_or(track.channel_index as i32, |c| c.channel as i32);
+    let program = track
+        .midi_program_gpif
+        .or(channel.map(|c| c.instrument))
+        .unwrap_or(0);
+
+    // Tuning: GPIF lists the pitches from the lowest string to the highest one
+    let pitches: Vec<String> = track
+        .strings
+        .iter()
+        .rev()
+        .map(|(_, pitch)| pitch.to_string())
+        .collect();
+    let properties = vec![
+        Property {
+            name: "CapoFret".to_string(),
+            fret: Some(track.offset),
+            ..Default::default()
+        },
+        Property {
+            name: "FretCount".to_string(),
+            number: Some(track.fret_count as i32),
+            ..Default::default()
+        },
+        Property {
+            name: "Tuning".to_string(),
+            pitches: Some(pitches.join(" ")),
+            ..Default::default()
+        },
+    ];
+
+    let mut g_track = Track {
+        id: t_idx as i32,
+        name: track.name.clone(),
+        short_name: track.short_name.clone(),
+        color: Some(format!(
+            "{} {} {}",
+            (track.color >> 16) & 0xff,
+            (track.color >> 8) & 0xff,
+            track.color & 0xff
+        )),
+        transpose: Some(Transpose {
+            chromatic: Some(track.transpose_chromatic),
+            octave: Some(track.transpose_octave),
+        }),
+        ..Default::default()
+    };
+
+    if version.0 >= 7 {
+        g_track.staves = Some(StavesWrapper {
+            staves: vec![Staff {
+                properties: Some(StaffPropertiesWrapper { properties }),
+            }],
+        });
+        g_track.midi_connection = Some(MidiConnection {
+            port: Some(track.port as i32),
+            primary_channel: Some(primary_channel),
+            secondary_channel: Some(channel.map_or(primary_channel, |c| c.effect_channel as i32)),
+        });
+        g_track.sounds = Some(SoundsWrapper {
+            sounds: vec![Sound {
+                name: track.name.clone(),
+                label: track.name.clone(),
+                midi: Some(SoundMidi {
+                    lsb: 0,
+                    msb: 0,
+                    program,
+                }),
+            }],
+        });
+    } else {
+        g_track.properties = Some(TrackPropertiesWrapper { properties });
+        if !track.rse.instrument.effect_category.is_empty() {
+            g_track.rse = Some(RseTrackWrapper {
+                channel_strip: None,
+                effect_chains: Some(EffectChainsWrapper {
+                    effect_chains: vec![EffectChain {
+                        name: track.rse.instrument.effect_category.clone(),
+                        rails: Vec::new(),
+                    }],
+                }),
+            });
+        }
+        g_track.general_midi = Some(GeneralMidi {
+            program: Some(program),
+            port: Some(track.port as i32),
+            primary_channel: Some(primary_channel),
+            secondary_channel: Some(channel.map_or(primary_channel, |c| c.effect_channel as i32)),
+        });
+    }
+    g_track
+}
+
+/// Write one bar per measure header for `track` and return the bar IDs.
+fn write_bars(w: &mut GpifWriter, song: &Song, track: &SongTrack) -> Vec<i32> {
+    let mut state = TrackState::default();
+    let mut bar_ids = Vec::with_capacity(song.measure_headers.len());
+
+    for m_idx in 0..song.measure_headers.len() {
+        let bar_id = w.gpif.bars.bars.len() as i32;
+        let mut bar = Bar {
+            id: bar_id,
+            ..Default::default()
+        };
+        let mut voice_ids = [-1i32; 4];
+
+        if let Some(measure) = track.measures.get(m_idx) {
+            bar.clef = Some(clef_name(&measure.clef).to_string());
+            bar.simile_mark = measure.simile_mark.clone();
+
+            for (v_idx, voice) in measure.voices.iter().enumerate().take(voice_ids.len()) {
+                // GP3-5 always store two voices, the unused one only holds empty beats
+                if v_idx > 0 && voice.beats.iter().all(|b| b.status == BeatStatus::Empty) {
+                    continue;
+                }
+                let mut beat_ids = Vec::with_capacity(voice.beats.len());
+                for beat in &voice.beats {
+                    beat_ids.extend(convert_beat(w, &mut state, beat, &track.strings));
+                }
+                let voice_id = w.gpif.voices.voices.len() as i32;
+                w.gpif.voices.voices.push(Voice {
+                    id: voice_id,
+                    beats: join_ids(&beat_ids),
+                });
+                voice_ids[v_idx] = voice_id;
+            }
+        }
+
+        bar.voices = join_ids(&voice_ids);
+        w.gpif.bars.bars.push(bar);
+        bar_ids.push(bar_id);
+    }
+    bar_ids
+}
+
+fn convert_header(mh: &MeasureHeader, bar_ids: &[i32]) -> MasterBar {
+    let mut mb = MasterBar {
+        key: Some(Key {
+            accidental_count: mh.key_signature.key as i32,
+            mode: if mh.key_signature.is_minor {
+                "Minor"
+            } else {
+                "Major"
+            }
+            .to_string(),
+        }),
+        time: format!(
+            "{}/{}",
+            mh.time_signature.numerator, mh.time_signature.denominator.value
+        ),
+        bars: join_ids(bar_ids),
+        double_bar: mh.double_bar.then(String::new),
+        free_time: mh.free_time.then(String::new),
+        ..Default::default()
+    };
+
+    // Repeat: the song model stores the number of repeats, GPIF the play count
+    if mh.repeat_open || mh.repeat_close > -1 {
+        mb.repeat = Some(Repeat {
+            start: mh.repeat_open.to_string(),
+            end: (mh.repeat_close > -1).to_string(),
+            count: if mh.repeat_close > -1 {
+                mh.repeat_close as i32 + 1
+            } else {
+                0
+            },
+        });
+    }
+
+    // Alternate endings (volta)
+    if mh.repeat_alternative != 0 {
+        let endings: Vec<String> = (0..8)
+            .filter(|i| mh.repeat_alternative & (1 << i) != 0)
+            .map(|i| (i + 1).to_string())
+            .collect();
+        mb.alternate_endings = Some(endings.join(" "));
+    }
+
+    // Marker (Section)
+    if let Some(marker) = &mh.marker {
+        mb.section = Some(Section {
+            letter: None,
+            text: Some(marker.title.clone()),
+        });
+    }
+
+    // Fermatas
+    if !mh.fermatas.is_empty() {
+        mb.fermatas = Some(FermatasWrapper {
+            fermatas: mh
+                .fermatas
+                .iter()
+                .map(|f| Fermata {
+                    fermata_type: Some(fermata_type_name(&f.fermata_type).to_string()),
+                    offset: Some(format!("{}/{}", f.offset.0, f.offset.1)),
+                    length: None,
+                })
+                .collect(),
+        });
+    }
+
+    // Directions
+    if let Some(direction) = &mh.direction {
+        let (name, is_target) = direction_sign_name(direction);
+        mb.directions = Some(if is_target {
+            DirectionsWrapper {
+                target: Some(name.to_string()),
+                jump: None,
+            }
+        } else {
+            DirectionsWrapper {
+                target: None,
+                jump: Some(name.to_string()),
+            }
+        });
+    }
+
+    mb
+}
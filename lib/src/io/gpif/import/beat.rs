@@ -2,8 +2,11 @@
 use super::bend::{build_bend_effect, build_whammy_effect};
 use super::helpers::*;
 use super::note::convert_note;
+use super::report::GpifReport;
+use crate::error::ScorePart;
 use crate::io::gpif::model::{Beat, Note, Rhythm};
 use crate::model::beat::Beat as SongBeat;
+use crate::model::effects::FORTE;
 use crate::model::key_signature::DURATION_EIGHTH;
 use crate::model::mix_table::{MixTableChange, WahEffect};
 use crate::types::enums::*;
@@ -14,6 +17,8 @@ pub(crate) fn convert_beat(
     rhythms_map: &HashMap<i32, &Rhythm>,
     notes_map: &HashMap<i32, &Note>,
     current_velocity: &mut i16,
+    string_count: usize,
+    report: &mut GpifReport,
 ) -> SongBeat {
     let mut s_beat = SongBeat::default();
 
@@ -21,7 +26,11 @@ pub(crate) fn convert_beat(
     let mut grace_duration: u8 = 1;
     if let Some(rhythm_ref) = &g_beat.rhythm {
         if let Some(rhythm) = rhythms_map.get(&rhythm_ref.r#ref) {
-            s_beat.duration.value = note_value_to_duration(&rhythm.note_value);
+            s_beat.duration.value =
+                note_value_to_duration(&rhythm.note_value).unwrap_or_else(|| {
+                    report.malformed("NoteValue", &rhythm.note_value);
+                    4
+                });
             if let Some(dot) = &rhythm.augmentation_dot {
                 match dot.count {
                     1 => s_beat.duration.dotted = true,
@@ -37,16 +46,22 @@ pub(crate) fn convert_beat(
             if g_beat.grace_notes.is_some() {
                 grace_duration = grace_note_value_to_duration(&rhythm.note_value);
             }
+        } else {
+            report.dangling("Rhythm", rhythm_ref.r#ref);
         }
     }
 
     // Dynamic
     if let Some(dyn_str) = &g_beat.dynamic {
-        *current_velocity = dynamic_to_velocity(dyn_str);
+        *current_velocity = dynamic_to_velocity(dyn_str).unwrap_or_else(|| {
+            report.malformed("Dynamic", dyn_str);
+            FORTE
+        });
     }
 
     // Grace notes
     let is_grace_beat = g_beat.grace_notes.is_some();
+    s_beat.grace = is_grace_beat;
     let grace_on_beat = g_beat.grace_notes.as_deref() == Some("OnBeat");
 
     // Text
@@ -58,6 +73,8 @@ pub(crate) fn convert_beat(
     if let Some(fadding) = &g_beat.fadding {
         if fadding == "FadeIn" {
             s_beat.effect.fade_in = true;
+        } else {
+            report.ignored_element(format!("Fadding {}", fadding));
         }
     }
 
@@ -71,15 +88,20 @@ pub(crate) fn convert_beat(
                 }),
                 ..Default::default()
             });
+        } else {
+            report.ignored_element(format!("Wah {}", wah_str));
         }
     }
 
     // Tremolo bar (simple)
     if let Some(tremolo_str) = &g_beat.tremolo {
-        if let Ok(val) = tremolo_str.parse::<f64>() {
-            if val != 0.0 {
+        match tremolo_str.parse::<f64>() {
+            Ok(val) if val != 0.0 => {
                 s_beat.effect.tremolo_bar = Some(build_bend_effect(0.0, val));
             }
+            Ok(_) => {}
+            // Tremolo picking speed, e.g. `1/8`
+            Err(_) => report.ignored_element(format!("Tremolo {}", tremolo_str)),
         }
     }
 
@@ -93,7 +115,10 @@ pub(crate) fn convert_beat(
         s_beat.effect.stroke.direction = match arp.as_str() {
             "Down" => BeatStrokeDirection::Down,
             "Up" => BeatStrokeDirection::Up,
-            _ => BeatStrokeDirection::None,
+            _ => {
+                report.malformed("Arpeggio", arp);
+                BeatStrokeDirection::None
+            }
         };
         if s_beat.effect.stroke.direction != BeatStrokeDirection::None {
             s_beat.effect.stroke.value = DURATION_EIGHTH as u16;
@@ -107,7 +132,10 @@ pub(crate) fn convert_beat(
             "8vb" => Octave::OttavaBassa,
             "15ma" => Octave::Quindicesima,
             "15mb" => Octave::QuindicesimaBassa,
-            _ => Octave::None,
+            _ => {
+                report.malformed("Ottavia", ott);
+                Octave::None
+            }
         };
     }
 
@@ -137,29 +165,25 @@ pub(crate) fn convert_beat(
                         };
                     }
                 }
-                "Slapped" => {
-                    if bp.enable.is_some() {
-                        s_beat.effect.slap_effect = SlapEffect::Slapping;
-                    }
+                "Slapped" if bp.enable.is_some() => {
+                    s_beat.effect.slap_effect = SlapEffect::Slapping;
                 }
-                "Popped" => {
-                    if bp.enable.is_some() {
-                        s_beat.effect.slap_effect = SlapEffect::Popping;
-                    }
+                "Popped" if bp.enable.is_some() => {
+                    s_beat.effect.slap_effect = SlapEffect::Popping;
                 }
                 "VibratoWTremBar" => {
                     s_beat.effect.vibrato = true;
                 }
-                "WhammyBar" => {
-                    if s_beat.effect.tremolo_bar.is_none() {
-                        if let Some(val) = bp.float {
-                            if val != 0.0 {
-                                s_beat.effect.tremolo_bar = Some(build_bend_effect(0.0, val));
-                            }
+                "WhammyBar" if s_beat.effect.tremolo_bar.is_none() => {
+                    if let Some(val) = bp.float {
+                        if val != 0.0 {
+                            s_beat.effect.tremolo_bar = Some(build_bend_effect(0.0, val));
                         }
                     }
                 }
-                _ => {}
+                // Disabled flags, or a whammy bar already read from its element
+                "Slapped" | "Popped" | "WhammyBar" => {}
+                name => report.ignored_property("Beat", name),
             }
         }
     }
@@ -167,7 +191,7 @@ pub(crate) fn convert_beat(
     // Notes
     match &g_beat.notes {
         Some(notes_str) => {
-            let note_ids = parse_ids(notes_str);
+            let note_ids = report.ids("Notes", notes_str);
             s_beat.status = if note_ids.is_empty() {
                 BeatStatus::Rest
             } else {
@@ -175,23 +199,29 @@ pub(crate) fn convert_beat(
             };
 
             for &nid in &note_ids {
-                if let Some(g_note) = notes_map.get(&nid) {
-                    let s_note = convert_note(
-                        g_note,
-                        *current_velocity,
-                        is_grace_beat,
-                        grace_on_beat,
-                        grace_duration,
-                    );
-                    s_beat.notes.push(s_note);
-
-                    // Tap technique is a beat-level effect (SlapEffect::Tapping)
-                    if s_beat.effect.slap_effect == SlapEffect::None {
-                        for prop in &g_note.properties.properties {
-                            if prop.name == "Tapped" && prop.enable.is_some() {
-                                s_beat.effect.slap_effect = SlapEffect::Tapping;
-                                break;
-                            }
+                let Some(g_note) = notes_map.get(&nid) else {
+                    report.dangling("Note", nid);
+                    continue;
+                };
+                report.location.push(ScorePart::Note(s_beat.notes.len()));
+                let s_note = convert_note(
+                    g_note,
+                    *current_velocity,
+                    is_grace_beat,
+                    grace_on_beat,
+                    grace_duration,
+                    string_count,
+                    report,
+                );
+                report.location.pop();
+                s_beat.notes.push(s_note);
+
+                // Tap technique is a beat-level effect (SlapEffect::Tapping)
+                if s_beat.effect.slap_effect == SlapEffect::None {
+                    for prop in &g_note.properties.properties {
+                        if prop.name == "Tapped" && prop.enable.is_some() {
+                            s_beat.effect.slap_effect = SlapEffect::Tapping;
+                            break;
                         }
                     }
                 }
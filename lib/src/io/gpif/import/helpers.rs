@@ -4,10 +4,9 @@ use crate::model::effects::{HarmonicEffect, FORTE, MIN_VELOCITY, VELOCITY_INCREM
 use crate::model::headers::FermataType;
 use crate::types::enums::*;
 
-/// Convert GPIF note value string to Duration.value.
-/// Falls back to Quarter (4) for unknown values.
-pub(crate) fn note_value_to_duration(s: &str) -> u16 {
-    match s {
+/// Convert GPIF note value string to Duration.value, None for unknown values.
+pub(crate) fn note_value_to_duration(s: &str) -> Option<u16> {
+    Some(match s {
         "Whole" => 1,
         "Half" => 2,
         "Quarter" => 4,
@@ -16,8 +15,8 @@ pub(crate) fn note_value_to_duration(s: &str) -> u16 {
         "32nd" => 32,
         "64th" => 64,
         "128th" => 128,
-        _ => 4,
-    }
+        _ => return None,
+    })
 }
 
 /// Convert GPIF grace note rhythm NoteValue to the in-memory grace duration value.
@@ -32,9 +31,9 @@ pub(crate) fn grace_note_value_to_duration(s: &str) -> u8 {
     }
 }
 
-/// Convert GPIF dynamic string to MIDI velocity
-pub(crate) fn dynamic_to_velocity(s: &str) -> i16 {
-    match s {
+/// Convert GPIF dynamic string to MIDI velocity, None for unknown values.
+pub(crate) fn dynamic_to_velocity(s: &str) -> Option<i16> {
+    Some(match s {
         "PPP" => MIN_VELOCITY,
         "PP" => MIN_VELOCITY + VELOCITY_INCREMENT,
         "P" => MIN_VELOCITY + VELOCITY_INCREMENT * 2,
@@ -43,15 +42,8 @@ pub(crate) fn dynamic_to_velocity(s: &str) -> i16 {
         "F" => FORTE,
         "FF" => MIN_VELOCITY + VELOCITY_INCREMENT * 6,
         "FFF" => MIN_VELOCITY + VELOCITY_INCREMENT * 7,
-        _ => FORTE,
-    }
-}
-
-/// Parse space-separated integer IDs from a string.
-pub(crate) fn parse_ids(s: &str) -> Vec<i32> {
-    s.split_whitespace()
-        .filter_map(|tok| tok.parse::<i32>().ok())
-        .collect()
+        _ => return None,
+    })
 }
 
 /// Parse slide flags bitmask into a list of `SlideType` values.
@@ -87,9 +79,9 @@ pub(crate) fn parse_slide_flags(flags: i32) -> Vec<SlideType> {
 }
 
 /// Parse a GPIF harmonic type string (e.g. "Natural", "Artificial", "Pinch")
-/// into a `HarmonicEffect`. Falls back to `Natural` for unrecognised values.
+/// into a `HarmonicEffect`, None for unrecognised values.
 /// "Feedback" is mapped to `Pinch` as Guitar Pro treats them equivalently.
-pub(crate) fn parse_harmonic_type(htype: &str) -> HarmonicEffect {
+pub(crate) fn parse_harmonic_type(htype: &str) -> Option<HarmonicEffect> {
     let kind = match htype {
         "Natural" => HarmonicType::Natural,
         "Artificial" => HarmonicType::Artificial,
@@ -97,12 +89,12 @@ pub(crate) fn parse_harmonic_type(htype: &str) -> HarmonicEffect {
         "Tap" | "Tapped" => HarmonicType::Tapped,
         "Semi" => HarmonicType::Semi,
         "Feedback" => HarmonicType::Pinch,
-        _ => HarmonicType::Natural,
+        _ => return None,
     };
-    HarmonicEffect {
+    Some(HarmonicEffect {
         kind,
         ..Default::default()
-    }
+    })
 }
 
 /// Parse direction string to DirectionSign enum.
@@ -131,7 +123,15 @@ pub(crate) fn parse_direction_sign(s: &str) -> Option<DirectionSign> {
     }
 }
 
+/// Find a property by name in a property list.
+pub(crate) fn find_property<'a>(properties: &'a [Property], name: &str) -> Option<&'a Property> {
+    properties.iter().find(|p| p.name == name)
+}
+
 /// Extract tuning pitches from a property list.
+///
+/// GPIF lists the pitches from the lowest string to the highest one, while the
+/// song model numbers strings from the highest (string 1) down, like GP3-GP5.
 pub(crate) fn extract_tuning(properties: &[Property]) -> Vec<(i8, i8)> {
     for prop in properties {
         if prop.name == "Tuning" {
@@ -142,6 +142,7 @@ pub(crate) fn extract_tuning(properties: &[Property]) -> Vec<(i8, i8)> {
                     .collect();
                 return pitches
                     .iter()
+                    .rev()
                     .enumerate()
                     .map(|(i, &pitch)| ((i + 1) as i8, pitch))
                     .collect();
@@ -151,17 +152,32 @@ pub(crate) fn extract_tuning(properties: &[Property]) -> Vec<(i8, i8)> {
     Vec::new()
 }
 
-/// Parse GPIF fingering string to Fingering enum.
-pub(crate) fn parse_fingering(s: &str) -> Fingering {
+/// Convert a GPIF string index (0 = lowest string) to a song string number (1 = highest string).
+pub(crate) fn gpif_string_to_song(string: i32, string_count: usize) -> i8 {
+    (string_count as i32 - string).clamp(i8::MIN as i32, i8::MAX as i32) as i8
+}
+
+/// Parse GPIF clef string to MeasureClef enum.
+pub(crate) fn parse_clef(s: &str) -> MeasureClef {
     match s {
+        "F4" => MeasureClef::Bass,
+        "C4" => MeasureClef::Tenor,
+        "C3" => MeasureClef::Alto,
+        _ => MeasureClef::Treble,
+    }
+}
+
+/// Parse GPIF fingering string to Fingering enum, None for unknown values.
+pub(crate) fn parse_fingering(s: &str) -> Option<Fingering> {
+    Some(match s {
         "Open" => Fingering::Open,
         "P" => Fingering::Thumb,
         "I" => Fingering::Index,
         "M" => Fingering::Middle,
         "A" => Fingering::Annular,
         "C" => Fingering::Little,
-        _ => Fingering::Open,
-    }
+        _ => return None,
+    })
 }
 
 /// Parse GPIF fermata type string to FermataType enum.
@@ -4,7 +4,9 @@ pub mod helpers;
 pub mod bend;
 pub mod beat;
 pub mod note;
+pub mod report;
 pub mod song;
 
 // Re-export main trait
+pub use report::{GpifIssue, GpifIssueKind};
 pub use song::SongGpifOps;
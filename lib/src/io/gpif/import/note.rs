@@ -1,6 +1,7 @@
 // GPIF note conversion
 use super::bend::build_bend_effect_full;
 use super::helpers::*;
+use super::report::GpifReport;
 use crate::io::gpif::model::Note;
 use crate::model::key_signature::Duration;
 use crate::model::note::Note as SongNote;
@@ -13,6 +14,8 @@ pub(crate) fn convert_note(
     is_grace_beat: bool,
     grace_on_beat: bool,
     grace_duration: u8,
+    string_count: usize,
+    report: &mut GpifReport,
 ) -> SongNote {
     let mut s_note = SongNote {
         velocity,
@@ -36,13 +39,11 @@ pub(crate) fn convert_note(
             }
             "String" => {
                 if let Some(s) = prop.string {
-                    s_note.string = s as i8;
+                    s_note.string = gpif_string_to_song(s, string_count);
                 }
             }
-            "PalmMuted" => {
-                if prop.enable.is_some() {
-                    s_note.effect.palm_mute = true;
-                }
+            "PalmMuted" if prop.enable.is_some() => {
+                s_note.effect.palm_mute = true;
             }
             "BendOriginValue" => {
                 bend_origin = prop.float;
@@ -72,7 +73,11 @@ pub(crate) fn convert_note(
             }
             "HarmonicType" => {
                 if let Some(htype) = &prop.htype {
-                    s_note.effect.harmonic = Some(parse_harmonic_type(htype));
+                    let harmonic = parse_harmonic_type(htype);
+                    if harmonic.is_none() {
+                        report.malformed("HarmonicType", htype);
+                    }
+                    s_note.effect.harmonic = Some(harmonic.unwrap_or_default());
                 }
             }
             "HarmonicFret" => {
@@ -82,26 +87,24 @@ pub(crate) fn convert_note(
                     }
                 }
             }
-            "HopoOrigin" | "HopoDestination" => {
-                if prop.enable.is_some() {
-                    s_note.effect.hammer = true;
-                }
+            "HopoOrigin" | "HopoDestination" if prop.enable.is_some() => {
+                s_note.effect.hammer = true;
             }
-            "Dead" | "Muted" => {
-                if prop.enable.is_some() {
-                    s_note.kind = NoteType::Dead;
-                }
+            "Dead" | "Muted" if prop.enable.is_some() => {
+                s_note.kind = NoteType::Dead;
             }
             // Note: "Tapped" (tap technique) is a beat-level effect (SlapEffect::Tapping),
             // handled in convert_beat after note processing.
             "Tapped" => {}
-            _ => {}
+            // Disabled flags
+            "PalmMuted" | "HopoOrigin" | "HopoDestination" | "Dead" | "Muted" => {}
+            name => report.ignored_property("Note", name),
         }
     }
 
     // Bend (with optional middle value and offsets for improved accuracy)
     if let (Some(orig), Some(dest)) = (bend_origin, bend_dest) {
-        if orig != 0.0 || dest != 0.0 {
+        if orig != 0.0 || dest != 0.0 || bend_middle.is_some_and(|m| m != 0.0) {
             s_note.effect.bend = Some(build_bend_effect_full(
                 orig,
                 bend_middle,
@@ -162,11 +165,19 @@ pub(crate) fn convert_note(
     }
 
     // Fingering
-    if let Some(lf) = &g_note.left_fingering {
-        s_note.effect.left_hand_finger = parse_fingering(lf);
-    }
-    if let Some(rf) = &g_note.right_fingering {
-        s_note.effect.right_hand_finger = parse_fingering(rf);
+    for (finger, fingering) in [
+        (&mut s_note.effect.left_hand_finger, &g_note.left_fingering),
+        (
+            &mut s_note.effect.right_hand_finger,
+            &g_note.right_fingering,
+        ),
+    ] {
+        if let Some(fingering) = fingering {
+            *finger = parse_fingering(fingering).unwrap_or_else(|| {
+                report.malformed("Fingering", fingering);
+                Fingering::Open
+            });
+        }
     }
 
     // Grace note
@@ -0,0 +1,108 @@
+// Report of the GPIF import: what the song could not take from the document
+use std::collections::HashSet;
+use std::fmt;
+
+use crate::error::ScorePart;
+
+/// What the GPIF import could not map to the song.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GpifIssueKind {
+    /// An element, or a value of an element, the song has no place for, e.g.
+    /// `Automation Chorus` or `Wah Closed`
+    IgnoredElement { element: String },
+    /// A property of a track, beat or note that is not read
+    IgnoredProperty { owner: &'static str, name: String },
+    /// A value that could not be parsed, replaced by a default
+    MalformedValue {
+        element: &'static str,
+        value: String,
+    },
+    /// A reference to an id missing from the list of its elements
+    DanglingId { element: &'static str, id: i32 },
+}
+
+/// A problem of the GPIF import and where it is in the score.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GpifIssue {
+    /// Parts of the score being imported, from the outermost; empty for the whole score
+    pub location: Vec<ScorePart>,
+    pub kind: GpifIssueKind,
+}
+
+impl fmt::Display for GpifIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.location.is_empty() {
+            write!(f, "score: ")?;
+        } else {
+            let parts: Vec<String> = self.location.iter().map(|p| p.to_string()).collect();
+            write!(f, "{}: ", parts.join(" \u{203a} "))?;
+        }
+        match &self.kind {
+            GpifIssueKind::IgnoredElement { element } => write!(f, "{} ignored", element),
+            GpifIssueKind::IgnoredProperty { owner, name } => {
+                write!(f, "{} property {} ignored", owner, name)
+            }
+            GpifIssueKind::MalformedValue { element, value } => {
+                write!(f, "malformed {} '{}'", element, value)
+            }
+            GpifIssueKind::DanglingId { element, id } => write!(f, "{} {} not found", element, id),
+        }
+    }
+}
+
+/// Issues gathered during the import, at the location being imported. Ignored elements
+/// and properties are reported once, where first met.
+#[derive(Debug, Default)]
+pub(crate) struct GpifReport {
+    pub(crate) issues: Vec<GpifIssue>,
+    pub(crate) location: Vec<ScorePart>,
+    ignored: HashSet<String>,
+}
+
+impl GpifReport {
+    fn report(&mut self, kind: GpifIssueKind) {
+        self.issues.push(GpifIssue {
+            location: self.location.clone(),
+            kind,
+        });
+    }
+
+    pub(crate) fn ignored_element(&mut self, element: String) {
+        if self.ignored.insert(element.clone()) {
+            self.report(GpifIssueKind::IgnoredElement { element });
+        }
+    }
+
+    pub(crate) fn ignored_property(&mut self, owner: &'static str, name: &str) {
+        if self.ignored.insert(format!("{} property {}", owner, name)) {
+            self.report(GpifIssueKind::IgnoredProperty {
+                owner,
+                name: name.to_string(),
+            });
+        }
+    }
+
+    pub(crate) fn malformed(&mut self, element: &'static str, value: &str) {
+        self.report(GpifIssueKind::MalformedValue {
+            element,
+            value: value.to_string(),
+        });
+    }
+
+    pub(crate) fn dangling(&mut self, element: &'static str, id: i32) {
+        self.report(GpifIssueKind::DanglingId { element, id });
+    }
+
+    /// Ids of a list, reporting the tokens that are not numbers.
+    pub(crate) fn ids(&mut self, element: &'static str, list: &str) -> Vec<i32> {
+        list.split_whitespace()
+            .filter_map(|token| {
+                let id = token.parse::<i32>().ok();
+                if id.is_none() {
+                    self.malformed(element, token);
+                }
+                id
+            })
+            .collect()
+    }
+}
@@ -1,7 +1,10 @@
 // GPIF Song conversion - Main implementation
 use super::beat::convert_beat;
 use super::helpers::*;
+use super::report::{GpifIssue, GpifReport};
+use crate::error::{GpError, GpResult, ScorePart};
 use crate::io::gpif::model::*;
+use crate::io::limits::{check, ReadLimits};
 use crate::model::{
     beat::Voice as SongVoice,
     headers::{Marker, MeasureFermata, MeasureHeader},
@@ -13,11 +16,19 @@ use crate::types::effects::*;
 use std::collections::HashMap;
 
 pub trait SongGpifOps {
-    fn read_gpif(&mut self, gpif: &Gpif);
+    fn read_gpif(&mut self, gpif: &Gpif, limits: &ReadLimits) -> GpResult<Vec<GpifIssue>>;
+    fn write_gpif(&self, version: (u8, u8, u8)) -> Gpif;
 }
 
 impl SongGpifOps for Song {
-    fn read_gpif(&mut self, gpif: &Gpif) {
+    /// Read a GPIF document into the song. Returns what the song could not take from it:
+    /// ignored elements and properties (once each), malformed values replaced by defaults
+    /// and ids of bars, voices, beats, notes and rhythms missing from their lists. Fails
+    /// on a malformed time signature and past the tracks, measures, beats and notes of
+    /// `limits`.
+    fn read_gpif(&mut self, gpif: &Gpif, limits: &ReadLimits) -> GpResult<Vec<GpifIssue>> {
+        let mut report = GpifReport::default();
+
         // 0. Version
         let default_version = self.version.number;
         self.version.number = parse_gpif_version(gpif, default_version);
@@ -41,7 +52,19 @@ impl SongGpifOps for Song {
         // 2. Tempo from MasterTrack automations
         if let Some(automations) = &gpif.master_track.automations {
             for auto in &automations.automations {
-                if auto.automation_type == "Tempo" && auto.bar == 0 {
+                if auto.automation_type != "Tempo" {
+                    report.ignored_element(format!("Automation {}", auto.automation_type));
+                } else if auto
+                    .value
+                    .split_whitespace()
+                    .next()
+                    .map(str::parse::<f64>)
+                    .is_some_and(|v| v.is_err())
+                {
+                    report.location = vec![ScorePart::MeasureHeader(auto.bar.max(0) as usize)];
+                    report.malformed("Automation", &auto.value);
+                    report.location.clear();
+                } else if auto.bar == 0 {
                     if let Some(tempo_str) = auto.value.split_whitespace().next() {
                         self.tempo = match tempo_str.parse::<f64>() {
                             Ok(v) => v as i16,
@@ -75,6 +98,12 @@ impl SongGpifOps for Song {
         // 4. Measure Headers (MasterBars) — also collects per-track bar IDs
         self.measure_headers.clear();
         let num_tracks = gpif.tracks.tracks.len();
+        check("tracks", num_tracks, limits.max_tracks)?;
+        check(
+            "measures",
+            gpif.master_bars.master_bars.len(),
+            limits.max_measures,
+        )?;
         let mut track_bar_ids: Vec<Vec<i32>> = vec![Vec::new(); num_tracks];
 
         for (mh_idx, mb) in gpif.master_bars.master_bars.iter().enumerate() {
@@ -83,12 +112,25 @@ impl SongGpifOps for Song {
                 ..Default::default()
             };
 
+            report.location = vec![ScorePart::MeasureHeader(mh_idx)];
+
             // Time signature
-            let time_parts: Vec<&str> = mb.time.split('/').collect();
-            if time_parts.len() == 2 {
-                mh.time_signature.numerator = time_parts[0].parse().unwrap_or(4) as i8;
-                mh.time_signature.denominator.value = time_parts[1].parse().unwrap_or(4) as u16;
-            }
+            let time = mb
+                .time
+                .split_once('/')
+                .and_then(|(n, d)| {
+                    Some((n.trim().parse::<i8>().ok()?, d.trim().parse::<u16>().ok()?))
+                })
+                .filter(|(n, d)| *n > 0 && *d > 0);
+            let Some((numerator, denominator)) = time else {
+                return Err(GpError::FormatError(format!(
+                    "Invalid time signature '{}' in master bar {}",
+                    mb.time,
+                    mh_idx + 1
+                )));
+            };
+            mh.time_signature.numerator = numerator;
+            mh.time_signature.denominator.value = denominator;
 
             // Key signature
             if let Some(key) = &mb.key {
@@ -102,6 +144,7 @@ impl SongGpifOps for Song {
                     if auto.automation_type == "Tempo" && auto.bar == mh_idx as i32 {
                         if let Some(tempo_str) = auto.value.split_whitespace().next() {
                             mh.tempo = tempo_str.parse::<f64>().unwrap_or(0.0) as i32;
+                            mh.tempo_linear = auto.linear == Some(true);
                         }
                     }
                 }
@@ -111,7 +154,8 @@ impl SongGpifOps for Song {
             if let Some(repeat) = &mb.repeat {
                 mh.repeat_open = repeat.start == "true";
                 if repeat.end == "true" {
-                    mh.repeat_close = repeat.count.max(1) as i8;
+                    // GPIF stores the play count, the song model the number of repeats
+                    mh.repeat_close = (repeat.count - 1).clamp(0, i8::MAX as i32) as i8;
                 }
             }
 
@@ -119,10 +163,9 @@ impl SongGpifOps for Song {
             if let Some(alt_str) = &mb.alternate_endings {
                 let mut bitmask: u8 = 0;
                 for tok in alt_str.split_whitespace() {
-                    if let Ok(n) = tok.parse::<u8>() {
-                        if n > 0 && n <= 8 {
-                            bitmask |= 1 << (n - 1);
-                        }
+                    match tok.parse::<u8>() {
+                        Ok(n) if n > 0 && n <= 8 => bitmask |= 1 << (n - 1),
+                        _ => report.malformed("AlternateEndings", tok),
                     }
                 }
                 mh.repeat_alternative = bitmask;
@@ -169,7 +212,10 @@ impl SongGpifOps for Song {
             }
 
             // Per-track bar IDs
-            let bar_ids = parse_ids(&mb.bars);
+            let bar_ids = report.ids("Bars", &mb.bars);
+            if bar_ids.len() < num_tracks {
+                report.malformed("Bars", &mb.bars);
+            }
             for (t_idx, &bar_id) in bar_ids.iter().enumerate() {
                 if t_idx < num_tracks {
                     track_bar_ids[t_idx].push(bar_id);
@@ -179,10 +225,13 @@ impl SongGpifOps for Song {
             self.measure_headers.push(mh);
         }
 
+        report.location.clear();
         let num_measures = self.measure_headers.len();
 
         // 5. Tracks
         self.tracks.clear();
+        // Bars, voices and beats are shared by id: count what the song gets
+        let (mut beat_count, mut note_count) = (0, 0);
 
         for (t_idx, g_track) in gpif.tracks.tracks.iter().enumerate() {
             let mut track = SongTrack {
@@ -200,32 +249,46 @@ impl SongGpifOps for Song {
                     .collect();
                 if rgb.len() == 3 {
                     track.color = rgb[0] * 65536 + rgb[1] * 256 + rgb[2];
+                } else {
+                    report.location = vec![ScorePart::Track(t_idx)];
+                    report.malformed("Color", color_str);
                 }
             }
 
-            // Tuning: GP6 track-level properties, GP7 staves
-            if let Some(props) = &g_track.properties {
-                track.strings = extract_tuning(&props.properties);
+            // Tuning, capo and fret count: GP6 track-level properties, GP7 staves
+            let mut instrument_props: Option<&Vec<Property>> =
+                g_track.properties.as_ref().map(|p| &p.properties);
+            if let Some(staves) = &g_track.staves {
+                if instrument_props.is_none_or(|p| extract_tuning(p).is_empty()) {
+                    instrument_props = staves
+                        .staves
+                        .iter()
+                        .filter_map(|s| s.properties.as_ref().map(|p| &p.properties))
+                        .find(|p| !extract_tuning(p).is_empty())
+                        .or(instrument_props);
+                }
             }
-            if track.strings.is_empty() {
-                if let Some(staves) = &g_track.staves {
-                    for staff in &staves.staves {
-                        if let Some(props) = &staff.properties {
-                            track.strings = extract_tuning(&props.properties);
-                            if !track.strings.is_empty() {
-                                break;
-                            }
-                        }
+            track.fret_count = 24;
+            if let Some(props) = instrument_props {
+                report.location = vec![ScorePart::Track(t_idx)];
+                for prop in props {
+                    if !["Tuning", "CapoFret", "FretCount"].contains(&prop.name.as_str()) {
+                        report.ignored_property("Track", &prop.name);
                     }
                 }
+                let tuning = extract_tuning(props);
+                if !tuning.is_empty() {
+                    track.strings = tuning;
+                }
+                if let Some(capo) = find_property(props, "CapoFret").and_then(|p| p.fret) {
+                    track.offset = capo;
+                }
+                if let Some(frets) = find_property(props, "FretCount").and_then(|p| p.number) {
+                    track.fret_count = frets.clamp(0, u8::MAX as i32) as u8;
+                }
             }
-            if track.strings.is_empty() {
-                track.strings = vec![(1, 64), (2, 59), (3, 55), (4, 50), (5, 45), (6, 40)];
-            }
-
-            track.fret_count = 24;
 
-            // MIDI
+            // MIDI: GP6 GeneralMidi, GP7 MidiConnection + Sounds
             if let Some(gm) = &g_track.general_midi {
                 if let Some(ch) = gm.primary_channel {
                     track.channel_index = ch as usize;
@@ -235,6 +298,22 @@ impl SongGpifOps for Song {
                 if let Some(port) = gm.port {
                     track.port = port as u8;
                 }
+            } else if let Some(mc) = &g_track.midi_connection {
+                if let Some(ch) = mc.primary_channel {
+                    track.channel_index = ch as usize;
+                    track.percussion_track = ch == 9;
+                }
+                if let Some(port) = mc.port {
+                    track.port = port as u8;
+                }
+            }
+            if track.midi_program_gpif.is_none() {
+                if let Some(sounds) = &g_track.sounds {
+                    track.midi_program_gpif = sounds
+                        .sounds
+                        .iter()
+                        .find_map(|s| s.midi.as_ref().map(|m| m.program));
+                }
             }
 
             // Transpose
@@ -276,38 +355,72 @@ impl SongGpifOps for Song {
                     -1
                 };
 
-                if let Some(bar) = bars_map.get(&bar_id) {
-                    measure.simile_mark = bar.simile_mark.clone();
-                    let voice_ids = parse_ids(&bar.voices);
-                    measure.voices.clear();
+                report.location = vec![ScorePart::Track(t_idx), ScorePart::Measure(m_idx)];
+                match bars_map.get(&bar_id) {
+                    Some(bar) => {
+                        measure.simile_mark = bar.simile_mark.clone();
+                        if let Some(clef) = &bar.clef {
+                            measure.clef = parse_clef(clef);
+                        }
+                        let voice_ids = report.ids("Voices", &bar.voices);
+                        measure.voices.clear();
 
-                    for &vid in &voice_ids {
-                        if vid < 0 {
-                            continue;
+                        // A bar has four voice slots: the voices past them are reported
+                        for &vid in voice_ids.iter().skip(4).filter(|&&vid| vid >= 0) {
+                            report.ignored_element(format!("Voice {}", vid));
                         }
-                        let mut s_voice = SongVoice::default();
-
-                        if let Some(g_voice) = voices_map.get(&vid) {
-                            let beat_ids = parse_ids(&g_voice.beats);
-
-                            for &bid in &beat_ids {
-                                if let Some(g_beat) = beats_map.get(&bid) {
-                                    let s_beat = convert_beat(
-                                        g_beat,
-                                        &rhythms_map,
-                                        &notes_map,
-                                        &mut current_velocity,
-                                    );
-                                    s_voice.beats.push(s_beat);
+                        for &vid in voice_ids.iter().take(4) {
+                            if vid < 0 {
+                                continue;
+                            }
+                            let mut s_voice = SongVoice::default();
+                            report.location.push(ScorePart::Voice(measure.voices.len()));
+
+                            match voices_map.get(&vid) {
+                                Some(g_voice) => {
+                                    let beat_ids = report.ids("Beats", &g_voice.beats);
+
+                                    for &bid in &beat_ids {
+                                        beat_count += 1;
+                                        check("beats", beat_count, limits.max_beats)?;
+                                        let Some(g_beat) = beats_map.get(&bid) else {
+                                            report.dangling("Beat", bid);
+                                            continue;
+                                        };
+                                        report.location.push(ScorePart::Beat(s_voice.beats.len()));
+                                        let s_beat = convert_beat(
+                                            g_beat,
+                                            &rhythms_map,
+                                            &notes_map,
+                                            &mut current_velocity,
+                                            track.strings.len(),
+                                            &mut report,
+                                        );
+                                        report.location.pop();
+                                        note_count += s_beat.notes.len();
+                                        check("notes", note_count, limits.max_notes)?;
+                                        s_voice.beats.push(s_beat);
+                                    }
                                 }
+                                None => report.dangling("Voice", vid),
                             }
+                            report.location.pop();
+                            measure.voices.push(s_voice);
                         }
-                        measure.voices.push(s_voice);
                     }
+                    // Missing from the bars of the master bar, reported above
+                    None if bar_id < 0 => {}
+                    None => report.dangling("Bar", bar_id),
                 }
                 track.measures.push(measure);
             }
             self.tracks.push(track);
         }
+        Ok(report.issues)
+    }
+
+    /// Convert the song to a GPIF document. See `Song::write_gp()` for the GP7 container.
+    fn write_gpif(&self, version: (u8, u8, u8)) -> Gpif {
+        crate::io::gpif::export::song::write_gpif(self, version)
     }
 }
@@ -1,5 +1,6 @@
 // GPIF (GP6/7 XML format) module
 
+pub mod export;
 pub mod import;
 pub mod model;
 
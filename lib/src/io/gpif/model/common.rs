@@ -1,49 +1,53 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 /// An empty self-closing tag used as a presence flag (e.g., `<Enable />`, `<LetRing />`).
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct EnableTag;
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct Property {
     #[serde(rename = "@name", default)]
     pub name: String,
     // Value sub-elements — each property uses at most one of these
-    #[serde(rename = "Fret", default)]
+    #[serde(rename = "Fret", default, skip_serializing_if = "Option::is_none")]
     pub fret: Option<i32>,
-    #[serde(rename = "String", default)]
+    #[serde(rename = "String", default, skip_serializing_if = "Option::is_none")]
     pub string: Option<i32>,
-    #[serde(rename = "Pitch", default)]
+    #[serde(rename = "Pitch", default, skip_serializing_if = "Option::is_none")]
     pub pitch: Option<Pitch>,
-    #[serde(rename = "Number", default)]
+    #[serde(rename = "Number", default, skip_serializing_if = "Option::is_none")]
     pub number: Option<i32>,
-    #[serde(rename = "Enable", default)]
+    #[serde(rename = "Enable", default, skip_serializing_if = "Option::is_none")]
     pub enable: Option<EnableTag>,
-    #[serde(rename = "Float", default)]
+    #[serde(rename = "Float", default, skip_serializing_if = "Option::is_none")]
     pub float: Option<f64>,
-    #[serde(rename = "Flags", default)]
+    #[serde(rename = "Flags", default, skip_serializing_if = "Option::is_none")]
     pub flags: Option<i32>,
-    #[serde(rename = "HFret", default)]
+    #[serde(rename = "HFret", default, skip_serializing_if = "Option::is_none")]
     pub hfret: Option<f64>,
-    #[serde(rename = "HType", default)]
+    #[serde(rename = "HType", default, skip_serializing_if = "Option::is_none")]
     pub htype: Option<String>,
-    #[serde(rename = "Pitches", default)]
+    #[serde(rename = "Pitches", default, skip_serializing_if = "Option::is_none")]
     pub pitches: Option<String>,
-    #[serde(rename = "Direction", default)]
+    #[serde(rename = "Direction", default, skip_serializing_if = "Option::is_none")]
     pub direction: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct Pitch {
     #[serde(rename = "Step", default)]
     pub step: String,
     #[serde(rename = "Octave", default)]
     pub octave: i32,
-    #[serde(rename = "Accidental", default)]
+    #[serde(
+        rename = "Accidental",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
     pub accidental: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct TieInfo {
     #[serde(rename = "@origin", default)]
     pub origin: String,
@@ -1,41 +1,49 @@
 use super::common::EnableTag;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 // ---------------------------------------------------------------------------
 // MasterBars (measure headers)
 // ---------------------------------------------------------------------------
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct MasterBarsWrapper {
     #[serde(rename = "MasterBar", default)]
     pub master_bars: Vec<MasterBar>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct MasterBar {
-    #[serde(rename = "Key", default)]
+    #[serde(rename = "Key", default, skip_serializing_if = "Option::is_none")]
     pub key: Option<Key>,
     #[serde(rename = "Time", default)]
     pub time: String,
     #[serde(rename = "Bars", default)]
     pub bars: String,
-    #[serde(rename = "Repeat", default)]
+    #[serde(rename = "Repeat", default, skip_serializing_if = "Option::is_none")]
     pub repeat: Option<Repeat>,
-    #[serde(rename = "AlternateEndings", default)]
+    #[serde(
+        rename = "AlternateEndings",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
     pub alternate_endings: Option<String>,
-    #[serde(rename = "DoubleBar", default)]
+    #[serde(rename = "DoubleBar", default, skip_serializing_if = "Option::is_none")]
     pub double_bar: Option<String>,
-    #[serde(rename = "Section", default)]
+    #[serde(rename = "Section", default, skip_serializing_if = "Option::is_none")]
     pub section: Option<Section>,
-    #[serde(rename = "Fermatas", default)]
+    #[serde(rename = "Fermatas", default, skip_serializing_if = "Option::is_none")]
     pub fermatas: Option<FermatasWrapper>,
-    #[serde(rename = "FreeTime", default)]
+    #[serde(rename = "FreeTime", default, skip_serializing_if = "Option::is_none")]
     pub free_time: Option<String>,
-    #[serde(rename = "Directions", default)]
+    #[serde(
+        rename = "Directions",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
     pub directions: Option<DirectionsWrapper>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct Key {
     #[serde(rename = "AccidentalCount", default)]
     pub accidental_count: i32,
@@ -52,7 +60,7 @@ impl Default for Key {
     }
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct Repeat {
     #[serde(rename = "@start", default)]
     pub start: String,
@@ -62,35 +70,35 @@ pub struct Repeat {
     pub count: i32,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct Section {
-    #[serde(rename = "Letter", default)]
+    #[serde(rename = "Letter", default, skip_serializing_if = "Option::is_none")]
     pub letter: Option<String>,
-    #[serde(rename = "Text", default)]
+    #[serde(rename = "Text", default, skip_serializing_if = "Option::is_none")]
     pub text: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct DirectionsWrapper {
-    #[serde(rename = "Target", default)]
+    #[serde(rename = "Target", default, skip_serializing_if = "Option::is_none")]
     pub target: Option<String>,
-    #[serde(rename = "Jump", default)]
+    #[serde(rename = "Jump", default, skip_serializing_if = "Option::is_none")]
     pub jump: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct FermatasWrapper {
     #[serde(rename = "Fermata", default)]
     pub fermatas: Vec<Fermata>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct Fermata {
-    #[serde(rename = "Type", default)]
+    #[serde(rename = "Type", default, skip_serializing_if = "Option::is_none")]
     pub fermata_type: Option<String>,
-    #[serde(rename = "Offset", default)]
+    #[serde(rename = "Offset", default, skip_serializing_if = "Option::is_none")]
     pub offset: Option<String>,
-    #[serde(rename = "Length", default)]
+    #[serde(rename = "Length", default, skip_serializing_if = "Option::is_none")]
     pub length: Option<String>,
 }
 
@@ -98,21 +106,25 @@ pub struct Fermata {
 // Bars (per-track measures)
 // ---------------------------------------------------------------------------
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct BarsWrapper {
     #[serde(rename = "Bar", default)]
     pub bars: Vec<Bar>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct Bar {
     #[serde(rename = "@id", default)]
     pub id: i32,
     #[serde(rename = "Voices", default)]
     pub voices: String,
-    #[serde(rename = "Clef", default)]
+    #[serde(rename = "Clef", default, skip_serializing_if = "Option::is_none")]
     pub clef: Option<String>,
-    #[serde(rename = "SimileMark", default)]
+    #[serde(
+        rename = "SimileMark",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
     pub simile_mark: Option<String>,
 }
 
@@ -120,13 +132,13 @@ pub struct Bar {
 // Voices
 // ---------------------------------------------------------------------------
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct VoicesWrapper {
     #[serde(rename = "Voice", default)]
     pub voices: Vec<Voice>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct Voice {
     #[serde(rename = "@id", default)]
     pub id: i32,
@@ -138,84 +150,120 @@ pub struct Voice {
 // Beats
 // ---------------------------------------------------------------------------
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct BeatsWrapper {
     #[serde(rename = "Beat", default)]
     pub beats: Vec<Beat>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct Beat {
     #[serde(rename = "@id", default)]
     pub id: i32,
-    #[serde(rename = "Notes", default)]
+    #[serde(rename = "Notes", default, skip_serializing_if = "Option::is_none")]
     pub notes: Option<String>,
-    #[serde(rename = "Rhythm", default)]
+    #[serde(rename = "Rhythm", default, skip_serializing_if = "Option::is_none")]
     pub rhythm: Option<RhythmRef>,
-    #[serde(rename = "Dynamic", default)]
+    #[serde(rename = "Dynamic", default, skip_serializing_if = "Option::is_none")]
     pub dynamic: Option<String>,
-    #[serde(rename = "GraceNotes", default)]
+    #[serde(
+        rename = "GraceNotes",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
     pub grace_notes: Option<String>,
     /// Note: "Fadding" is a typo in the upstream GP6 XML format (should be "Fading").
-    #[serde(rename = "Fadding", default)]
+    #[serde(rename = "Fadding", default, skip_serializing_if = "Option::is_none")]
     pub fadding: Option<String>,
-    #[serde(rename = "Tremolo", default)]
+    #[serde(rename = "Tremolo", default, skip_serializing_if = "Option::is_none")]
     pub tremolo: Option<String>,
-    #[serde(rename = "Wah", default)]
+    #[serde(rename = "Wah", default, skip_serializing_if = "Option::is_none")]
     pub wah: Option<String>,
-    #[serde(rename = "FreeText", default)]
+    #[serde(rename = "FreeText", default, skip_serializing_if = "Option::is_none")]
     pub free_text: Option<String>,
-    #[serde(rename = "Arpeggio", default)]
+    #[serde(rename = "Arpeggio", default, skip_serializing_if = "Option::is_none")]
     pub arpeggio: Option<String>,
-    #[serde(rename = "Ottavia", default)]
+    #[serde(rename = "Ottavia", default, skip_serializing_if = "Option::is_none")]
     pub ottavia: Option<String>,
-    #[serde(rename = "Whammy", default)]
+    #[serde(rename = "Whammy", default, skip_serializing_if = "Option::is_none")]
     pub whammy: Option<WhammyInfo>,
-    #[serde(rename = "Properties", default)]
+    #[serde(
+        rename = "Properties",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
     pub properties: Option<BeatPropertiesWrapper>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct WhammyInfo {
-    #[serde(rename = "@originValue", default)]
+    #[serde(
+        rename = "@originValue",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
     pub origin_value: Option<f64>,
-    #[serde(rename = "@middleValue", default)]
+    #[serde(
+        rename = "@middleValue",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
     pub middle_value: Option<f64>,
-    #[serde(rename = "@destinationValue", default)]
+    #[serde(
+        rename = "@destinationValue",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
     pub destination_value: Option<f64>,
-    #[serde(rename = "@originOffset", default)]
+    #[serde(
+        rename = "@originOffset",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
     pub origin_offset: Option<f64>,
-    #[serde(rename = "@middleOffset1", default)]
+    #[serde(
+        rename = "@middleOffset1",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
     pub middle_offset1: Option<f64>,
-    #[serde(rename = "@middleOffset2", default)]
+    #[serde(
+        rename = "@middleOffset2",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
     pub middle_offset2: Option<f64>,
-    #[serde(rename = "@destinationOffset", default)]
+    #[serde(
+        rename = "@destinationOffset",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
     pub destination_offset: Option<f64>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct BeatPropertiesWrapper {
     #[serde(rename = "Property", default)]
     pub properties: Vec<BeatProperty>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct BeatProperty {
     #[serde(rename = "@name", default)]
     pub name: String,
-    #[serde(rename = "Direction", default)]
+    #[serde(rename = "Direction", default, skip_serializing_if = "Option::is_none")]
     pub direction: Option<String>,
-    #[serde(rename = "Enable", default)]
+    #[serde(rename = "Enable", default, skip_serializing_if = "Option::is_none")]
     pub enable: Option<EnableTag>,
-    #[serde(rename = "Float", default)]
+    #[serde(rename = "Float", default, skip_serializing_if = "Option::is_none")]
     pub float: Option<f64>,
-    #[serde(rename = "Flags", default)]
+    #[serde(rename = "Flags", default, skip_serializing_if = "Option::is_none")]
     pub flags: Option<i32>,
-    #[serde(rename = "Strength", default)]
+    #[serde(rename = "Strength", default, skip_serializing_if = "Option::is_none")]
     pub strength: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct RhythmRef {
     #[serde(rename = "@ref", default)]
     pub r#ref: i32,
@@ -225,31 +273,39 @@ pub struct RhythmRef {
 // Rhythms
 // ---------------------------------------------------------------------------
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct RhythmsWrapper {
     #[serde(rename = "Rhythm", default)]
     pub rhythms: Vec<Rhythm>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct Rhythm {
     #[serde(rename = "@id", default)]
     pub id: i32,
     #[serde(rename = "NoteValue", default)]
     pub note_value: String,
-    #[serde(rename = "AugmentationDot", default)]
+    #[serde(
+        rename = "AugmentationDot",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
     pub augmentation_dot: Option<AugmentationDot>,
-    #[serde(rename = "PrimaryTuplet", default)]
+    #[serde(
+        rename = "PrimaryTuplet",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
     pub primary_tuplet: Option<PrimaryTuplet>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct AugmentationDot {
     #[serde(rename = "@count", default)]
     pub count: i32,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct PrimaryTuplet {
     #[serde(rename = "@num", default)]
     pub num: i32,
@@ -1,39 +1,51 @@
 use super::common::{EnableTag, Property, TieInfo};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct NotesWrapper {
     #[serde(rename = "Note", default)]
     pub notes: Vec<Note>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct Note {
     #[serde(rename = "@id", default)]
     pub id: i32,
     #[serde(rename = "Properties")]
     pub properties: NoteProperties,
-    #[serde(rename = "Tie", default)]
+    #[serde(rename = "Tie", default, skip_serializing_if = "Option::is_none")]
     pub tie: Option<TieInfo>,
-    #[serde(rename = "Vibrato", default)]
+    #[serde(rename = "Vibrato", default, skip_serializing_if = "Option::is_none")]
     pub vibrato: Option<String>,
-    #[serde(rename = "LetRing", default)]
+    #[serde(rename = "LetRing", default, skip_serializing_if = "Option::is_none")]
     pub let_ring: Option<EnableTag>,
-    #[serde(rename = "AntiAccent", default)]
+    #[serde(
+        rename = "AntiAccent",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
     pub anti_accent: Option<String>,
-    #[serde(rename = "Accent", default)]
+    #[serde(rename = "Accent", default, skip_serializing_if = "Option::is_none")]
     pub accent: Option<i32>,
-    #[serde(rename = "Trill", default)]
+    #[serde(rename = "Trill", default, skip_serializing_if = "Option::is_none")]
     pub trill: Option<i32>,
-    #[serde(rename = "Ornament", default)]
+    #[serde(rename = "Ornament", default, skip_serializing_if = "Option::is_none")]
     pub ornament: Option<String>,
-    #[serde(rename = "LeftFingering", default)]
+    #[serde(
+        rename = "LeftFingering",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
     pub left_fingering: Option<String>,
-    #[serde(rename = "RightFingering", default)]
+    #[serde(
+        rename = "RightFingering",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
     pub right_fingering: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct NoteProperties {
     #[serde(rename = "Property", default)]
     pub properties: Vec<Property>,
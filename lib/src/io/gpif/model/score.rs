@@ -1,15 +1,19 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 use super::measure::{BarsWrapper, BeatsWrapper, MasterBarsWrapper, RhythmsWrapper, VoicesWrapper};
 use super::note::NotesWrapper;
 use super::track::TracksWrapper;
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct Gpif {
     /// GP7 uses "GPVersion", GP6 uses "GPRevision"
-    #[serde(rename = "GPVersion", default)]
+    #[serde(rename = "GPVersion", default, skip_serializing_if = "Option::is_none")]
     pub version: Option<String>,
-    #[serde(rename = "GPRevision", default)]
+    #[serde(
+        rename = "GPRevision",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
     pub revision: Option<String>,
     #[serde(rename = "Score")]
     pub score: Score,
@@ -35,7 +39,7 @@ pub struct Gpif {
 // Score metadata
 // ---------------------------------------------------------------------------
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 #[serde(rename_all = "PascalCase")]
 pub struct Score {
     #[serde(default)]
@@ -64,25 +68,29 @@ pub struct Score {
 // MasterTrack (tempo automations)
 // ---------------------------------------------------------------------------
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct MasterTrack {
     #[serde(rename = "Tracks", default)]
     pub tracks_count: String,
-    #[serde(rename = "Automations", default)]
+    #[serde(
+        rename = "Automations",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
     pub automations: Option<AutomationsWrapper>,
-    #[serde(rename = "RSE", default)]
+    #[serde(rename = "RSE", default, skip_serializing_if = "Option::is_none")]
     pub rse: Option<RseMasterWrapper>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct RseMasterWrapper {
-    #[serde(rename = "Master", default)]
+    #[serde(rename = "Master", default, skip_serializing_if = "Option::is_none")]
     pub master: Option<RseMaster>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct RseMaster {
-    #[serde(rename = "Volume", default)]
+    #[serde(rename = "Volume", default, skip_serializing_if = "Option::is_none")]
     pub volume: Option<f32>,
     #[serde(rename = "Effect", default)]
     pub effects: Vec<RseEffect>,
@@ -101,20 +109,24 @@ pub struct RseMaster {
 
 use super::track::RseEffect;
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct AutomationsWrapper {
     #[serde(rename = "Automation", default)]
     pub automations: Vec<Automation>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct Automation {
     #[serde(rename = "Type", default)]
     pub automation_type: String,
-    #[serde(rename = "Value", default)]
-    pub value: String,
+    #[serde(rename = "Linear", default, skip_serializing_if = "Option::is_none")]
+    pub linear: Option<bool>,
     #[serde(rename = "Bar", default)]
     pub bar: i32,
     #[serde(rename = "Position", default)]
     pub position: i32,
+    #[serde(rename = "Visible", default, skip_serializing_if = "Option::is_none")]
+    pub visible: Option<bool>,
+    #[serde(rename = "Value", default)]
+    pub value: String,
 }
@@ -1,12 +1,12 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct TracksWrapper {
     #[serde(rename = "Track", default)]
     pub tracks: Vec<Track>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct Track {
     #[serde(rename = "@id", default)]
     pub id: i32,
@@ -14,43 +14,73 @@ pub struct Track {
     pub name: String,
     #[serde(rename = "ShortName", default)]
     pub short_name: String,
-    #[serde(rename = "Color", default)]
+    #[serde(rename = "Color", default, skip_serializing_if = "Option::is_none")]
     pub color: Option<String>,
     /// GP6: track-level properties (Tuning, DiagramCollection, etc.)
-    #[serde(rename = "Properties", default)]
+    #[serde(
+        rename = "Properties",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
     pub properties: Option<TrackPropertiesWrapper>,
     /// GP7: staves with per-staff properties
-    #[serde(rename = "Staves", default)]
+    #[serde(rename = "Staves", default, skip_serializing_if = "Option::is_none")]
     pub staves: Option<StavesWrapper>,
-    #[serde(rename = "GeneralMidi", default)]
+    #[serde(
+        rename = "GeneralMidi",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
     pub general_midi: Option<GeneralMidi>,
-    #[serde(rename = "Transpose", default)]
+    /// GP7: MIDI port and channels
+    #[serde(
+        rename = "MidiConnection",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub midi_connection: Option<MidiConnection>,
+    /// GP7: sounds with their MIDI program
+    #[serde(rename = "Sounds", default, skip_serializing_if = "Option::is_none")]
+    pub sounds: Option<SoundsWrapper>,
+    #[serde(rename = "Transpose", default, skip_serializing_if = "Option::is_none")]
     pub transpose: Option<Transpose>,
-    #[serde(rename = "RSE", default)]
+    #[serde(rename = "RSE", default, skip_serializing_if = "Option::is_none")]
     pub rse: Option<RseTrackWrapper>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct RseTrackWrapper {
-    #[serde(rename = "ChannelStrip", default)]
+    #[serde(
+        rename = "ChannelStrip",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
     pub channel_strip: Option<ChannelStrip>,
-    #[serde(rename = "EffectChains", default)]
+    #[serde(
+        rename = "EffectChains",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
     pub effect_chains: Option<EffectChainsWrapper>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct ChannelStrip {
-    #[serde(rename = "Parameters", default)]
+    #[serde(
+        rename = "Parameters",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
     pub parameters: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct EffectChainsWrapper {
     #[serde(rename = "EffectChain", default)]
     pub effect_chains: Vec<EffectChain>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct EffectChain {
     #[serde(rename = "Name", default)]
     pub name: String,
@@ -58,7 +88,7 @@ pub struct EffectChain {
     pub rails: Vec<Rail>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct Rail {
     #[serde(rename = "@name", default)]
     pub name: String,
@@ -66,15 +96,19 @@ pub struct Rail {
     pub effects: Vec<RseEffect>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct RseEffect {
     #[serde(rename = "@id", default)]
     pub id: String,
-    #[serde(rename = "Parameters", default)]
+    #[serde(
+        rename = "Parameters",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
     pub parameters: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct TrackPropertiesWrapper {
     #[serde(rename = "Property", default)]
     pub properties: Vec<Property>,
@@ -86,40 +120,96 @@ pub struct TrackPropertiesWrapper {
 
 use super::common::Property;
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct StavesWrapper {
     #[serde(rename = "Staff", default)]
     pub staves: Vec<Staff>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct Staff {
-    #[serde(rename = "Properties", default)]
+    #[serde(
+        rename = "Properties",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
     pub properties: Option<StaffPropertiesWrapper>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct StaffPropertiesWrapper {
     #[serde(rename = "Property", default)]
     pub properties: Vec<Property>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct GeneralMidi {
-    #[serde(rename = "Program", default)]
+    #[serde(rename = "Program", default, skip_serializing_if = "Option::is_none")]
     pub program: Option<i32>,
-    #[serde(rename = "Port", default)]
+    #[serde(rename = "Port", default, skip_serializing_if = "Option::is_none")]
+    pub port: Option<i32>,
+    #[serde(
+        rename = "PrimaryChannel",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub primary_channel: Option<i32>,
+    #[serde(
+        rename = "SecondaryChannel",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub secondary_channel: Option<i32>,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct MidiConnection {
+    #[serde(rename = "Port", default, skip_serializing_if = "Option::is_none")]
     pub port: Option<i32>,
-    #[serde(rename = "PrimaryChannel", default)]
+    #[serde(
+        rename = "PrimaryChannel",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
     pub primary_channel: Option<i32>,
-    #[serde(rename = "SecondaryChannel", default)]
+    #[serde(
+        rename = "SecondaryChannel",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
     pub secondary_channel: Option<i32>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct SoundsWrapper {
+    #[serde(rename = "Sound", default)]
+    pub sounds: Vec<Sound>,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct Sound {
+    #[serde(rename = "Name", default)]
+    pub name: String,
+    #[serde(rename = "Label", default)]
+    pub label: String,
+    #[serde(rename = "MIDI", default, skip_serializing_if = "Option::is_none")]
+    pub midi: Option<SoundMidi>,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct SoundMidi {
+    #[serde(rename = "LSB", default)]
+    pub lsb: i32,
+    #[serde(rename = "MSB", default)]
+    pub msb: i32,
+    #[serde(rename = "Program", default)]
+    pub program: i32,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct Transpose {
-    #[serde(rename = "Chromatic", default)]
+    #[serde(rename = "Chromatic", default, skip_serializing_if = "Option::is_none")]
     pub chromatic: Option<i32>,
-    #[serde(rename = "Octave", default)]
+    #[serde(rename = "Octave", default, skip_serializing_if = "Option::is_none")]
     pub octave: Option<i32>,
 }
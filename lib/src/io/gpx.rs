@@ -1,27 +1,108 @@
-use crate::error::GpResult;
+use crate::error::{GpError, GpResult};
 use crate::io::gpif::model::Gpif;
+use crate::io::limits::{check, ReadLimits};
 use quick_xml::de::from_str;
-use std::io::{Cursor, Read};
-use zip::ZipArchive;
+use quick_xml::escape::unescape;
+use quick_xml::se::to_string_with_root;
+use std::collections::HashMap;
+use std::io::{Cursor, Write};
+use zip::write::FileOptions;
+use zip::{ZipArchive, ZipWriter};
 
 /// Reads a .gp (GP7+) file which is a ZIP archive containing 'Content/score.gpif'.
-pub fn read_gp(data: &[u8]) -> GpResult<Gpif> {
+pub fn read_gp(data: &[u8], limits: &ReadLimits) -> GpResult<Gpif> {
     let cursor = Cursor::new(data);
     let mut zip = ZipArchive::new(cursor).map_err(|e| format!("Zip error: {}", e))?;
 
     // Standard path for GP7 files
-    let mut file = zip
+    let file = zip
         .by_name("Content/score.gpif")
         .map_err(|e| format!("Could not find score.gpif: {}", e))?;
 
-    let mut contents = String::new();
-    file.read_to_string(&mut contents)
+    let contents = String::from_utf8(limits.read_decompressed(file)?)
         .map_err(|e| format!("Read error: {}", e))?;
 
     let gpif: Gpif = from_str(&contents).map_err(|e| format!("XML Parse error: {}", e))?;
     Ok(gpif)
 }
 
+/// Serialize a GPIF document to XML, with the declaration Guitar Pro expects.
+pub fn write_gpif_xml(gpif: &Gpif) -> GpResult<String> {
+    let body = to_string_with_root("GPIF", gpif).map_err(|e| format!("XML write error: {}", e))?;
+    Ok(format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n{}",
+        free_texts_to_cdata(&body)?
+    ))
+}
+
+/// Write the beat texts as CDATA sections, like Guitar Pro does: the XML reader trims the
+/// whitespace surrounding text content, but keeps the content of CDATA sections.
+fn free_texts_to_cdata(body: &str) -> GpResult<String> {
+    const OPEN: &str = "<FreeText>";
+    const CLOSE: &str = "</FreeText>";
+    let mut xml = String::with_capacity(body.len());
+    let mut rest = body;
+    while let Some(start) = rest.find(OPEN) {
+        let content_start = start + OPEN.len();
+        let Some(length) = rest[content_start..].find(CLOSE) else {
+            break;
+        };
+        let text = unescape(&rest[content_start..content_start + length])
+            .map_err(|e| format!("XML write error: {}", e))?;
+        xml.push_str(&rest[..content_start]);
+        xml.push_str("<![CDATA[");
+        // A CDATA section cannot hold its end marker: split it over two sections
+        xml.push_str(&text.replace("]]>", "]]]]><![CDATA[>"));
+        xml.push_str("]]>");
+        rest = &rest[content_start + length..];
+    }
+    xml.push_str(rest);
+    Ok(xml)
+}
+
+/// Build the `Content/PartConfiguration` entry: one view with every track, then one view per track.
+///
+/// Each view is a big-endian structure: multi-rest flag (`byte`), track count (`int`) and
+/// one flag byte per track (0x01: standard notation, 0x02: tablature).
+fn part_configuration(track_count: usize) -> Vec<u8> {
+    let mut data = Vec::new();
+    data.extend_from_slice(&(track_count as i32 + 1).to_be_bytes());
+    let mut write_view = |tracks: usize| {
+        data.push(0);
+        data.extend_from_slice(&(tracks as i32).to_be_bytes());
+        data.extend(std::iter::repeat_n(0x03, tracks));
+    };
+    write_view(track_count);
+    for _ in 0..track_count {
+        write_view(1);
+    }
+    data.extend_from_slice(&0i32.to_be_bytes());
+    data
+}
+
+/// Writes a .gp (GP7+) file: a ZIP archive holding 'Content/score.gpif'.
+pub fn write_gp(gpif: &Gpif) -> GpResult<Vec<u8>> {
+    let xml = write_gpif_xml(gpif)?;
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = FileOptions::default();
+    let entries: [(&str, &[u8]); 3] = [
+        ("VERSION", b"7.0"),
+        ("Content/score.gpif", xml.as_bytes()),
+        (
+            "Content/PartConfiguration",
+            &part_configuration(gpif.tracks.tracks.len()),
+        ),
+    ];
+    for (name, content) in entries {
+        zip.start_file(name, options)
+            .map_err(|e| format!("Zip error: {}", e))?;
+        zip.write_all(content)
+            .map_err(|e| format!("Zip write error: {}", e))?;
+    }
+    let cursor = zip.finish().map_err(|e| format!("Zip error: {}", e))?;
+    Ok(cursor.into_inner())
+}
+
 // ---------------------------------------------------------------------------
 // GP6 (.gpx) BCFZ/BCFS container support
 // ---------------------------------------------------------------------------
@@ -83,21 +164,23 @@ impl<'a> BitStream<'a> {
     }
 }
 
-/// Decompress a BCFZ-compressed buffer into raw BCFS data.
-fn decompress_bcfz(data: &[u8]) -> Result<Vec<u8>, String> {
+/// Decompress a BCFZ-compressed buffer into raw BCFS data of at most `max_size` bytes.
+pub(crate) fn decompress_bcfz(data: &[u8], max_size: usize) -> GpResult<Vec<u8>> {
     if data.len() < 8 {
-        return Err("BCFZ data too short".to_string());
+        return Err("BCFZ data too short".to_string().into());
     }
     if &data[0..4] != BCFZ_MAGIC {
-        return Err(format!("Expected BCFZ magic, got {:?}", &data[0..4]));
+        return Err(format!("Expected BCFZ magic, got {:?}", &data[0..4]).into());
     }
 
     let raw_len = i32::from_le_bytes([data[4], data[5], data[6], data[7]]);
     if raw_len < 0 {
-        return Err(format!("BCFZ: negative expected length {}", raw_len));
+        return Err(format!("BCFZ: negative expected length {}", raw_len).into());
     }
     let expected_len = raw_len as usize;
-    let mut output = Vec::with_capacity(expected_len);
+    check("decompressed bytes", expected_len, max_size)?;
+    // The expected length comes from the file: only reserve what the data can hold
+    let mut output = Vec::with_capacity(expected_len.min(data.len().saturating_mul(8)));
     let mut bits = BitStream::new(&data[8..]);
 
     while output.len() < expected_len && !bits.is_eof() {
@@ -112,7 +195,8 @@ fn decompress_bcfz(data: &[u8]) -> Result<Vec<u8>, String> {
                     "BCFZ: invalid back-reference offset {} (output len {})",
                     offset,
                     output.len()
-                ));
+                )
+                .into());
             }
             let source_start = output.len() - offset;
             // LZ77 overlapping copy: when size > offset the source overlaps
@@ -141,37 +225,33 @@ struct BcfsFile {
     data: Vec<u8>,
 }
 
-/// Read the integer at the given offset (little-endian i32).
-fn read_le_i32(data: &[u8], offset: usize) -> i32 {
-    i32::from_le_bytes([
-        data[offset],
-        data[offset + 1],
-        data[offset + 2],
-        data[offset + 3],
-    ])
+/// Read the integer at the given offset (little-endian i32), `None` past the end of the data.
+fn read_le_i32(data: &[u8], offset: usize) -> Option<i32> {
+    let bytes = data.get(offset..offset.checked_add(4)?)?;
+    Some(i32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
 }
 
-/// Parse the BCFS virtual filesystem and extract all files.
+/// Parse the BCFS virtual filesystem and extract all files, of at most `max_size` bytes
+/// together.
 ///
 /// The BCFS format starts with a 4-byte magic ("BCFS"), followed by sector-based data.
 /// The Java reference implementation (TuxGuitar) strips the 4-byte magic and then treats
 /// the remaining data as a virtual disk with 0x1000-byte sectors.
-fn parse_bcfs(data: &[u8]) -> Result<Vec<BcfsFile>, String> {
+fn parse_bcfs(data: &[u8], max_size: usize) -> GpResult<Vec<BcfsFile>> {
     if data.len() < 4 {
-        return Err("BCFS data too short".to_string());
+        return Err("BCFS data too short".to_string().into());
     }
     if &data[0..4] != BCFS_MAGIC {
-        return Err(format!("Expected BCFS magic, got {:?}", &data[0..4]));
+        return Err(format!("Expected BCFS magic, got {:?}", &data[0..4]).into());
     }
 
     // Strip the 4-byte magic — all sector offsets are relative to this base.
     let disk = &data[4..];
     let mut files = Vec::new();
+    let mut extracted = 0usize;
     let mut sector_offset = SECTOR_SIZE; // Skip sector 0 (header area)
 
-    while sector_offset + 3 < disk.len() {
-        let entry_type = read_le_i32(disk, sector_offset);
-
+    while let Some(entry_type) = read_le_i32(disk, sector_offset) {
         if entry_type == 2 {
             // File directory entry — requires at least 0x98 bytes from sector_offset
             if sector_offset + 0x98 > disk.len() {
@@ -188,22 +268,29 @@ fn parse_bcfs(data: &[u8]) -> Result<Vec<BcfsFile>, String> {
                 .unwrap_or(name_bytes.len());
             let name = String::from_utf8_lossy(&name_bytes[..name_len]).to_string();
 
-            let file_size = read_le_i32(disk, sector_offset + 0x8C) as usize;
+            let file_size = read_le_i32(disk, sector_offset + 0x8C).unwrap_or(0);
+            let file_size = usize::try_from(file_size)
+                .map_err(|_| format!("BCFS: negative size {} for file {}", file_size, name))?;
 
             // Block index table at +0x94, array of i32, terminated by 0
-            let mut file_data = Vec::with_capacity(file_size);
+            let mut file_data = Vec::with_capacity(file_size.min(disk.len()));
             let mut idx_offset = sector_offset + 0x94;
-            loop {
-                if idx_offset + 4 > sector_offset + SECTOR_SIZE {
-                    break;
-                }
-                let block_idx = read_le_i32(disk, idx_offset);
-                if block_idx == 0 {
-                    break;
-                }
-                let block_start = block_idx as usize * SECTOR_SIZE;
-                let block_end = (block_start + SECTOR_SIZE).min(disk.len());
+            while idx_offset + 4 <= sector_offset + SECTOR_SIZE {
+                let block_idx = match read_le_i32(disk, idx_offset) {
+                    Some(0) | None => break,
+                    Some(block_idx) => block_idx,
+                };
+                let block_start = usize::try_from(block_idx)
+                    .ok()
+                    .and_then(|block| block.checked_mul(SECTOR_SIZE))
+                    .ok_or_else(|| {
+                        format!("BCFS: invalid block index {} for file {}", block_idx, name)
+                    })?;
                 if block_start < disk.len() {
+                    let block_end = (block_start + SECTOR_SIZE).min(disk.len());
+                    // Blocks can be shared by files: count what is extracted, not the disk
+                    extracted += block_end - block_start;
+                    check("decompressed bytes", extracted, max_size)?;
                     file_data.extend_from_slice(&disk[block_start..block_end]);
                 }
                 idx_offset += 4;
@@ -224,10 +311,15 @@ fn parse_bcfs(data: &[u8]) -> Result<Vec<BcfsFile>, String> {
     Ok(files)
 }
 
-/// Reads a .gpx (GP6) file which is a BCFZ/BCFS container holding 'score.gpif'.
-pub fn read_gpx(data: &[u8]) -> GpResult<Gpif> {
-    let decompressed = decompress_bcfz(data)?;
-    let files = parse_bcfs(&decompressed)?;
+/// Reads a .gpx (GP6) file which is a BCFZ/BCFS container holding 'score.gpif'. An
+/// uncompressed BCFS container is read as is.
+pub fn read_gpx(data: &[u8], limits: &ReadLimits) -> GpResult<Gpif> {
+    let files = if data.starts_with(BCFS_MAGIC) {
+        parse_bcfs(data, limits.max_decompressed_size)?
+    } else {
+        let decompressed = decompress_bcfz(data, limits.max_decompressed_size)?;
+        parse_bcfs(&decompressed, limits.max_decompressed_size)?
+    };
 
     let score_file = files
         .iter()
@@ -248,3 +340,211 @@ pub fn read_gpx(data: &[u8]) -> GpResult<Gpif> {
 
     Ok(gpif)
 }
+
+/// Bit-level writer for BCFZ compression, the counterpart of `BitStream`.
+/// Writes bits MSB-first within each byte.
+struct BitWriter {
+    data: Vec<u8>,
+    bit_position: usize,
+}
+
+impl BitWriter {
+    fn new() -> Self {
+        BitWriter {
+            data: Vec::new(),
+            bit_position: 0,
+        }
+    }
+
+    /// Write a single bit (MSB-first within the current byte).
+    fn write_bit(&mut self, bit: u32) {
+        if self.bit_position.is_multiple_of(8) {
+            self.data.push(0);
+        }
+        if bit & 1 != 0 {
+            let last = self.data.len() - 1;
+            self.data[last] |= 1 << (7 - (self.bit_position % 8));
+        }
+        self.bit_position += 1;
+    }
+
+    /// Write the `count` low bits of `value`, big-endian (MSB first).
+    fn write_bits(&mut self, value: u32, count: usize) {
+        for i in (0..count).rev() {
+            self.write_bit(value >> i);
+        }
+    }
+
+    /// Write the `count` low bits of `value`, little-endian (LSB first / "reversed").
+    fn write_bits_reversed(&mut self, value: u32, count: usize) {
+        for i in 0..count {
+            self.write_bit(value >> i);
+        }
+    }
+}
+
+/// Largest offset or length a BCFZ back-reference can encode (4-bit word size).
+const BCFZ_MAX_WORD: usize = (1 << 15) - 1;
+/// Shortest back-reference worth writing: 4 literal bytes cost more bits than any reference.
+const BCFZ_MIN_MATCH: usize = 4;
+/// Number of previous occurrences examined when looking for a back-reference.
+const BCFZ_MAX_CANDIDATES: usize = 32;
+
+/// Compress raw BCFS data into a BCFZ buffer.
+///
+/// Greedy LZ77: each position takes the longest back-reference found among recent
+/// occurrences of its first bytes, otherwise the byte is queued as a literal.
+pub(crate) fn compress_bcfz(data: &[u8]) -> Vec<u8> {
+    let mut bits = BitWriter::new();
+    let mut literals: Vec<u8> = Vec::new();
+    let flush_literals = |bits: &mut BitWriter, literals: &mut Vec<u8>| {
+        for chunk in literals.chunks(3) {
+            bits.write_bit(0);
+            bits.write_bits_reversed(chunk.len() as u32, 2);
+            for &byte in chunk {
+                bits.write_bits(byte as u32, 8);
+            }
+        }
+        literals.clear();
+    };
+
+    // Hash chains over the first BCFZ_MIN_MATCH bytes of every position
+    let key = |pos: usize| -> u32 {
+        u32::from_le_bytes([data[pos], data[pos + 1], data[pos + 2], data[pos + 3]])
+    };
+    let mut heads: HashMap<u32, usize> = HashMap::new();
+    let mut previous: Vec<usize> = vec![usize::MAX; data.len()];
+    let insert = |pos: usize, heads: &mut HashMap<u32, usize>, previous: &mut Vec<usize>| {
+        if pos + BCFZ_MIN_MATCH <= data.len() {
+            if let Some(head) = heads.insert(key(pos), pos) {
+                previous[pos] = head;
+            }
+        }
+    };
+
+    let mut pos = 0;
+    while pos < data.len() {
+        let mut best = (0, 0);
+        if pos + BCFZ_MIN_MATCH <= data.len() {
+            let max_len = (data.len() - pos).min(BCFZ_MAX_WORD);
+            let mut candidate = heads.get(&key(pos)).copied();
+            for _ in 0..BCFZ_MAX_CANDIDATES {
+                let Some(start) = candidate else { break };
+                let offset = pos - start;
+                if offset > BCFZ_MAX_WORD {
+                    break;
+                }
+                // Overlapping copies are allowed, like in the decompressor
+                let len = (0..max_len)
+                    .take_while(|&i| data[start + i] == data[pos + i])
+                    .count();
+                if len > best.1 {
+                    best = (offset, len);
+                }
+                candidate = Some(previous[start]).filter(|&p| p != usize::MAX);
+            }
+        }
+
+        let (offset, len) = best;
+        if len >= BCFZ_MIN_MATCH {
+            flush_literals(&mut bits, &mut literals);
+            let word_size = (usize::BITS - offset.max(len).leading_zeros()) as usize;
+            bits.write_bit(1);
+            bits.write_bits(word_size as u32, 4);
+            bits.write_bits_reversed(offset as u32, word_size);
+            bits.write_bits_reversed(len as u32, word_size);
+            for p in pos..pos + len {
+                insert(p, &mut heads, &mut previous);
+            }
+            pos += len;
+        } else {
+            literals.push(data[pos]);
+            insert(pos, &mut heads, &mut previous);
+            pos += 1;
+        }
+    }
+    flush_literals(&mut bits, &mut literals);
+
+    let mut output = Vec::with_capacity(8 + bits.data.len());
+    output.extend_from_slice(BCFZ_MAGIC);
+    output.extend_from_slice(&(data.len() as i32).to_le_bytes());
+    output.extend_from_slice(&bits.data);
+    output
+}
+
+/// Maximum number of data blocks listed in a BCFS file entry (block table from +0x94,
+/// terminated by 0, within one sector).
+const BCFS_MAX_BLOCKS: usize = (SECTOR_SIZE - 0x94) / 4 - 1;
+
+/// Write the entry header shared by directories (type 1) and files (type 2).
+fn write_bcfs_entry(sector: &mut [u8], entry_type: i32, name: &str, size: usize, blocks: &[usize]) {
+    sector[0..4].copy_from_slice(&entry_type.to_le_bytes());
+    let name = name.as_bytes();
+    let name_len = name.len().min(127);
+    sector[4..4 + name_len].copy_from_slice(&name[..name_len]);
+    sector[0x88..0x8C].copy_from_slice(&1i32.to_le_bytes());
+    sector[0x8C..0x90].copy_from_slice(&(size as i32).to_le_bytes());
+    for (i, &block) in blocks.iter().enumerate() {
+        let at = 0x94 + i * 4;
+        sector[at..at + 4].copy_from_slice(&(block as i32).to_le_bytes());
+    }
+}
+
+/// Build a BCFS virtual filesystem holding `files` in its root directory.
+///
+/// Layout: sector 0 is the header, sector 1 the root directory entry, sector 2 the list
+/// of file entry sectors, then every file as an entry sector followed by its data blocks.
+fn build_bcfs(files: &[(&str, &[u8])]) -> GpResult<Vec<u8>> {
+    let mut sectors: Vec<Vec<u8>> = Vec::new();
+    let mut header = vec![0xff; SECTOR_SIZE];
+    header[0..3].copy_from_slice(&[0x00, 0x00, 0xfe]);
+    sectors.push(header);
+    sectors.push(vec![0; SECTOR_SIZE]);
+    sectors.push(vec![0; SECTOR_SIZE]);
+
+    let mut entry_sectors = Vec::with_capacity(files.len());
+    for (name, content) in files {
+        let block_count = content.len().div_ceil(SECTOR_SIZE);
+        if block_count > BCFS_MAX_BLOCKS {
+            return Err(GpError::FormatError(format!(
+                "{} is too large for a GPX archive ({} bytes)",
+                name,
+                content.len()
+            )));
+        }
+        let entry_sector = sectors.len();
+        let blocks: Vec<usize> = (entry_sector + 1..=entry_sector + block_count).collect();
+        let mut entry = vec![0; SECTOR_SIZE];
+        write_bcfs_entry(&mut entry, 2, name, content.len(), &blocks);
+        sectors.push(entry);
+        for chunk in content.chunks(SECTOR_SIZE) {
+            let mut block = chunk.to_vec();
+            block.resize(SECTOR_SIZE, 0);
+            sectors.push(block);
+        }
+        entry_sectors.push(entry_sector);
+    }
+
+    write_bcfs_entry(&mut sectors[1], 1, "/", entry_sectors.len() * 4, &[2]);
+    for (i, &entry_sector) in entry_sectors.iter().enumerate() {
+        sectors[2][i * 4..i * 4 + 4].copy_from_slice(&(entry_sector as i32).to_le_bytes());
+    }
+
+    let mut data = Vec::with_capacity(4 + sectors.len() * SECTOR_SIZE);
+    data.extend_from_slice(BCFS_MAGIC);
+    for sector in sectors {
+        data.extend(sector);
+    }
+    Ok(data)
+}
+
+/// Writes a .gpx (GP6) file: 'score.gpif' stored in a BCFS filesystem, compressed to BCFZ.
+pub fn write_gpx(gpif: &Gpif) -> GpResult<Vec<u8>> {
+    let xml = write_gpif_xml(gpif)?;
+    let part_configuration = part_configuration(gpif.tracks.tracks.len());
+    let files: [(&str, &[u8]); 2] = [
+        ("score.gpif", xml.as_bytes()),
+        ("PartConfiguration", &part_configuration),
+    ];
+    Ok(compress_bcfz(&build_bcfs(&files)?))
+}
@@ -0,0 +1,115 @@
+// Limits of the readers, guarding against files crafted to exhaust memory or time
+use std::io::Read;
+
+use crate::error::{GpError, GpResult};
+use crate::model::song::Song;
+
+/// Maximum sizes of what the readers accept. Reading a file past one of them fails with
+/// `GpError::LimitExceeded`. Pass them to `Song::from_bytes_with_limits` or
+/// `Song::open_with_limits`; the `Song::read_*` methods use the defaults.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReadLimits {
+    /// Bytes decompressed from a GPX container or extracted from a ZIP archive (.gp, .mscz, .mxl)
+    pub max_decompressed_size: usize,
+    pub max_tracks: usize,
+    /// Measures of the song, i.e. measure headers
+    pub max_measures: usize,
+    /// Beats of the song, over all tracks, measures and voices
+    pub max_beats: usize,
+    /// Notes of the song, over all beats
+    pub max_notes: usize,
+    /// Characters of a text: song information, track names, lyrics, markers, beat texts
+    pub max_string_length: usize,
+}
+
+impl Default for ReadLimits {
+    fn default() -> Self {
+        ReadLimits {
+            max_decompressed_size: 64 * 1024 * 1024,
+            max_tracks: 1024,
+            max_measures: 16_384,
+            max_beats: 2_000_000,
+            max_notes: 4_000_000,
+            max_string_length: 65_536,
+        }
+    }
+}
+
+/// Fail with `GpError::LimitExceeded` when `count` is over `max`.
+pub(crate) fn check(limit: &'static str, count: usize, max: usize) -> GpResult<()> {
+    if count > max {
+        return Err(GpError::LimitExceeded { limit, max });
+    }
+    Ok(())
+}
+
+impl ReadLimits {
+    /// Read a decompressed stream to its end, failing past the maximum decompressed size.
+    pub(crate) fn read_decompressed(&self, reader: impl Read) -> GpResult<Vec<u8>> {
+        let mut contents = Vec::new();
+        reader
+            .take(self.max_decompressed_size as u64 + 1)
+            .read_to_end(&mut contents)
+            .map_err(|e| format!("Read error: {}", e))?;
+        check(
+            "decompressed bytes",
+            contents.len(),
+            self.max_decompressed_size,
+        )?;
+        Ok(contents)
+    }
+
+    /// Check the song read against the limits: tracks, measures, beats, notes and texts.
+    pub(crate) fn check_song(&self, song: &Song) -> GpResult<()> {
+        check("tracks", song.tracks.len(), self.max_tracks)?;
+        check("measures", song.measure_headers.len(), self.max_measures)?;
+        let voices = song
+            .tracks
+            .iter()
+            .flat_map(|t| &t.measures)
+            .flat_map(|m| &m.voices);
+        let (mut beats, mut notes) = (0, 0);
+        for voice in voices {
+            beats += voice.beats.len();
+            notes += voice.beats.iter().map(|b| b.notes.len()).sum::<usize>();
+        }
+        check("beats", beats, self.max_beats)?;
+        check("notes", notes, self.max_notes)?;
+
+        let info = [
+            &song.name,
+            &song.subtitle,
+            &song.artist,
+            &song.album,
+            &song.words,
+            &song.author,
+            &song.date,
+            &song.copyright,
+            &song.writer,
+            &song.transcriber,
+            &song.instructions,
+            &song.comments,
+            &song.tempo_name,
+        ];
+        let texts = info
+            .into_iter()
+            .chain(&song.notice)
+            .chain(song.lyrics.lines.iter().map(|(_, _, text)| text))
+            .chain(song.tracks.iter().flat_map(|t| [&t.name, &t.short_name]))
+            .chain(
+                song.measure_headers
+                    .iter()
+                    .filter_map(|h| h.marker.as_ref().map(|m| &m.title)),
+            )
+            .chain(
+                song.tracks
+                    .iter()
+                    .flat_map(|t| &t.measures)
+                    .flat_map(|m| &m.voices)
+                    .flat_map(|v| &v.beats)
+                    .map(|b| &b.text),
+            );
+        let longest = texts.map(|t| t.chars().count()).max().unwrap_or(0);
+        check("characters in a text", longest, self.max_string_length)
+    }
+}
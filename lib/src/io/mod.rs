@@ -1,5 +1,11 @@
 pub mod primitive;
+pub mod ascii_tab;
 pub mod gpif;
 pub mod gpif_import;
+pub mod format;
 pub mod gpx;
 pub mod gp345;
+pub mod limits;
+pub mod mscz;
+pub mod musicxml;
+pub mod xml;
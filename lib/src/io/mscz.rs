@@ -0,0 +1,637 @@
+// MuseScore (.mscz/.mscx) reader
+use std::collections::HashMap;
+use std::io::Cursor;
+
+use zip::ZipArchive;
+
+use crate::error::GpResult;
+use crate::io::limits::ReadLimits;
+use crate::io::musicxml::import::{
+    build_tracks, ending_mask, fill_durations, header_at, reset_song, set_tempo, NoteEvent,
+    ReadPart, Syllable, MAX_VOICES,
+};
+use crate::io::musicxml::{note_type_value, sign_direction, DIRECTION_SIGNS};
+use crate::io::xml::{parse_xml, XmlNode};
+use crate::model::key_signature::{Duration, DURATION_QUARTER_TIME};
+use crate::model::note::Note;
+use crate::model::song::Song;
+use crate::types::effects::{BendEffect, BendPoint, HarmonicEffect, BEND_EFFECT_MAX_POSITION};
+use crate::types::enums::{BendType, DirectionSign, HarmonicType, MeasureClef, NoteType};
+use crate::types::measure::{Marker, MeasureHeader};
+
+/// Chord children marking a grace note.
+const GRACE_TYPES: [&str; 8] = [
+    "acciaccatura",
+    "appoggiatura",
+    "grace4",
+    "grace16",
+    "grace32",
+    "grace8after",
+    "grace16after",
+    "grace32after",
+];
+
+/// Ticks of a fraction of a whole note, as written in `<duration>` and `<fractions>` ("3/4").
+fn fraction_ticks(text: &str) -> Option<i64> {
+    let (numerator, denominator) = text.split_once('/')?;
+    let numerator = numerator.trim().parse::<i64>().ok()?;
+    let denominator = denominator.trim().parse::<i64>().ok().filter(|d| *d > 0)?;
+    Some(numerator * DURATION_QUARTER_TIME * 4 / denominator)
+}
+
+/// Note value of a `<durationType>`. Breves and longs are read as whole notes.
+fn duration_value(name: &str) -> Option<u16> {
+    match name {
+        "breve" | "long" => Some(1),
+        _ => note_type_value(name),
+    }
+}
+
+fn read_metadata(song: &mut Song, score: &XmlNode) {
+    for tag in score.children("metaTag") {
+        let text = tag.text.trim().to_string();
+        match tag.attribute("name") {
+            Some("workTitle") => song.name = text,
+            Some("subtitle") => song.subtitle = text,
+            Some("composer") => song.author = text,
+            Some("lyricist") => song.words = text,
+            Some("copyright") => song.copyright = text,
+            _ => {}
+        }
+    }
+    if song.name.is_empty() {
+        // Scores without a work title show it in the title frame of the first staff
+        let title = score
+            .children("Staff")
+            .flat_map(|s| s.children("VBox"))
+            .flat_map(|b| b.children("Text"))
+            .find(|t| t.child_text("style") == Some("Title"));
+        if let Some(title) = title.and_then(|t| t.child_text("text")) {
+            song.name = title.to_string();
+        }
+    }
+}
+
+/// Names, strings and MIDI settings of a `<Part>`'s instrument.
+fn read_instrument(part: &XmlNode, read: &mut ReadPart) {
+    let instrument = part.child("Instrument");
+    let name = part
+        .child_text("trackName")
+        .filter(|n| !n.is_empty())
+        .or_else(|| instrument.and_then(|i| i.child_text("trackName")))
+        .or_else(|| instrument.and_then(|i| i.child_text("longName")));
+    read.info.name = name.unwrap_or_default().to_string();
+    let Some(instrument) = instrument else {
+        return;
+    };
+    read.info.abbreviation = instrument
+        .child_text("shortName")
+        .unwrap_or_default()
+        .to_string();
+    read.percussion = instrument.child_text("useDrumset") == Some("1");
+    if let Some(strings) = instrument.child("StringData") {
+        read.fret_count = strings.child_value::<u8>("frets");
+        read.tuning = strings
+            .children("string")
+            .filter_map(|s| s.text.trim().parse::<i8>().ok())
+            .collect();
+    }
+    if let Some(channel) = instrument.child("Channel") {
+        read.info.program = channel
+            .child("program")
+            .and_then(|p| p.attribute("value"))
+            .and_then(|v| v.parse::<u8>().ok());
+        read.info.channel = channel.child_value::<u8>("midiChannel").map(|c| c.min(15));
+        for controller in channel.children("controller") {
+            let value = controller
+                .attribute("value")
+                .and_then(|v| v.parse::<i8>().ok());
+            match controller.attribute("ctrl") {
+                Some("7") => read.info.volume = value,
+                Some("10") => read.info.balance = value,
+                _ => {}
+            }
+        }
+    }
+}
+
+/// The clef of a `<Clef>` or `<ClefType>`, `None` for TAB and percussion clefs.
+fn clef(node: &XmlNode) -> Option<MeasureClef> {
+    let kind = node
+        .child_text("concertClefType")
+        .or_else(|| node.child_text("subtype"))
+        .unwrap_or(node.text.trim());
+    match kind {
+        k if k.starts_with('G') => Some(MeasureClef::Treble),
+        k if k.starts_with('F') => Some(MeasureClef::Bass),
+        "C4" | "C4_8vb" => Some(MeasureClef::Tenor),
+        k if k.starts_with('C') => Some(MeasureClef::Alto),
+        _ => None,
+    }
+}
+
+/// The segno or coda of a `<Marker>`.
+fn marker_sign(marker: &XmlNode) -> Option<DirectionSign> {
+    let kind = marker
+        .child_text("subtype")
+        .or_else(|| marker.child_text("label"))?
+        .to_ascii_lowercase();
+    match kind.as_str() {
+        "segno" => Some(DirectionSign::Segno),
+        "varsegno" => Some(DirectionSign::SegnoSegno),
+        "coda" | "codab" => Some(DirectionSign::Coda),
+        "varcoda" | "codetta" => Some(DirectionSign::DoubleCoda),
+        "fine" => Some(DirectionSign::Fine),
+        "tocoda" | "tocodasym" => Some(DirectionSign::DaCoda),
+        _ => None,
+    }
+}
+
+/// The jump of a `<Jump>`, from its targets or, failing that, its text.
+fn jump_sign(jump: &XmlNode) -> Option<DirectionSign> {
+    let target = |name: &str| {
+        jump.child_text(name)
+            .unwrap_or_default()
+            .to_ascii_lowercase()
+    };
+    let (to, until, continue_at) = (target("jumpTo"), target("playUntil"), target("continueAt"));
+    let until = match until.as_str() {
+        "" | "end" => "",
+        "fine" => "fine",
+        _ if until.starts_with("var") || continue_at.starts_with("var") => "double",
+        _ => "coda",
+    };
+    let sign = match (to.as_str(), until) {
+        ("start", "") => DirectionSign::DaCapo,
+        ("start", "fine") => DirectionSign::DaCapoAlFine,
+        ("start", "coda") => DirectionSign::DaCapoAlCoda,
+        ("start", "double") => DirectionSign::DaCapoAlDoubleCoda,
+        ("segno", "") => DirectionSign::DaSegno,
+        ("segno", "fine") => DirectionSign::DaSegnoAlFine,
+        ("segno", "coda") => DirectionSign::DaSegnoAlCoda,
+        ("segno", "double") => DirectionSign::DaSegnoAlDoubleCoda,
+        ("varsegno", "") => DirectionSign::DaSegnoSegno,
+        ("varsegno", "fine") => DirectionSign::DaSegnoSegnoAlFine,
+        ("varsegno", "coda") => DirectionSign::DaSegnoSegnoAlCoda,
+        ("varsegno", "double") => DirectionSign::DaSegnoSegnoAlDoubleCoda,
+        _ => {
+            let text = jump.child_text("text")?;
+            return DIRECTION_SIGNS
+                .iter()
+                .find(|sign| sign_direction(sign).text.eq_ignore_ascii_case(text))
+                .cloned();
+        }
+    };
+    Some(sign)
+}
+
+/// A `<Bend>`: points at `time` 0..60 with `pitch` in hundredths of a tone.
+fn read_bend(bend: &XmlNode) -> Option<BendEffect> {
+    let points: Vec<BendPoint> = bend
+        .children("point")
+        .filter_map(|point| {
+            let time = point.attribute("time")?.parse::<i32>().ok()?;
+            let pitch = point.attribute("pitch")?.parse::<i32>().ok()?;
+            Some(BendPoint {
+                position: (time * i32::from(BEND_EFFECT_MAX_POSITION) / 60)
+                    .clamp(0, i32::from(BEND_EFFECT_MAX_POSITION)) as u8,
+                value: (pitch / 25).clamp(i32::from(i8::MIN), i32::from(i8::MAX)) as i8,
+                vibrato: point.attribute("vibrato") == Some("1"),
+            })
+        })
+        .collect();
+    let highest = points.iter().map(|p| p.value).max()?;
+    if highest <= 0 {
+        return None;
+    }
+    let kind = match (points.first()?.value, points.last()?.value) {
+        (first, last) if first > 0 && last < first => BendType::PrebendRelease,
+        (first, _) if first > 0 => BendType::Prebend,
+        (_, last) if last < highest => BendType::BendRelease,
+        _ => BendType::Bend,
+    };
+    Some(BendEffect {
+        kind,
+        value: i16::from(highest) * 25,
+        points,
+        ..Default::default()
+    })
+}
+
+/// Staff texts waiting for the next chord of a voice.
+#[derive(Default)]
+struct Words {
+    text: String,
+    palm_mute: bool,
+    let_ring: bool,
+}
+
+/// State of the staff being read.
+#[derive(Default)]
+struct StaffReader {
+    clef: MeasureClef,
+    /// Volta bitmask and the index of the measure after its end
+    ending: (u8, usize),
+    words: Vec<Words>,
+    /// Palm mute and let ring spanners open in each voice
+    palm_mute: Vec<bool>,
+    let_ring: Vec<bool>,
+    max_beats: usize,
+}
+
+impl StaffReader {
+    fn read_staff(
+        &mut self,
+        song: &mut Song,
+        staff: &XmlNode,
+        read: &mut ReadPart,
+    ) -> GpResult<()> {
+        self.words = (0..MAX_VOICES).map(|_| Words::default()).collect();
+        self.palm_mute = vec![false; MAX_VOICES];
+        self.let_ring = vec![false; MAX_VOICES];
+        for (m, measure) in staff.children("Measure").enumerate() {
+            header_at(&mut song.measure_headers, m);
+            // MuseScore 2 writes the content of the single voice in the measure itself
+            let mut voices: Vec<&XmlNode> = measure.children("voice").collect();
+            if voices.is_empty() {
+                voices.push(measure);
+            }
+            let mut events = Vec::new();
+            for (v, voice) in voices.into_iter().enumerate().take(MAX_VOICES) {
+                self.read_voice(song, voice, m, v, &mut events)?;
+                read.voice_count = read.voice_count.max(v + 1);
+            }
+            for item in &measure.children {
+                self.read_measure_element(&mut song.measure_headers[m], item);
+            }
+            if m < self.ending.1 {
+                song.measure_headers[m].repeat_alternative |= self.ending.0;
+            }
+            read.measures.push(events);
+            read.clefs.push(self.clef.clone());
+        }
+        Ok(())
+    }
+
+    /// Repeats, markers and jumps, written in the measure or in its first voice.
+    fn read_measure_element(&mut self, header: &mut MeasureHeader, item: &XmlNode) {
+        match item.name.as_str() {
+            "startRepeat" => header.repeat_open = true,
+            "endRepeat" => {
+                let count = item.text.trim().parse::<i32>().unwrap_or(2);
+                header.repeat_close = (count - 1).clamp(0, i32::from(i8::MAX)) as i8;
+            }
+            "Marker" => {
+                if let Some(sign) = marker_sign(item) {
+                    header.direction = Some(sign);
+                }
+            }
+            "Jump" => {
+                if let Some(sign) = jump_sign(item) {
+                    header.direction = Some(sign);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn read_voice(
+        &mut self,
+        song: &mut Song,
+        voice: &XmlNode,
+        m: usize,
+        v: usize,
+        events: &mut Vec<NoteEvent>,
+    ) -> GpResult<()> {
+        let mut tick = 0i64;
+        // (actual notes, normal notes) of the open tuplets, innermost last
+        let mut tuplets: Vec<(u8, u8)> = Vec::new();
+        for item in &voice.children {
+            let header = &mut song.measure_headers[m];
+            match item.name.as_str() {
+                "TimeSig" => {
+                    if let Some(numerator) = item.child_value::<i8>("sigN") {
+                        header.time_signature.numerator = numerator.max(1);
+                    }
+                    if let Some(denominator) = item.child_value::<u16>("sigD") {
+                        header.time_signature.denominator.value = denominator.max(1);
+                    }
+                }
+                "KeySig" => {
+                    let key = item
+                        .child_value::<i8>("concertKey")
+                        .or_else(|| item.child_value::<i8>("accidental"));
+                    if let Some(key) = key {
+                        header.key_signature.key = key.clamp(-7, 7);
+                    }
+                    header.key_signature.is_minor = item.child_text("mode") == Some("minor");
+                }
+                "Clef" => {
+                    if let Some(clef) = clef(item) {
+                        self.clef = clef;
+                    }
+                }
+                "Tempo" => {
+                    // Quarter notes per second
+                    if let Some(tempo) = item.child_value::<f64>("tempo") {
+                        set_tempo(song, m, tick.max(0) as u64, (tempo * 60.0).round() as i32);
+                    }
+                }
+                "RehearsalMark" => {
+                    header.marker = Some(Marker {
+                        title: item.child_text("text").unwrap_or_default().to_string(),
+                        ..Default::default()
+                    })
+                }
+                "StaffText" | "SystemText" => {
+                    let text = item.child_text("text").unwrap_or_default();
+                    let words = &mut self.words[v];
+                    match text.to_ascii_lowercase().as_str() {
+                        "p.m." | "pm" | "p.m" | "palm mute" => words.palm_mute = true,
+                        "let ring" => words.let_ring = true,
+                        "" => {}
+                        _ => {
+                            if !words.text.is_empty() {
+                                words.text.push(' ');
+                            }
+                            words.text.push_str(text);
+                        }
+                    }
+                }
+                "Spanner" => self.read_spanner(item, m, v),
+                "Tuplet" => tuplets.push((
+                    item.child_value::<u8>("actualNotes").unwrap_or(1),
+                    item.child_value::<u8>("normalNotes").unwrap_or(1),
+                )),
+                "endTuplet" => {
+                    tuplets.pop();
+                }
+                "location" => {
+                    let fractions = item.child_text("fractions").and_then(fraction_ticks);
+                    tick = (tick + fractions.unwrap_or(0)).max(0);
+                }
+                "Chord" | "Rest" => {
+                    let tuplet = tuplets.last().copied();
+                    tick += self.read_chord(item, v, tick as u64, tuplet, events)? as i64;
+                }
+                _ => self.read_measure_element(header, item),
+            }
+        }
+        Ok(())
+    }
+
+    /// Voltas, palm mute and let ring spanners. Starts have a `<next>` location, ends a `<prev>`.
+    fn read_spanner(&mut self, spanner: &XmlNode, m: usize, v: usize) {
+        let starts = spanner.has_child("next");
+        match spanner.attribute("type") {
+            Some("Volta") if starts => {
+                let Some(volta) = spanner.child("Volta") else {
+                    return;
+                };
+                let measures = spanner
+                    .child("next")
+                    .and_then(|n| n.child("location"))
+                    .and_then(|l| l.child_value::<usize>("measures"))
+                    .unwrap_or(1)
+                    .max(1);
+                self.ending = (
+                    ending_mask(volta.child_text("endings").unwrap_or_default()),
+                    m + measures,
+                );
+            }
+            Some("PalmMute") => self.palm_mute[v] = starts,
+            Some("LetRing") => self.let_ring[v] = starts,
+            _ => {}
+        }
+    }
+
+    /// Read a chord or rest at `tick` into events. Returns its length in ticks.
+    fn read_chord(
+        &mut self,
+        chord: &XmlNode,
+        v: usize,
+        tick: u64,
+        tuplet: Option<(u8, u8)>,
+        events: &mut Vec<NoteEvent>,
+    ) -> GpResult<u64> {
+        let hidden = chord.child_text("visible") == Some("0");
+        let kind = chord.child_text("durationType").unwrap_or("quarter");
+        if kind == "measure" {
+            // Whole measure rests: rests filling the written duration
+            let length = chord.child_text("duration").and_then(fraction_ticks);
+            let mut start = tick;
+            for duration in fill_durations(length.unwrap_or(0).max(0) as u64, self.max_beats)? {
+                let length = u64::from(duration.time());
+                events.push(NoteEvent {
+                    voice: v,
+                    start,
+                    length,
+                    duration,
+                    grace: false,
+                    grace_on_beat: false,
+                    hidden,
+                    pitch: None,
+                    position: None,
+                    note: Note::default(),
+                    text: String::new(),
+                    lyrics: Vec::new(),
+                });
+                start += length;
+            }
+            return Ok(start - tick);
+        }
+
+        let dots = chord.child_value::<u8>("dots").unwrap_or(0);
+        let mut duration = Duration {
+            value: duration_value(kind).unwrap_or(4),
+            dotted: dots == 1,
+            double_dotted: dots > 1,
+            ..Default::default()
+        };
+        if let Some((actual, normal)) = tuplet.filter(|(a, n)| *a > 0 && *n > 0) {
+            duration.tuplet_enters = actual;
+            duration.tuplet_times = normal;
+        }
+        let grace = GRACE_TYPES.iter().any(|g| chord.has_child(g));
+        let length = if grace { 0 } else { u64::from(duration.time()) };
+
+        let words = if grace {
+            Words::default()
+        } else {
+            std::mem::take(&mut self.words[v])
+        };
+        let mut accent = (false, false, false);
+        for articulation in chord.children("Articulation") {
+            let subtype = articulation.child_text("subtype").unwrap_or_default();
+            accent.0 |= subtype.contains("Accent");
+            accent.1 |= subtype.contains("Marcato");
+            accent.2 |= subtype.contains("Staccato");
+        }
+        let lyrics: Vec<Syllable> = chord
+            .children("Lyrics")
+            .filter_map(|lyric| {
+                let number = lyric.child_value::<u8>("no").unwrap_or(0).saturating_add(1);
+                Some((
+                    number,
+                    lyric.child_text("syllabic").unwrap_or("single").to_string(),
+                    lyric.child_text("text")?.to_string(),
+                ))
+            })
+            .collect();
+
+        let notes: Vec<&XmlNode> = chord.children("Note").collect();
+        if notes.is_empty() {
+            events.push(NoteEvent {
+                voice: v,
+                start: tick,
+                length,
+                duration,
+                grace,
+                grace_on_beat: false,
+                hidden,
+                pitch: None,
+                position: None,
+                note: Note::default(),
+                text: words.text,
+                lyrics,
+            });
+            return Ok(length);
+        }
+        for (i, node) in notes.into_iter().enumerate() {
+            let mut note = Note {
+                kind: NoteType::Normal,
+                ..Default::default()
+            };
+            // Tie ends: a tie spanner coming from a previous note (MuseScore 3+) or an end
+            // spanner (MuseScore 2)
+            let tied = node
+                .children("Spanner")
+                .any(|s| s.attribute("type") == Some("Tie") && s.has_child("prev"))
+                || node.has_child("endSpanner");
+            if tied {
+                note.kind = NoteType::Tie;
+            }
+            match node.child_text("headGroup") {
+                Some("cross") => note.kind = NoteType::Dead,
+                Some("diamond") => {
+                    note.effect.harmonic = Some(HarmonicEffect {
+                        kind: HarmonicType::Natural,
+                        pitch: None,
+                        octave: None,
+                        fret: None,
+                    })
+                }
+                _ => {}
+            }
+            if node.child_text("dead") == Some("1") {
+                note.kind = NoteType::Dead;
+            }
+            note.effect.ghost_note = node.child_text("ghost") == Some("1");
+            note.effect.bend = node.child("Bend").and_then(read_bend);
+            note.effect.accentuated_note = accent.0;
+            note.effect.heavy_accentuated_note = accent.1;
+            note.effect.staccato = accent.2;
+            note.effect.palm_mute = self.palm_mute[v] || words.palm_mute;
+            note.effect.let_ring = self.let_ring[v] || words.let_ring;
+
+            // Strings are numbered from 0, the highest string
+            let string = node.child_value::<i8>("string").filter(|s| *s >= 0);
+            let fret = node.child_value::<i16>("fret").filter(|f| *f >= 0);
+            events.push(NoteEvent {
+                voice: v,
+                start: tick,
+                length,
+                duration: duration.clone(),
+                grace,
+                grace_on_beat: false,
+                hidden,
+                pitch: node.child_value::<i32>("pitch"),
+                position: string.zip(fret).map(|(s, f)| (s + 1, f)),
+                note,
+                text: if i == 0 {
+                    words.text.clone()
+                } else {
+                    String::new()
+                },
+                lyrics: if i == 0 { lyrics.clone() } else { Vec::new() },
+            });
+        }
+        Ok(length)
+    }
+}
+
+/// Read a MuseScore (2 to 4) score, `.mscx` XML, into `song`. Each part becomes a track read
+/// from its TAB staff if it has one, otherwise from its first staff.
+pub fn read_mscx(song: &mut Song, data: &[u8], limits: &ReadLimits) -> GpResult<()> {
+    let root = parse_xml(&String::from_utf8_lossy(data))?;
+    if root.name != "museScore" {
+        return Err(format!("Not a MuseScore file: root element <{}>", root.name).into());
+    }
+    let score = root
+        .child("Score")
+        .ok_or_else(|| String::from("MuseScore file without <Score>"))?;
+
+    read_metadata(song, score);
+    reset_song(song);
+    let staves: HashMap<&str, &XmlNode> = score
+        .children("Staff")
+        .filter_map(|s| Some((s.attribute("id")?, s)))
+        .collect();
+    let mut parts = Vec::new();
+    for part in score.children("Part") {
+        let mut read = ReadPart::default();
+        read_instrument(part, &mut read);
+        let group = |staff: &XmlNode| {
+            staff
+                .child("StaffType")
+                .and_then(|t| t.attribute("group"))
+                .unwrap_or("pitched")
+                .to_string()
+        };
+        let part_staves: Vec<&XmlNode> = part.children("Staff").collect();
+        read.percussion |= part_staves.iter().any(|s| group(s) == "percussion");
+        let staff = part_staves
+            .iter()
+            .find(|s| group(s) == "tablature")
+            .or_else(|| part_staves.first())
+            .and_then(|s| staves.get(s.attribute("id")?));
+        if let Some(staff) = staff {
+            let mut reader = StaffReader {
+                max_beats: limits.max_beats,
+                ..Default::default()
+            };
+            reader.read_staff(song, staff, &mut read)?;
+        }
+        parts.push(read);
+    }
+    build_tracks(song, parts, limits)
+}
+
+/// Read a compressed MuseScore score (`.mscz`): the `.mscx` named by `META-INF/container.xml`,
+/// or the first one in the archive.
+pub fn read_mscz(song: &mut Song, data: &[u8], limits: &ReadLimits) -> GpResult<()> {
+    let mut zip = ZipArchive::new(Cursor::new(data)).map_err(|e| format!("Zip error: {}", e))?;
+    let mut root = None;
+    if let Ok(container) = zip.by_name("META-INF/container.xml") {
+        let contents = String::from_utf8(limits.read_decompressed(container)?)
+            .map_err(|e| format!("Read error: {}", e))?;
+        root = parse_xml(&contents)?
+            .child("rootfiles")
+            .and_then(|r| r.child("rootfile"))
+            .and_then(|r| r.attribute("full-path"))
+            .map(String::from);
+    }
+    let root = match root {
+        Some(root) => root,
+        None => zip
+            .file_names()
+            .find(|n| n.ends_with(".mscx"))
+            .map(String::from)
+            .ok_or_else(|| String::from("No .mscx score in the archive"))?,
+    };
+    let file = zip
+        .by_name(&root)
+        .map_err(|e| format!("Could not find {}: {}", root, e))?;
+    let contents = limits.read_decompressed(file)?;
+    read_mscx(song, &contents, limits)
+}
@@ -0,0 +1,1149 @@
+// MusicXML 4.0 export
+use std::collections::{BTreeSet, VecDeque};
+
+use super::*;
+use crate::audio::smf::export::track_output;
+use crate::model::beat::Beat;
+use crate::model::key_signature::{Duration, DURATION_QUARTER_TIME};
+use crate::model::note::Note;
+use crate::model::song::Song;
+use crate::model::track::Track;
+use crate::types::enums::{BeatStatus, HarmonicType, MeasureClef, NoteType, SlideType};
+
+/// Voice numbers of the TAB staff follow the ones of the notation staff.
+const TAB_VOICE_OFFSET: usize = 4;
+
+/// A syllable of the lyrics with its `<syllabic>` value.
+struct Syllable {
+    text: String,
+    syllabic: &'static str,
+}
+
+/// A line of lyrics: its number, the index of its first measure and the syllables left to place.
+struct LyricLine {
+    number: u8,
+    start: usize,
+    syllables: VecDeque<Syllable>,
+}
+
+/// A legato technique linking a note to the next one on the same string.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Link {
+    Hammer,
+    Slide,
+}
+
+/// Position of the beat being written, and how its notes are drawn.
+struct BeatContext<'b> {
+    measure: usize,
+    voice: usize,
+    index: usize,
+    staff: u8,
+    voice_number: String,
+    beat: &'b Beat,
+    grace: bool,
+    tuplet_start: bool,
+    tuplet_stop: bool,
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+/// Length of a duration in quarter notes, as a reduced (numerator, denominator) fraction.
+fn quarter_fraction(duration: &Duration) -> (u64, u64) {
+    let (dot_num, dot_den) = if duration.double_dotted {
+        (7, 4)
+    } else if duration.dotted {
+        (3, 2)
+    } else {
+        (1, 1)
+    };
+    let num = 4 * dot_num * u64::from(duration.tuplet_times.max(1));
+    let den = u64::from(duration.value.max(1)) * dot_den * u64::from(duration.tuplet_enters.max(1));
+    let g = gcd(num, den);
+    (num / g, den / g)
+}
+
+/// Smallest `<divisions>` per quarter note expressing every beat and measure as an integer.
+fn divisions(song: &Song) -> u64 {
+    let mut divisions = 1;
+    let mut add = |den: u64| divisions = divisions / gcd(divisions, den) * den;
+    for header in &song.measure_headers {
+        let num = 4 * header.time_signature.numerator.max(1) as u64;
+        let den = header.time_signature.denominator.value.max(1) as u64;
+        add(den / gcd(num, den));
+    }
+    for track in &song.tracks {
+        for measure in &track.measures {
+            for voice in &measure.voices {
+                for beat in &voice.beats {
+                    add(quarter_fraction(&beat.duration).1);
+                }
+            }
+        }
+    }
+    divisions
+}
+
+fn duration_divisions(duration: &Duration, divisions: u64) -> u64 {
+    let (num, den) = quarter_fraction(duration);
+    divisions * num / den
+}
+
+/// Split a line of lyrics into syllables: blanks separate words, `-` separates the syllables
+/// of a word, `+` joins two syllables sung on one beat and `[...]` is hidden text.
+fn syllables(text: &str) -> VecDeque<Syllable> {
+    let mut visible = String::with_capacity(text.len());
+    let mut hidden = false;
+    for c in text.chars() {
+        match c {
+            '[' => hidden = true,
+            ']' => hidden = false,
+            _ if !hidden => visible.push(c),
+            _ => {}
+        }
+    }
+    let mut syllables = VecDeque::new();
+    for word in visible.split_whitespace() {
+        let parts: Vec<&str> = word.split('-').filter(|p| !p.is_empty()).collect();
+        for (i, part) in parts.iter().enumerate() {
+            let syllabic = match (i == 0, i + 1 == parts.len()) {
+                (true, true) => "single",
+                (true, false) => "begin",
+                (false, true) => "end",
+                (false, false) => "middle",
+            };
+            syllables.push_back(Syllable {
+                text: part.replace('+', " "),
+                syllabic,
+            });
+        }
+    }
+    syllables
+}
+
+fn is_rest(beat: &Beat) -> bool {
+    beat.status != BeatStatus::Normal || beat.notes.iter().all(|n| n.kind == NoteType::Rest)
+}
+
+fn note_on_string(beat: Option<&Beat>, string: i8) -> Option<&Note> {
+    beat.filter(|b| b.status == BeatStatus::Normal)?
+        .notes
+        .iter()
+        .find(|n| n.string == string && n.kind != NoteType::Rest)
+}
+
+fn links(note: &Note) -> Option<Link> {
+    if note.effect.hammer {
+        Some(Link::Hammer)
+    } else if note
+        .effect
+        .slides
+        .iter()
+        .any(|s| matches!(s, SlideType::ShiftSlideTo | SlideType::LegatoSlideTo))
+    {
+        Some(Link::Slide)
+    } else {
+        None
+    }
+}
+
+fn part_id(index: usize) -> String {
+    format!("P{}", index + 1)
+}
+
+/// Percentage of a beat of `length` ticks taken by an on-beat grace note of `grace` ticks,
+/// for `steal-time-following`.
+fn steal_time(grace: i64, length: i64) -> f64 {
+    if length <= 0 {
+        return 0.0;
+    }
+    let percent = grace.clamp(0, length) as f64 * 100.0 / length as f64;
+    (percent * 100.0).round() / 100.0
+}
+
+/// Id of the instrument playing a MIDI key of a percussion part.
+fn percussion_instrument_id(part: &str, key: i32) -> String {
+    format!("{}-I{}", part, key + 1)
+}
+
+/// MIDI note of a fret on a string of the track, the capo included.
+fn track_pitch(track: &Track, fret: i16, string: i8) -> i32 {
+    let tuning = track
+        .strings
+        .get((string as usize).saturating_sub(1))
+        .map_or(0, |s| i32::from(s.1));
+    let capo = if track.percussion_track {
+        0
+    } else {
+        track.offset
+    };
+    tuning + i32::from(fret) + capo
+}
+
+/// MIDI keys played in a percussion track, grace notes included: each one gets its own
+/// instrument, as `<display-step>` and `<display-octave>` cannot tell sharp keys apart.
+fn percussion_keys(track: &Track) -> BTreeSet<i32> {
+    let notes = track
+        .measures
+        .iter()
+        .flat_map(|m| &m.voices)
+        .flat_map(|v| &v.beats)
+        .flat_map(|b| &b.notes)
+        .filter(|n| n.kind != NoteType::Rest);
+    let mut keys = BTreeSet::new();
+    for note in notes {
+        keys.insert(track_pitch(track, note.value, note.string));
+        if let Some(grace) = &note.effect.grace {
+            keys.insert(track_pitch(track, grace.fret.into(), note.string));
+        }
+    }
+    keys
+}
+
+/// Export a song as a MusicXML 4.0 partwise score.
+///
+/// Each track becomes a part. Fretted tracks get two staves: standard notation and a TAB
+/// staff whose notes carry their `<string>`/`<fret>`. Percussion tracks get a single
+/// percussion staff.
+pub fn write_musicxml(song: &Song) -> String {
+    let mut w = XmlWriter::default();
+    w.out
+        .push_str("<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"no\"?>\n");
+    w.out.push_str(PARTWISE_DOCTYPE);
+    w.out.push('\n');
+    w.open("score-partwise", &[("version", MUSICXML_VERSION)]);
+    write_identification(&mut w, song);
+    write_part_list(&mut w, song);
+
+    let divisions = divisions(song);
+    let lyric_track = (song.lyrics.track_choice as usize).checked_sub(1);
+    for (index, track) in song.tracks.iter().enumerate() {
+        let lyrics = if lyric_track == Some(index) {
+            song.lyrics
+                .lines
+                .iter()
+                .filter(|(_, _, text)| !text.trim().is_empty())
+                .map(|(line, start, text)| LyricLine {
+                    number: line + 1,
+                    start: (*start as usize).saturating_sub(1),
+                    syllables: syllables(text),
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+        let mut part = PartWriter {
+            w: &mut w,
+            song,
+            track,
+            part_id: part_id(index),
+            first_part: index == 0,
+            divisions,
+            tab: !track.percussion_track && !track.strings.is_empty(),
+            lyrics,
+            tempo: 0,
+        };
+        part.w.open("part", &[("id", &part.part_id)]);
+        for m in 0..song.measure_headers.len() {
+            part.write_measure(m);
+        }
+        part.w.close("part");
+    }
+    w.close("score-partwise");
+    w.out
+}
+
+fn write_identification(w: &mut XmlWriter, song: &Song) {
+    if !song.name.is_empty() {
+        w.open("work", &[]);
+        w.text("work-title", &song.name);
+        w.close("work");
+    }
+    if !song.subtitle.is_empty() {
+        w.text("movement-title", &song.subtitle);
+    }
+    w.open("identification", &[]);
+    if !song.author.is_empty() {
+        w.text_with("creator", &[("type", "composer")], &song.author);
+    }
+    if !song.words.is_empty() {
+        w.text_with("creator", &[("type", "lyricist")], &song.words);
+    }
+    if !song.copyright.is_empty() {
+        w.text("rights", &song.copyright);
+    }
+    w.open("encoding", &[]);
+    w.text("software", "scorelib");
+    w.close("encoding");
+    w.close("identification");
+}
+
+fn write_part_list(w: &mut XmlWriter, song: &Song) {
+    w.open("part-list", &[]);
+    for (index, track) in song.tracks.iter().enumerate() {
+        let id = part_id(index);
+        // One instrument per key of a percussion part, one for the whole part otherwise
+        let keys = if track.percussion_track {
+            percussion_keys(track)
+        } else {
+            BTreeSet::new()
+        };
+        let instruments: Vec<(String, Option<i32>)> = if keys.is_empty() {
+            vec![(format!("{}-I1", id), None)]
+        } else {
+            keys.iter()
+                .map(|&key| (percussion_instrument_id(&id, key), Some(key)))
+                .collect()
+        };
+        w.open("score-part", &[("id", &id)]);
+        w.text("part-name", &track.name);
+        if !track.short_name.is_empty() {
+            w.text("part-abbreviation", &track.short_name);
+        }
+        for (instrument, key) in &instruments {
+            w.open("score-instrument", &[("id", instrument)]);
+            match key {
+                Some(key) => w.text("instrument-name", format!("{} {}", track.name, key)),
+                None => w.text("instrument-name", &track.name),
+            }
+            w.close("score-instrument");
+        }
+
+        let output = track_output(song, track);
+        for (instrument, key) in &instruments {
+            w.open("midi-instrument", &[("id", instrument)]);
+            w.text("midi-channel", output.channel + 1);
+            if output.bank > 0 {
+                w.text("midi-bank", u16::from(output.bank) + 1);
+            }
+            w.text("midi-program", output.program + 1);
+            if let Some(key) = key {
+                w.text("midi-unpitched", key + 1);
+            }
+            if let Some(mixer) = output.mixer {
+                w.text(
+                    "volume",
+                    (f64::from(mixer.volume.max(0)) * 100.0 / 127.0).round(),
+                );
+                w.text(
+                    "pan",
+                    ((i32::from(mixer.balance.max(0)) - 64) * 90 / 64).clamp(-90, 90),
+                );
+            }
+            w.close("midi-instrument");
+        }
+        w.close("score-part");
+    }
+    w.close("part-list");
+}
+
+/// Writes the measures of one track.
+struct PartWriter<'a> {
+    w: &'a mut XmlWriter,
+    song: &'a Song,
+    track: &'a Track,
+    part_id: String,
+    /// Measure level directions (markers, tempo, signs) are written in the first part only.
+    first_part: bool,
+    divisions: u64,
+    /// Whether the part has a TAB staff below the notation staff.
+    tab: bool,
+    lyrics: Vec<LyricLine>,
+    /// Last tempo written, in BPM.
+    tempo: i32,
+}
+
+impl PartWriter<'_> {
+    fn write_measure(&mut self, m: usize) {
+        let header = &self.song.measure_headers[m];
+        let measure_length =
+            header.length().max(0) as u64 * self.divisions / DURATION_QUARTER_TIME as u64;
+        self.w.open("measure", &[("number", &(m + 1).to_string())]);
+        self.write_attributes(m);
+        self.write_left_barline(m);
+        self.write_measure_directions(m, false);
+
+        let voices: Vec<usize> = self
+            .track
+            .measures
+            .get(m)
+            .map(|measure| {
+                (0..measure.voices.len())
+                    .filter(|&v| {
+                        measure.voices[v]
+                            .beats
+                            .iter()
+                            .any(|b| b.status != BeatStatus::Empty)
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        let staves: &[u8] = if self.tab { &[1, 2] } else { &[1] };
+        let mut position = 0;
+        for (i, &staff) in staves.iter().enumerate() {
+            if voices.is_empty() {
+                if i > 0 {
+                    self.write_move("backup", position);
+                }
+                self.write_measure_rest(staff, measure_length);
+                position = measure_length;
+            }
+            for (j, &v) in voices.iter().enumerate() {
+                if i > 0 || j > 0 {
+                    self.write_move("backup", position);
+                }
+                position = self.write_voice(m, v, staff);
+            }
+        }
+        if self.first_part
+            && header
+                .direction
+                .as_ref()
+                .is_some_and(|d| sign_direction(d).at_end)
+        {
+            if position < measure_length {
+                self.write_move("forward", measure_length - position);
+            }
+            self.write_measure_directions(m, true);
+        }
+        self.write_right_barline(m);
+        self.w.close("measure");
+    }
+
+    fn write_move(&mut self, kind: &str, length: u64) {
+        if length > 0 {
+            self.w.open(kind, &[]);
+            self.w.text("duration", length);
+            self.w.close(kind);
+        }
+    }
+
+    fn write_attributes(&mut self, m: usize) {
+        let headers = &self.song.measure_headers;
+        let header = &headers[m];
+        let previous = m.checked_sub(1).map(|p| &headers[p]);
+        let key_changed = previous.is_none_or(|p| p.key_signature != header.key_signature);
+        let time_changed = previous.is_none_or(|p| {
+            p.time_signature.numerator != header.time_signature.numerator
+                || p.time_signature.denominator.value != header.time_signature.denominator.value
+        });
+        let clef = self.track.measures.get(m).map(|measure| &measure.clef);
+        let clef_changed = m == 0
+            || clef.is_some_and(|c| self.track.measures.get(m - 1).is_some_and(|p| p.clef != *c));
+        if !key_changed && !time_changed && !clef_changed {
+            return;
+        }
+
+        self.w.open("attributes", &[]);
+        if m == 0 {
+            self.w.text("divisions", self.divisions);
+        }
+        if key_changed {
+            self.w.open("key", &[]);
+            self.w.text("fifths", header.key_signature.key);
+            self.w.text(
+                "mode",
+                if header.key_signature.is_minor {
+                    "minor"
+                } else {
+                    "major"
+                },
+            );
+            self.w.close("key");
+        }
+        if time_changed {
+            self.w.open("time", &[]);
+            self.w.text("beats", header.time_signature.numerator);
+            self.w
+                .text("beat-type", header.time_signature.denominator.value);
+            self.w.close("time");
+        }
+        if m == 0 && self.tab {
+            self.w.text("staves", 2);
+        }
+        if clef_changed {
+            self.write_clef(clef.unwrap_or(&MeasureClef::Treble));
+            if m == 0 && self.tab {
+                self.w.open("clef", &[("number", "2")]);
+                self.w.text("sign", "TAB");
+                self.w.text("line", 5);
+                self.w.close("clef");
+            }
+        }
+        if m == 0 && self.tab {
+            self.write_staff_details();
+        }
+        self.w.close("attributes");
+    }
+
+    fn write_clef(&mut self, clef: &MeasureClef) {
+        let attributes: &[(&str, &str)] = if self.tab { &[("number", "1")] } else { &[] };
+        self.w.open("clef", attributes);
+        if self.track.percussion_track {
+            self.w.text("sign", "percussion");
+        } else {
+            let (sign, line) = match clef {
+                MeasureClef::Treble => ("G", 2),
+                MeasureClef::Bass => ("F", 4),
+                MeasureClef::Tenor => ("C", 4),
+                MeasureClef::Alto => ("C", 3),
+            };
+            self.w.text("sign", sign);
+            self.w.text("line", line);
+            // Fretted instruments sound an octave below the written notes
+            if self.tab && matches!(clef, MeasureClef::Treble | MeasureClef::Bass) {
+                self.w.text("clef-octave-change", -1);
+            }
+        }
+        self.w.close("clef");
+    }
+
+    /// TAB staff lines and tuning, from the lowest string (line 1) to the highest.
+    fn write_staff_details(&mut self) {
+        self.w.open("staff-details", &[("number", "2")]);
+        self.w.text("staff-lines", self.track.strings.len());
+        for (line, (_, tuning)) in self.track.strings.iter().rev().enumerate() {
+            let pitch = Pitch::from_midi(i32::from(*tuning), false);
+            self.w
+                .open("staff-tuning", &[("line", &(line + 1).to_string())]);
+            self.w.text("tuning-step", pitch.step);
+            if pitch.alter != 0 {
+                self.w.text("tuning-alter", pitch.alter);
+            }
+            self.w.text("tuning-octave", pitch.octave);
+            self.w.close("staff-tuning");
+        }
+        if self.track.offset > 0 {
+            self.w.text("capo", self.track.offset);
+        }
+        self.w.close("staff-details");
+    }
+
+    fn ending_numbers(alternative: u8) -> String {
+        (0..8)
+            .filter(|bit| alternative & (1 << bit) != 0)
+            .map(|bit| (bit + 1).to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    fn write_left_barline(&mut self, m: usize) {
+        let headers = &self.song.measure_headers;
+        let header = &headers[m];
+        let alternative = header.repeat_alternative;
+        let start_ending =
+            alternative != 0 && (m == 0 || headers[m - 1].repeat_alternative != alternative);
+        if !header.repeat_open && !start_ending {
+            return;
+        }
+        self.w.open("barline", &[("location", "left")]);
+        if header.repeat_open {
+            self.w.text("bar-style", "heavy-light");
+        }
+        if start_ending {
+            let numbers = Self::ending_numbers(alternative);
+            let text = numbers.replace(", ", ". ") + ".";
+            self.w
+                .text_with("ending", &[("number", &numbers), ("type", "start")], &text);
+        }
+        if header.repeat_open {
+            self.w.empty("repeat", &[("direction", "forward")]);
+        }
+        self.w.close("barline");
+    }
+
+    fn write_right_barline(&mut self, m: usize) {
+        let headers = &self.song.measure_headers;
+        let header = &headers[m];
+        let alternative = header.repeat_alternative;
+        let stop_ending = alternative != 0
+            && headers
+                .get(m + 1)
+                .is_none_or(|next| next.repeat_alternative != alternative);
+        let repeat_close = header.repeat_close >= 0;
+        let last = m + 1 == headers.len();
+        if !repeat_close && !stop_ending && !header.double_bar && !last {
+            return;
+        }
+        self.w.open("barline", &[("location", "right")]);
+        if repeat_close || last {
+            self.w.text("bar-style", "light-heavy");
+        } else if header.double_bar {
+            self.w.text("bar-style", "light-light");
+        }
+        if stop_ending {
+            let kind = if repeat_close { "stop" } else { "discontinue" };
+            self.w.empty(
+                "ending",
+                &[
+                    ("number", &Self::ending_numbers(alternative)),
+                    ("type", kind),
+                ],
+            );
+        }
+        if repeat_close {
+            let times = (i32::from(header.repeat_close) + 1).max(2).to_string();
+            self.w
+                .empty("repeat", &[("direction", "backward"), ("times", &times)]);
+        }
+        self.w.close("barline");
+    }
+
+    /// Open a `<direction>` and its `<direction-type>`.
+    fn open_direction(&mut self, placement: &str) {
+        self.w.open("direction", &[("placement", placement)]);
+        self.w.open("direction-type", &[]);
+    }
+
+    /// Close the `<direction-type>` and the `<direction>`, with an optional `<sound>`.
+    fn close_direction(&mut self, sound: Option<(&str, &str)>) {
+        self.w.close("direction-type");
+        if self.tab {
+            self.w.text("staff", 1);
+        }
+        if let Some(sound) = sound {
+            self.w.empty("sound", &[sound]);
+        }
+        self.w.close("direction");
+    }
+
+    fn write_words(&mut self, text: &str, placement: &str) {
+        self.open_direction(placement);
+        self.w.text("words", text);
+        self.close_direction(None);
+    }
+
+    fn write_tempo(&mut self, tempo: i32) {
+        self.tempo = tempo;
+        self.open_direction("above");
+        self.w.open("metronome", &[]);
+        self.w.text("beat-unit", "quarter");
+        self.w.text("per-minute", tempo);
+        self.w.close("metronome");
+        self.close_direction(Some(("tempo", &tempo.to_string())));
+    }
+
+    /// Marker, tempo and direction sign of a measure, at its start or (for jumps) its end.
+    fn write_measure_directions(&mut self, m: usize, at_end: bool) {
+        if !self.first_part {
+            return;
+        }
+        let header = &self.song.measure_headers[m];
+        if !at_end {
+            if let Some(marker) = &header.marker {
+                self.open_direction("above");
+                self.w.text("rehearsal", &marker.title);
+                self.close_direction(None);
+            }
+            let tempo = if header.tempo > 0 {
+                header.tempo
+            } else if m == 0 {
+                i32::from(self.song.tempo)
+            } else {
+                0
+            };
+            if tempo > 0 && tempo != self.tempo {
+                self.write_tempo(tempo);
+            }
+        }
+        let Some(sign) = header.direction.as_ref().map(sign_direction) else {
+            return;
+        };
+        if sign.at_end != at_end {
+            return;
+        }
+        self.open_direction("above");
+        match sign.symbol {
+            Some(symbol) => self.w.empty(symbol, &[]),
+            None => self.w.text("words", sign.text),
+        }
+        self.close_direction(Some(sign.sound));
+    }
+
+    fn write_measure_rest(&mut self, staff: u8, length: u64) {
+        self.w.open("note", &[]);
+        self.w.empty("rest", &[("measure", "yes")]);
+        self.w.text("duration", length);
+        let voice = if staff == 2 { 1 + TAB_VOICE_OFFSET } else { 1 };
+        self.w.text("voice", voice);
+        if self.tab {
+            self.w.text("staff", staff);
+        }
+        self.w.close("note");
+    }
+
+    /// Write the beats of a voice on a staff and return the length written.
+    fn write_voice(&mut self, m: usize, v: usize, staff: u8) -> u64 {
+        let voice = &self.track.measures[m].voices[v];
+        let voice_number = (v + 1 + if staff == 2 { TAB_VOICE_OFFSET } else { 0 }).to_string();
+        let mut position = 0;
+        let mut tuplet_left: Option<u64> = None;
+        for (index, beat) in voice.beats.iter().enumerate() {
+            let grace = beat.is_grace_beat();
+            let length = if grace {
+                0
+            } else {
+                duration_divisions(&beat.duration, self.divisions)
+            };
+            let duration = &beat.duration;
+            let mut tuplet_start = false;
+            let mut tuplet_stop = false;
+            if !grace
+                && duration.tuplet_enters > 1
+                && duration.tuplet_enters != duration.tuplet_times
+            {
+                let left = match tuplet_left {
+                    Some(left) => left,
+                    None => {
+                        tuplet_start = true;
+                        u64::from(duration.tuplet_times) * self.divisions * 4
+                            / u64::from(duration.value.max(1))
+                    }
+                }
+                .saturating_sub(length);
+                tuplet_stop = left == 0;
+                tuplet_left = Some(left).filter(|&l| l > 0);
+            } else if !grace {
+                tuplet_left = None;
+            }
+
+            let context = BeatContext {
+                measure: m,
+                voice: v,
+                index,
+                staff,
+                voice_number: voice_number.clone(),
+                beat,
+                grace,
+                tuplet_start,
+                tuplet_stop,
+            };
+            if staff == 1 {
+                self.write_beat_directions(&context);
+            }
+            self.write_beat(&context);
+            position += length;
+        }
+        position
+    }
+
+    /// The beat before or after a beat of a voice, continuing in the neighbour measures.
+    fn neighbour_beat(&self, context: &BeatContext, forward: bool) -> Option<&Beat> {
+        let measures = &self.track.measures;
+        let beats = &measures[context.measure].voices[context.voice].beats;
+        if forward {
+            match beats.get(context.index + 1) {
+                Some(beat) => Some(beat),
+                None => measures
+                    .get(context.measure + 1)?
+                    .voices
+                    .get(context.voice)?
+                    .beats
+                    .first(),
+            }
+        } else if context.index > 0 {
+            beats.get(context.index - 1)
+        } else {
+            let previous = context.measure.checked_sub(1)?;
+            measures[previous].voices.get(context.voice)?.beats.last()
+        }
+    }
+
+    /// Beat text, tempo changes and the start of palm mute or let ring passages.
+    fn write_beat_directions(&mut self, context: &BeatContext) {
+        let beat = context.beat;
+        if !beat.text.trim().is_empty() {
+            self.write_words(beat.text.trim(), "above");
+        }
+        let tempo = beat
+            .effect
+            .mix_table_change
+            .as_ref()
+            .and_then(|mtc| mtc.tempo.as_ref())
+            .map_or(0, |t| i32::from(t.value));
+        if tempo > 0 && tempo != self.tempo {
+            self.write_tempo(tempo);
+        }
+        if is_rest(beat) {
+            return;
+        }
+        let previous = self.neighbour_beat(context, false).filter(|b| !is_rest(b));
+        let starts = |effect: fn(&Note) -> bool| {
+            beat.notes.iter().any(effect) && !previous.is_some_and(|p| p.notes.iter().any(effect))
+        };
+        let palm_mute = starts(|n| n.effect.palm_mute);
+        let let_ring = starts(|n| n.effect.let_ring);
+        if palm_mute {
+            self.write_words("P.M.", "below");
+        }
+        if let_ring {
+            self.write_words("let ring", "below");
+        }
+    }
+
+    fn write_beat(&mut self, context: &BeatContext) {
+        let beat = context.beat;
+        if is_rest(beat) {
+            let hidden = beat.status == BeatStatus::Empty;
+            self.w.open(
+                "note",
+                if hidden {
+                    &[("print-object", "no")]
+                } else {
+                    &[]
+                },
+            );
+            self.w.empty("rest", &[]);
+            self.w.text(
+                "duration",
+                duration_divisions(&beat.duration, self.divisions),
+            );
+            self.w.text("voice", &context.voice_number);
+            self.write_note_type(&beat.duration);
+            if self.tab {
+                self.w.text("staff", context.staff);
+            }
+            self.write_tuplet_notations(context);
+            self.w.close("note");
+            return;
+        }
+
+        // Grace notes of GP3-5 are an effect of the note they precede
+        if !context.grace {
+            let graces: Vec<&Note> = beat
+                .notes
+                .iter()
+                .filter(|n| n.kind != NoteType::Rest && n.effect.grace.is_some())
+                .collect();
+            for (i, note) in graces.iter().enumerate() {
+                self.write_grace_effect(context, note, i > 0);
+            }
+        }
+
+        let mut lyrics = Vec::new();
+        if context.staff == 1
+            && context.voice == 0
+            && !context.grace
+            && beat
+                .notes
+                .iter()
+                .any(|n| n.kind == NoteType::Normal || n.kind == NoteType::Dead)
+        {
+            for line in &mut self.lyrics {
+                if line.start <= context.measure {
+                    if let Some(syllable) = line.syllables.pop_front() {
+                        lyrics.push((line.number, syllable));
+                    }
+                }
+            }
+        }
+        let notes = beat.notes.iter().filter(|n| n.kind != NoteType::Rest);
+        for (i, note) in notes.enumerate() {
+            let lyrics = if i == 0 {
+                std::mem::take(&mut lyrics)
+            } else {
+                Vec::new()
+            };
+            self.write_note(context, note, i > 0, &lyrics);
+        }
+    }
+
+    fn pitch_value(&self, fret: i16, string: i8) -> i32 {
+        track_pitch(self.track, fret, string)
+    }
+
+    /// The `<instrument>` of a percussion note, naming the MIDI key it plays.
+    fn write_instrument(&mut self, midi: i32) {
+        if self.track.percussion_track {
+            let id = percussion_instrument_id(&self.part_id, midi);
+            self.w.empty("instrument", &[("id", &id)]);
+        }
+    }
+
+    fn write_pitch(&mut self, midi: i32, measure: usize) {
+        let flats = self.song.measure_headers[measure].key_signature.key < 0;
+        let pitch = Pitch::from_midi(midi, flats);
+        if self.track.percussion_track {
+            self.w.open("unpitched", &[]);
+            self.w.text("display-step", pitch.step);
+            self.w.text("display-octave", pitch.octave);
+            self.w.close("unpitched");
+        } else {
+            self.w.open("pitch", &[]);
+            self.w.text("step", pitch.step);
+            if pitch.alter != 0 {
+                self.w.text("alter", pitch.alter);
+            }
+            self.w.text("octave", pitch.octave);
+            self.w.close("pitch");
+        }
+    }
+
+    fn write_note_type(&mut self, duration: &Duration) {
+        if let Some(name) = note_type_name(duration.value) {
+            self.w.text("type", name);
+        }
+        if duration.dotted || duration.double_dotted {
+            self.w.empty("dot", &[]);
+        }
+        if duration.double_dotted {
+            self.w.empty("dot", &[]);
+        }
+        if duration.tuplet_enters > 1 && duration.tuplet_enters != duration.tuplet_times {
+            self.w.open("time-modification", &[]);
+            self.w.text("actual-notes", duration.tuplet_enters);
+            self.w.text("normal-notes", duration.tuplet_times);
+            self.w.close("time-modification");
+        }
+    }
+
+    fn write_tuplet_notations(&mut self, context: &BeatContext) {
+        if context.tuplet_start || context.tuplet_stop {
+            self.w.open("notations", &[]);
+            self.write_tuplets(context);
+            self.w.close("notations");
+        }
+    }
+
+    fn write_tuplets(&mut self, context: &BeatContext) {
+        if context.tuplet_start {
+            self.w
+                .empty("tuplet", &[("type", "start"), ("bracket", "yes")]);
+        }
+        if context.tuplet_stop {
+            self.w.empty("tuplet", &[("type", "stop")]);
+        }
+    }
+
+    /// Write `<grace>`: slashed before the beat, or taking `steal` percent of the time of
+    /// the following note on the beat.
+    fn write_grace(&mut self, steal: Option<f64>) {
+        match steal {
+            Some(steal) => {
+                let steal = steal.to_string();
+                self.w.empty("grace", &[("steal-time-following", &steal)]);
+            }
+            None => self.w.empty("grace", &[("slash", "yes")]),
+        }
+    }
+
+    /// Length in ticks of the beat a grace beat leads to, 0 when none follows in the measure.
+    fn grace_target_length(&self, context: &BeatContext) -> i64 {
+        self.track.measures[context.measure].voices[context.voice].beats[context.index + 1..]
+            .iter()
+            .find(|b| !b.is_grace_beat())
+            .map_or(0, |b| i64::from(b.duration.time()))
+    }
+
+    /// A grace note set as an effect on a note (GP3-5), written before the beat or, on the
+    /// beat, taking at most half of its time.
+    fn write_grace_effect(&mut self, context: &BeatContext, note: &Note, chord: bool) {
+        let Some(grace) = &note.effect.grace else {
+            return;
+        };
+        let steal = grace.is_on_beat.then(|| {
+            let length = i64::from(context.beat.duration.time());
+            let grace_length = DURATION_QUARTER_TIME * 4 / i64::from(grace.duration.max(1));
+            steal_time(grace_length.min(length / 2), length)
+        });
+        self.w.open("note", &[]);
+        self.write_grace(steal);
+        if chord {
+            self.w.empty("chord", &[]);
+        }
+        let midi = self.pitch_value(grace.fret.into(), note.string);
+        self.write_pitch(midi, context.measure);
+        self.write_instrument(midi);
+        self.w.text("voice", &context.voice_number);
+        self.w.text(
+            "type",
+            note_type_name(grace.duration.into()).unwrap_or("16th"),
+        );
+        if grace.is_dead {
+            self.w.text("notehead", "x");
+        }
+        if self.tab {
+            self.w.text("staff", context.staff);
+        }
+        if context.staff == 2 {
+            self.w.open("notations", &[]);
+            self.w.open("technical", &[]);
+            self.w.text("string", note.string);
+            self.w.text("fret", grace.fret);
+            self.w.close("technical");
+            self.w.close("notations");
+        }
+        self.w.close("note");
+    }
+
+    fn write_note(
+        &mut self,
+        context: &BeatContext,
+        note: &Note,
+        chord: bool,
+        lyrics: &[(u8, Syllable)],
+    ) {
+        let next = note_on_string(self.neighbour_beat(context, true), note.string);
+        let previous = note_on_string(self.neighbour_beat(context, false), note.string);
+        let tie_start = next.is_some_and(|n| n.kind == NoteType::Tie);
+        let tie_stop = note.kind == NoteType::Tie;
+        let link_start = links(note).filter(|_| next.is_some_and(|n| n.kind != NoteType::Tie));
+        let link_stop = previous.and_then(links).filter(|_| !tie_stop);
+        let pull_off_start = next.is_some_and(|n| n.value < note.value);
+        let pull_off_stop = previous.is_some_and(|p| p.value > note.value);
+
+        self.w.open("note", &[]);
+        if context.grace {
+            let on_beat = note.effect.grace.as_ref().is_some_and(|g| g.is_on_beat);
+            let steal = on_beat.then(|| {
+                let length = i64::from(context.beat.duration.time());
+                steal_time(length, self.grace_target_length(context))
+            });
+            self.write_grace(steal);
+        }
+        if chord {
+            self.w.empty("chord", &[]);
+        }
+        let midi = self.pitch_value(note.value, note.string);
+        self.write_pitch(midi, context.measure);
+        if !context.grace {
+            self.w.text(
+                "duration",
+                duration_divisions(&context.beat.duration, self.divisions),
+            );
+        }
+        if tie_stop {
+            self.w.empty("tie", &[("type", "stop")]);
+        }
+        if tie_start {
+            self.w.empty("tie", &[("type", "start")]);
+        }
+        self.write_instrument(midi);
+        self.w.text("voice", &context.voice_number);
+        self.write_note_type(&context.beat.duration);
+        if note.kind == NoteType::Dead {
+            self.w.text("notehead", "x");
+        } else if note.effect.ghost_note {
+            self.w
+                .text_with("notehead", &[("parentheses", "yes")], "normal");
+        }
+        if self.tab {
+            self.w.text("staff", context.staff);
+        }
+
+        let notations = self.w.open_optional("notations");
+        if tie_stop {
+            self.w.empty("tied", &[("type", "stop")]);
+        }
+        if tie_start {
+            self.w.empty("tied", &[("type", "start")]);
+        }
+        if !chord {
+            self.write_tuplets(context);
+        }
+        if link_stop == Some(Link::Slide) {
+            self.w
+                .empty("slide", &[("type", "stop"), ("line-type", "solid")]);
+        }
+        if link_start == Some(Link::Slide) {
+            self.w
+                .empty("slide", &[("type", "start"), ("line-type", "solid")]);
+        }
+        self.write_technical(
+            context,
+            note,
+            link_start,
+            link_stop,
+            (pull_off_start, pull_off_stop),
+        );
+        self.write_articulations(note);
+        self.w.close_optional("notations", notations);
+
+        for (number, syllable) in lyrics {
+            self.w.open("lyric", &[("number", &number.to_string())]);
+            self.w.text("syllabic", syllable.syllabic);
+            self.w.text("text", &syllable.text);
+            self.w.close("lyric");
+        }
+        self.w.close("note");
+    }
+
+    fn write_technical(
+        &mut self,
+        context: &BeatContext,
+        note: &Note,
+        link_start: Option<Link>,
+        link_stop: Option<Link>,
+        pull_off: (bool, bool),
+    ) {
+        let technical = self.w.open_optional("technical");
+        if link_stop == Some(Link::Hammer) {
+            let tag = if pull_off.1 { "pull-off" } else { "hammer-on" };
+            self.w.empty(tag, &[("type", "stop")]);
+        }
+        if link_start == Some(Link::Hammer) {
+            let (tag, text) = if pull_off.0 {
+                ("pull-off", "P")
+            } else {
+                ("hammer-on", "H")
+            };
+            self.w.text_with(tag, &[("type", "start")], text);
+        }
+        if let Some(bend) = &note.effect.bend {
+            let max = bend.points.iter().map(|p| p.value).max().unwrap_or(0);
+            if max > 0 {
+                // Bend point values are in quarter tones
+                self.w.open("bend", &[]);
+                self.w.text("bend-alter", f64::from(max) / 2.0);
+                if bend.points.first().is_some_and(|p| p.value > 0) {
+                    self.w.empty("pre-bend", &[]);
+                } else if bend.points.last().is_some_and(|p| p.value < max) {
+                    self.w.empty("release", &[]);
+                }
+                self.w.close("bend");
+            }
+        }
+        if let Some(harmonic) = &note.effect.harmonic {
+            self.w.open("harmonic", &[]);
+            match harmonic.kind {
+                HarmonicType::Natural => self.w.empty("natural", &[]),
+                _ => self.w.empty("artificial", &[]),
+            }
+            self.w.close("harmonic");
+        }
+        if context.staff == 2 {
+            self.w.text("string", note.string);
+            self.w.text("fret", note.value);
+        }
+        self.w.close_optional("technical", technical);
+    }
+
+    fn write_articulations(&mut self, note: &Note) {
+        let articulations = self.w.open_optional("articulations");
+        if note.effect.accentuated_note {
+            self.w.empty("accent", &[]);
+        }
+        if note.effect.heavy_accentuated_note {
+            self.w.empty("strong-accent", &[]);
+        }
+        if note.effect.staccato {
+            self.w.empty("staccato", &[]);
+        }
+        for slide in &note.effect.slides {
+            match slide {
+                SlideType::IntoFromBelow => self.w.empty("scoop", &[]),
+                SlideType::IntoFromAbove => self.w.empty("plop", &[]),
+                SlideType::OutDownwards => self.w.empty("falloff", &[]),
+                SlideType::OutUpWards => self.w.empty("doit", &[]),
+                _ => {}
+            }
+        }
+        self.w.close_optional("articulations", articulations);
+    }
+}
@@ -0,0 +1,1283 @@
+// MusicXML import
+use std::collections::HashMap;
+use std::io::Cursor;
+
+use zip::ZipArchive;
+
+use super::*;
+use crate::audio::midi::{MidiChannel, DEFAULT_PERCUSSION_CHANNEL};
+use crate::audio::smf::import::{assign_drums, assign_strings, Position};
+use crate::error::GpResult;
+use crate::io::limits::{check, ReadLimits};
+use crate::io::xml::{parse_xml, XmlNode};
+use crate::model::beat::{Beat, Voice};
+use crate::model::key_signature::{Duration, DURATION_QUARTER_TIME};
+use crate::model::lyric::Lyrics;
+use crate::model::measure::Measure;
+use crate::model::note::Note;
+use crate::model::song::Song;
+use crate::model::track::Track;
+use crate::types::effects::{
+    BendEffect, BendPoint, GraceEffect, HarmonicEffect, BEND_EFFECT_MAX_POSITION, GP_BEND_SEMITONE,
+};
+use crate::types::enums::{
+    BeatStatus, BendType, DirectionSign, GraceEffectTransition, HarmonicType, MeasureClef,
+    NoteType, SlideType,
+};
+use crate::types::measure::{Marker, MeasureHeader};
+
+/// Voices kept per measure, as in GP6/7.
+pub(crate) const MAX_VOICES: usize = 4;
+/// Lyric lines of the `Lyrics` model.
+const LYRIC_LINES: usize = 5;
+
+/// MIDI settings and names of a `<score-part>`.
+#[derive(Default)]
+pub(crate) struct PartInfo {
+    pub(crate) name: String,
+    pub(crate) abbreviation: String,
+    pub(crate) channel: Option<u8>,
+    pub(crate) program: Option<u8>,
+    pub(crate) bank: u8,
+    pub(crate) volume: Option<i8>,
+    pub(crate) balance: Option<i8>,
+    /// MIDI key of each unpitched instrument, by instrument id
+    pub(crate) unpitched: HashMap<String, i32>,
+}
+
+/// A `<lyric>`: line number, syllabic and text.
+pub(crate) type Syllable = (u8, String, String);
+
+/// A note, rest or grace note read from a measure, placed in song ticks from the measure start.
+pub(crate) struct NoteEvent {
+    pub(crate) voice: usize,
+    pub(crate) start: u64,
+    pub(crate) length: u64,
+    pub(crate) duration: Duration,
+    pub(crate) grace: bool,
+    /// Grace note played on the beat, taking its time from the note it leads to
+    pub(crate) grace_on_beat: bool,
+    pub(crate) hidden: bool,
+    /// MIDI key, `None` for rests
+    pub(crate) pitch: Option<i32>,
+    /// String and fret written in the score
+    pub(crate) position: Option<Position>,
+    pub(crate) note: Note,
+    pub(crate) text: String,
+    pub(crate) lyrics: Vec<Syllable>,
+}
+
+/// A part read from a score, ready to become a track.
+#[derive(Default)]
+pub(crate) struct ReadPart {
+    pub(crate) info: PartInfo,
+    pub(crate) percussion: bool,
+    /// Open strings from the lowest, standard tuning when empty
+    pub(crate) tuning: Vec<i8>,
+    pub(crate) fret_count: Option<u8>,
+    pub(crate) capo: i32,
+    pub(crate) voice_count: usize,
+    pub(crate) measures: Vec<Vec<NoteEvent>>,
+    pub(crate) clefs: Vec<MeasureClef>,
+}
+
+/// Direction words of a measure, applied to the beat at or after their position.
+#[derive(Default)]
+struct Words {
+    position: u64,
+    text: String,
+    palm_mute: bool,
+    let_ring: bool,
+}
+
+/// State of the part being read.
+#[derive(Default)]
+struct PartReader {
+    divisions: u64,
+    tab_staff: Option<String>,
+    percussion: bool,
+    /// Open strings by staff line, from the lowest line
+    tuning: Vec<i8>,
+    capo: i32,
+    clef: MeasureClef,
+    /// (staff, voice) of the MusicXML voices kept, by voice index
+    voices: Vec<(String, String)>,
+    /// Endings started and not stopped yet
+    ending: u8,
+    words: Vec<Words>,
+    /// Lyrics of the notation staff notes when the notes are read from the TAB staff
+    lyrics: Vec<(u64, Vec<Syllable>)>,
+    measures: Vec<Vec<NoteEvent>>,
+    clefs: Vec<MeasureClef>,
+}
+
+fn read_archive(data: &[u8], limits: &ReadLimits) -> GpResult<String> {
+    let mut zip = ZipArchive::new(Cursor::new(data)).map_err(|e| format!("Zip error: {}", e))?;
+    let read_entry = |zip: &mut ZipArchive<Cursor<&[u8]>>, name: &str| -> GpResult<String> {
+        let file = zip
+            .by_name(name)
+            .map_err(|e| format!("Could not find {}: {}", name, e))?;
+        let contents = String::from_utf8(limits.read_decompressed(file)?)
+            .map_err(|e| format!("Read error: {}", e))?;
+        Ok(contents)
+    };
+    // The container names the score; archives without one hold it at their root
+    let root = match read_entry(&mut zip, "META-INF/container.xml") {
+        Ok(container) => parse_xml(&container)?
+            .child("rootfiles")
+            .and_then(|r| r.child("rootfile"))
+            .and_then(|r| r.attribute("full-path"))
+            .map(String::from),
+        Err(_) => None,
+    };
+    let root = match root {
+        Some(root) => root,
+        None => zip
+            .file_names()
+            .find(|n| {
+                !n.starts_with("META-INF") && (n.ends_with(".xml") || n.ends_with(".musicxml"))
+            })
+            .map(String::from)
+            .ok_or_else(|| String::from("No MusicXML score in the archive"))?,
+    };
+    read_entry(&mut zip, &root)
+}
+
+fn read_part_list(score: &XmlNode) -> HashMap<String, PartInfo> {
+    let mut parts = HashMap::new();
+    let Some(list) = score.child("part-list") else {
+        return parts;
+    };
+    for part in list.children("score-part") {
+        let mut info = PartInfo {
+            name: part.child_text("part-name").unwrap_or_default().to_string(),
+            abbreviation: part
+                .child_text("part-abbreviation")
+                .unwrap_or_default()
+                .to_string(),
+            ..Default::default()
+        };
+        for midi in part.children("midi-instrument") {
+            if let Some(key) = midi.child_value::<i32>("midi-unpitched") {
+                let id = midi.attribute("id").unwrap_or_default().to_string();
+                info.unpitched.insert(id, key - 1);
+            }
+            if info.channel.is_none() {
+                info.channel = midi
+                    .child_value::<u8>("midi-channel")
+                    .map(|c| c.clamp(1, 16) - 1);
+            }
+            if info.program.is_none() {
+                info.program = midi
+                    .child_value::<u8>("midi-program")
+                    .map(|p| p.clamp(1, 128) - 1);
+                info.bank = midi
+                    .child_value::<u16>("midi-bank")
+                    .map_or(0, |b| (b.clamp(1, 128) - 1) as u8);
+                info.volume = midi
+                    .child_value::<f64>("volume")
+                    .map(|v| (v.clamp(0.0, 100.0) * 127.0 / 100.0).round() as i8);
+                info.balance = midi.child_value::<f64>("pan").map(|p| {
+                    (64.0 + p.clamp(-90.0, 90.0) * 64.0 / 90.0)
+                        .round()
+                        .min(127.0) as i8
+                });
+            }
+        }
+        parts.insert(part.attribute("id").unwrap_or_default().to_string(), info);
+    }
+    parts
+}
+
+fn read_identification(song: &mut Song, score: &XmlNode) {
+    song.name = score
+        .path_text("work/work-title")
+        .unwrap_or_default()
+        .to_string();
+    let movement = score.child_text("movement-title").unwrap_or_default();
+    if song.name.is_empty() {
+        song.name = movement.to_string();
+    } else {
+        song.subtitle = movement.to_string();
+    }
+    if let Some(identification) = score.child("identification") {
+        for creator in identification.children("creator") {
+            match creator.attribute("type") {
+                Some("composer") => song.author = creator.text.trim().to_string(),
+                Some("lyricist") => song.words = creator.text.trim().to_string(),
+                _ => {}
+            }
+        }
+        if let Some(rights) = identification.child_text("rights") {
+            song.copyright = rights.to_string();
+        }
+    }
+}
+
+/// Endings numbers (`"1, 2"`, `"1,2"` or `"1 2"`) as a `repeat_alternative` bitmask.
+pub(crate) fn ending_mask(numbers: &str) -> u8 {
+    numbers
+        .split([',', ' '])
+        .filter_map(|n| n.trim().parse::<u8>().ok())
+        .filter(|n| (1..=8).contains(n))
+        .fold(0, |mask, n| mask | (1 << (n - 1)))
+}
+
+/// Length in ticks of the note values, dotted first, longest first.
+fn straight_durations() -> Vec<(u64, Duration)> {
+    let mut candidates = Vec::new();
+    for value in [1u16, 2, 4, 8, 16, 32, 64, 128] {
+        for dotted in [true, false] {
+            let mut ticks = DURATION_QUARTER_TIME as u64 * 4 / value as u64;
+            if dotted {
+                ticks += ticks / 2;
+            }
+            let duration = Duration {
+                value,
+                dotted,
+                ..Default::default()
+            };
+            candidates.push((ticks, duration));
+        }
+    }
+    candidates.sort_by_key(|(ticks, _)| std::cmp::Reverse(*ticks));
+    candidates
+}
+
+/// Longest straight duration fitting in a length in ticks.
+pub(crate) fn longest_duration(ticks: u64) -> Option<Duration> {
+    straight_durations()
+        .into_iter()
+        .find(|(t, _)| *t <= ticks)
+        .map(|(_, duration)| duration)
+}
+
+/// Decompose a length in ticks into note durations, longest first. Fails with
+/// `GpError::LimitExceeded` past `max_beats` durations.
+pub(crate) fn fill_durations(mut ticks: u64, max_beats: usize) -> GpResult<Vec<Duration>> {
+    let candidates = straight_durations();
+    let mut durations = Vec::new();
+    while let Some((length, duration)) = candidates.iter().find(|(t, _)| *t <= ticks) {
+        check("beats", durations.len() + 1, max_beats)?;
+        durations.push(duration.clone());
+        ticks -= length;
+    }
+    Ok(durations)
+}
+
+fn read_duration(node: &XmlNode, ticks: u64) -> Duration {
+    let mut duration = match node.child_text("type").and_then(note_type_value) {
+        Some(value) => {
+            let dots = node.children("dot").count();
+            Duration {
+                value,
+                dotted: dots == 1,
+                double_dotted: dots > 1,
+                ..Default::default()
+            }
+        }
+        None => longest_duration(ticks).unwrap_or_default(),
+    };
+    if let Some(modification) = node.child("time-modification") {
+        let actual = modification.child_value::<u8>("actual-notes").unwrap_or(1);
+        let normal = modification.child_value::<u8>("normal-notes").unwrap_or(1);
+        if actual > 0 && normal > 0 {
+            duration.tuplet_enters = actual;
+            duration.tuplet_times = normal;
+        }
+    }
+    duration
+}
+
+fn read_bend(node: &XmlNode) -> Option<BendEffect> {
+    // Bend point values are in quarter tones
+    let alter = node.child_value::<f64>("bend-alter")?;
+    let value = (alter.abs() * 2.0).round().min(i8::MAX as f64) as i8;
+    if value == 0 {
+        return None;
+    }
+    let max = BEND_EFFECT_MAX_POSITION;
+    let (kind, points) = if node.has_child("pre-bend") {
+        (BendType::Prebend, vec![(0, value), (max, value)])
+    } else if node.has_child("release") {
+        (
+            BendType::BendRelease,
+            vec![(0, 0), (max / 4, value), (max / 2, value), (max * 3 / 4, 0)],
+        )
+    } else {
+        (BendType::Bend, vec![(0, 0), (max / 2, value), (max, value)])
+    };
+    Some(BendEffect {
+        kind,
+        value: value as i16 * GP_BEND_SEMITONE as i16,
+        points: points
+            .into_iter()
+            .map(|(position, value)| BendPoint {
+                position,
+                value,
+                vibrato: false,
+            })
+            .collect(),
+        ..Default::default()
+    })
+}
+
+/// Read the notations of a note into its effects. Returns the string and fret of `<technical>`.
+fn read_notations(node: &XmlNode, note: &mut Note) -> Option<Position> {
+    let mut string = None;
+    let mut fret = None;
+    let starts = |n: &XmlNode| n.attribute("type") == Some("start");
+    for notations in node.children("notations") {
+        for item in &notations.children {
+            match item.name.as_str() {
+                "tied" if item.attribute("type") == Some("stop") => note.kind = NoteType::Tie,
+                "slide" | "glissando" if starts(item) => {
+                    note.effect.slides.push(SlideType::ShiftSlideTo)
+                }
+                "technical" => {
+                    for technical in &item.children {
+                        match technical.name.as_str() {
+                            "string" => string = technical.text.trim().parse::<i8>().ok(),
+                            "fret" => fret = technical.text.trim().parse::<i16>().ok(),
+                            "hammer-on" | "pull-off" if starts(technical) => {
+                                note.effect.hammer = true
+                            }
+                            "bend" => note.effect.bend = read_bend(technical),
+                            "harmonic" => {
+                                note.effect.harmonic = Some(HarmonicEffect {
+                                    kind: if technical.has_child("artificial") {
+                                        HarmonicType::Artificial
+                                    } else {
+                                        HarmonicType::Natural
+                                    },
+                                    pitch: None,
+                                    octave: None,
+                                    fret: None,
+                                })
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                "articulations" => {
+                    for articulation in &item.children {
+                        match articulation.name.as_str() {
+                            "accent" => note.effect.accentuated_note = true,
+                            "strong-accent" => note.effect.heavy_accentuated_note = true,
+                            "staccato" => note.effect.staccato = true,
+                            "scoop" => note.effect.slides.push(SlideType::IntoFromBelow),
+                            "plop" => note.effect.slides.push(SlideType::IntoFromAbove),
+                            "falloff" => note.effect.slides.push(SlideType::OutDownwards),
+                            "doit" => note.effect.slides.push(SlideType::OutUpWards),
+                            _ => {}
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    Some((string.filter(|s| *s > 0)?, fret.filter(|f| *f >= 0)?))
+}
+
+/// The sign written by a direction: from its words, its `<sound>` jump or its symbol.
+fn direction_sign(
+    words: &[String],
+    sound: Option<&XmlNode>,
+    symbol: Option<&str>,
+) -> Option<DirectionSign> {
+    let by_words = DIRECTION_SIGNS.iter().find(|sign| {
+        let written = sign_direction(sign);
+        written.symbol.is_none() && words.iter().any(|w| w.eq_ignore_ascii_case(written.text))
+    });
+    let by_sound = || {
+        let sound = sound?;
+        DIRECTION_SIGNS.iter().find(|sign| {
+            let (name, value) = sign_direction(sign).sound;
+            sound.attribute(name) == Some(value)
+        })
+    };
+    let by_symbol = || {
+        DIRECTION_SIGNS.iter().find(|sign| {
+            sign_direction(sign).symbol.is_some() && sign_direction(sign).symbol == symbol
+        })
+    };
+    by_words.or_else(by_sound).or_else(by_symbol).cloned()
+}
+
+/// Tempo in quarter notes per minute of a `<metronome>`.
+fn metronome_tempo(metronome: &XmlNode) -> Option<i32> {
+    let per_minute = metronome.child_value::<f64>("per-minute")?;
+    let unit = metronome
+        .child_text("beat-unit")
+        .and_then(note_type_value)
+        .unwrap_or(4);
+    let mut quarters = 4.0 / unit as f64;
+    if metronome.has_child("beat-unit-dot") {
+        quarters *= 1.5;
+    }
+    Some((per_minute * quarters).round() as i32)
+}
+
+pub(crate) fn header_at(headers: &mut Vec<MeasureHeader>, m: usize) -> &mut MeasureHeader {
+    while headers.len() <= m {
+        let mut header = MeasureHeader {
+            number: headers.len() as u16 + 1,
+            ..Default::default()
+        };
+        if let Some(previous) = headers.last() {
+            header.time_signature = previous.time_signature.clone();
+            header.key_signature = previous.key_signature.clone();
+        }
+        headers.push(header);
+    }
+    &mut headers[m]
+}
+
+/// A tempo change: the song tempo at the very start, the measure tempo elsewhere.
+pub(crate) fn set_tempo(song: &mut Song, m: usize, position: u64, tempo: i32) {
+    if tempo <= 0 {
+        return;
+    }
+    if m == 0 && position == 0 {
+        song.tempo = tempo.min(i16::MAX as i32) as i16;
+    } else {
+        song.measure_headers[m].tempo = tempo;
+    }
+}
+
+impl PartReader {
+    fn ticks(&self, divisions: u64) -> u64 {
+        divisions * DURATION_QUARTER_TIME as u64 / self.divisions.max(1)
+    }
+
+    fn read_measure(&mut self, song: &mut Song, info: &PartInfo, measure: &XmlNode, m: usize) {
+        let header = header_at(&mut song.measure_headers, m);
+        if self.ending != 0 {
+            header.repeat_alternative = self.ending;
+        }
+        let mut events = Vec::new();
+        let mut position = 0u64;
+        let mut last_start = 0u64;
+        for child in &measure.children {
+            let length = child.child_value::<u64>("duration").unwrap_or(0);
+            match child.name.as_str() {
+                "attributes" => self.read_attributes(child, &mut song.measure_headers[m]),
+                "direction" => self.read_direction(song, child, m, position),
+                "sound" => {
+                    if let Some(tempo) =
+                        child.attribute("tempo").and_then(|t| t.parse::<f64>().ok())
+                    {
+                        set_tempo(song, m, position, tempo.round() as i32);
+                    }
+                }
+                "barline" => self.read_barline(child, &mut song.measure_headers[m]),
+                "backup" => position = position.saturating_sub(self.ticks(length)),
+                "forward" => position += self.ticks(length),
+                "note" => {
+                    let grace = child.has_child("grace");
+                    let start = if child.has_child("chord") {
+                        last_start
+                    } else {
+                        last_start = position;
+                        position
+                    };
+                    if !grace && !child.has_child("chord") {
+                        position += self.ticks(length);
+                    }
+                    if let Some(event) = self.read_note(info, child, start, self.ticks(length)) {
+                        events.push(event);
+                    }
+                }
+                _ => {}
+            }
+        }
+        self.attach_words(&mut events);
+        self.measures.push(events);
+        self.clefs.push(self.clef.clone());
+    }
+
+    fn read_attributes(&mut self, attributes: &XmlNode, header: &mut MeasureHeader) {
+        if let Some(divisions) = attributes.child_value::<u64>("divisions") {
+            self.divisions = divisions.max(1);
+        }
+        if let Some(key) = attributes.child("key") {
+            header.key_signature.key = key.child_value::<i8>("fifths").unwrap_or(0).clamp(-7, 7);
+            header.key_signature.is_minor = key.child_text("mode") == Some("minor");
+        }
+        if let Some(time) = attributes.child("time") {
+            let beats: i32 = time
+                .child_text("beats")
+                .unwrap_or("4")
+                .split('+')
+                .filter_map(|b| b.trim().parse::<i32>().ok())
+                .sum();
+            header.time_signature.numerator = beats.clamp(1, i8::MAX as i32) as i8;
+            header.time_signature.denominator.value =
+                time.child_value::<u16>("beat-type").unwrap_or(4).max(1);
+        }
+        for clef in attributes.children("clef") {
+            let number = clef.attribute("number").unwrap_or("1").to_string();
+            match clef.child_text("sign").unwrap_or_default() {
+                "TAB" => self.tab_staff = Some(number),
+                "percussion" => self.percussion = true,
+                sign if self.tab_staff.as_ref() != Some(&number) => {
+                    let line = clef.child_value::<u8>("line").unwrap_or(0);
+                    self.clef = match (sign, line) {
+                        ("F", _) => MeasureClef::Bass,
+                        ("C", 4) => MeasureClef::Tenor,
+                        ("C", _) => MeasureClef::Alto,
+                        _ => MeasureClef::Treble,
+                    };
+                }
+                _ => {}
+            }
+        }
+        for details in attributes.children("staff-details") {
+            let lines = details.children("staff-tuning").filter_map(|tuning| {
+                let line = tuning.attribute("line")?.parse::<usize>().ok()?;
+                let pitch = Pitch {
+                    step: tuning.child_text("tuning-step")?.chars().next()?,
+                    alter: tuning
+                        .child_value::<f64>("tuning-alter")
+                        .unwrap_or(0.0)
+                        .round() as i8,
+                    octave: tuning.child_value("tuning-octave")?,
+                };
+                Some((line, pitch.to_midi().clamp(0, 127) as i8))
+            });
+            let lines: Vec<(usize, i8)> =
+                lines.filter(|(line, _)| (1..=12).contains(line)).collect();
+            if !lines.is_empty() {
+                let count = lines.iter().map(|(line, _)| *line).max().unwrap_or(0);
+                self.tuning = vec![0; count];
+                for (line, tuning) in lines {
+                    self.tuning[line - 1] = tuning;
+                }
+            }
+            if let Some(capo) = details.child_value::<i32>("capo") {
+                self.capo = capo.max(0);
+            }
+        }
+    }
+
+    fn read_direction(&mut self, song: &mut Song, direction: &XmlNode, m: usize, position: u64) {
+        let sound = direction.child("sound");
+        let mut words = Vec::new();
+        let mut symbol = None;
+        let mut tempo = None;
+        for item in direction
+            .children("direction-type")
+            .flat_map(|t| &t.children)
+        {
+            match item.name.as_str() {
+                "words" if !item.text.trim().is_empty() => words.push(item.text.trim().to_string()),
+                "segno" => symbol = Some("segno"),
+                "coda" => symbol = Some("coda"),
+                "rehearsal" => {
+                    song.measure_headers[m].marker = Some(Marker {
+                        title: item.text.trim().to_string(),
+                        ..Default::default()
+                    })
+                }
+                "metronome" => tempo = metronome_tempo(item),
+                _ => {}
+            }
+        }
+        if let Some(t) = sound
+            .and_then(|s| s.attribute("tempo"))
+            .and_then(|t| t.parse::<f64>().ok())
+        {
+            tempo = Some(t.round() as i32);
+        }
+        if let Some(tempo) = tempo {
+            set_tempo(song, m, position, tempo);
+        }
+
+        let sign = direction_sign(&words, sound, symbol);
+        if let Some(sign) = &sign {
+            song.measure_headers[m].direction = Some(sign.clone());
+        }
+        for word in words {
+            if sign
+                .as_ref()
+                .is_some_and(|s| sign_direction(s).text.eq_ignore_ascii_case(&word))
+            {
+                continue;
+            }
+            let mut written = Words {
+                position,
+                ..Default::default()
+            };
+            match word.to_ascii_lowercase().as_str() {
+                "p.m." | "pm" | "p.m" | "palm mute" => written.palm_mute = true,
+                "let ring" => written.let_ring = true,
+                _ => written.text = word,
+            }
+            self.words.push(written);
+        }
+    }
+
+    /// Apply the direction words and the lyrics read apart to the notes they belong to: the
+    /// first voice's beat at the same position (the next beat for words).
+    fn attach_words(&mut self, events: &mut [NoteEvent]) {
+        let beat_at = |events: &[NoteEvent], position: u64, exact: bool| {
+            let start = events
+                .iter()
+                .filter(|e| !e.grace && (e.start == position || !exact && e.start > position))
+                .map(|e| e.start)
+                .min()?;
+            let voice = events
+                .iter()
+                .filter(|e| !e.grace && e.start == start)
+                .map(|e| e.voice)
+                .min()?;
+            Some((start, voice))
+        };
+        for words in std::mem::take(&mut self.words) {
+            let Some((start, voice)) = beat_at(events, words.position, false) else {
+                continue;
+            };
+            let mut beat = events
+                .iter_mut()
+                .filter(|e| !e.grace && e.start == start && e.voice == voice);
+            if let Some(first) = beat.next() {
+                if !words.text.is_empty() {
+                    if !first.text.is_empty() {
+                        first.text.push(' ');
+                    }
+                    first.text.push_str(&words.text);
+                }
+                for event in std::iter::once(first).chain(beat) {
+                    event.note.effect.palm_mute |= words.palm_mute;
+                    event.note.effect.let_ring |= words.let_ring;
+                }
+            }
+        }
+        for (position, lyrics) in std::mem::take(&mut self.lyrics) {
+            let Some((start, voice)) = beat_at(events, position, true) else {
+                continue;
+            };
+            if let Some(event) = events
+                .iter_mut()
+                .find(|e| !e.grace && e.start == start && e.voice == voice)
+            {
+                event.lyrics.extend(lyrics);
+            }
+        }
+    }
+
+    fn read_barline(&mut self, barline: &XmlNode, header: &mut MeasureHeader) {
+        if let Some(ending) = barline.child("ending") {
+            let mask = ending_mask(ending.attribute("number").unwrap_or_default());
+            match ending.attribute("type") {
+                Some("start") => {
+                    self.ending = mask;
+                    header.repeat_alternative = mask;
+                }
+                _ => {
+                    header.repeat_alternative |= mask;
+                    self.ending = 0;
+                }
+            }
+        }
+        if let Some(repeat) = barline.child("repeat") {
+            if repeat.attribute("direction") == Some("forward") {
+                header.repeat_open = true;
+            } else {
+                let times = repeat
+                    .attribute("times")
+                    .and_then(|t| t.parse::<i32>().ok())
+                    .unwrap_or(2);
+                header.repeat_close = (times - 1).clamp(0, i8::MAX as i32) as i8;
+            }
+        }
+        if barline.attribute("location") == Some("right")
+            && barline.child_text("bar-style") == Some("light-light")
+        {
+            header.double_bar = true;
+        }
+    }
+
+    fn read_note(
+        &mut self,
+        info: &PartInfo,
+        node: &XmlNode,
+        start: u64,
+        length: u64,
+    ) -> Option<NoteEvent> {
+        let lyrics: Vec<Syllable> = node
+            .children("lyric")
+            .filter_map(|lyric| {
+                let number = lyric
+                    .attribute("number")
+                    .and_then(|n| n.parse::<u8>().ok())
+                    .unwrap_or(1);
+                Some((
+                    number,
+                    lyric.child_text("syllabic").unwrap_or("single").to_string(),
+                    lyric.child_text("text")?.to_string(),
+                ))
+            })
+            .collect();
+        let staff = node.child_text("staff").unwrap_or("1").to_string();
+        if self.tab_staff.as_ref().is_some_and(|tab| *tab != staff) {
+            if !lyrics.is_empty() {
+                self.lyrics.push((start, lyrics));
+            }
+            return None;
+        }
+        let key = (staff, node.child_text("voice").unwrap_or("1").to_string());
+        let voice = match self.voices.iter().position(|v| *v == key) {
+            Some(voice) => voice,
+            None if self.voices.len() < MAX_VOICES => {
+                self.voices.push(key);
+                self.voices.len() - 1
+            }
+            None => return None,
+        };
+
+        let pitch = if let Some(pitch) = node.child("pitch") {
+            Some(
+                Pitch {
+                    step: pitch.child_text("step")?.chars().next()?,
+                    alter: pitch.child_value::<f64>("alter").unwrap_or(0.0).round() as i8,
+                    octave: pitch.child_value("octave")?,
+                }
+                .to_midi(),
+            )
+        } else if let Some(unpitched) = node.child("unpitched") {
+            let instrument = node.child("instrument").and_then(|i| i.attribute("id"));
+            match instrument.and_then(|id| info.unpitched.get(id)) {
+                Some(key) => Some(*key),
+                None => Some(
+                    Pitch {
+                        step: unpitched.child_text("display-step")?.chars().next()?,
+                        alter: 0,
+                        octave: unpitched.child_value("display-octave")?,
+                    }
+                    .to_midi(),
+                ),
+            }
+        } else {
+            None
+        };
+
+        let mut note = Note {
+            kind: NoteType::Normal,
+            ..Default::default()
+        };
+        if node
+            .children("tie")
+            .any(|t| t.attribute("type") == Some("stop"))
+        {
+            note.kind = NoteType::Tie;
+        }
+        let position = read_notations(node, &mut note);
+        if let Some(notehead) = node.child("notehead") {
+            if notehead.text.trim() == "x" {
+                note.kind = NoteType::Dead;
+            }
+            note.effect.ghost_note = notehead.attribute("parentheses") == Some("yes");
+        }
+        Some(NoteEvent {
+            voice,
+            start,
+            length,
+            duration: read_duration(node, length),
+            grace: node.has_child("grace"),
+            // `steal-time-previous` or no attribute: before the beat
+            grace_on_beat: node
+                .child("grace")
+                .is_some_and(|g| g.attribute("steal-time-following").is_some()),
+            hidden: node.attribute("print-object") == Some("no"),
+            pitch,
+            position,
+            note,
+            text: String::new(),
+            lyrics,
+        })
+    }
+}
+
+/// Builds the measures of a track from the events read from its part.
+struct TrackBuilder<'a> {
+    track: &'a mut Track,
+    capo: i32,
+    /// Last position of each (voice, MIDI key), to keep tied notes on their string
+    positions: HashMap<(usize, i32), Position>,
+    /// Fingering planned for the notes without written string: (measure, voice, start,
+    /// MIDI key)
+    planned: HashMap<(usize, usize, u64, i32), Position>,
+    /// Text of each lyric line and the index of its first measure
+    lyrics: Vec<(Option<usize>, String)>,
+    max_beats: usize,
+}
+
+impl TrackBuilder<'_> {
+    fn build(&mut self, headers: &[MeasureHeader], part: ReadPart) -> GpResult<()> {
+        let voice_count = part.voice_count.clamp(2, MAX_VOICES);
+        if !self.track.percussion_track {
+            self.plan_fingering(&part, voice_count);
+        }
+        for (m, (header, events)) in headers.iter().zip(part.measures).enumerate() {
+            let mut voices: Vec<Voice> = (0..voice_count)
+                .map(|_| Voice {
+                    measure_index: m as i16,
+                    ..Default::default()
+                })
+                .collect();
+            let mut by_voice: Vec<Vec<NoteEvent>> = (0..voice_count).map(|_| Vec::new()).collect();
+            for event in events.into_iter().filter(|e| e.voice < voice_count) {
+                by_voice[event.voice].push(event);
+            }
+            for (v, mut events) in by_voice.into_iter().enumerate() {
+                events.sort_by_key(|e| e.start);
+                self.build_voice(header, m, v, events, &mut voices[v])?;
+                if voices[v].beats.is_empty() {
+                    let status = if v == 0 {
+                        BeatStatus::Rest
+                    } else {
+                        BeatStatus::Empty
+                    };
+                    for duration in fill_durations(header.length().max(0) as u64, self.max_beats)? {
+                        voices[v].beats.push(Beat {
+                            duration,
+                            status: status.clone(),
+                            start: Some(header.start),
+                            ..Default::default()
+                        });
+                        if status == BeatStatus::Empty {
+                            break;
+                        }
+                    }
+                }
+            }
+            self.track.measures.push(Measure {
+                number: m + 1,
+                start: header.start,
+                track_index: self.track.number as usize - 1,
+                header_index: m,
+                time_signature: header.time_signature.clone(),
+                key_signature: header.key_signature.clone(),
+                clef: part.clefs.get(m).cloned().unwrap_or_default(),
+                voices,
+                ..Default::default()
+            });
+        }
+        Ok(())
+    }
+
+    /// Plan the strings and frets of the notes the score does not place, voice by voice
+    /// along the part, the notes starting together as chords.
+    fn plan_fingering(&mut self, part: &ReadPart, voice_count: usize) {
+        for v in 0..voice_count {
+            let mut groups: Vec<Vec<(usize, u64, i32)>> = Vec::new();
+            for (m, events) in part.measures.iter().enumerate() {
+                let mut events: Vec<&NoteEvent> = events
+                    .iter()
+                    .filter(|e| e.voice == v && !e.grace && e.position.is_none())
+                    .filter(|e| e.pitch.is_some() && e.note.kind != NoteType::Tie)
+                    .collect();
+                events.sort_by_key(|e| e.start);
+                for event in events {
+                    let key = (m, event.start, event.pitch.unwrap_or(0));
+                    match groups.last_mut() {
+                        Some(group) if (group[0].0, group[0].1) == (m, event.start) => {
+                            group.push(key)
+                        }
+                        _ => groups.push(vec![key]),
+                    }
+                }
+            }
+            let chords: Vec<Vec<u8>> = groups
+                .iter()
+                .map(|group| group.iter().map(|k| k.2.clamp(0, 127) as u8).collect())
+                .collect();
+            let fingering = crate::edit::fingering::fingering(
+                &chords,
+                &self.track.strings,
+                self.track.fret_count,
+                self.capo,
+            );
+            for (group, positions) in groups.iter().zip(fingering) {
+                for (&(m, start, pitch), position) in group.iter().zip(positions) {
+                    if let Some(position) = position {
+                        self.planned.insert((m, v, start, pitch), position);
+                    }
+                }
+            }
+        }
+    }
+
+    fn rests(
+        &self,
+        header: &MeasureHeader,
+        start: u64,
+        length: u64,
+        voice: &mut Voice,
+    ) -> GpResult<()> {
+        let mut tick = start;
+        for duration in fill_durations(length, self.max_beats)? {
+            let ticks = duration.time() as u64;
+            voice.beats.push(Beat {
+                duration,
+                status: BeatStatus::Rest,
+                start: Some(header.start + tick as i64),
+                ..Default::default()
+            });
+            tick += ticks;
+        }
+        Ok(())
+    }
+
+    fn build_voice(
+        &mut self,
+        header: &MeasureHeader,
+        m: usize,
+        v: usize,
+        events: Vec<NoteEvent>,
+        voice: &mut Voice,
+    ) -> GpResult<()> {
+        let mut cursor = 0u64;
+        let mut graces: Vec<NoteEvent> = Vec::new();
+        let mut events = events.into_iter().peekable();
+        while let Some(first) = events.next() {
+            if first.grace {
+                graces.push(first);
+                continue;
+            }
+            let mut group = vec![first];
+            while let Some(next) = events.next_if(|e| !e.grace && e.start == group[0].start) {
+                group.push(next);
+            }
+            let start = group[0].start;
+            if start > cursor {
+                self.rests(header, cursor, start - cursor, voice)?;
+            }
+            cursor = cursor.max(start + group[0].length);
+
+            let mut beat = Beat {
+                duration: group[0].duration.clone(),
+                start: Some(header.start + start as i64),
+                text: group
+                    .iter()
+                    .map(|e| e.text.as_str())
+                    .collect::<Vec<_>>()
+                    .join(" ")
+                    .trim()
+                    .to_string(),
+                ..Default::default()
+            };
+            beat.notes = self.place_notes(m, v, &group);
+            beat.status = if !beat.notes.is_empty() {
+                BeatStatus::Normal
+            } else if group.iter().all(|e| e.hidden) {
+                BeatStatus::Empty
+            } else {
+                BeatStatus::Rest
+            };
+            for grace in graces.drain(..) {
+                self.attach_grace(&grace, &mut beat.notes);
+            }
+            if beat.status == BeatStatus::Normal {
+                self.add_lyrics(m, &group);
+            }
+            voice.beats.push(beat);
+        }
+        Ok(())
+    }
+
+    /// Notes of a beat on the strings: `<technical>` positions first, then the planned
+    /// fingering, then the other pitches on the free strings.
+    fn place_notes(&mut self, m: usize, v: usize, group: &[NoteEvent]) -> Vec<Note> {
+        let sounding: Vec<&NoteEvent> = group.iter().filter(|e| e.pitch.is_some()).collect();
+        let mut positions: Vec<Option<Position>> = sounding
+            .iter()
+            .map(|e| {
+                e.position.or_else(|| {
+                    if e.note.kind != NoteType::Tie {
+                        return None;
+                    }
+                    self.positions.get(&(v, e.pitch.unwrap_or(0))).copied()
+                })
+            })
+            .collect();
+        let mut busy: Vec<i8> = positions.iter().flatten().map(|p| p.0).collect();
+        for (i, event) in sounding.iter().enumerate() {
+            let key = (m, v, event.start, event.pitch.unwrap_or(0));
+            if let Some(&planned) = self.planned.get(&key) {
+                if positions[i].is_none() && !busy.contains(&planned.0) {
+                    positions[i] = Some(planned);
+                    busy.push(planned.0);
+                }
+            }
+        }
+        let missing: Vec<usize> = (0..sounding.len())
+            .filter(|&i| positions[i].is_none())
+            .collect();
+        let pitches: Vec<u8> = missing
+            .iter()
+            .map(|&i| {
+                let offset = if self.track.percussion_track {
+                    0
+                } else {
+                    self.capo
+                };
+                (sounding[i].pitch.unwrap_or(0) - offset).clamp(0, 127) as u8
+            })
+            .collect();
+        let placed = if self.track.percussion_track {
+            assign_drums(&pitches, &self.track.strings, &busy)
+        } else {
+            assign_strings(&pitches, &self.track.strings, &busy, self.track.fret_count)
+        };
+        for (&i, position) in missing.iter().zip(placed) {
+            positions[i] = position;
+        }
+
+        let mut notes = Vec::new();
+        for (event, position) in sounding.iter().zip(positions) {
+            let Some((string, fret)) = position else {
+                continue;
+            };
+            self.positions
+                .insert((v, event.pitch.unwrap_or(0)), (string, fret));
+            let mut note = event.note.clone();
+            note.string = string;
+            note.value = fret;
+            notes.push(note);
+        }
+        notes.sort_by_key(|n| n.string);
+        notes
+    }
+
+    /// Set a grace note as the grace effect of the note it leads to.
+    fn attach_grace(&self, grace: &NoteEvent, notes: &mut [Note]) {
+        let index = grace
+            .position
+            .and_then(|(string, _)| notes.iter().position(|n| n.string == string))
+            .unwrap_or(0);
+        let Some(target) = notes.get_mut(index) else {
+            return;
+        };
+        let fret = match grace.position {
+            Some((_, fret)) => fret,
+            None => {
+                let tuning = self
+                    .track
+                    .strings
+                    .iter()
+                    .find(|s| s.0 == target.string)
+                    .map_or(0, |s| i32::from(s.1));
+                let offset = if self.track.percussion_track {
+                    0
+                } else {
+                    self.capo
+                };
+                (grace.pitch.unwrap_or(0) - tuning - offset).max(0) as i16
+            }
+        };
+        target.effect.grace = Some(GraceEffect {
+            duration: grace.duration.value.clamp(16, 64) as u8,
+            fret: fret.clamp(0, i8::MAX as i16) as i8,
+            is_dead: grace.note.kind == NoteType::Dead,
+            is_on_beat: grace.grace_on_beat,
+            transition: if grace.note.effect.hammer {
+                GraceEffectTransition::Hammer
+            } else if !grace.note.effect.slides.is_empty() {
+                GraceEffectTransition::Slide
+            } else {
+                GraceEffectTransition::None
+            },
+            ..Default::default()
+        });
+    }
+
+    fn add_lyrics(&mut self, m: usize, group: &[NoteEvent]) {
+        for (number, syllabic, text) in group.iter().flat_map(|e| &e.lyrics) {
+            let Some(line) = self.lyrics.get_mut((*number as usize).wrapping_sub(1)) else {
+                continue;
+            };
+            line.0.get_or_insert(m);
+            // Spaces inside a syllable are written `+` in Guitar Pro lyrics
+            line.1.push_str(&text.replace(' ', "+"));
+            line.1.push(if syllabic == "begin" || syllabic == "middle" {
+                '-'
+            } else {
+                ' '
+            });
+        }
+    }
+}
+
+/// Read a MusicXML partwise score, plain (`.musicxml`) or compressed (`.mxl`), into `song`.
+///
+/// Fretted parts take their strings and frets from `<technical>` when present, otherwise
+/// notes are placed on the strings of the part's `<staff-tuning>` (standard guitar tuning
+/// when missing). Parts with a TAB staff are read from that staff only.
+pub fn read_musicxml(song: &mut Song, data: &[u8], limits: &ReadLimits) -> GpResult<()> {
+    let text = if data.starts_with(b"PK\x03\x04") {
+        read_archive(data, limits)?
+    } else {
+        String::from_utf8_lossy(data).into_owned()
+    };
+    let score = parse_xml(&text)?;
+    if score.name != "score-partwise" {
+        return Err(format!(
+            "Unsupported MusicXML root element <{}>: only partwise scores are read",
+            score.name
+        )
+        .into());
+    }
+
+    let mut infos = read_part_list(&score);
+    read_identification(song, &score);
+    reset_song(song);
+
+    let mut parts = Vec::new();
+    for part in score.children("part") {
+        let info = infos.get(part.attribute("id").unwrap_or_default());
+        let default_info = PartInfo::default();
+        let info = info.unwrap_or(&default_info);
+        let mut reader = PartReader {
+            divisions: 1,
+            ..Default::default()
+        };
+        for (m, measure) in part.children("measure").enumerate() {
+            reader.read_measure(song, info, measure, m);
+        }
+        parts.push((part.attribute("id").unwrap_or_default().to_string(), reader));
+    }
+    let parts = parts
+        .into_iter()
+        .map(|(id, reader)| ReadPart {
+            info: infos.remove(&id).unwrap_or_default(),
+            percussion: reader.percussion,
+            tuning: reader.tuning,
+            fret_count: None,
+            capo: reader.capo,
+            voice_count: reader.voices.len(),
+            measures: reader.measures,
+            clefs: reader.clefs,
+        })
+        .collect();
+    build_tracks(song, parts, limits)
+}
+
+/// Clear the song before it is read from a score: no tracks, measures or lyrics, and the
+/// 64 default MIDI channels.
+pub(crate) fn reset_song(song: &mut Song) {
+    song.tempo = 120;
+    song.tracks.clear();
+    song.measure_headers.clear();
+    song.lyrics = Lyrics::default();
+    song.channels = (0..64u8)
+        .map(|channel| MidiChannel {
+            channel,
+            effect_channel: channel,
+            ..Default::default()
+        })
+        .collect();
+}
+
+/// Place the measure headers in time and turn each part into a track: MIDI channel,
+/// strings, measures and beats. The first part with lyrics gives the song lyrics.
+pub(crate) fn build_tracks(
+    song: &mut Song,
+    parts: Vec<ReadPart>,
+    limits: &ReadLimits,
+) -> GpResult<()> {
+    if song.measure_headers.is_empty() {
+        header_at(&mut song.measure_headers, 0);
+    }
+    let mut start = DURATION_QUARTER_TIME;
+    for header in &mut song.measure_headers {
+        header.start = start;
+        start += header.length();
+    }
+    song.key = song.measure_headers[0].key_signature.clone();
+
+    let mut used_channels: Vec<u8> = Vec::new();
+    for (index, part) in parts.into_iter().enumerate() {
+        let info = &part.info;
+        let percussion = part.percussion || info.channel == Some(DEFAULT_PERCUSSION_CHANNEL);
+        let channel = match info.channel {
+            _ if percussion => DEFAULT_PERCUSSION_CHANNEL,
+            Some(channel) => channel,
+            None => (0..16u8)
+                .find(|c| *c != DEFAULT_PERCUSSION_CHANNEL && !used_channels.contains(c))
+                .unwrap_or(0),
+        };
+        used_channels.push(channel);
+        let midi = &mut song.channels[channel as usize];
+        midi.set_instrument(i32::from(info.program.unwrap_or(if percussion {
+            0
+        } else {
+            25
+        })));
+        midi.bank = info.bank;
+        if let Some(volume) = info.volume {
+            midi.volume = volume;
+        }
+        if let Some(balance) = info.balance {
+            midi.balance = balance;
+        }
+
+        let mut track = Track {
+            number: index as i32 + 1,
+            name: if info.name.is_empty() {
+                format!("Track {}", index + 1)
+            } else {
+                info.name.clone()
+            },
+            short_name: info.abbreviation.clone(),
+            channel_index: channel as usize,
+            percussion_track: percussion,
+            offset: part.capo,
+            ..Default::default()
+        };
+        if percussion {
+            track.strings = (1..=6).map(|s| (s, 0)).collect();
+            track.fret_count = 127;
+        } else if !part.tuning.is_empty() {
+            track.strings = part
+                .tuning
+                .iter()
+                .rev()
+                .enumerate()
+                .map(|(i, tuning)| (i as i8 + 1, *tuning))
+                .collect();
+        }
+        if let Some(frets) = part.fret_count.filter(|_| !percussion) {
+            track.fret_count = frets;
+        }
+
+        let mut builder = TrackBuilder {
+            track: &mut track,
+            capo: part.capo,
+            positions: HashMap::new(),
+            planned: HashMap::new(),
+            lyrics: vec![(None, String::new()); LYRIC_LINES],
+            max_beats: limits.max_beats,
+        };
+        builder.build(&song.measure_headers, part)?;
+        let lyrics = std::mem::take(&mut builder.lyrics);
+        if song.lyrics.track_choice == 0 && lyrics.iter().any(|(start, _)| start.is_some()) {
+            song.lyrics.track_choice = index as u8 + 1;
+            song.lyrics.lines = lyrics
+                .into_iter()
+                .enumerate()
+                .map(|(i, (start, text))| {
+                    (
+                        i as u8,
+                        start.map_or(1, |m| m as u16 + 1),
+                        text.trim_end().to_string(),
+                    )
+                })
+                .collect();
+        }
+        song.tracks.push(track);
+    }
+    Ok(())
+}
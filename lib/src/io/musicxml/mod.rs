@@ -0,0 +1,240 @@
+// MusicXML 4.0 module
+
+pub mod export;
+pub mod import;
+
+use quick_xml::escape::escape;
+
+use crate::types::enums::DirectionSign;
+
+/// MusicXML root element version written and expected by this module.
+pub const MUSICXML_VERSION: &str = "4.0";
+
+const PARTWISE_DOCTYPE: &str = "<!DOCTYPE score-partwise PUBLIC \"-//Recordare//DTD MusicXML 4.0 Partwise//EN\" \"http://www.musicxml.org/dtds/partwise.dtd\">";
+
+/// Pitch steps of the twelve semitones, spelled with sharps then with flats, as (step, alter).
+const SHARP_SPELLING: [(char, i8); 12] = [
+    ('C', 0),
+    ('C', 1),
+    ('D', 0),
+    ('D', 1),
+    ('E', 0),
+    ('F', 0),
+    ('F', 1),
+    ('G', 0),
+    ('G', 1),
+    ('A', 0),
+    ('A', 1),
+    ('B', 0),
+];
+const FLAT_SPELLING: [(char, i8); 12] = [
+    ('C', 0),
+    ('D', -1),
+    ('D', 0),
+    ('E', -1),
+    ('E', 0),
+    ('F', 0),
+    ('G', -1),
+    ('G', 0),
+    ('A', -1),
+    ('A', 0),
+    ('B', -1),
+    ('B', 0),
+];
+
+/// MusicXML `<type>` names of the note values, from whole to 128th.
+const NOTE_TYPES: [(u16, &str); 8] = [
+    (1, "whole"),
+    (2, "half"),
+    (4, "quarter"),
+    (8, "eighth"),
+    (16, "16th"),
+    (32, "32nd"),
+    (64, "64th"),
+    (128, "128th"),
+];
+
+/// A MusicXML pitch: step letter, chromatic alteration and octave.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Pitch {
+    pub(crate) step: char,
+    pub(crate) alter: i8,
+    pub(crate) octave: i32,
+}
+
+impl Pitch {
+    /// Spell a MIDI key, with flats when `flats` is set (keys with flats in the signature).
+    pub(crate) fn from_midi(midi: i32, flats: bool) -> Pitch {
+        let spelling = if flats {
+            &FLAT_SPELLING
+        } else {
+            &SHARP_SPELLING
+        };
+        let (step, alter) = spelling[midi.rem_euclid(12) as usize];
+        Pitch {
+            step,
+            alter,
+            octave: midi.div_euclid(12) - 1,
+        }
+    }
+
+    /// Read a pitch back to its MIDI key.
+    pub(crate) fn to_midi(self) -> i32 {
+        let base = SHARP_SPELLING
+            .iter()
+            .position(|(step, alter)| *step == self.step && *alter == 0)
+            .unwrap_or(0) as i32;
+        (self.octave + 1) * 12 + base + i32::from(self.alter)
+    }
+}
+
+fn note_type_name(value: u16) -> Option<&'static str> {
+    NOTE_TYPES
+        .iter()
+        .find(|(v, _)| *v == value)
+        .map(|(_, n)| *n)
+}
+
+pub(crate) fn note_type_value(name: &str) -> Option<u16> {
+    NOTE_TYPES.iter().find(|(_, n)| *n == name).map(|(v, _)| *v)
+}
+
+/// How a `DirectionSign` is written: a segno/coda symbol or words, with its playback attribute.
+pub(crate) struct SignDirection {
+    pub(crate) symbol: Option<&'static str>,
+    pub(crate) text: &'static str,
+    pub(crate) sound: (&'static str, &'static str),
+    /// Jumps are written at the end of the measure, targets at its start.
+    pub(crate) at_end: bool,
+}
+
+/// Every `DirectionSign`, to look one up from its written form.
+pub(crate) const DIRECTION_SIGNS: [DirectionSign; 19] = [
+    DirectionSign::Coda,
+    DirectionSign::DoubleCoda,
+    DirectionSign::Segno,
+    DirectionSign::SegnoSegno,
+    DirectionSign::Fine,
+    DirectionSign::DaCapo,
+    DirectionSign::DaCapoAlCoda,
+    DirectionSign::DaCapoAlDoubleCoda,
+    DirectionSign::DaCapoAlFine,
+    DirectionSign::DaSegno,
+    DirectionSign::DaSegnoAlCoda,
+    DirectionSign::DaSegnoAlDoubleCoda,
+    DirectionSign::DaSegnoAlFine,
+    DirectionSign::DaSegnoSegno,
+    DirectionSign::DaSegnoSegnoAlCoda,
+    DirectionSign::DaSegnoSegnoAlDoubleCoda,
+    DirectionSign::DaSegnoSegnoAlFine,
+    DirectionSign::DaCoda,
+    DirectionSign::DaDoubleCoda,
+];
+
+pub(crate) fn sign_direction(sign: &DirectionSign) -> SignDirection {
+    let (symbol, text, sound, at_end) = match sign {
+        DirectionSign::Coda => (Some("coda"), "", ("coda", "coda"), false),
+        DirectionSign::DoubleCoda => (Some("coda"), "", ("coda", "coda2"), false),
+        DirectionSign::Segno => (Some("segno"), "", ("segno", "segno"), false),
+        DirectionSign::SegnoSegno => (Some("segno"), "", ("segno", "segno2"), false),
+        DirectionSign::Fine => (None, "Fine", ("fine", "yes"), true),
+        DirectionSign::DaCapo => (None, "D.C.", ("dacapo", "yes"), true),
+        DirectionSign::DaCapoAlCoda => (None, "D.C. al Coda", ("dacapo", "yes"), true),
+        DirectionSign::DaCapoAlDoubleCoda => (None, "D.C. al Double Coda", ("dacapo", "yes"), true),
+        DirectionSign::DaCapoAlFine => (None, "D.C. al Fine", ("dacapo", "yes"), true),
+        DirectionSign::DaSegno => (None, "D.S.", ("dalsegno", "segno"), true),
+        DirectionSign::DaSegnoAlCoda => (None, "D.S. al Coda", ("dalsegno", "segno"), true),
+        DirectionSign::DaSegnoAlDoubleCoda => {
+            (None, "D.S. al Double Coda", ("dalsegno", "segno"), true)
+        }
+        DirectionSign::DaSegnoAlFine => (None, "D.S. al Fine", ("dalsegno", "segno"), true),
+        DirectionSign::DaSegnoSegno => (None, "D.S.S.", ("dalsegno", "segno2"), true),
+        DirectionSign::DaSegnoSegnoAlCoda => (None, "D.S.S. al Coda", ("dalsegno", "segno2"), true),
+        DirectionSign::DaSegnoSegnoAlDoubleCoda => {
+            (None, "D.S.S. al Double Coda", ("dalsegno", "segno2"), true)
+        }
+        DirectionSign::DaSegnoSegnoAlFine => (None, "D.S.S. al Fine", ("dalsegno", "segno2"), true),
+        DirectionSign::DaCoda => (None, "To Coda", ("tocoda", "coda"), true),
+        DirectionSign::DaDoubleCoda => (None, "To Double Coda", ("tocoda", "coda2"), true),
+    };
+    SignDirection {
+        symbol,
+        text,
+        sound,
+        at_end,
+    }
+}
+
+/// Minimal indenting XML writer. Attribute values and text are escaped.
+#[derive(Default)]
+struct XmlWriter {
+    out: String,
+    depth: usize,
+}
+
+impl XmlWriter {
+    fn start_tag(&mut self, tag: &str, attributes: &[(&str, &str)]) {
+        for _ in 0..self.depth {
+            self.out.push_str("  ");
+        }
+        self.out.push('<');
+        self.out.push_str(tag);
+        for (name, value) in attributes {
+            self.out.push(' ');
+            self.out.push_str(name);
+            self.out.push_str("=\"");
+            self.out.push_str(&escape(value));
+            self.out.push('"');
+        }
+    }
+
+    fn open(&mut self, tag: &str, attributes: &[(&str, &str)]) {
+        self.start_tag(tag, attributes);
+        self.out.push_str(">\n");
+        self.depth += 1;
+    }
+
+    fn close(&mut self, tag: &str) {
+        self.depth -= 1;
+        for _ in 0..self.depth {
+            self.out.push_str("  ");
+        }
+        self.out.push_str("</");
+        self.out.push_str(tag);
+        self.out.push_str(">\n");
+    }
+
+    /// Open an element that `close_optional` removes again when nothing was written inside.
+    fn open_optional(&mut self, tag: &str) -> (usize, usize) {
+        let start = self.out.len();
+        self.open(tag, &[]);
+        (start, self.out.len())
+    }
+
+    fn close_optional(&mut self, tag: &str, (start, content): (usize, usize)) {
+        if self.out.len() == content {
+            self.out.truncate(start);
+            self.depth -= 1;
+        } else {
+            self.close(tag);
+        }
+    }
+
+    fn empty(&mut self, tag: &str, attributes: &[(&str, &str)]) {
+        self.start_tag(tag, attributes);
+        self.out.push_str("/>\n");
+    }
+
+    fn text_with(&mut self, tag: &str, attributes: &[(&str, &str)], text: &str) {
+        self.start_tag(tag, attributes);
+        self.out.push('>');
+        self.out.push_str(&escape(text));
+        self.out.push_str("</");
+        self.out.push_str(tag);
+        self.out.push_str(">\n");
+    }
+
+    fn text(&mut self, tag: &str, text: impl ToString) {
+        self.text_with(tag, &[], &text.to_string());
+    }
+}
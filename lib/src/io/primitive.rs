@@ -228,6 +228,19 @@ pub(crate) fn read_version_string(
     Ok(v)
 }
 
+/// Convert a value read from the file to the type it is stored in, failing with
+/// `GpError::InvalidValue` when it does not fit.
+pub(crate) fn convert<V, T>(value: V, context: &'static str) -> GpResult<T>
+where
+    V: Copy + Into<i64>,
+    T: TryFrom<V>,
+{
+    T::try_from(value).map_err(|_| GpError::InvalidValue {
+        context,
+        value: value.into(),
+    })
+}
+
 /// Read a color. Colors are used by `Marker` and `Track`. They consist of 3 consecutive bytes and one blank byte.
 pub(crate) fn read_color(data: &[u8], seek: &mut usize) -> GpResult<i32> {
     let r = read_byte(data, seek)?.to_i32().unwrap_or(0); // u8 to i32 is safe
@@ -0,0 +1,120 @@
+// Generic XML tree used by the MusicXML and MuseScore readers
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+
+use crate::error::GpResult;
+
+/// An XML element with its attributes, text content and child elements.
+#[derive(Debug, Clone, Default)]
+pub struct XmlNode {
+    pub name: String,
+    pub attributes: Vec<(String, String)>,
+    pub text: String,
+    pub children: Vec<XmlNode>,
+}
+
+impl XmlNode {
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn child(&self, name: &str) -> Option<&XmlNode> {
+        self.children.iter().find(|c| c.name == name)
+    }
+
+    pub fn children<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a XmlNode> + 'a {
+        self.children.iter().filter(move |c| c.name == name)
+    }
+
+    pub fn has_child(&self, name: &str) -> bool {
+        self.child(name).is_some()
+    }
+
+    /// Trimmed text of a child element.
+    pub fn child_text(&self, name: &str) -> Option<&str> {
+        self.child(name).map(|c| c.text.trim())
+    }
+
+    /// Child text parsed as a number (or any `FromStr` type).
+    pub fn child_value<T: std::str::FromStr>(&self, name: &str) -> Option<T> {
+        self.child_text(name).and_then(|t| t.parse().ok())
+    }
+
+    /// Text of the element at a `/`-separated path of child names.
+    pub fn path_text(&self, path: &str) -> Option<&str> {
+        let mut node = self;
+        for name in path.split('/') {
+            node = node.child(name)?;
+        }
+        Some(node.text.trim())
+    }
+}
+
+fn node(e: &BytesStart) -> GpResult<XmlNode> {
+    let mut attributes = Vec::new();
+    for attribute in e.attributes() {
+        let attribute = attribute.map_err(|e| format!("XML attribute error: {}", e))?;
+        let value = attribute
+            .unescape_value()
+            .map_err(|e| format!("XML attribute error: {}", e))?;
+        attributes.push((
+            String::from_utf8_lossy(attribute.key.as_ref()).into_owned(),
+            value.into_owned(),
+        ));
+    }
+    Ok(XmlNode {
+        name: String::from_utf8_lossy(e.name().as_ref()).into_owned(),
+        attributes,
+        ..Default::default()
+    })
+}
+
+/// Parse a document and return its root element.
+pub fn parse_xml(xml: &str) -> GpResult<XmlNode> {
+    let mut reader = Reader::from_str(xml.trim_start_matches('\u{feff}'));
+    let mut stack = vec![XmlNode::default()];
+    loop {
+        let event = reader
+            .read_event()
+            .map_err(|e| format!("XML parse error at {}: {}", reader.buffer_position(), e))?;
+        match event {
+            Event::Start(e) => stack.push(node(&e)?),
+            Event::Empty(e) => {
+                let empty = node(&e)?;
+                if let Some(parent) = stack.last_mut() {
+                    parent.children.push(empty);
+                }
+            }
+            Event::Text(t) => {
+                let text = t.unescape().map_err(|e| format!("XML text error: {}", e))?;
+                if let Some(current) = stack.last_mut() {
+                    current.text.push_str(&text);
+                }
+            }
+            Event::CData(t) => {
+                if let Some(current) = stack.last_mut() {
+                    current.text.push_str(&String::from_utf8_lossy(&t));
+                }
+            }
+            Event::End(_) => {
+                let done = stack.pop().unwrap_or_default();
+                match stack.last_mut() {
+                    Some(parent) => parent.children.push(done),
+                    None => return Err(String::from("XML parse error: unbalanced elements").into()),
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    if stack.len() != 1 {
+        return Err(String::from("XML parse error: unclosed elements").into());
+    }
+    stack
+        .pop()
+        .and_then(|document| document.children.into_iter().next())
+        .ok_or_else(|| String::from("XML parse error: no root element").into())
+}
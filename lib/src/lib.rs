@@ -1,12 +1,15 @@
 pub mod audio;
+pub mod edit;
 pub mod error;
 pub mod io;
 pub mod model;
+pub mod theory;
 pub mod traits;
 pub mod types;
+pub mod validate;
 
 // Re-export error types
-pub use crate::error::{GpError, GpResult};
+pub use crate::error::{GpError, GpResult, ScorePart};
 
 // Re-export core types
 pub use crate::model::beat::{Beat, Voice};
@@ -18,6 +21,16 @@ pub use crate::model::note::Note;
 pub use crate::model::page::PageSetup;
 pub use crate::model::song::Song;
 pub use crate::model::track::Track;
+pub use crate::io::ascii_tab::parse::TabIssue;
+pub use crate::io::format::{detect_format, FileFormat};
+pub use crate::io::gp345::recovery::ReadWarning;
+pub use crate::io::limits::ReadLimits;
+pub use crate::io::gpif::import::report::{GpifIssue, GpifIssueKind};
+pub use crate::io::ascii_tab::render::TabOptions;
+pub use crate::audio::tempo::{TempoMap, TempoSegment};
+pub use crate::audio::timeline::NoteEvent;
+pub use crate::edit::retune::UnplacedNote;
+pub use crate::validate::diagnostic::{Diagnostic, Issue, Location, Severity};
 pub use crate::types::enums::*;
 
 // Re-export traits for easy use
@@ -35,6 +35,16 @@ pub struct KeySignature {
     pub is_minor: bool,
 }
 //impl Default for KeySignature { fn default() -> Self { KeySignature { key: 0, is_minor: false, }} }
+impl KeySignature {
+    /// Key signature of the key `semitones` higher: the one with the fewest accidentals,
+    /// keeping the side of the circle of fifths on a tritone (6 sharps or 6 flats).
+    pub(crate) fn transposed(&self, semitones: i32) -> KeySignature {
+        // A fifth up (7 semitones) adds a sharp, so a semitone up adds 7 modulo 12
+        let mut key = (i32::from(self.key) + 7 * semitones).rem_euclid(12);
+        if key > 6 || (key == 6 && self.key < 0) { key -= 12; }
+        KeySignature { key: key as i8, is_minor: self.is_minor }
+    }
+}
 impl std::fmt::Display for KeySignature {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         let index: usize = if self.is_minor {(23i8 + self.key).to_usize().unwrap()} else {(8i8 + self.key).to_usize().unwrap()};
@@ -43,7 +53,7 @@ impl std::fmt::Display for KeySignature {
 }
 
 
-const SUPPORTED_TUPLETS: [(u8, u8); 10] = [(1,1), (3,2), (5,4), (6,4), (7,4), (9,8), (10,8), (11,8), (12,8), (13,8)];
+pub(crate) const SUPPORTED_TUPLETS: [(u8, u8); 10] = [(1,1), (3,2), (5,4), (6,4), (7,4), (9,8), (10,8), (11,8), (12,8), (13,8)];
 
 #[derive(Debug,Clone,PartialEq,Eq)]
 pub struct Duration {
@@ -68,7 +78,9 @@ impl Duration {
     pub(crate) fn is_supported(&self) -> bool { SUPPORTED_TUPLETS.contains(&(self.tuplet_enters, self.tuplet_times))}
 
     pub(crate) fn convert_time(&self, time: u32) -> u32 {
-        let result = fraction::Fraction::new(time * self.tuplet_enters.to_u32().unwrap(), self.tuplet_times.to_u32().unwrap());
+        // A n:m tuplet plays n notes in the time of m
+        if self.tuplet_enters == 0 {return time;}
+        let result = fraction::Fraction::new(time * self.tuplet_times.to_u32().unwrap(), self.tuplet_enters.to_u32().unwrap());
         if *result.denom().unwrap() == 1 {(*result.numer().unwrap()).to_u32().unwrap()}
         else {result.trunc().to_u32().unwrap()}
     }
@@ -77,6 +89,7 @@ impl Duration {
         let mut result = (f64::from(DURATION_QUARTER_TIME.to_i32().unwrap()) * 4f64 / f64::from(self.value)).trunc();
         //println!("\tDuration.time(): result: {}", result);
         if self.dotted { result += (result/2f64).trunc(); }
+        else if self.double_dotted { result += (result/2f64).trunc() + (result/4f64).trunc(); }
         //if self.dotted { result += (result/4f64).trunc() * 3f64; }
         //println!("\tDuration.time(): result: {}", result);
         self.convert_time(result.to_u32().unwrap())
@@ -118,7 +131,7 @@ impl Duration {
 pub(crate) fn read_duration(data: &[u8], seek: &mut usize, flags: u8) -> GpResult<Duration> {
     //println!("read_duration()");
     let b = read_signed_byte(data, seek)?;
-    let shift = b + 2;
+    let shift = i16::from(b) + 2;
     let val = if (0..16).contains(&shift) { 1u16 << shift } else { 1u16 }; // Fallback to 1 (whole note?) or whatever safe
     let mut d = Duration{value: val, ..Default::default()};
     //let b = read_signed_byte(data, seek); println!("B: {}", b); d.value = 1 << (b + 2);
@@ -38,9 +38,9 @@ impl SongLyricOps for Song {
     /// First, read an `i32` that points to the track lyrics are bound to. Then it is followed by 5 lyric lines. Each one consists of
     /// number of starting measure encoded in`i32` and`int-size-string` holding text of the lyric line.
     fn read_lyrics(&self, data: &[u8], seek: &mut usize) -> GpResult<Lyrics> {
-        let mut lyrics = Lyrics{track_choice: read_int(data, seek)?.to_u8().unwrap(), ..Default::default()};
+        let mut lyrics = Lyrics{track_choice: convert(read_int(data, seek)?, "lyrics track")?, ..Default::default()};
         for i in 0..5u8 {
-            let starting_measure = read_int(data, seek)?.to_u16().unwrap();
+            let starting_measure = convert(read_int(data, seek)?, "lyrics starting measure")?;
             lyrics.lines.push((i, starting_measure, read_int_size_string(data, seek)?));
         }
         Ok(lyrics)
@@ -1,6 +1,6 @@
 use fraction::ToPrimitive;
 
-use crate::error::GpResult;
+use crate::error::{GpResult, ScorePart};
 use crate::{
     io::primitive::*,
     model::{key_signature::*, song::*},
@@ -119,13 +119,18 @@ impl SongMeasureOps for Song {
                     header_index: h,
                     ..Default::default()
                 };
-                self.current_measure_number = Some(m.number);
-                if self.version.number < (5, 0, 0) {
-                    self.read_measure(data, seek, &mut m, t)?;
+                self.current_measure_number = Some(h + 1);
+                let read = if self.version.number < (5, 0, 0) {
+                    self.read_measure(data, seek, &mut m, t)
                 } else {
-                    self.read_measure_v5(data, seek, &mut m, t)?;
-                }
+                    self.read_measure_v5(data, seek, &mut m, t)
+                };
+                // Keep what was read of the measure, for the lenient reading
                 self.tracks[t].measures.push(m);
+                read.map_err(|e| {
+                    e.within(ScorePart::Measure(h), *seek)
+                        .within(ScorePart::Track(t), *seek)
+                })?;
             }
             //println!("read_measures(), start: {} \t numerator: {} \t denominator: {} \t length: {}", start, self.measure_headers[h].time_signature.numerator, self.measure_headers[h].time_signature.denominator.value, self.measure_headers[h].length());
         }
@@ -145,9 +150,10 @@ impl SongMeasureOps for Song {
         //println!("read_measure()");
         let mut voice = Voice::default();
         self.current_voice_number = Some(1);
-        self.read_voice(data, seek, &mut voice, &mut measure.start, track_index)?;
-        self.current_voice_number = None;
+        let read = self.read_voice(data, seek, &mut voice, &mut measure.start, track_index);
         measure.voices.push(voice);
+        read.map_err(|e| e.within(ScorePart::Voice(0), *seek))?;
+        self.current_voice_number = None;
         /*
         //read a voice
         let beats = read_int(data, seek).to_usize().unwrap();
@@ -180,8 +186,9 @@ impl SongMeasureOps for Song {
             self.current_voice_number = Some(number + 1);
             //println!("read_measure_v5() {:?}",self.current_voice_number);
             let mut voice = Voice::default();
-            self.read_voice(data, seek, &mut voice, &mut start, track_index)?;
+            let read = self.read_voice(data, seek, &mut voice, &mut start, track_index);
             measure.voices.push(voice);
+            read.map_err(|e| e.within(ScorePart::Voice(number), *seek))?;
         }
         self.current_voice_number = None;
         if *seek < data.len() {
@@ -215,10 +222,11 @@ impl SongMeasureOps for Song {
             self.current_beat_number = Some(i + 1);
             //println!("read_measure() read_voice(), start: {}", measure.start);
             *start += if self.version.number < (5, 0, 0) {
-                self.read_beat(data, seek, voice, *start, track_index)?
+                self.read_beat(data, seek, voice, *start, track_index)
             } else {
-                self.read_beat_v5(data, seek, voice, &mut *start, track_index)?
-            };
+                self.read_beat_v5(data, seek, voice, &mut *start, track_index)
+            }
+            .map_err(|e| e.within(ScorePart::Beat(i), *seek))?;
             //println!("read_measure() read_voice(), start: {}", measure.start);
         }
         self.current_beat_number = None;
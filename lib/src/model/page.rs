@@ -91,14 +91,14 @@ impl SongPageOps for Song {
     ///   * copyright2, e.g. *"All Rights Reserved - International Copyright Secured"*
     ///   * pageNumber
     fn read_page_setup(&mut self, data: &[u8], seek: &mut usize) -> GpResult<()> {
-        self.page_setup.page_size.x = read_int(data, seek)?.to_u16().unwrap();
-        self.page_setup.page_size.y = read_int(data, seek)?.to_u16().unwrap();
-        self.page_setup.page_margin.left   = read_int(data, seek)?.to_u16().unwrap();
-        self.page_setup.page_margin.right  = read_int(data, seek)?.to_u16().unwrap();
-        self.page_setup.page_margin.top    = read_int(data, seek)?.to_u16().unwrap();
-        self.page_setup.page_margin.bottom = read_int(data, seek)?.to_u16().unwrap();
-        self.page_setup.score_size_proportion = read_int(data, seek)?.to_f32().unwrap() / 100.0;
-        self.page_setup.header_and_footer = read_short(data, seek)?.to_u16().unwrap();
+        self.page_setup.page_size.x = convert(read_int(data, seek)?, "page width")?;
+        self.page_setup.page_size.y = convert(read_int(data, seek)?, "page height")?;
+        self.page_setup.page_margin.left   = convert(read_int(data, seek)?, "left margin")?;
+        self.page_setup.page_margin.right  = convert(read_int(data, seek)?, "right margin")?;
+        self.page_setup.page_margin.top    = convert(read_int(data, seek)?, "top margin")?;
+        self.page_setup.page_margin.bottom = convert(read_int(data, seek)?, "bottom margin")?;
+        self.page_setup.score_size_proportion = read_int(data, seek)? as f32 / 100.0;
+        self.page_setup.header_and_footer = read_short(data, seek)? as u16;
         self.page_setup.title =          read_int_size_string(data, seek)?;
         self.page_setup.subtitle =       read_int_size_string(data, seek)?;
         self.page_setup.artist =         read_int_size_string(data, seek)?;
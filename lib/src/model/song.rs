@@ -1,8 +1,12 @@
 use fraction::ToPrimitive;
 
 use crate::audio::midi::*;
-use crate::error::GpResult;
+use crate::error::{GpError, GpResult};
+use crate::io::format::{detect_format, FileFormat};
+use crate::io::gp345::recovery::{ReadPart, ReadProgress};
+use crate::io::gpif::Gpif;
 use crate::io::gpif_import::*;
+use crate::io::limits::{check, ReadLimits};
 use crate::io::primitive::*;
 use crate::model::key_signature::*;
 use crate::model::lyric::*;
@@ -104,6 +108,84 @@ impl Default for Song {
     }
 }
 impl Song {
+    /// Read a song in any supported format, found from the content rather than the file
+    /// name (see `detect_format`). Returns the song and its format.
+    pub fn from_bytes(data: &[u8]) -> GpResult<(Song, FileFormat)> {
+        Song::from_bytes_with_limits(data, ReadLimits::default())
+    }
+    /// Read a song in any supported format, see `Song::from_bytes`, failing with
+    /// `GpError::LimitExceeded` past the limits.
+    pub fn from_bytes_with_limits(data: &[u8], limits: ReadLimits) -> GpResult<(Song, FileFormat)> {
+        let format = detect_format(data)
+            .ok_or_else(|| GpError::FormatError(String::from("Unknown file format")))?;
+        let mut song = Song::default();
+        song.read_format(format, data, &limits)?;
+        Ok((song, format))
+    }
+    /// Read a song file in any supported format, see `Song::from_bytes`.
+    pub fn open<P: AsRef<std::path::Path>>(path: P) -> GpResult<(Song, FileFormat)> {
+        Song::from_bytes(&std::fs::read(path)?)
+    }
+    /// Read a song file in any supported format, failing with `GpError::LimitExceeded`
+    /// past the limits, see `Song::from_bytes_with_limits`.
+    pub fn open_with_limits<P: AsRef<std::path::Path>>(
+        path: P,
+        limits: ReadLimits,
+    ) -> GpResult<(Song, FileFormat)> {
+        Song::from_bytes_with_limits(&std::fs::read(path)?, limits)
+    }
+    /// Read data of a known format into the song, failing past the limits.
+    fn read_format(
+        &mut self,
+        format: FileFormat,
+        data: &[u8],
+        limits: &ReadLimits,
+    ) -> GpResult<()> {
+        use crate::io::gpx::{read_gp, read_gpx};
+        match format {
+            FileFormat::Gp3 => self.read_gp345(data, limits, Song::read_gp3_parts),
+            FileFormat::Gp4 => self.read_gp345(data, limits, Song::read_gp4_parts),
+            FileFormat::Gp5 => self.read_gp345(data, limits, Song::read_gp5_parts),
+            FileFormat::Gpx => self
+                .read_gpif_within(&read_gpx(data, limits)?, (6, 0, 0), limits)
+                .map(drop),
+            FileFormat::Gp => self
+                .read_gpif_within(&read_gp(data, limits)?, (7, 0, 0), limits)
+                .map(drop),
+            FileFormat::Midi => {
+                crate::audio::smf::import::read_smf(self, data, limits)?;
+                limits.check_song(self)
+            }
+            FileFormat::MusicXml => {
+                crate::io::musicxml::import::read_musicxml(self, data, limits)?;
+                limits.check_song(self)
+            }
+            FileFormat::Mscz => {
+                crate::io::mscz::read_mscz(self, data, limits)?;
+                limits.check_song(self)
+            }
+            FileFormat::Mscx => {
+                crate::io::mscz::read_mscx(self, data, limits)?;
+                limits.check_song(self)
+            }
+            FileFormat::AsciiTab => {
+                crate::io::ascii_tab::parse::read_ascii_tab(self, data, limits)?;
+                limits.check_song(self)
+            }
+        }
+    }
+    /// Read a Guitar Pro 3, 4 or 5 file with one of the `read_gp*_parts` functions, the
+    /// error located at the part being read.
+    fn read_gp345(
+        &mut self,
+        data: &[u8],
+        limits: &ReadLimits,
+        read_parts: fn(&mut Song, &[u8], &ReadLimits, &mut ReadProgress) -> GpResult<()>,
+    ) -> GpResult<()> {
+        let mut progress = ReadProgress::default();
+        read_parts(self, data, limits, &mut progress)
+            .map_err(|e| progress.locate(e, self.version.number))
+    }
     /// Read the song. A song consists of score information, triplet feel, tempo, song key, MIDI channels, measure and track count, measure headers, tracks, measures.
     /// - Version: `byte-size-string` of size 30.
     /// - Score information. See `readInfo`.
@@ -117,25 +199,42 @@ impl Song {
     /// - Tracks. See `read_tracks()`.
     /// - Measures. See `read_measures()`.
     pub fn read_gp3(&mut self, data: &[u8]) -> GpResult<()> {
-        let mut seek: usize = 0;
-        self.version = read_version_string(data, &mut seek)?;
-        self.read_info(data, &mut seek)?;
-        self.triplet_feel = if read_bool(data, &mut seek)? {
+        self.read_format(FileFormat::Gp3, data, &ReadLimits::default())
+    }
+    /// Read a Guitar Pro 3 song, see `read_gp3`, keeping track of the part being read.
+    pub(crate) fn read_gp3_parts(
+        &mut self,
+        data: &[u8],
+        limits: &ReadLimits,
+        progress: &mut ReadProgress,
+    ) -> GpResult<()> {
+        let seek = &mut progress.seek;
+        progress.part = ReadPart::Version;
+        self.version = read_version_string(data, seek)?;
+        progress.part = ReadPart::Info;
+        self.read_info(data, seek)?;
+        self.triplet_feel = if read_bool(data, seek)? {
             TripletFeel::Eighth
         } else {
             TripletFeel::None
         };
-        self.tempo = read_int(data, &mut seek)?.to_i16().unwrap_or(120);
-        self.key.key = read_int(data, &mut seek)?.to_i8().unwrap_or(0);
-        self.read_midi_channels(data, &mut seek)?;
-        let measure_count = read_int(data, &mut seek)?.to_usize().unwrap_or(0);
-        let track_count = read_int(data, &mut seek)?.to_usize().unwrap_or(0);
+        self.tempo = read_int(data, seek)?.to_i16().unwrap_or(120);
+        self.key.key = read_int(data, seek)?.to_i8().unwrap_or(0);
+        progress.part = ReadPart::Channels;
+        self.read_midi_channels(data, seek)?;
+        progress.part = ReadPart::MeasureHeaders;
+        let measure_count = read_int(data, seek)?.to_usize().unwrap_or(0);
+        let track_count = read_int(data, seek)?.to_usize().unwrap_or(0);
+        check("measures", measure_count, limits.max_measures)?;
+        check("tracks", track_count, limits.max_tracks)?;
         // Read measure headers. The *measures* are written one after another, their number have been specified previously.
-        self.read_measure_headers(data, &mut seek, measure_count)?;
+        self.read_measure_headers(data, seek, measure_count)?;
         self.current_measure_number = Some(0);
-        self.read_tracks(data, &mut seek, track_count)?;
-        self.read_measures(data, &mut seek)?;
-        Ok(())
+        progress.part = ReadPart::Tracks;
+        self.read_tracks(data, seek, track_count)?;
+        progress.part = ReadPart::Measures;
+        self.read_measures(data, seek)?;
+        limits.check_song(self)
     }
     /// Read the song. A song consists of score information, triplet feel, tempo, song key, MIDI channels, measure and track count, measure headers, tracks, measures.
     /// - Version: `byte-size-string` of size 30.
@@ -152,71 +251,129 @@ impl Song {
     /// - Tracks. See `read_tracks()`.
     /// - Measures. See `read_measures()`.
     pub fn read_gp4(&mut self, data: &[u8]) -> GpResult<()> {
-        let mut seek: usize = 0;
-        self.version = read_version_string(data, &mut seek)?;
-        self.read_clipboard(data, &mut seek)?;
-        self.read_info(data, &mut seek)?;
-        self.triplet_feel = if read_bool(data, &mut seek)? {
+        self.read_format(FileFormat::Gp4, data, &ReadLimits::default())
+    }
+    /// Read a Guitar Pro 4 song, see `read_gp4`, keeping track of the part being read.
+    pub(crate) fn read_gp4_parts(
+        &mut self,
+        data: &[u8],
+        limits: &ReadLimits,
+        progress: &mut ReadProgress,
+    ) -> GpResult<()> {
+        let seek = &mut progress.seek;
+        progress.part = ReadPart::Version;
+        self.version = read_version_string(data, seek)?;
+        progress.part = ReadPart::Info;
+        self.read_clipboard(data, seek)?;
+        self.read_info(data, seek)?;
+        self.triplet_feel = if read_bool(data, seek)? {
             TripletFeel::Eighth
         } else {
             TripletFeel::None
         };
-        self.lyrics = self.read_lyrics(data, &mut seek)?; //read lyrics
-        self.tempo = read_int(data, &mut seek)?.to_i16().unwrap_or(120);
-        self.key.key = read_int(data, &mut seek)?.to_i8().unwrap_or(0);
-        read_signed_byte(data, &mut seek)?; //octave
-        self.read_midi_channels(data, &mut seek)?;
-        let measure_count = read_int(data, &mut seek)?.to_usize().unwrap_or(0);
-        let track_count = read_int(data, &mut seek)?.to_usize().unwrap_or(0);
+        progress.part = ReadPart::Lyrics;
+        self.lyrics = self.read_lyrics(data, seek)?; //read lyrics
+        progress.part = ReadPart::Info;
+        self.tempo = read_int(data, seek)?.to_i16().unwrap_or(120);
+        self.key.key = read_int(data, seek)?.to_i8().unwrap_or(0);
+        read_signed_byte(data, seek)?; //octave
+        progress.part = ReadPart::Channels;
+        self.read_midi_channels(data, seek)?;
+        progress.part = ReadPart::MeasureHeaders;
+        let measure_count = read_int(data, seek)?.to_usize().unwrap_or(0);
+        let track_count = read_int(data, seek)?.to_usize().unwrap_or(0);
+        check("measures", measure_count, limits.max_measures)?;
+        check("tracks", track_count, limits.max_tracks)?;
         // Read measure headers. The *measures* are written one after another, their number have been specified previously.
-        self.read_measure_headers(data, &mut seek, measure_count)?;
+        self.read_measure_headers(data, seek, measure_count)?;
         //self.current_measure_number = Some(0);
-        self.read_tracks(data, &mut seek, track_count)?;
-        self.read_measures(data, &mut seek)?;
-        Ok(())
+        progress.part = ReadPart::Tracks;
+        self.read_tracks(data, seek, track_count)?;
+        progress.part = ReadPart::Measures;
+        self.read_measures(data, seek)?;
+        limits.check_song(self)
     }
     pub fn read_gp5(&mut self, data: &[u8]) -> GpResult<()> {
-        let mut seek: usize = 0;
-        self.version = read_version_string(data, &mut seek)?;
-        self.read_clipboard(data, &mut seek)?;
-        self.read_info(data, &mut seek)?;
-        self.lyrics = self.read_lyrics(data, &mut seek)?; //read lyrics
-        self.master_effect = self.read_rse_master_effect(data, &mut seek)?;
-        self.read_page_setup(data, &mut seek)?;
-        self.tempo_name = read_int_size_string(data, &mut seek)?;
-        self.tempo = read_int(data, &mut seek)?.to_i16().unwrap_or(120);
+        self.read_format(FileFormat::Gp5, data, &ReadLimits::default())
+    }
+    /// Read a Guitar Pro 5 song, see `read_gp5`, keeping track of the part being read.
+    pub(crate) fn read_gp5_parts(
+        &mut self,
+        data: &[u8],
+        limits: &ReadLimits,
+        progress: &mut ReadProgress,
+    ) -> GpResult<()> {
+        let seek = &mut progress.seek;
+        progress.part = ReadPart::Version;
+        self.version = read_version_string(data, seek)?;
+        progress.part = ReadPart::Info;
+        self.read_clipboard(data, seek)?;
+        self.read_info(data, seek)?;
+        progress.part = ReadPart::Lyrics;
+        self.lyrics = self.read_lyrics(data, seek)?; //read lyrics
+        progress.part = ReadPart::Info;
+        self.master_effect = self.read_rse_master_effect(data, seek)?;
+        self.read_page_setup(data, seek)?;
+        self.tempo_name = read_int_size_string(data, seek)?;
+        self.tempo = read_int(data, seek)?.to_i16().unwrap_or(120);
         self.hide_tempo = if self.version.number > (5, 0, 0) {
-            read_bool(data, &mut seek)?
+            read_bool(data, seek)?
         } else {
             false
         };
-        self.key.key = read_signed_byte(data, &mut seek)?;
-        read_int(data, &mut seek)?; //octave
-        self.read_midi_channels(data, &mut seek)?;
-        let directions = self.read_directions(data, &mut seek)?;
-        self.master_effect.reverb = read_int(data, &mut seek)?.to_f32().unwrap_or(0.0);
-        let measure_count = read_int(data, &mut seek)?.to_usize().unwrap_or(0);
-        let track_count = read_int(data, &mut seek)?.to_usize().unwrap_or(0);
-        self.read_measure_headers_v5(data, &mut seek, measure_count, &directions)?;
-        self.read_tracks_v5(data, &mut seek, track_count)?;
-        self.read_measures(data, &mut seek)?;
-        Ok(())
+        self.key.key = read_signed_byte(data, seek)?;
+        read_int(data, seek)?; //octave
+        progress.part = ReadPart::Channels;
+        self.read_midi_channels(data, seek)?;
+        progress.part = ReadPart::MeasureHeaders;
+        let directions = self.read_directions(data, seek)?;
+        self.master_effect.reverb = read_int(data, seek)?.to_f32().unwrap_or(0.0);
+        let measure_count = read_int(data, seek)?.to_usize().unwrap_or(0);
+        let track_count = read_int(data, seek)?.to_usize().unwrap_or(0);
+        check("measures", measure_count, limits.max_measures)?;
+        check("tracks", track_count, limits.max_tracks)?;
+        self.read_measure_headers_v5(data, seek, measure_count, &directions)?;
+        progress.part = ReadPart::Tracks;
+        self.read_tracks_v5(data, seek, track_count)?;
+        progress.part = ReadPart::Measures;
+        self.read_measures(data, seek)?;
+        limits.check_song(self)
+    }
+    /// Read a Guitar Pro 3, 4 or 5 file leniently: a truncated or damaged file gives the
+    /// song read up to the failure, closed off, and warnings with the byte offset and the
+    /// structure being read. See `read_lenient`.
+    pub fn read_lenient(
+        &mut self,
+        data: &[u8],
+    ) -> GpResult<Vec<crate::io::gp345::recovery::ReadWarning>> {
+        crate::io::gp345::recovery::read_lenient(self, data, &ReadLimits::default())
     }
-    /// Read Guitar Pro 7+ file (.gp)
-    pub fn read_gp(&mut self, data: &[u8]) -> GpResult<()> {
+    /// Read Guitar Pro 7+ file (.gp). Returns the content of the score that could not be
+    /// mapped to the song, see `GpifIssue`.
+    pub fn read_gp(&mut self, data: &[u8]) -> GpResult<Vec<GpifIssue>> {
         use crate::io::gpx::read_gp;
-        let gpif = read_gp(data)?;
-        self.version.number = (7, 0, 0); // default, overridden by read_gpif if GPVersion is present
-        self.read_gpif(&gpif);
-        Ok(())
+        let limits = ReadLimits::default();
+        self.read_gpif_within(&read_gp(data, &limits)?, (7, 0, 0), &limits)
     }
-    /// Read Guitar Pro 6 file (.gpx)
-    pub fn read_gpx(&mut self, data: &[u8]) -> GpResult<()> {
+    /// Read Guitar Pro 6 file (.gpx). Returns the content of the score that could not be
+    /// mapped to the song, see `GpifIssue`.
+    pub fn read_gpx(&mut self, data: &[u8]) -> GpResult<Vec<GpifIssue>> {
         use crate::io::gpx::read_gpx;
-        let gpif = read_gpx(data)?;
-        self.version.number = (6, 0, 0);
-        self.read_gpif(&gpif);
-        Ok(())
+        let limits = ReadLimits::default();
+        self.read_gpif_within(&read_gpx(data, &limits)?, (6, 0, 0), &limits)
+    }
+    /// Read the GPIF document of a .gp or .gpx file, failing past the limits. The version
+    /// is a default, overridden by `read_gpif` if the document has a GPVersion.
+    fn read_gpif_within(
+        &mut self,
+        gpif: &Gpif,
+        version: (u8, u8, u8),
+        limits: &ReadLimits,
+    ) -> GpResult<Vec<GpifIssue>> {
+        self.version.number = version;
+        let issues = self.read_gpif(gpif, limits)?;
+        limits.check_song(self)?;
+        Ok(issues)
     }
 
     /// Read information (name, artist, ...)
@@ -295,6 +452,132 @@ impl Song {
         write_i32(&mut data, 0);
         Ok(data)
     }
+    /// Write Guitar Pro 7+ file (.gp): a ZIP archive holding the score as GPIF XML.
+    pub fn write_gp(&self) -> GpResult<Vec<u8>> {
+        use crate::io::gpx::write_gp;
+        write_gp(&self.write_gpif((7, 0, 0)))
+    }
+    /// Write Guitar Pro 6 file (.gpx): the score as GPIF XML in a BCFZ-compressed BCFS container.
+    pub fn write_gpx(&self) -> GpResult<Vec<u8>> {
+        use crate::io::gpx::write_gpx;
+        write_gpx(&self.write_gpif((6, 0, 0)))
+    }
+    /// Read a Standard MIDI File (.mid, format 0 or 1). Notes are quantized to beats and
+    /// placed on the strings of each track; channel 10 tracks become percussion tracks.
+    pub fn read_midi(&mut self, data: &[u8]) -> GpResult<()> {
+        self.read_format(FileFormat::Midi, data, &ReadLimits::default())
+    }
+    /// Export the song as a Type-1 Standard MIDI File (.mid): a conductor track with the
+    /// tempo map, time and key signatures, then one track per song track.
+    pub fn to_midi(&self) -> Vec<u8> {
+        crate::audio::smf::export::write_smf(self)
+    }
+    /// Indexes of the measure headers in the order they are played, with the repeats,
+    /// alternate endings and D.C./D.S./Coda/Fine directions unrolled.
+    pub fn playback_order(&self) -> Vec<usize> {
+        crate::audio::playback::playback_order(self)
+    }
+    /// Tempo map of the song in written order, to convert ticks to seconds and back.
+    pub fn tempo_map(&self) -> crate::audio::tempo::TempoMap {
+        crate::audio::tempo::TempoMap::new(self)
+    }
+    /// Played notes of all the tracks in time order, measures in written order: absolute
+    /// tick and seconds, duration with the tied notes, sounding pitch, velocity, position
+    /// in the score and effects.
+    pub fn events(&self) -> Vec<crate::audio::timeline::NoteEvent> {
+        crate::audio::timeline::events(self)
+    }
+    /// Played notes of all the tracks in time order, measures in playback order (see
+    /// `Song::playback_order`).
+    pub fn played_events(&self) -> Vec<crate::audio::timeline::NoteEvent> {
+        crate::audio::timeline::played_events(self)
+    }
+    /// Recognize the chord played by a span of beats of a voice in a measure: root,
+    /// quality, extensions, slash bass and name.
+    pub fn recognize_chord(
+        &self,
+        track: usize,
+        measure: usize,
+        voice: usize,
+        beats: std::ops::Range<usize>,
+    ) -> Option<crate::types::chord::Chord> {
+        crate::theory::chord::span_chord(self, track, measure, voice, beats)
+    }
+    /// Set the recognized chords on the beats of a track that have none, where the chord
+    /// changes. Returns the number of chords added.
+    pub fn label_chords(&mut self, track: usize) -> usize {
+        crate::theory::chord::label_chords(self, track)
+    }
+    /// Check the song for inconsistencies of damaged files: voice durations, strings and
+    /// frets of the notes, ties, repeats, measure counts and channels.
+    pub fn validate(&self) -> Vec<crate::validate::diagnostic::Diagnostic> {
+        crate::validate::checks::validate(self)
+    }
+    /// Detect the key of the song from the pitches played (Krumhansl-Kessler profiles):
+    /// tonic, mode and confidence. None when no pitched note is played.
+    pub fn detect_key(&self) -> Option<crate::theory::key::KeyEstimate> {
+        crate::theory::key::song_key(self)
+    }
+    /// Detect the key of each section of the song, split at the markers.
+    pub fn detect_section_keys(&self) -> Vec<crate::theory::key::SectionKey> {
+        crate::theory::key::section_keys(self)
+    }
+    /// Write the detected keys to the key signatures of the measures, the key of the song
+    /// or of each section. Returns the number of measures whose key signature changed.
+    pub fn apply_detected_keys(&mut self, per_section: bool) -> usize {
+        crate::theory::key::apply_keys(self, per_section)
+    }
+    /// Transpose the song by a number of semitones, the notes kept on the neck of their
+    /// track, along with the key signatures and the chords. Percussion tracks are left
+    /// unchanged.
+    pub fn transpose(&mut self, semitones: i32) {
+        crate::edit::transpose::transpose_song(self, semitones)
+    }
+    /// Transpose one track by a number of semitones, the notes kept on its neck, along with
+    /// its chords.
+    pub fn transpose_track(&mut self, track: usize, semitones: i32) {
+        crate::edit::transpose::transpose_track(self, track, semitones)
+    }
+    /// Export the song as a MusicXML 4.0 partwise score. Fretted tracks get a notation
+    /// staff and a TAB staff; percussion tracks get a single percussion staff.
+    pub fn to_musicxml(&self) -> String {
+        crate::io::musicxml::export::write_musicxml(self)
+    }
+    /// Read a MusicXML partwise score, plain (.musicxml) or compressed (.mxl). Strings and
+    /// frets come from `<technical>` when written, otherwise from the part's staff tuning.
+    pub fn read_musicxml(&mut self, data: &[u8]) -> GpResult<()> {
+        self.read_format(FileFormat::MusicXml, data, &ReadLimits::default())
+    }
+    /// Read a compressed MuseScore score (.mscz). Each part becomes a track; TAB staves keep
+    /// their strings and frets.
+    pub fn read_mscz(&mut self, data: &[u8]) -> GpResult<()> {
+        self.read_format(FileFormat::Mscz, data, &ReadLimits::default())
+    }
+    /// Read an uncompressed MuseScore score (.mscx).
+    pub fn read_mscx(&mut self, data: &[u8]) -> GpResult<()> {
+        self.read_format(FileFormat::Mscx, data, &ReadLimits::default())
+    }
+    /// Render tracks as ASCII tablature, their measures aligned and wrapped at the width of
+    /// the options.
+    pub fn to_ascii_tab(
+        &self,
+        tracks: &[usize],
+        options: &crate::io::ascii_tab::render::TabOptions,
+    ) -> String {
+        crate::io::ascii_tab::render::render_tab(self, tracks, options)
+    }
+    /// Read a plain-text ASCII tab into a song of one track, with the tuning of the line
+    /// labels and the rhythm of the note spacing. Returns what could not be read, by line
+    /// and column.
+    pub fn read_ascii_tab(
+        &mut self,
+        data: &[u8],
+    ) -> GpResult<Vec<crate::io::ascii_tab::parse::TabIssue>> {
+        let limits = ReadLimits::default();
+        let issues = crate::io::ascii_tab::parse::read_ascii_tab(self, data, &limits)?;
+        limits.check_song(self)?;
+        Ok(issues)
+    }
     fn write_info(&self, data: &mut Vec<u8>, version: (u8, u8, u8)) {
         write_int_byte_size_string(data, &self.name);
         write_int_byte_size_string(data, &self.subtitle);
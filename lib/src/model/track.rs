@@ -1,6 +1,6 @@
 use fraction::ToPrimitive;
 
-use crate::error::GpResult;
+use crate::error::{GpResult, ScorePart};
 use crate::{
     audio::midi::*,
     io::primitive::*,
@@ -66,9 +66,9 @@ pub struct Track {
     pub settings: TrackSettings,
     /// MIDI program from GPIF (GP6/GP7)
     pub midi_program_gpif: Option<i32>,
-    /// Chromatic transposition (GP6/GP7)
+    /// Chromatic transposition of the notation (GP6/GP7): the written pitch, not the sounding one
     pub transpose_chromatic: i32,
-    /// Octave transposition (GP6/GP7)
+    /// Octave transposition of the notation (GP6/GP7), e.g. -1 for a guitar written an octave up
     pub transpose_octave: i32,
 }
 impl Default for Track {
@@ -100,6 +100,32 @@ impl Default for Track {
         }
     }
 }
+impl Track {
+    /// Change the tuning to `strings`, re-fretting the notes and the chord diagrams so that
+    /// they keep their pitch. Returns the notes the new tuning cannot play.
+    pub fn retune(&mut self, strings: Vec<(i8, i8)>) -> Vec<crate::edit::retune::UnplacedNote> {
+        crate::edit::retune::retune(self, strings)
+    }
+    /// Strings and frets of a sequence of chords (sets of sounding MIDI pitches) on the
+    /// track, with its tuning, fret count and capo, minimizing the moves and stretches of
+    /// the hand.
+    pub fn fingering(&self, chords: &[Vec<u8>]) -> Vec<Vec<Option<(i8, i16)>>> {
+        crate::edit::fingering::fingering(chords, &self.strings, self.fret_count, self.offset)
+    }
+    /// Playable voicings of a chord on the track, with its tuning, fret count and capo,
+    /// the easiest first. See `crate::theory::chord::parse_chord` to read a chord name.
+    pub fn chord_voicings(
+        &self,
+        chord: &crate::types::chord::Chord,
+    ) -> Vec<crate::types::chord::Chord> {
+        crate::theory::voicing::chord_voicings(chord, &self.strings, self.fret_count, self.offset)
+    }
+    /// Add the easiest voicing as diagram to the chords of the track that have none.
+    /// Returns the number of diagrams added.
+    pub fn add_chord_diagrams(&mut self) -> usize {
+        crate::theory::voicing::add_chord_diagrams(self)
+    }
+}
 
 pub trait SongTrackOps {
     fn read_tracks(&mut self, data: &[u8], seek: &mut usize, track_count: usize) -> GpResult<()>;
@@ -118,7 +144,8 @@ impl SongTrackOps for Song {
     fn read_tracks(&mut self, data: &[u8], seek: &mut usize, track_count: usize) -> GpResult<()> {
         //println!("read_tracks()");
         for i in 0..track_count {
-            self.read_track(data, seek, i)?;
+            self.read_track(data, seek, i)
+                .map_err(|e| e.within(ScorePart::Track(i), *seek))?;
         }
         Ok(())
     }
@@ -131,7 +158,8 @@ impl SongTrackOps for Song {
     ) -> GpResult<()> {
         //println!("read_tracks_v5(): {:?} {}", self.version.number, self.version.number == (5,1,0));
         for i in 0..track_count {
-            self.read_track_v5(data, seek, i)?;
+            self.read_track_v5(data, seek, i)
+                .map_err(|e| e.within(ScorePart::Track(i), *seek))?;
         }
         *seek += if self.version.number == (5, 0, 0) {
             2
@@ -171,21 +199,22 @@ impl SongTrackOps for Song {
         track.banjo_track = (flags & 0x04) == 0x04; //Banjo track
 
         track.name = read_byte_size_string(data, seek, 40)?;
-        let string_count = read_int(data, seek)?.to_u8().unwrap();
+        let string_count: u8 = convert(read_int(data, seek)?, "string count")?;
         track.strings.clear();
         for i in 0..7i8 {
-            let i_tuning = read_int(data, seek)?.to_i8().unwrap();
-            if string_count.to_i8().unwrap() > i {
+            let i_tuning = convert(read_int(data, seek)?, "string tuning")?;
+            if usize::from(string_count) > i as usize {
                 track.strings.push((i + 1, i_tuning));
             }
         }
         //println!("tuning: {:?}", track.strings);
-        track.port = read_int(data, seek)?.to_u8().unwrap();
+        track.port = convert(read_int(data, seek)?, "MIDI port")?;
         let index = self.read_channel(data, seek)?;
+        track.channel_index = index;
         if self.channels[index].channel == 9 {
             track.percussion_track = true;
         }
-        track.fret_count = read_int(data, seek)?.to_u8().unwrap();
+        track.fret_count = convert(read_int(data, seek)?, "fret count")?;
         track.offset = read_int(data, seek)?;
         track.color = read_color(data, seek)?;
         //println!("\tInstrument: {} \t Strings: {}/{} ({:?})", self.channels[index].get_instrument_name(), string_count, track.strings.len(), track.strings);
@@ -251,20 +280,21 @@ impl SongTrackOps for Song {
         //let string_count = read_int(data, seek).to_u8().unwrap();
         let sc = read_int(data, seek)?;
         //println!("read_track_v5(), track:name: \"{}\", string count: {}", track.name, sc);
-        let string_count = sc.to_u8().unwrap();
+        let string_count: u8 = convert(sc, "string count")?;
         track.strings.clear();
         for i in 0i8..7i8 {
-            let i_tuning = read_int(data, seek)?.to_i8().unwrap();
-            if string_count.to_i8().unwrap() > i {
+            let i_tuning = convert(read_int(data, seek)?, "string tuning")?;
+            if usize::from(string_count) > i as usize {
                 track.strings.push((i + 1, i_tuning));
             }
         }
-        track.port = read_int(data, seek)?.to_u8().unwrap();
-        self.read_channel(data, seek)?;
-        if self.channels[number].channel == 9 {
+        track.port = convert(read_int(data, seek)?, "MIDI port")?;
+        let index = self.read_channel(data, seek)?;
+        track.channel_index = index;
+        if self.channels[index].channel == 9 {
             track.percussion_track = true;
         }
-        track.fret_count = read_int(data, seek)?.to_u8().unwrap();
+        track.fret_count = convert(read_int(data, seek)?, "fret count")?;
         track.offset = read_int(data, seek)?;
         track.color = read_color(data, seek)?;
 
@@ -284,7 +314,7 @@ impl SongTrackOps for Song {
         track.settings.extend_rythmic = (flags2 & 0x0800) == 0x0800;
 
         track.rse.auto_accentuation = get_accentuation(read_byte(data, seek)?)?;
-        self.channels[number].bank = read_byte(data, seek)?;
+        self.channels[index].bank = read_byte(data, seek)?;
         self.read_track_rse(data, seek, &mut track)?;
         self.tracks.push(track);
         Ok(())
@@ -0,0 +1,4 @@
+// ASCII tablature tests
+
+pub mod parse;
+pub mod render;
@@ -0,0 +1,215 @@
+// ASCII tablature parsing tests
+use crate::io::ascii_tab::render::TabOptions;
+use crate::model::song::Song;
+use crate::tests::read_file;
+use crate::types::enums::{BeatStatus, BendType, NoteType, SlideType};
+
+fn parse(text: &str) -> Song {
+    let mut song = Song::default();
+    let issues = song.read_ascii_tab(text.as_bytes()).unwrap();
+    assert!(issues.is_empty(), "{:?}", issues);
+    song
+}
+
+/// (string, fret) of the notes of each played beat of a measure.
+fn positions(song: &Song, m: usize) -> Vec<Vec<(i8, i16)>> {
+    song.tracks[0].measures[m].voices[0]
+        .beats
+        .iter()
+        .filter(|b| b.status == BeatStatus::Normal)
+        .map(|b| b.notes.iter().map(|n| (n.string, n.value)).collect())
+        .collect()
+}
+
+#[test]
+fn test_parse_tuning() {
+    let song = parse("Intro\nD|-----|\nA|-----|\nF|-----|\nC|-----|\nG|-----|\nC|-----|\n");
+    assert_eq!(
+        song.tracks[0].strings,
+        vec![(1, 62), (2, 57), (3, 53), (4, 48), (5, 43), (6, 36)]
+    );
+    let song = parse("e|---|\nB|---|\nG|---|\nD|---|\nA|---|\nE|---|\nB|---|\n");
+    assert_eq!(song.tracks[0].strings[6], (7, 35));
+    assert_eq!(song.tracks[0].strings[0], (1, 64));
+    let song = parse("|---|\n|---|\n|---|\n|---|\n");
+    assert_eq!(
+        song.tracks[0].strings,
+        vec![(1, 43), (2, 38), (3, 33), (4, 28)]
+    );
+    let song = parse("Eb|---|\nBb|---|\nGb|---|\nDb|---|\nAb|---|\nEb|---|\n");
+    assert_eq!(song.tracks[0].strings[5], (6, 39));
+}
+
+#[test]
+fn test_parse_rhythm() {
+    let song = parse(
+        "e|-0-2-3-5-|0-------2-------|\n\
+         B|---------|1-------3-------|\n\
+         G|---------|0---------------|\n\
+         D|---------|2---------------|\n\
+         A|---------|3---------------|\n\
+         E|---------|----------------|\n",
+    );
+    assert_eq!(song.measure_headers.len(), 2);
+    assert_eq!(song.tracks[0].measures.len(), 2);
+    let first = &song.tracks[0].measures[0].voices[0].beats;
+    assert_eq!(first.len(), 4);
+    assert!(first.iter().all(|b| b.duration.value == 4));
+    assert_eq!(
+        positions(&song, 0),
+        vec![vec![(1, 0)], vec![(1, 2)], vec![(1, 3)], vec![(1, 5)]]
+    );
+
+    let second = &song.tracks[0].measures[1].voices[0].beats;
+    assert_eq!(second.len(), 2);
+    assert_eq!(second[0].duration.value, 2);
+    assert_eq!(
+        positions(&song, 1),
+        vec![
+            vec![(1, 0), (2, 1), (3, 0), (4, 2), (5, 3)],
+            vec![(1, 2), (2, 3)]
+        ]
+    );
+    // A note after a rest
+    let song =
+        parse("|----------5-----|\n|----------------|\n|----------------|\n|----------------|\n");
+    let beats = &song.tracks[0].measures[0].voices[0].beats;
+    assert_eq!(beats[0].status, BeatStatus::Rest);
+    assert_eq!(beats.last().unwrap().notes[0].value, 5);
+}
+
+#[test]
+fn test_parse_techniques() {
+    let song = parse(
+        "e|5h7p5-----------|-----------------|\n\
+         B|------7b9r7-----|----<12>---(3)---|\n\
+         G|------------x---|-----------------|\n\
+         D|--------------7~|-----------------|\n\
+         A|/5-----5/7--5\\--|-----------------|\n\
+         E|----------------|-----------------|\n",
+    );
+    let notes: Vec<_> = song.tracks[0].measures[0].voices[0]
+        .beats
+        .iter()
+        .flat_map(|b| b.notes.iter())
+        .collect();
+    let find = |string: i8, fret: i16| {
+        notes
+            .iter()
+            .find(|n| n.string == string && n.value == fret)
+            .unwrap()
+    };
+    assert!(notes[0].effect.hammer);
+    assert_eq!((notes[0].string, notes[0].value), (1, 5));
+    assert_eq!(find(5, 5).effect.slides, vec![SlideType::IntoFromBelow]);
+    assert!(find(1, 7).effect.hammer);
+    assert!(!notes.iter().rfind(|n| n.string == 1).unwrap().effect.hammer);
+
+    let bend = find(2, 7).effect.bend.as_ref().unwrap();
+    assert_eq!(bend.kind, BendType::BendRelease);
+    assert_eq!(bend.points.iter().map(|p| p.value).max(), Some(4));
+    assert_eq!(bend.points.last().unwrap().value, 0);
+
+    assert_eq!(find(3, 0).kind, NoteType::Dead);
+    assert!(find(4, 7).effect.vibrato);
+    let slides: Vec<_> = notes
+        .iter()
+        .filter(|n| n.string == 5)
+        .map(|n| n.effect.slides.clone())
+        .collect();
+    assert_eq!(
+        slides[1..],
+        [
+            vec![SlideType::ShiftSlideTo],
+            vec![],
+            vec![SlideType::OutDownwards]
+        ]
+    );
+
+    let second: Vec<_> = song.tracks[0].measures[1].voices[0]
+        .beats
+        .iter()
+        .flat_map(|b| b.notes.iter())
+        .collect();
+    assert!(second[0].effect.harmonic.is_some());
+    assert_eq!(second[0].value, 12);
+    assert!(second[1].effect.ghost_note);
+    assert_eq!(second[1].value, 3);
+}
+
+#[test]
+fn test_parse_repeats() {
+    let song = parse(
+        "e||-----|-----||\n\
+         B||*-0--|---0*||\n\
+         G||-----|-----||\n\
+         D||-----|-----||\n\
+         A||*----|----*||\n\
+         E||-----|-----||\n",
+    );
+    assert_eq!(song.measure_headers.len(), 2);
+    assert!(song.measure_headers[0].repeat_open);
+    assert_eq!(song.measure_headers[0].repeat_close, -1);
+    assert_eq!(song.measure_headers[1].repeat_close, 1);
+    assert_eq!(positions(&song, 1), vec![vec![(2, 0)]]);
+}
+
+#[test]
+fn test_parse_issues() {
+    let text = "Riff\n\
+                e|--0--|\n\
+                B|--1-?|\n\
+                G|--0---|--|\n\
+                D|-----|\n\
+                A|-----|\n\
+                E|-----|\n\
+                \n\
+                e|--0--|\n\
+                B|--0--|\n";
+    let mut song = Song::default();
+    let issues = song.read_ascii_tab(text.as_bytes()).unwrap();
+    let located: Vec<(usize, usize)> = issues.iter().map(|i| (i.line, i.column)).collect();
+    assert_eq!(located, vec![(3, 7), (4, 8), (9, 1)]);
+    assert_eq!(
+        issues[0].to_string(),
+        "line 3, column 7: unexpected character '?'"
+    );
+    assert_eq!(positions(&song, 0), vec![vec![(1, 0), (2, 1), (3, 0)]]);
+
+    assert!(song.read_ascii_tab(b"no tablature here").is_err());
+}
+
+#[test]
+fn test_parse_rendered_tab() {
+    for name in ["volta.gp5", "bend.gp5", "Slides.gp5"] {
+        let mut song = Song::default();
+        song.read_gp5(&read_file(String::from(name))).unwrap();
+        let text = song.to_ascii_tab(&[0], &TabOptions::default());
+        let read = parse(&text);
+        assert_eq!(read.tracks[0].strings, song.tracks[0].strings);
+        assert_eq!(read.measure_headers.len(), song.measure_headers.len());
+        // The voices of the song are read as one, tied notes are not written
+        for m in 0..song.measure_headers.len() {
+            let notes = |song: &Song| {
+                let mut notes: Vec<(i8, i16)> = song.tracks[0].measures[m]
+                    .voices
+                    .iter()
+                    .flat_map(|v| &v.beats)
+                    .flat_map(|b| &b.notes)
+                    .filter(|n| n.kind != NoteType::Tie)
+                    .map(|n| (n.string, n.value))
+                    .collect();
+                notes.sort();
+                notes
+            };
+            assert_eq!(notes(&read), notes(&song), "{} measure {}", name, m + 1);
+        }
+        let repeats = |song: &Song| -> Vec<(bool, bool)> {
+            song.measure_headers
+                .iter()
+                .map(|h| (h.repeat_open, h.repeat_close >= 0))
+                .collect()
+        };
+        assert_eq!(repeats(&read), repeats(&song));
+    }
+}
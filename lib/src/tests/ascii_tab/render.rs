@@ -0,0 +1,115 @@
+// ASCII tablature rendering tests
+use crate::io::ascii_tab::render::TabOptions;
+use crate::io::ascii_tab::string_labels;
+use crate::model::song::Song;
+use crate::model::track::Track;
+use crate::tests::read_file;
+
+fn read_song(name: &str) -> Song {
+    let data = read_file(String::from(name));
+    let mut song = Song::default();
+    if name.ends_with(".gp4") {
+        song.read_gp4(&data).unwrap();
+    } else {
+        song.read_gp5(&data).unwrap();
+    }
+    song
+}
+
+fn render(name: &str) -> String {
+    read_song(name).to_ascii_tab(&[0], &TabOptions::default())
+}
+
+#[test]
+fn test_string_labels() {
+    let mut track = Track::default();
+    assert_eq!(string_labels(&track), vec!["e", "B", "G", "D", "A", "E"]);
+    track.strings = vec![(1, 64), (2, 59), (3, 55), (4, 50), (5, 45), (6, 38)];
+    assert_eq!(string_labels(&track), vec!["E", "B", "G", "D", "A", "D"]);
+    track.strings = vec![(1, 43), (2, 38), (3, 33), (4, 28), (5, 23)];
+    assert_eq!(string_labels(&track), vec!["G", "D", "A", "E", "B"]);
+    track.percussion_track = true;
+    assert_eq!(string_labels(&track), vec!["1", "2", "3", "4", "5"]);
+    track.strings.clear();
+    assert!(string_labels(&track).is_empty());
+}
+
+#[test]
+fn test_tab_output() {
+    assert_eq!(
+        render("slur.gp4"),
+        "e|-------|\nB|3p-0---|\nG|-------|\nD|-------|\nA|-------|\nE|-------|\n"
+    );
+}
+
+#[test]
+fn test_tab_techniques() {
+    assert!(render("bend.gp5").contains("2b4r2"));
+    assert!(render("Harmonics.gp5").contains("E|<3>-<3>-"));
+    assert!(render("shift-slide.gp5").contains("D|--7/-9---|"));
+    assert!(render("slide-in-below.gp5").contains("E|/5---"));
+    assert!(render("slide-out-down.gp5").contains("E|5\\---"));
+    assert!(render("vibrato.gp5").contains("G|5~-|"));
+
+    let palm_mute = render("palm-mute.gp5");
+    let lines: Vec<&str> = palm_mute.lines().collect();
+    assert_eq!(lines[0], "  PM");
+    assert_eq!(lines[3], "G|5-2-4-7-|");
+}
+
+#[test]
+fn test_tab_skips_notes_off_the_strings() {
+    let mut song = read_song("slur.gp4");
+    let expected = song.to_ascii_tab(&[0], &TabOptions::default());
+    let notes = &mut song.tracks[0].measures[0].voices[0].beats[0].notes;
+    let mut note = notes[0].clone();
+    note.string = 0;
+    notes.push(note.clone());
+    note.string = i8::MIN;
+    notes.push(note);
+    assert_eq!(song.to_ascii_tab(&[0], &TabOptions::default()), expected);
+}
+
+#[test]
+fn test_tab_repeats() {
+    let tab = render("Repeat.gp5");
+    let lines: Vec<&str> = tab.lines().collect();
+    assert!(lines[0].trim_start().starts_with("1.2.3.5.6.7."));
+    assert!(lines[0].ends_with("x4"));
+    assert!(lines[1].starts_with("e||-----|"));
+    assert!(lines[2].starts_with("B||*----|"));
+    assert!(lines[2].ends_with("*||"));
+}
+
+#[test]
+fn test_tab_wrapping() {
+    let song = read_song("volta.gp5");
+    let tab = song.to_ascii_tab(&[0], &TabOptions { width: 60 });
+    assert!(tab.lines().all(|l| l.chars().count() <= 60));
+    let systems: Vec<&str> = tab.split("\n\n").collect();
+    assert_eq!(systems.len(), 3);
+    for system in systems {
+        let strings: Vec<&str> = system.lines().filter(|l| l.contains('|')).collect();
+        assert_eq!(strings.len(), 6);
+        assert!(strings.iter().all(|l| l.len() == strings[0].len()));
+    }
+
+    let unwrapped = song.to_ascii_tab(&[0], &TabOptions { width: 0 });
+    assert_eq!(unwrapped.lines().filter(|l| l.contains('|')).count(), 6);
+}
+
+#[test]
+fn test_tab_several_tracks() {
+    let mut song = read_song("slur.gp4");
+    let mut bass = song.tracks[0].clone();
+    bass.name = String::from("Bass");
+    bass.strings = vec![(1, 43), (2, 38), (3, 33), (4, 28)];
+    song.tracks.push(bass);
+    let tab = song.to_ascii_tab(&[0, 1], &TabOptions::default());
+    let lines: Vec<&str> = tab.lines().collect();
+    assert_eq!(lines[0], song.tracks[0].name);
+    assert_eq!(lines[8], "Bass");
+    assert_eq!(lines[10], "D|3p-0---|");
+    assert_eq!(lines.len(), 13);
+    assert!(song.to_ascii_tab(&[5], &TabOptions::default()).is_empty());
+}
@@ -0,0 +1,82 @@
+// Fingering optimizer tests
+use crate::edit::fingering::fingering;
+use crate::model::track::Track;
+
+const STANDARD: [(i8, i8); 6] = [(1, 64), (2, 59), (3, 55), (4, 50), (5, 45), (6, 40)];
+
+fn pitch(position: (i8, i16)) -> i32 {
+    i32::from(STANDARD[position.0 as usize - 1].1) + i32::from(position.1)
+}
+
+#[test]
+fn test_fingering_chords() {
+    // Open E minor: every string open, each pitch on its own string
+    let result = fingering(&[vec![40, 47, 52, 55, 59, 64]], &STANDARD, 24, 0);
+    assert_eq!(
+        result[0],
+        vec![
+            Some((6, 0)),
+            Some((5, 2)),
+            Some((4, 2)),
+            Some((3, 0)),
+            Some((2, 0)),
+            Some((1, 0))
+        ]
+    );
+
+    // Seven pitches on six strings, one out of range: left out
+    let result = fingering(
+        &[vec![40, 45, 50, 55, 59, 64, 69], vec![30]],
+        &STANDARD,
+        24,
+        0,
+    );
+    assert_eq!(result[0].iter().filter(|p| p.is_none()).count(), 1);
+    let mut strings: Vec<i8> = result[0].iter().flatten().map(|p| p.0).collect();
+    strings.sort();
+    strings.dedup();
+    assert_eq!(strings.len(), 6);
+    assert_eq!(result[1], vec![None]);
+
+    // Empty chords stay empty
+    assert_eq!(fingering(&[vec![]], &STANDARD, 24, 0), vec![vec![]]);
+}
+
+#[test]
+fn test_fingering_capo() {
+    // Capo on the 2nd fret: F# is the open low string, E cannot be played
+    let result = fingering(&[vec![42], vec![40]], &STANDARD, 24, 2);
+    assert_eq!(result, vec![vec![Some((6, 0))], vec![None]]);
+
+    let track = Track {
+        offset: 2,
+        ..Default::default()
+    };
+    assert_eq!(track.fingering(&[vec![42]]), vec![vec![Some((6, 0))]]);
+}
+
+#[test]
+fn test_fingering_hand_position() {
+    // The A before a chord high on the neck is played next to it, not at the 5th fret
+    let result = fingering(&[vec![69], vec![74, 79]], &STANDARD, 24, 0);
+    assert_eq!(result[0], vec![Some((3, 14))]);
+    assert_eq!(result[1], vec![Some((2, 15)), Some((1, 15))]);
+    // Alone, it is played low
+    assert_eq!(
+        fingering(&[vec![69]], &STANDARD, 24, 0)[0],
+        vec![Some((1, 5))]
+    );
+
+    // A melody keeps the positions close
+    let melody = [76, 74, 72, 71, 69, 67];
+    let chords: Vec<Vec<u8>> = melody.iter().map(|p| vec![*p]).collect();
+    let result = fingering(&chords, &STANDARD, 24, 0);
+    for (position, expected) in result.iter().zip(melody) {
+        assert_eq!(pitch(position[0].unwrap()), i32::from(expected));
+    }
+    let frets: Vec<i16> = result.iter().map(|c| c[0].unwrap().1).collect();
+    assert!(
+        frets.windows(2).all(|f| (f[0] - f[1]).abs() <= 3),
+        "{frets:?}"
+    );
+}
@@ -0,0 +1,21 @@
+// Editing tests
+use super::read_file;
+use crate::model::song::Song;
+
+pub mod fingering;
+pub mod retune;
+pub mod transpose;
+
+pub(super) fn read_gp5(filename: &str) -> Song {
+    let mut song = Song::default();
+    song.read_gp5(&read_file(String::from(filename))).unwrap();
+    song
+}
+
+pub(super) fn notes(song: &Song) -> Vec<Vec<(i8, i16)>> {
+    song.tracks[0].measures[0].voices[0]
+        .beats
+        .iter()
+        .map(|b| b.notes.iter().map(|n| (n.string, n.value)).collect())
+        .collect()
+}
@@ -0,0 +1,107 @@
+// Re-tuning tests
+use super::super::song_of;
+use super::{notes, read_gp5};
+use crate::edit::retune::UnplacedNote;
+
+const STANDARD: [(i8, i8); 6] = [(1, 64), (2, 59), (3, 55), (4, 50), (5, 45), (6, 40)];
+const DROP_D: [(i8, i8); 6] = [(1, 64), (2, 59), (3, 55), (4, 50), (5, 45), (6, 38)];
+
+#[test]
+fn test_retune_keeps_pitches() {
+    let song = read_gp5("test/tempo.gp5");
+    let tunings = [
+        DROP_D.to_vec(),
+        // E flat
+        STANDARD.iter().map(|s| (s.0, s.1 - 1)).collect(),
+        // 7 strings
+        [STANDARD.to_vec(), vec![(7, 35)]].concat(),
+    ];
+    for strings in tunings {
+        let mut retuned = song.clone();
+        assert!(retuned.tracks[0].retune(strings.clone()).is_empty());
+        assert_eq!(retuned.tracks[0].strings, strings);
+        let before = song.events();
+        let after = retuned.events();
+        assert_eq!(before.len(), after.len());
+        for (b, a) in before.iter().zip(&after) {
+            assert_eq!((a.tick, a.pitch), (b.tick, b.pitch));
+        }
+    }
+}
+
+#[test]
+fn test_retune_positions() {
+    // Drop D: the low string is fretted 2 higher, the others keep their fret
+    let mut song = song_of(&[vec![vec![(6, 0), (5, 2), (1, 3)]]]);
+    song.tracks[0].retune(DROP_D.to_vec());
+    assert_eq!(notes(&song), vec![vec![(6, 2), (5, 2), (1, 3)]]);
+
+    // Without the low string: A on the 5th string, G cannot be played
+    let mut song = song_of(&[vec![vec![(6, 5)], vec![(6, 3), (3, 0)]]]);
+    let unplaced = song.tracks[0].retune(STANDARD[..5].to_vec());
+    assert_eq!(notes(&song), vec![vec![(5, 0)], vec![(6, 3), (3, 0)]]);
+    assert_eq!(
+        unplaced,
+        vec![UnplacedNote {
+            measure: 0,
+            voice: 0,
+            beat: 1,
+            note: 0,
+            pitch: 43
+        }]
+    );
+    assert_eq!(
+        unplaced[0].to_string(),
+        "measure 1, voice 1, beat 2: pitch 43 cannot be played"
+    );
+
+    // Off the neck with the E flat tuning
+    let mut song = song_of(&[vec![vec![(1, 24)]]]);
+    let strings = STANDARD.iter().map(|s| (s.0, s.1 - 1)).collect();
+    assert_eq!(song.tracks[0].retune(strings).len(), 1);
+}
+
+#[test]
+fn test_retune_hand_position() {
+    // The open high E of a high string tuned to F# moves next to the hand
+    let tuning = [vec![(1, 66)], STANDARD[1..].to_vec()].concat();
+    let mut song = song_of(&[vec![vec![(1, 0)]]]);
+    song.tracks[0].retune(tuning.clone());
+    assert_eq!(notes(&song), vec![vec![(2, 5)]]);
+
+    let mut song = song_of(&[vec![vec![(2, 10)], vec![(1, 0)]]]);
+    song.tracks[0].retune(tuning.clone());
+    assert_eq!(notes(&song), vec![vec![(2, 10)], vec![(3, 9)]]);
+
+    // Two notes of a beat never share a string
+    let mut song = song_of(&[vec![vec![(1, 0), (2, 5)]]]);
+    song.tracks[0].retune(tuning);
+    assert_eq!(notes(&song), vec![vec![(3, 9), (2, 5)]]);
+}
+
+#[test]
+fn test_retune_chords() {
+    let mut song = read_gp5("test/Chords.gp5");
+    song.tracks[0].retune(DROP_D.to_vec());
+    let chords: Vec<_> = song.tracks[0]
+        .measures
+        .iter()
+        .flat_map(|m| &m.voices[0].beats)
+        .filter_map(|b| b.effect.chord.clone())
+        .collect();
+    // C: the low string is not played
+    assert_eq!(chords[0].strings, vec![0, 1, 0, 2, 3, -1]);
+    // F: the barre on the low string moves up 2 frets
+    assert_eq!(chords[12].name, "F");
+    assert_eq!(chords[12].strings, vec![1, 1, 2, 3, 3, 3]);
+    assert!(chords[12].barres.is_empty());
+}
+
+#[test]
+fn test_retune_percussion() {
+    let song = read_gp5("test/all-percussion.gp5");
+    let mut retuned = song.clone();
+    assert!(retuned.tracks[0].retune(DROP_D.to_vec()).is_empty());
+    assert_eq!(song.tracks[0].strings, retuned.tracks[0].strings);
+    assert_eq!(song.events(), retuned.events());
+}
@@ -0,0 +1,128 @@
+// Transposition tests
+use super::super::song_of;
+use super::{notes, read_gp5};
+use crate::model::key_signature::KeySignature;
+use crate::model::song::Song;
+use crate::types::enums::NoteType;
+
+#[test]
+fn test_transpose_pitches() {
+    let song = read_gp5("test/tempo.gp5");
+    for semitones in [-5, 3, 12] {
+        let mut transposed = song.clone();
+        transposed.transpose(semitones);
+        let before = song.events();
+        let after = transposed.events();
+        assert_eq!(before.len(), after.len());
+        for (b, a) in before.iter().zip(&after) {
+            assert_eq!(i32::from(a.pitch), i32::from(b.pitch) + semitones);
+            assert_eq!(a.tick, b.tick);
+        }
+    }
+}
+
+#[test]
+fn test_transpose_off_the_neck() {
+    // Fret 22 on the B string, 4 semitones up: fret 21 on the high E string, the note
+    // on the high E string of the next beat going to the B string
+    let mut song = song_of(&[vec![vec![(2, 22)], vec![(2, 22), (1, 0)]]]);
+    song.tracks[0].measures[0].voices[0].beats[1].notes[0].kind = NoteType::Tie;
+    let mut up = song.clone();
+    up.transpose(4);
+    assert_eq!(notes(&up), vec![vec![(1, 21)], vec![(1, 21), (2, 9)]]);
+
+    // Low E string open, 2 semitones down: an octave up on the same string
+    let mut song = song_of(&[vec![vec![(6, 0), (1, 5)]]]);
+    song.transpose(-2);
+    assert_eq!(notes(&song), vec![vec![(6, 10), (1, 3)]]);
+
+    // With a capo on the 5th fret, 24 frets: 19 playable frets above it
+    let mut song = song_of(&[vec![vec![(1, 18)]]]);
+    song.tracks[0].offset = 5;
+    song.transpose(2);
+    assert_eq!(notes(&song), vec![vec![(1, 8)]]);
+}
+
+#[test]
+fn test_transpose_key_signatures() {
+    let key = |key, is_minor| KeySignature { key, is_minor };
+    assert_eq!(key(0, false).transposed(2), key(2, false));
+    assert_eq!(key(0, false).transposed(1), key(-5, false));
+    assert_eq!(key(0, false).transposed(-1), key(5, false));
+    assert_eq!(key(-1, true).transposed(12), key(-1, true));
+    // Tritone: the side of the circle of fifths is kept
+    assert_eq!(key(0, false).transposed(6), key(6, false));
+    assert_eq!(key(-1, false).transposed(1), key(-6, false));
+    assert_eq!(key(1, false).transposed(-1), key(6, false));
+
+    let mut song = read_gp5("test/Chords.gp5");
+    song.transpose(2);
+    assert_eq!(song.key, key(2, false));
+    assert!(song
+        .measure_headers
+        .iter()
+        .all(|h| h.key_signature == key(2, false)));
+}
+
+#[test]
+fn test_transpose_chords() {
+    let chords = |song: &Song| -> Vec<_> {
+        song.tracks[0]
+            .measures
+            .iter()
+            .flat_map(|m| &m.voices[0].beats)
+            .filter_map(|b| b.effect.chord.clone())
+            .collect()
+    };
+    let mut song = read_gp5("test/Chords.gp5");
+    song.transpose(2);
+    let names: Vec<_> = chords(&song).iter().map(|c| c.name.clone()).collect();
+    assert_eq!(names[0], "D");
+    assert_eq!(names[6], "D/B");
+    assert_eq!(names[7], "D11/9-");
+    assert_eq!(names[14], "Am6add9/G");
+    let last = &chords(&song)[14];
+    assert_eq!(last.root.as_ref().unwrap().to_string(), "A");
+    assert_eq!(last.bass.as_ref().unwrap().to_string(), "G");
+    assert_eq!(&last.strings[..6], &[5, 5, 4, 4, 3, 3]);
+    assert_eq!(last.barres[0].fret, 3);
+
+    // A semitone up is spelled with flats, in Db major
+    let mut song = read_gp5("test/Chords.gp5");
+    song.transpose(1);
+    let first = &chords(&song)[0];
+    assert_eq!(first.name, "Db");
+    assert_eq!(first.root.as_ref().unwrap().to_string(), "Db");
+    assert_eq!(&first.strings[..5], &[1, 2, 1, 3, 4]);
+
+    // Down a semitone the open C chord moves up the neck
+    let mut song = read_gp5("test/Chords.gp5");
+    song.transpose(-1);
+    let first = &chords(&song)[0];
+    assert_eq!(first.name, "B");
+    assert_eq!(&first.strings[..5], &[11, 12, 11, 13, 14]);
+}
+
+#[test]
+fn test_transpose_track() {
+    let mut song = song_of(&[vec![vec![(3, 2)]]]);
+    song.tracks.push(song.tracks[0].clone());
+    song.transpose_track(1, 3);
+    assert_eq!(song.key.key, 0);
+    assert_eq!(
+        song.tracks[0].measures[0].voices[0].beats[0].notes[0].value,
+        2
+    );
+    assert_eq!(
+        song.tracks[1].measures[0].voices[0].beats[0].notes[0].value,
+        5
+    );
+}
+
+#[test]
+fn test_transpose_percussion() {
+    let song = read_gp5("test/all-percussion.gp5");
+    let mut transposed = song.clone();
+    transposed.transpose(5);
+    assert_eq!(song.events(), transposed.events());
+}
@@ -40,6 +40,7 @@ pub mod pick_up_down;
 pub mod rasg;
 pub mod repeated_bars;
 pub mod repeats;
+pub mod report;
 pub mod rest_centered;
 pub mod sforzato;
 pub mod slides;
@@ -57,3 +58,4 @@ pub mod vibrato;
 pub mod volta;
 pub mod volume_swell;
 pub mod wah;
+pub mod writing;
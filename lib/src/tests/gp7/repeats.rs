@@ -1,7 +1,6 @@
 // GP7 repeats tests
 use super::super::{read_gp7};
 
-
 #[test]
 fn test_gp7_repeats() {
     let song = read_gp7("test/repeats.gp");
@@ -16,3 +15,14 @@ fn test_gp7_repeats() {
     );
 }
 
+#[test]
+fn test_gp7_repeat_counts() {
+    // GPIF stores the play count (3 and 2), the song model the number of repeats
+    let song = read_gp7("test/repeats.gp");
+    let repeats: Vec<(bool, i8)> = song
+        .measure_headers
+        .iter()
+        .map(|mh| (mh.repeat_open, mh.repeat_close))
+        .collect();
+    assert_eq!(repeats, vec![(false, 2), (true, -1), (false, 1)]);
+}
@@ -0,0 +1,134 @@
+// GP7 import report tests
+use crate::error::{GpError, ScorePart};
+use crate::io::gpif::import::report::GpifIssueKind;
+use crate::io::gpif::Gpif;
+use crate::io::gpif_import::SongGpifOps;
+use crate::io::gpx::read_gp;
+use crate::io::limits::ReadLimits;
+use crate::model::song::Song;
+
+use super::super::read_file;
+
+fn read_gpif(filename: &str) -> Gpif {
+    read_gp(&read_file(String::from(filename)), &ReadLimits::default()).unwrap()
+}
+
+#[test]
+fn test_gp7_report_ignored() {
+    let issues = Song::default()
+        .read_gp(&read_file(String::from("test/wah.gp")))
+        .unwrap();
+    let wah = issues
+        .iter()
+        .find(|i| matches!(&i.kind, GpifIssueKind::IgnoredElement { element } if element == "Wah Closed"))
+        .unwrap();
+    assert_eq!(
+        wah.location,
+        vec![
+            ScorePart::Track(0),
+            ScorePart::Measure(0),
+            ScorePart::Voice(0),
+            ScorePart::Beat(3)
+        ]
+    );
+    assert_eq!(
+        wah.to_string(),
+        "track 1 \u{203a} measure 1 \u{203a} voice 1 \u{203a} beat 4: Wah Closed ignored"
+    );
+    // Ignored properties are reported once
+    let midi = issues
+        .iter()
+        .filter(|i| {
+            i.kind
+                == GpifIssueKind::IgnoredProperty {
+                    owner: "Note",
+                    name: String::from("Midi"),
+                }
+        })
+        .count();
+    assert_eq!(midi, 1);
+    // Tremolo picking is not read from the beat
+    let issues = Song::default()
+        .read_gp(&read_file(String::from("test/tremolos.gp")))
+        .unwrap();
+    assert!(issues.iter().any(|i| i.kind
+        == GpifIssueKind::IgnoredElement {
+            element: String::from("Tremolo 1/8")
+        }));
+    // GP6 files report the same
+    let issues = Song::default()
+        .read_gpx(&read_file(String::from("test/tremolos.gpx")))
+        .unwrap();
+    assert!(issues.iter().any(|i| i.kind
+        == GpifIssueKind::IgnoredElement {
+            element: String::from("Tremolo 1/8")
+        }));
+}
+
+#[test]
+fn test_gp7_report_dangling_and_malformed() {
+    let mut gpif = read_gpif("test/accent.gp");
+    let voice = gpif.voices.voices[0].beats.clone();
+    gpif.voices.voices[0].beats = format!("{} 999 x", voice);
+    gpif.beats.beats[0].dynamic = Some(String::from("FFFFF"));
+    if let Some(rhythm) = gpif.beats.beats[1].rhythm.as_mut() {
+        rhythm.r#ref = 998;
+    }
+    let mut song = Song::default();
+    let issues = song.read_gpif(&gpif, &ReadLimits::default()).unwrap();
+    let kinds: Vec<&GpifIssueKind> = issues.iter().map(|i| &i.kind).collect();
+    assert!(kinds.contains(&&GpifIssueKind::DanglingId {
+        element: "Beat",
+        id: 999
+    }));
+    assert!(kinds.contains(&&GpifIssueKind::DanglingId {
+        element: "Rhythm",
+        id: 998
+    }));
+    assert!(kinds.contains(&&GpifIssueKind::MalformedValue {
+        element: "Beats",
+        value: String::from("x")
+    }));
+    assert!(kinds.contains(&&GpifIssueKind::MalformedValue {
+        element: "Dynamic",
+        value: String::from("FFFFF")
+    }));
+    // The beats found are still imported
+    assert!(!song.tracks[0].measures[0].voices[0].beats.is_empty());
+}
+
+#[test]
+fn test_gp7_report_extra_voices() {
+    // A fifth voice of the first bar has no slot in the measure
+    let mut gpif = read_gpif("test/accent.gp");
+    let first_bar: i32 = gpif.master_bars.master_bars[0]
+        .bars
+        .split_whitespace()
+        .next()
+        .unwrap()
+        .parse()
+        .unwrap();
+    let bar = gpif.bars.bars.iter_mut().find(|b| b.id == first_bar).unwrap();
+    let voice = bar.voices.split_whitespace().next().unwrap().to_string();
+    bar.voices = format!("{} -1 -1 -1 {}", voice, voice);
+    let mut song = Song::default();
+    let issues = song.read_gpif(&gpif, &ReadLimits::default()).unwrap();
+    let extra = issues
+        .iter()
+        .find(|i| matches!(&i.kind, GpifIssueKind::IgnoredElement { element } if element.starts_with("Voice ")))
+        .unwrap();
+    assert_eq!(
+        extra.location,
+        vec![ScorePart::Track(0), ScorePart::Measure(0)]
+    );
+    assert_eq!(song.tracks[0].measures[0].voices.len(), 1);
+}
+
+#[test]
+fn test_gp7_report_time_signature() {
+    let mut gpif = read_gpif("test/accent.gp");
+    gpif.master_bars.master_bars[0].time = String::from("4/0");
+    let err = Song::default().read_gpif(&gpif, &ReadLimits::default()).unwrap_err();
+    assert!(matches!(err, GpError::FormatError(_)));
+    assert!(err.to_string().contains("'4/0'"));
+}
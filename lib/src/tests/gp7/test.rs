@@ -1,5 +1,6 @@
 // GP7 test tests
-use super::super::{read_gp7};
+use super::super::{read_file, read_gp7};
+use crate::model::song::Song;
 
 
 #[test]
@@ -8,3 +9,32 @@ fn test_gp7_test() {
     assert!(!song.tracks.is_empty());
 }
 
+#[test]
+fn test_gp7_strings_match_gp5() {
+    // GPIF numbers strings from the lowest, the song model from the highest like GP5
+    let song = read_gp7("test/test.gp");
+    let mut gp5 = Song::default();
+    gp5.read_gp5(&read_file(String::from("test/test.gp5")))
+        .unwrap();
+    assert_eq!(
+        song.tracks[0].strings,
+        vec![(1, 64), (2, 59), (3, 55), (4, 50), (5, 45), (6, 40)]
+    );
+    assert_eq!(song.tracks[0].strings, gp5.tracks[0].strings);
+
+    let notes = |song: &Song| -> Vec<(i8, i16)> {
+        song.tracks[0]
+            .measures
+            .iter()
+            .flat_map(|m| &m.voices[0].beats)
+            .flat_map(|b| &b.notes)
+            .map(|n| (n.string, n.value))
+            .collect()
+    };
+    let notes_gp7 = notes(&song);
+    assert_eq!(
+        &notes_gp7[..8],
+        &[(6, 0), (6, 0), (6, 0), (6, 0), (5, 2), (5, 2), (5, 2), (5, 2)]
+    );
+    assert_eq!(notes_gp7, notes(&gp5));
+}
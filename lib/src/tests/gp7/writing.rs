@@ -0,0 +1,195 @@
+// GP7 writing tests
+use super::super::{read_file, read_gp7};
+use crate::model::beat::Beat;
+use crate::model::song::Song;
+use crate::types::enums::NoteType;
+use std::fs;
+use std::io::Cursor;
+
+fn round_trip(song: &Song) -> Song {
+    let data = song.write_gp().unwrap();
+    let mut written = Song::default();
+    written.read_gp(&data).unwrap();
+    written
+}
+
+pub fn assert_same_structure(name: &str, expected: &Song, actual: &Song) {
+    assert_eq!(
+        expected.measure_headers.len(),
+        actual.measure_headers.len(),
+        "{}: measure count",
+        name
+    );
+    for (e, a) in expected.measure_headers.iter().zip(&actual.measure_headers) {
+        let ctx = format!("{}: measure {}", name, e.number);
+        assert_eq!(
+            e.time_signature.numerator, a.time_signature.numerator,
+            "{}",
+            ctx
+        );
+        assert_eq!(
+            e.time_signature.denominator.value, a.time_signature.denominator.value,
+            "{}",
+            ctx
+        );
+        assert_eq!(e.repeat_open, a.repeat_open, "{}", ctx);
+        assert_eq!(e.repeat_close, a.repeat_close, "{}", ctx);
+        assert_eq!(e.repeat_alternative, a.repeat_alternative, "{}", ctx);
+        assert_eq!(e.direction, a.direction, "{}", ctx);
+    }
+
+    assert_eq!(
+        expected.tracks.len(),
+        actual.tracks.len(),
+        "{}: track count",
+        name
+    );
+    for (t_idx, (et, at)) in expected.tracks.iter().zip(&actual.tracks).enumerate() {
+        assert_eq!(et.name, at.name, "{}: track {} name", name, t_idx);
+        assert_eq!(et.strings, at.strings, "{}: track {} tuning", name, t_idx);
+        assert_eq!(et.offset, at.offset, "{}: track {} capo", name, t_idx);
+        for (m_idx, (em, am)) in et.measures.iter().zip(&at.measures).enumerate() {
+            let ctx = format!("{}: track {} measure {}", name, t_idx, m_idx + 1);
+            let expected_beats: Vec<&Beat> =
+                em.voices.iter().take(1).flat_map(|v| &v.beats).collect();
+            let actual_beats: Vec<&Beat> = am
+                .voices
+                .iter()
+                .take(1)
+                .flat_map(|v| &v.beats)
+                // Grace beats inserted for GP3-5 grace notes have no counterpart in the source song
+                .filter(|b| !b.is_grace_beat() || expected_beats.iter().any(|e| e.is_grace_beat()))
+                .collect();
+            assert_eq!(
+                expected_beats.len(),
+                actual_beats.len(),
+                "{}: beat count",
+                ctx
+            );
+            for (eb, ab) in expected_beats.iter().zip(&actual_beats) {
+                assert_eq!(eb.duration.value, ab.duration.value, "{}", ctx);
+                assert_eq!(eb.duration.dotted, ab.duration.dotted, "{}", ctx);
+                assert_eq!(
+                    eb.duration.tuplet_enters, ab.duration.tuplet_enters,
+                    "{}",
+                    ctx
+                );
+                assert_eq!(
+                    eb.duration.tuplet_times, ab.duration.tuplet_times,
+                    "{}",
+                    ctx
+                );
+                let expected_notes: Vec<_> = eb
+                    .notes
+                    .iter()
+                    .filter(|n| n.kind != NoteType::Rest)
+                    .collect();
+                assert_eq!(expected_notes.len(), ab.notes.len(), "{}: note count", ctx);
+                for (en, an) in expected_notes.iter().zip(&ab.notes) {
+                    assert_eq!(en.string, an.string, "{}", ctx);
+                    assert_eq!(en.kind, an.kind, "{}", ctx);
+                    // GP3-5 tied notes carry no fret
+                    if en.kind != NoteType::Tie {
+                        assert_eq!(en.value, an.value, "{}", ctx);
+                    }
+                }
+            }
+        }
+    }
+}
+
+pub fn assert_same_effects(name: &str, expected: &Song, actual: &Song) {
+    for (et, at) in expected.tracks.iter().zip(&actual.tracks) {
+        for (em, am) in et.measures.iter().zip(&at.measures) {
+            for (ev, av) in em.voices.iter().zip(&am.voices) {
+                for (eb, ab) in ev.beats.iter().zip(&av.beats) {
+                    assert_eq!(
+                        format!("{:?}", eb.effect),
+                        format!("{:?}", ab.effect),
+                        "{}",
+                        name
+                    );
+                    assert_eq!(eb.octave, ab.octave, "{}", name);
+                    assert_eq!(eb.text, ab.text, "{}", name);
+                    for (en, an) in eb.notes.iter().zip(&ab.notes) {
+                        assert_eq!(
+                            format!("{:?}", en.effect),
+                            format!("{:?}", an.effect),
+                            "{}",
+                            name
+                        );
+                        assert_eq!(en.velocity, an.velocity, "{}", name);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_gp7_write_round_trip_all_files() {
+    for entry in fs::read_dir("../test").unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension().is_some_and(|e| e == "gp") {
+            let name = path.file_name().unwrap().to_str().unwrap().to_string();
+            let song = read_gp7(&name);
+            let written = round_trip(&song);
+            assert_eq!(song.name, written.name, "{}: title", name);
+            assert_eq!(song.artist, written.artist, "{}: artist", name);
+            assert_eq!(song.tempo, written.tempo, "{}: tempo", name);
+            assert_same_structure(&name, &song, &written);
+            assert_same_effects(&name, &song, &written);
+        }
+    }
+}
+
+#[test]
+fn test_gp7_write_from_gp5() {
+    for entry in fs::read_dir("../test").unwrap() {
+        let path = entry.unwrap().path();
+        let has_gp7_pair = path.with_extension("gp").exists();
+        if path.extension().is_none_or(|e| e != "gp5") || !has_gp7_pair {
+            continue;
+        }
+        let name = path.file_name().unwrap().to_str().unwrap().to_string();
+        let mut song = Song::default();
+        song.read_gp5(&read_file(name.clone())).unwrap();
+        let written = round_trip(&song);
+        assert_same_structure(&name, &song, &written);
+    }
+}
+
+#[test]
+fn test_gp7_write_metadata() {
+    let mut song = read_gp7("test/copyright.gp");
+    song.name = String::from("Title & <More>");
+    song.notice = vec![String::from("first"), String::from("second")];
+    let written = round_trip(&song);
+    assert_eq!(written.name, "Title & <More>");
+    assert_eq!(written.copyright, song.copyright);
+    assert_eq!(written.notice, song.notice);
+}
+
+#[test]
+fn test_gp7_write_archive_layout() {
+    let song = read_gp7("test/bend.gp");
+    let data = song.write_gp().unwrap();
+    let mut zip = zip::ZipArchive::new(Cursor::new(data)).unwrap();
+    assert!(zip.by_name("VERSION").is_ok());
+    assert!(zip.by_name("Content/score.gpif").is_ok());
+    assert!(zip.by_name("Content/PartConfiguration").is_ok());
+}
+
+#[test]
+fn test_gp7_write_beat_text() {
+    let mut song = read_gp7("test/text.gp");
+    let texts = [" lead in", "fade out  ", "a ]]> b & <c>"];
+    let beats = song.tracks[0].measures[0].voices[0].beats.iter_mut();
+    for (beat, text) in beats.zip(texts) {
+        beat.text = String::from(text);
+    }
+    let written = round_trip(&song);
+    let beats = &written.tracks[0].measures[0].voices[0].beats;
+    let written_texts: Vec<&str> = beats.iter().take(3).map(|b| b.text.as_str()).collect();
+    assert_eq!(written_texts, texts);
+}
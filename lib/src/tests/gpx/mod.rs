@@ -56,3 +56,4 @@ pub mod vibrato;
 pub mod volta;
 pub mod volume_swell;
 pub mod wah;
+pub mod writing;
@@ -0,0 +1,73 @@
+// GPX writing tests
+use super::super::gp7::writing::{assert_same_effects, assert_same_structure};
+use super::super::{read_file, read_gpx};
+use crate::io::format::FileFormat;
+use crate::io::gpx::{compress_bcfz, decompress_bcfz};
+use crate::model::song::Song;
+use std::fs;
+
+fn round_trip(song: &Song) -> Song {
+    let data = song.write_gpx().unwrap();
+    let mut written = Song::default();
+    written.read_gpx(&data).unwrap();
+    written
+}
+
+#[test]
+fn test_gpx_write_round_trip_all_files() {
+    for entry in fs::read_dir("../test").unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension().is_some_and(|e| e == "gpx") {
+            let name = path.file_name().unwrap().to_str().unwrap().to_string();
+            let song = read_gpx(&format!("test/{}", name));
+            let written = round_trip(&song);
+            assert_eq!(song.name, written.name, "{}: title", name);
+            assert_eq!(song.artist, written.artist, "{}: artist", name);
+            assert_eq!(song.tempo, written.tempo, "{}: tempo", name);
+            assert_same_structure(&name, &song, &written);
+            assert_same_effects(&name, &song, &written);
+        }
+    }
+}
+
+#[test]
+fn test_gpx_write_from_gp5() {
+    let mut song = Song::default();
+    song.read_gp5(&read_file(String::from("test/bend.gp5")))
+        .unwrap();
+    let written = round_trip(&song);
+    assert_same_structure("bend.gp5", &song, &written);
+}
+
+#[test]
+fn test_gpx_write_keeps_rse_effect_category() {
+    let song = read_gpx("test/bend.gpx");
+    let written = round_trip(&song);
+    for (t, w) in song.tracks.iter().zip(&written.tracks) {
+        assert_eq!(
+            t.rse.instrument.effect_category,
+            w.rse.instrument.effect_category
+        );
+    }
+}
+
+#[test]
+fn test_bcfz_compress_round_trip() {
+    let mut data: Vec<u8> = b"BCFS".to_vec();
+    data.extend(std::iter::repeat_n(0xff, 5000));
+    data.extend((0..20000u32).map(|i| (i * 7 % 251) as u8));
+    data.extend(b"<Note><Fret>3</Fret></Note>".repeat(200));
+    let compressed = compress_bcfz(&data);
+    assert!(compressed.len() < data.len());
+    assert_eq!(decompress_bcfz(&compressed, usize::MAX).unwrap(), data);
+}
+
+#[test]
+fn test_gpx_read_uncompressed_bcfs() {
+    let song = read_gpx("test/bend.gpx");
+    let bcfs = decompress_bcfz(&song.write_gpx().unwrap(), usize::MAX).unwrap();
+    assert!(bcfs.starts_with(b"BCFS"));
+    let (read, format) = Song::from_bytes(&bcfs).unwrap();
+    assert_eq!(format, FileFormat::Gpx);
+    assert_same_structure("bend.gpx", &song, &read);
+}
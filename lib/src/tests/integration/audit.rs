@@ -27,12 +27,6 @@ fn test_audit_all_files() {
 
     for path in files {
         let filename = path.file_name().unwrap().to_str().unwrap().to_string();
-        let extension = path
-            .extension()
-            .and_then(|e| e.to_str())
-            .unwrap_or("")
-            .to_lowercase();
-
         let data = match fs::read(&path) {
             Ok(d) => d,
             Err(e) => {
@@ -41,28 +35,13 @@ fn test_audit_all_files() {
             }
         };
 
-        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
-            let mut song = Song::default();
-            match extension.as_str() {
-                "gp3" => {
-                    let _ = song.read_gp3(&data);
-                }
-                "gp4" => {
-                    let _ = song.read_gp4(&data);
-                }
-                "gp5" => {
-                    let _ = song.read_gp5(&data);
+        let result =
+            std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                match Song::from_bytes(&data) {
+                    Ok((_, format)) => format!("OK ({})", format),
+                    Err(e) => format!("ERROR ({})", e),
                 }
-                "gp" => {
-                    let _ = song.read_gp(&data);
-                }
-                "gpx" => {
-                    let _ = song.read_gpx(&data);
-                }
-                _ => return "SKIP".to_string(),
-            }
-            "OK".to_string()
-        }));
+            }));
 
         match result {
             Ok(status) => results.push(format!("{}: {}", filename, status)),
@@ -0,0 +1,56 @@
+// Error location tests
+use crate::error::{GpError, ScorePart};
+use crate::model::song::Song;
+use crate::tests::read_file;
+
+#[test]
+fn test_error_location() {
+    let data = read_file(String::from("Demo v5.gp5"));
+    let mut song = Song::default();
+    let error = song.read_gp5(&data[..0x3213]).unwrap_err();
+    assert_eq!(
+        error.location(),
+        &[
+            ScorePart::Track(1),
+            ScorePart::Measure(22),
+            ScorePart::Voice(0),
+            ScorePart::Beat(0),
+            ScorePart::Note(0),
+        ]
+    );
+    assert_eq!(error.version(), Some(song.version.number));
+    assert!(matches!(
+        error.cause(),
+        GpError::UnexpectedEof {
+            offset: 12819,
+            needed: 8
+        }
+    ));
+    assert_eq!(
+        error.to_string(),
+        format!(
+            "track 2 › measure 23 › voice 1 › beat 1 › note 1: Unexpected end of file at offset 12819, needed 8 more bytes at 0x3213 (version {}.{}.{})",
+            song.version.number.0, song.version.number.1, song.version.number.2
+        )
+    );
+}
+
+#[test]
+fn test_error_outside_measures() {
+    // In the MIDI channels of a GP4 file
+    let data = read_file(String::from("Effects.gp4"));
+    let error = Song::default().read_gp4(&data[..0x1F5]).unwrap_err();
+    assert_eq!(error.location(), &[ScorePart::Channel(31)]);
+    assert_eq!(error.version().map(|v| v.0), Some(4));
+
+    // In the score information of a GP3 file: no part of the score, but the offset
+    let data = read_file(String::from("Chords.gp3"));
+    let error = Song::default().read_gp3(&data[..0x1F]).unwrap_err();
+    assert!(error.location().is_empty());
+    assert!(error.to_string().ends_with("at 0x1F (version 3.0.0)"));
+
+    // Errors of other readers have no location
+    let error = Song::default().read_midi(b"RIFF0000").unwrap_err();
+    assert!(error.location().is_empty());
+    assert_eq!(error.version(), None);
+}
@@ -0,0 +1,74 @@
+// Format detection tests
+use std::fs;
+use std::path::Path;
+
+use crate::io::ascii_tab::render::TabOptions;
+use crate::io::format::{detect_format, FileFormat};
+use crate::model::song::Song;
+use crate::tests::read_file;
+
+#[test]
+fn test_detect_guitar_pro_files() {
+    let mut files: Vec<_> = fs::read_dir(Path::new("../test"))
+        .expect("Cannot read dir")
+        .map(|e| e.unwrap().path())
+        .collect();
+    files.sort();
+    for path in files {
+        let expected = match path.extension().and_then(|e| e.to_str()) {
+            Some("gp3") => FileFormat::Gp3,
+            Some("gp4") => FileFormat::Gp4,
+            Some("gp5") | Some("tmp") => FileFormat::Gp5,
+            Some("gpx") => FileFormat::Gpx,
+            Some("gp") => FileFormat::Gp,
+            _ => continue,
+        };
+        let data = fs::read(&path).unwrap();
+        assert_eq!(detect_format(&data), Some(expected), "{:?}", path);
+    }
+}
+
+#[test]
+fn test_from_bytes_ignores_file_name() {
+    // A GP5 file saved as .gp4 is read as GP5
+    let data = read_file(String::from("bend.gp5"));
+    let (song, format) = Song::from_bytes(&data).unwrap();
+    assert_eq!(format, FileFormat::Gp5);
+    assert_eq!(song.version.number.0, 5);
+
+    let (song, format) = Song::open("../test/2 whole bars.tmp").unwrap();
+    assert_eq!(format, FileFormat::Gp5);
+    assert!(song.version.clipboard);
+
+    let (song, format) = Song::open("../test/basic-bend.gpx").unwrap();
+    assert_eq!(format, FileFormat::Gpx);
+    assert!(!song.tracks.is_empty());
+    assert_eq!(Song::open("../test/bend.gp").unwrap().1, FileFormat::Gp);
+    assert!(Song::open("../test/missing.gp5").is_err());
+}
+
+#[test]
+fn test_detect_other_formats() {
+    let (song, _) = Song::from_bytes(&read_file(String::from("bend.gp5"))).unwrap();
+
+    let midi = song.to_midi();
+    assert_eq!(detect_format(&midi), Some(FileFormat::Midi));
+    let musicxml = song.to_musicxml();
+    assert_eq!(
+        Song::from_bytes(musicxml.as_bytes()).unwrap().1,
+        FileFormat::MusicXml
+    );
+    let tab = song.to_ascii_tab(&[0], &TabOptions::default());
+    let (read, format) = Song::from_bytes(format!("Bend\n\n{}", tab).as_bytes()).unwrap();
+    assert_eq!(format, FileFormat::AsciiTab);
+    assert_eq!(read.tracks.len(), 1);
+    assert_eq!(
+        detect_format(b"<?xml version=\"1.0\"?>\n<museScore version=\"3.02\"><Score/></museScore>"),
+        Some(FileFormat::Mscx)
+    );
+
+    assert_eq!(detect_format(b""), None);
+    assert_eq!(detect_format(b"Just some notes about a song"), None);
+    assert_eq!(detect_format(b"<html></html>"), None);
+    assert!(Song::from_bytes(b"PK\x03\x04 broken").is_err());
+}
@@ -0,0 +1,227 @@
+// Fuzz regression tests: damaged files of every format are read to an error, never a panic
+use std::fs;
+use std::io::{Cursor, Write};
+use std::path::PathBuf;
+
+use zip::write::FileOptions;
+use zip::ZipWriter;
+
+use crate::model::song::Song;
+use crate::tests::mscz::reading::SCORE;
+use crate::tests::read_file;
+
+/// Damaged copies made of each file
+const MUTATIONS: usize = 12;
+/// Truncated copies made of a file
+const TRUNCATIONS: usize = 200;
+
+/// Deterministic pseudo-random numbers, so a failure can be replayed.
+struct Lcg(u64);
+
+impl Lcg {
+    fn next(&mut self) -> usize {
+        self.0 = self
+            .0
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (self.0 >> 33) as usize
+    }
+}
+
+/// A damaged copy of the data: truncated, with random bytes, with extreme bytes, or with
+/// an integer overwritten by -1.
+fn mutate(data: &[u8], rng: &mut Lcg) -> Vec<u8> {
+    let mut data = data.to_vec();
+    if data.is_empty() {
+        return data;
+    }
+    match rng.next() % 4 {
+        0 => data.truncate(rng.next() % data.len()),
+        1 => {
+            for _ in 0..1 + rng.next() % 4 {
+                let i = rng.next() % data.len();
+                data[i] = rng.next() as u8;
+            }
+        }
+        2 => {
+            for _ in 0..1 + rng.next() % 4 {
+                let i = rng.next() % data.len();
+                data[i] = [0x00, 0xFF, 0x7F, 0x80][rng.next() % 4];
+            }
+        }
+        _ => {
+            let i = rng.next() % data.len();
+            let end = (i + 4).min(data.len());
+            data[i..end].fill(0xFF);
+        }
+    }
+    data
+}
+
+/// Sample files with the given extension.
+fn samples(extension: &str) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = fs::read_dir("../test")
+        .expect("Cannot read dir")
+        .map(|e| e.unwrap().path())
+        .filter(|p| p.extension().is_some_and(|e| e == extension))
+        .collect();
+    files.sort();
+    files
+}
+
+/// Read damaged copies of the sample files with the given extension, along with the format
+/// detection and the lenient reading.
+fn fuzz(extension: &str, seed: u64, read: fn(&mut Song, &[u8])) {
+    let mut rng = Lcg(seed);
+    let files = samples(extension);
+    assert!(!files.is_empty());
+    for path in files {
+        let data = fs::read(&path).unwrap();
+        for _ in 0..MUTATIONS {
+            let damaged = mutate(&data, &mut rng);
+            read(&mut Song::default(), &damaged);
+            let _ = Song::from_bytes(&damaged);
+            let _ = Song::default().read_lenient(&damaged);
+        }
+    }
+}
+
+/// Read truncations of the data, evenly spread over it.
+fn truncations(data: &[u8], read: fn(&mut Song, &[u8])) {
+    for end in (0..data.len()).step_by(data.len() / TRUNCATIONS + 1) {
+        read(&mut Song::default(), &data[..end]);
+    }
+}
+
+#[test]
+fn test_fuzz_gp3() {
+    fuzz("gp3", 3, |song, data| {
+        let _ = song.read_gp3(data);
+    });
+}
+
+#[test]
+fn test_fuzz_gp4() {
+    fuzz("gp4", 4, |song, data| {
+        let _ = song.read_gp4(data);
+    });
+}
+
+#[test]
+fn test_fuzz_gp5() {
+    fuzz("gp5", 5, |song, data| {
+        let _ = song.read_gp5(data);
+    });
+}
+
+#[test]
+fn test_fuzz_gpx() {
+    fuzz("gpx", 6, |song, data| {
+        let _ = song.read_gpx(data);
+    });
+}
+
+#[test]
+fn test_fuzz_gp() {
+    fuzz("gp", 7, |song, data| {
+        let _ = song.read_gp(data);
+    });
+}
+
+#[test]
+fn test_fuzz_midi() {
+    let mut song = Song::default();
+    song.read_gp5(&read_file(String::from("Demo v5.gp5")))
+        .unwrap();
+    let midi = song.to_midi();
+    truncations(&midi, |song, data| {
+        let _ = song.read_midi(data);
+        let _ = Song::from_bytes(data);
+    });
+    // Damaged delta times are bounded by the read limits
+    let mut rng = Lcg(9);
+    for _ in 0..MUTATIONS * 4 {
+        let _ = Song::default().read_midi(&mutate(&midi, &mut rng));
+    }
+}
+
+#[test]
+fn test_fuzz_text_formats() {
+    let mut song = Song::default();
+    song.read_gp5(&read_file(String::from("Demo v5.gp5")))
+        .unwrap();
+    truncations(song.to_musicxml().as_bytes(), |song, data| {
+        let _ = song.read_musicxml(data);
+    });
+    truncations(SCORE.as_bytes(), |song, data| {
+        let _ = song.read_mscx(data);
+    });
+
+    let tab = song.to_ascii_tab(&[0], &Default::default());
+    truncations(tab.as_bytes(), |song, data| {
+        let _ = song.read_ascii_tab(data);
+    });
+    let mut rng = Lcg(8);
+    for _ in 0..MUTATIONS * 4 {
+        let _ = Song::default().read_ascii_tab(&mutate(tab.as_bytes(), &mut rng));
+        let _ = Song::default().read_mscx(&mutate(SCORE.as_bytes(), &mut rng));
+    }
+}
+
+/// ZIP archive of a score, with the container pointing at it.
+fn archive(path: &str, score: &[u8]) -> Vec<u8> {
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = FileOptions::default();
+    zip.start_file("META-INF/container.xml", options).unwrap();
+    let container = format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<container><rootfiles><rootfile full-path="{}"/></rootfiles></container>"#,
+        path
+    );
+    zip.write_all(container.as_bytes()).unwrap();
+    zip.start_file(path, options).unwrap();
+    zip.write_all(score).unwrap();
+    zip.finish().unwrap().into_inner()
+}
+
+#[test]
+fn test_fuzz_archives() {
+    let mut song = Song::default();
+    song.read_gp5(&read_file(String::from("Demo v5.gp5")))
+        .unwrap();
+    let mxl = archive("score/Demo.musicxml", song.to_musicxml().as_bytes());
+    let mscz = archive("Demo.mscx", SCORE.as_bytes());
+    Song::default().read_musicxml(&mxl).unwrap();
+    Song::default().read_mscz(&mscz).unwrap();
+    truncations(&mxl, |song, data| {
+        let _ = song.read_musicxml(data);
+        let _ = Song::from_bytes(data);
+    });
+    truncations(&mscz, |song, data| {
+        let _ = song.read_mscz(data);
+        let _ = Song::from_bytes(data);
+    });
+
+    // Damaged archives, and archives of damaged scores
+    let mut rng = Lcg(10);
+    for _ in 0..MUTATIONS * 4 {
+        let _ = Song::default().read_musicxml(&mutate(&mxl, &mut rng));
+        let _ = Song::default().read_mscz(&mutate(&mscz, &mut rng));
+        let score = mutate(SCORE.as_bytes(), &mut rng);
+        let _ = Song::default().read_mscz(&archive("Demo.mscx", &score));
+    }
+}
+
+#[test]
+fn test_fuzz_invalid_values() {
+    // Values out of range for the song are errors, not overflows
+    let data = read_file(String::from("Chords.gp5"));
+    let mut rng = Lcg(0);
+    for _ in 0..MUTATIONS * 8 {
+        let mut damaged = data.clone();
+        let i = rng.next() % damaged.len();
+        let end = (i + 4).min(damaged.len());
+        damaged[i..end].fill(0x80);
+        let _ = Song::default().read_gp5(&damaged);
+    }
+}
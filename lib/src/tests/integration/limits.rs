@@ -0,0 +1,184 @@
+// Read limits tests
+use crate::error::GpError;
+use crate::io::format::FileFormat;
+use crate::io::gp345::recovery::read_lenient;
+use crate::io::limits::ReadLimits;
+use crate::model::song::Song;
+use crate::tests::read_file;
+
+/// The limit exceeded by the error, and its maximum.
+fn exceeded(error: &GpError) -> Option<(&'static str, usize)> {
+    match error.cause() {
+        GpError::LimitExceeded { limit, max } => Some((limit, *max)),
+        _ => None,
+    }
+}
+
+/// The error reading data within the limits.
+fn limited(data: &[u8], limits: ReadLimits) -> GpError {
+    Song::from_bytes_with_limits(data, limits).unwrap_err()
+}
+
+#[test]
+fn test_limits_gp5() {
+    let data = read_file(String::from("Demo v5.gp5"));
+    let mut song = Song::default();
+    song.read_gp5(&data).unwrap();
+    let measures = song.measure_headers.len();
+    assert!(measures > 2 && song.tracks.len() > 1);
+
+    // Counts of the file, before its measures are read
+    let error = limited(
+        &data,
+        ReadLimits {
+            max_measures: 2,
+            ..Default::default()
+        },
+    );
+    assert_eq!(exceeded(&error), Some(("measures", 2)));
+    let error = limited(
+        &data,
+        ReadLimits {
+            max_tracks: 1,
+            ..Default::default()
+        },
+    );
+    assert_eq!(exceeded(&error), Some(("tracks", 1)));
+
+    // Counts of the song read
+    for (limits, limit) in [
+        (
+            ReadLimits {
+                max_beats: 10,
+                ..Default::default()
+            },
+            "beats",
+        ),
+        (
+            ReadLimits {
+                max_notes: 10,
+                ..Default::default()
+            },
+            "notes",
+        ),
+        (
+            ReadLimits {
+                max_string_length: 3,
+                ..Default::default()
+            },
+            "characters in a text",
+        ),
+    ] {
+        let error = limited(&data, limits);
+        assert_eq!(exceeded(&error).map(|e| e.0), Some(limit));
+    }
+
+    // A file at the limits is read
+    let limits = ReadLimits {
+        max_measures: measures,
+        ..Default::default()
+    };
+    let (song, _) = Song::open_with_limits("../test/Demo v5.gp5", limits).unwrap();
+    assert_eq!(song.measure_headers.len(), measures);
+}
+
+#[test]
+fn test_limits_lenient_and_detected() {
+    let data = read_file(String::from("Demo v5.gp5"));
+    let limits = ReadLimits {
+        max_notes: 10,
+        ..Default::default()
+    };
+    // Exceeding a limit is not a damage to recover from
+    let error = read_lenient(&mut Song::default(), &data, &limits).unwrap_err();
+    assert_eq!(exceeded(&error), Some(("notes", 10)));
+
+    let error = Song::from_bytes_with_limits(&data, limits).unwrap_err();
+    assert_eq!(exceeded(&error), Some(("notes", 10)));
+    let (_, format) = Song::from_bytes_with_limits(&data, ReadLimits::default()).unwrap();
+    assert_eq!(format, FileFormat::Gp5);
+}
+
+#[test]
+fn test_limits_decompressed_size() {
+    let limits = ReadLimits {
+        max_decompressed_size: 1024,
+        ..Default::default()
+    };
+    let gpx = read_file(String::from("test/bend.gpx"));
+    let error = limited(&gpx, limits);
+    assert_eq!(exceeded(&error), Some(("decompressed bytes", 1024)));
+    let gp = read_file(String::from("test/bend.gp"));
+    let error = limited(&gp, limits);
+    assert_eq!(exceeded(&error), Some(("decompressed bytes", 1024)));
+    assert_eq!(
+        error.to_string(),
+        "Read limit exceeded: more than 1024 decompressed bytes"
+    );
+
+    // The sample files read within the default limits
+    Song::default().read_gpx(&gpx).unwrap();
+    Song::default().read_gp(&gp).unwrap();
+}
+
+#[test]
+fn test_limits_gpif_counts() {
+    let gp = read_file(String::from("test/bend.gp"));
+    let error = limited(
+        &gp,
+        ReadLimits {
+            max_beats: 1,
+            ..Default::default()
+        },
+    );
+    assert_eq!(exceeded(&error), Some(("beats", 1)));
+}
+
+#[test]
+fn test_limits_midi_long_note() {
+    // A note lasting 2^28 ticks, i.e. hundreds of thousands of measures
+    let mut data = b"MThd\x00\x00\x00\x06\x00\x00\x00\x01\x01\xE0MTrk".to_vec();
+    let track = [
+        0x00, 0x90, 60, 100, // note on
+        0xFF, 0xFF, 0xFF, 0x7F, 0x80, 60, 0, // note off
+        0x00, 0xFF, 0x2F, 0x00, // end of track
+    ];
+    data.extend_from_slice(&(track.len() as u32).to_be_bytes());
+    data.extend_from_slice(&track);
+    let error = Song::default().read_midi(&data).unwrap_err();
+    assert_eq!(
+        exceeded(&error),
+        Some(("measures", ReadLimits::default().max_measures))
+    );
+}
+
+#[test]
+fn test_limits_mscx_long_rest() {
+    let data = br#"<museScore version="3.02"><Score>
+        <Part><Staff id="1"/></Part>
+        <Staff id="1"><Measure><voice><Rest>
+            <durationType>measure</durationType><duration>2147483647/4</duration>
+        </Rest></voice></Measure></Staff>
+    </Score></museScore>"#;
+    let error = Song::default().read_mscx(data).unwrap_err();
+    assert_eq!(
+        exceeded(&error),
+        Some(("beats", ReadLimits::default().max_beats))
+    );
+}
+
+#[test]
+fn test_limits_musicxml() {
+    let mut song = Song::default();
+    song.read_gp5(&read_file(String::from("Demo v5.gp5")))
+        .unwrap();
+    let xml = song.to_musicxml();
+    let error = limited(
+        xml.as_bytes(),
+        ReadLimits {
+            max_tracks: 1,
+            ..Default::default()
+        },
+    );
+    assert_eq!(exceeded(&error), Some(("tracks", 1)));
+}
@@ -1,3 +1,8 @@
 // Integration tests
 
 pub mod audit;
+pub mod errors;
+pub mod format;
+pub mod fuzz;
+pub mod limits;
+pub mod recovery;
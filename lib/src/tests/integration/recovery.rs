@@ -0,0 +1,89 @@
+// Lenient reading tests
+use crate::model::song::Song;
+use crate::tests::read_file;
+
+/// Song read leniently from the first `length` bytes of a test file, and the warnings.
+fn read_cut(file: &str, length: usize) -> (Song, Vec<String>) {
+    let data = read_file(String::from(file));
+    let mut song = Song::default();
+    let warnings = song.read_lenient(&data[..length]).unwrap();
+    (song, warnings.iter().map(|w| w.to_string()).collect())
+}
+
+#[test]
+fn test_complete_file() {
+    let data = read_file(String::from("Demo v5.gp5"));
+    let mut song = Song::default();
+    assert!(song.read_lenient(&data).unwrap().is_empty());
+    let mut strict = Song::default();
+    strict.read_gp5(&data).unwrap();
+    assert_eq!(song.measure_headers.len(), strict.measure_headers.len());
+    assert_eq!(song.tracks.len(), strict.tracks.len());
+
+    assert!(Song::default().read_lenient(b"not a tab").is_err());
+}
+
+#[test]
+fn test_cut_in_measures() {
+    let data = read_file(String::from("Demo v5.gp5"));
+    let mut strict = Song::default();
+    assert!(strict.read_gp5(&data[..0x3213]).is_err());
+    let mut complete = Song::default();
+    complete.read_gp5(&data).unwrap();
+
+    let (song, warnings) = read_cut("Demo v5.gp5", 0x3213);
+    assert_eq!(
+        warnings,
+        vec![
+            "track 2 › measure 23 › voice 1 › beat 1 › note 1 at 0x3213: Unexpected end of file at offset 12819, needed 8 more bytes",
+            "measures at 0x3213: measures 24 to 49 not read",
+        ]
+    );
+    // Closed off: a measure per header in each track, two voices per measure
+    assert_eq!(song.measure_headers.len(), 23);
+    assert_eq!(song.tracks.len(), 5);
+    for track in &song.tracks {
+        assert_eq!(track.measures.len(), 23);
+        assert!(track.measures.iter().all(|m| m.voices.len() == 2));
+    }
+    // The measures before the failure are read as in the complete file
+    for (track, expected) in song.tracks.iter().zip(&complete.tracks) {
+        for (measure, other) in track.measures[..22].iter().zip(&expected.measures) {
+            for (voice, other) in measure.voices.iter().zip(&other.voices) {
+                assert_eq!(voice.beats, other.beats);
+            }
+        }
+    }
+    // The beat being read is dropped, the later tracks have an empty measure
+    assert!(song.tracks[1].measures[22].voices[0].beats.is_empty());
+    assert!(song.tracks[4].measures[22].voices[0].beats.is_empty());
+    assert_eq!(
+        song.tracks[0].measures[22].voices[0].beats,
+        complete.tracks[0].measures[22].voices[0].beats
+    );
+    assert!(!song.validate().iter().any(|d| matches!(
+        d.issue,
+        crate::validate::diagnostic::Issue::MeasureCount { .. }
+    )));
+}
+
+#[test]
+fn test_cut_before_measures() {
+    // In the tracks: the measure headers are kept
+    let (song, warnings) = read_cut("Chords.gp3", 0x3B0);
+    assert_eq!(
+        warnings,
+        vec!["track 1 at 0x3B0: Unexpected end of file at offset 944, needed 4 more bytes"]
+    );
+    assert_eq!(song.measure_headers.len(), 8);
+    assert!(song.tracks.is_empty());
+
+    // In the MIDI channels
+    let (song, warnings) = read_cut("Effects.gp4", 0x1F5);
+    assert_eq!(
+        warnings,
+        vec!["MIDI channel 32 at 0x1F5: Unexpected end of file at offset 501, needed 4 more bytes"]
+    );
+    assert_eq!(song.channels.len(), 31);
+    assert_eq!(song.version.number.0, 4);
+}
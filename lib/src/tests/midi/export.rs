@@ -0,0 +1,190 @@
+// Standard MIDI File export tests
+use super::super::{read_file, read_gp7};
+use crate::audio::smf::SMF_DIVISION;
+use crate::model::song::Song;
+
+/// An event of an exported track: absolute tick and raw bytes (status included).
+type Event = (u32, Vec<u8>);
+
+fn read_vlq(data: &[u8], pos: &mut usize) -> u32 {
+    let mut value = 0;
+    loop {
+        let byte = data[*pos];
+        *pos += 1;
+        value = (value << 7) | (byte & 0x7F) as u32;
+        if byte & 0x80 == 0 {
+            return value;
+        }
+    }
+}
+
+/// Split an exported file into its header fields and the events of every track.
+fn parse(data: &[u8]) -> ((u16, u16, u16), Vec<Vec<Event>>) {
+    assert_eq!(&data[0..4], b"MThd");
+    let be16 = |at: usize| u16::from_be_bytes([data[at], data[at + 1]]);
+    let header = (be16(8), be16(10), be16(12));
+    let mut tracks = Vec::new();
+    let mut pos = 14;
+    while pos < data.len() {
+        assert_eq!(&data[pos..pos + 4], b"MTrk");
+        let len = u32::from_be_bytes(data[pos + 4..pos + 8].try_into().unwrap()) as usize;
+        let chunk = &data[pos + 8..pos + 8 + len];
+        let mut events = Vec::new();
+        let mut at = 0;
+        let mut tick = 0;
+        while at < chunk.len() {
+            tick += read_vlq(chunk, &mut at);
+            let start = at;
+            let status = chunk[at];
+            at += 1;
+            match status {
+                0xFF => {
+                    at += 1;
+                    let size = read_vlq(chunk, &mut at) as usize;
+                    at += size;
+                }
+                s if s & 0xF0 == 0xC0 || s & 0xF0 == 0xD0 => at += 1,
+                _ => at += 2,
+            }
+            events.push((tick, chunk[start..at].to_vec()));
+        }
+        assert_eq!(events.last().unwrap().1, vec![0xFF, 0x2F, 0x00]);
+        tracks.push(events);
+        pos += 8 + len;
+    }
+    (header, tracks)
+}
+
+fn note_ons(events: &[Event]) -> Vec<&Event> {
+    events
+        .iter()
+        .filter(|(_, e)| e[0] & 0xF0 == 0x90 && e[2] > 0)
+        .collect()
+}
+
+fn tempos(events: &[Event]) -> Vec<(u32, u32)> {
+    events
+        .iter()
+        .filter(|(_, e)| e[0] == 0xFF && e[1] == 0x51)
+        .map(|(t, e)| (*t, 60_000_000 / u32::from_be_bytes([0, e[3], e[4], e[5]])))
+        .collect()
+}
+
+fn read_gp5(path: &str) -> Song {
+    let mut song = Song::default();
+    song.read_gp5(&read_file(String::from(path))).unwrap();
+    song
+}
+
+#[test]
+fn test_midi_export_header() {
+    let song = read_gp5("test/keysig.gp5");
+    let (header, tracks) = parse(&song.to_midi());
+    assert_eq!(header, (1, song.tracks.len() as u16 + 1, SMF_DIVISION));
+    assert_eq!(tracks.len(), song.tracks.len() + 1);
+}
+
+#[test]
+fn test_midi_export_key_and_time_signatures() {
+    let song = read_gp5("test/keysig.gp5");
+    let (_, tracks) = parse(&song.to_midi());
+    let keys: Vec<(i8, u8)> = tracks[0]
+        .iter()
+        .filter(|(_, e)| e[0] == 0xFF && e[1] == 0x59)
+        .map(|(_, e)| (e[3] as i8, e[4]))
+        .collect();
+    let mut expected: Vec<(i8, u8)> = Vec::new();
+    for mh in &song.measure_headers {
+        let key = (mh.key_signature.key, mh.key_signature.is_minor as u8);
+        if expected.last() != Some(&key) {
+            expected.push(key);
+        }
+    }
+    assert_eq!(keys, expected);
+    let time = tracks[0]
+        .iter()
+        .find(|(_, e)| e[0] == 0xFF && e[1] == 0x58)
+        .unwrap();
+    assert_eq!(time.0, 0);
+    assert_eq!(&time.1[3..5], &[4, 2]);
+}
+
+#[test]
+fn test_midi_export_tempo_from_mix_table() {
+    let song = read_gp5("test/tempo.gp5");
+    let (_, tracks) = parse(&song.to_midi());
+    let tempos = tempos(&tracks[0]);
+    assert_eq!(tempos[0], (0, song.tempo as u32));
+    assert!(tempos.len() > 1, "tempo changes: {:?}", tempos);
+}
+
+#[test]
+fn test_midi_export_tempo_from_measure_headers() {
+    let song = read_gp7("test/tempo.gp");
+    let (_, tracks) = parse(&song.to_midi());
+    let tempos = tempos(&tracks[0]);
+    let changes = song
+        .measure_headers
+        .iter()
+        .filter(|mh| mh.tempo > 0)
+        .count();
+    assert!(
+        tempos.len() >= changes.max(1),
+        "tempo changes: {:?}",
+        tempos
+    );
+}
+
+#[test]
+fn test_midi_export_notes() {
+    let song = read_gp5("test/keysig.gp5");
+    let (_, tracks) = parse(&song.to_midi());
+    let track = &song.tracks[0];
+    let channel = &song.channels[track.channel_index];
+
+    let program = tracks[1].iter().find(|(_, e)| e[0] & 0xF0 == 0xC0).unwrap();
+    assert_eq!(
+        program.1,
+        vec![0xC0 | channel.channel, channel.instrument as u8]
+    );
+
+    let mut expected: Vec<(u8, u8)> = track
+        .measures
+        .iter()
+        .flat_map(|m| &m.voices)
+        .flat_map(|v| &v.beats)
+        .flat_map(|b| &b.notes)
+        .filter(|n| n.kind == crate::types::enums::NoteType::Normal)
+        .map(|n| {
+            let pitch = n.real_value(&track.strings).unwrap() as i32 + track.offset;
+            (pitch as u8, n.velocity as u8)
+        })
+        .collect();
+    let mut actual: Vec<(u8, u8)> = note_ons(&tracks[1])
+        .iter()
+        .map(|(_, e)| (e[1], e[2]))
+        .collect();
+    expected.sort();
+    actual.sort();
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn test_midi_export_percussion_channel() {
+    let song = read_gp5("test/all-percussion.gp5");
+    let (_, tracks) = parse(&song.to_midi());
+    let notes = note_ons(&tracks[1]);
+    assert!(!notes.is_empty());
+    assert!(notes.iter().all(|(_, e)| e[0] == 0x99));
+}
+
+#[test]
+fn test_midi_export_notes_are_closed() {
+    let song = read_gp7("test/keysig.gp");
+    let (_, tracks) = parse(&song.to_midi());
+    for events in &tracks[1..] {
+        let ons = note_ons(events).len();
+        let offs = events.iter().filter(|(_, e)| e[0] & 0xF0 == 0x80).count();
+        assert_eq!(ons, offs);
+    }
+}
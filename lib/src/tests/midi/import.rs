@@ -0,0 +1,172 @@
+// Standard MIDI File import tests
+use super::super::{read_file, read_gp7};
+use crate::model::song::Song;
+use crate::types::enums::{BeatStatus, NoteType};
+
+/// Note-ons of an exported song as sorted (tick, channel, pitch) triples.
+fn played(song: &Song) -> Vec<(u32, u8, u8)> {
+    let data = song.to_midi();
+    let mut notes = Vec::new();
+    let mut pos = 14;
+    while pos < data.len() {
+        let len = u32::from_be_bytes(data[pos + 4..pos + 8].try_into().unwrap()) as usize;
+        let chunk = &data[pos + 8..pos + 8 + len];
+        let (mut at, mut tick) = (0, 0u32);
+        while at < chunk.len() {
+            let mut delta = 0;
+            loop {
+                let byte = chunk[at];
+                at += 1;
+                delta = (delta << 7) | (byte & 0x7F) as u32;
+                if byte & 0x80 == 0 {
+                    break;
+                }
+            }
+            tick += delta;
+            let status = chunk[at];
+            match status {
+                0xFF => at += 3 + chunk[at + 2] as usize,
+                s if s & 0xF0 == 0xC0 => at += 2,
+                s => {
+                    if s & 0xF0 == 0x90 && chunk[at + 2] > 0 {
+                        notes.push((tick, s & 0x0F, chunk[at + 1]));
+                    }
+                    at += 3;
+                }
+            }
+        }
+        pos += 8 + len;
+    }
+    notes.sort();
+    notes
+}
+
+fn read_midi(data: &[u8]) -> Song {
+    let mut song = Song::default();
+    song.read_midi(data).unwrap();
+    song
+}
+
+fn round_trip(name: &str, song: &Song) {
+    let imported = read_midi(&song.to_midi());
+    assert_eq!(
+        song.measure_headers.len(),
+        imported.measure_headers.len(),
+        "{}: measure count",
+        name
+    );
+    for (e, a) in song.measure_headers.iter().zip(&imported.measure_headers) {
+        assert_eq!(
+            e.time_signature.numerator, a.time_signature.numerator,
+            "{}",
+            name
+        );
+        assert_eq!(e.key_signature, a.key_signature, "{}", name);
+    }
+    assert_eq!(song.tempo, imported.tempo, "{}: tempo", name);
+    assert_eq!(played(song), played(&imported), "{}: notes", name);
+}
+
+#[test]
+fn test_midi_import_round_trip() {
+    for name in ["keysig.gp5", "dotted-tuplets.gp5", "tempo.gp5"] {
+        let mut song = Song::default();
+        song.read_gp5(&read_file(format!("test/{}", name))).unwrap();
+        round_trip(name, &song);
+    }
+    for name in ["testIrrTuplet.gp", "tuplet-with-slur.gp"] {
+        round_trip(name, &read_gp7(&format!("test/{}", name)));
+    }
+}
+
+#[test]
+fn test_midi_import_percussion() {
+    let mut song = Song::default();
+    song.read_gp5(&read_file(String::from("test/all-percussion.gp5")))
+        .unwrap();
+    let imported = read_midi(&song.to_midi());
+    assert!(imported.tracks[0].percussion_track);
+    assert_eq!(played(&song), played(&imported));
+}
+
+/// A format 0 file at 96 ticks per quarter: one triplet of eighth notes then a
+/// D major chord, written with running status, and a snare hit on channel 10.
+fn sample_file() -> Vec<u8> {
+    let mut track: Vec<u8> = vec![
+        0x00, 0xFF, 0x51, 0x03, 0x07, 0xA1, 0x20, // 120 BPM
+        0x00, 0xFF, 0x58, 0x04, 0x03, 0x02, 0x18, 0x08, // 3/4
+        0x00, 0xC0, 0x19, // steel string guitar
+        0x00, 0x99, 0x26, 0x64, // snare on
+        0x00, 0x90, 0x40, 0x50, // E4
+    ];
+    track.extend_from_slice(&[
+        0x20, 0x40, 0x00, // running status: E4 off after a triplet eighth (32 ticks)
+        0x00, 0x42, 0x50, 0x20, 0x42, 0x00, // F#4
+        0x00, 0x43, 0x50, 0x1E, 0x43, 0x00, // G4, released 2 ticks early
+        0x02, 0x89, 0x26, 0x00, // snare off
+        0x00, 0x90, 0x3E, 0x60, 0x00, 0x42, 0x60, 0x00, 0x45, 0x60, // D major chord
+        0x81, 0x40, 0x80, 0x3E, 0x00, 0x00, 0x42, 0x00, 0x00, 0x45, 0x00, // half note later
+        0x00, 0xFF, 0x2F, 0x00,
+    ]);
+    let mut data = b"MThd".to_vec();
+    data.extend_from_slice(&[0, 0, 0, 6, 0, 0, 0, 1, 0, 96]);
+    data.extend_from_slice(b"MTrk");
+    data.extend_from_slice(&(track.len() as u32).to_be_bytes());
+    data.extend(track);
+    data
+}
+
+#[test]
+fn test_midi_import_quantization() {
+    let song = read_midi(&sample_file());
+    assert_eq!(song.tempo, 120);
+    assert_eq!(song.measure_headers.len(), 1);
+    assert_eq!(song.measure_headers[0].time_signature.numerator, 3);
+    assert_eq!(song.tracks.len(), 2);
+
+    let guitar = &song.tracks[0];
+    assert!(!guitar.percussion_track);
+    assert_eq!(song.channels[guitar.channel_index].instrument, 0x19);
+    let beats = &guitar.measures[0].voices[0].beats;
+    assert_eq!(beats.len(), 4);
+    for beat in &beats[0..3] {
+        assert_eq!(beat.duration.value, 8);
+        assert_eq!(
+            (beat.duration.tuplet_enters, beat.duration.tuplet_times),
+            (3, 2)
+        );
+        assert_eq!(beat.notes.len(), 1);
+    }
+    assert_eq!(beats[3].duration.value, 2);
+    assert_eq!(beats[3].notes.len(), 3);
+
+    // Every note sits on its own string and plays the right pitch
+    let pitches: Vec<i8> = beats
+        .iter()
+        .flat_map(|b| &b.notes)
+        .map(|n| n.real_value(&guitar.strings).unwrap())
+        .collect();
+    assert_eq!(pitches.len(), 6);
+    assert_eq!(&pitches[0..3], &[0x40, 0x42, 0x43]);
+    let mut chord = pitches[3..].to_vec();
+    chord.sort();
+    assert_eq!(chord, vec![0x3E, 0x42, 0x45]);
+    let mut strings: Vec<i8> = beats[3].notes.iter().map(|n| n.string).collect();
+    strings.dedup();
+    assert_eq!(strings.len(), 3);
+
+    let drums = &song.tracks[1];
+    assert!(drums.percussion_track);
+    let beats = &drums.measures[0].voices[0].beats;
+    assert_eq!(beats[0].notes[0].value, 0x26);
+    assert_eq!(beats[0].notes[0].kind, NoteType::Normal);
+    assert!(beats[1..].iter().all(|b| b.status == BeatStatus::Rest));
+}
+
+#[test]
+fn test_midi_import_errors() {
+    let mut song = Song::default();
+    assert!(song.read_midi(b"RIFF0000").is_err());
+    let data = sample_file();
+    assert!(song.read_midi(&data[..data.len() - 10]).is_err());
+}
@@ -0,0 +1,4 @@
+// MIDI tests
+
+pub mod export;
+pub mod import;
@@ -1,15 +1,30 @@
 // Tests module - Organized by Guitar Pro version
+use crate::audio::midi::MidiChannel;
+use crate::model::beat::{Beat, Voice};
+use crate::model::measure::Measure;
+use crate::model::note::Note;
 use crate::model::song::Song;
+use crate::model::track::Track;
+use crate::types::enums::NoteType;
+use crate::types::measure::MeasureHeader;
 use fraction::ToPrimitive;
 use std::{fs, io::Read};
 
 // Test submodules
+pub mod ascii_tab;
+pub mod edit;
 pub mod gp3;
 pub mod gp4;
 pub mod gp5;
 pub mod gpx;
 pub mod gp7;
 pub mod integration;
+pub mod midi;
+pub mod mscz;
+pub mod musicxml;
+pub mod playback;
+pub mod theory;
+pub mod validate;
 
 /// Helper function to read test files
 pub fn read_file(path: String) -> Vec<u8> {
@@ -47,3 +62,35 @@ pub fn read_gp7(filename: &str) -> crate::model::song::Song {
     song.read_gp(&read_file(String::from(filename))).unwrap();
     song
 }
+
+/// Song of one standard tuned guitar track on a MIDI channel: a 4/4 measure per item of
+/// `measures`, made of a beat per list of (string, fret) notes.
+pub fn song_of(measures: &[Vec<Vec<(i8, i16)>>]) -> Song {
+    let mut song = Song::default();
+    song.channels.push(MidiChannel::default());
+    let mut track = Track::default();
+    for (index, beats) in measures.iter().enumerate() {
+        song.measure_headers.push(MeasureHeader {
+            number: index as u16 + 1,
+            ..Default::default()
+        });
+        let mut voice = Voice::default();
+        for notes in beats {
+            let mut beat = Beat::default();
+            for &(string, value) in notes {
+                beat.notes.push(Note {
+                    string,
+                    value,
+                    kind: NoteType::Normal,
+                    ..Default::default()
+                });
+            }
+            voice.beats.push(beat);
+        }
+        let mut measure = Measure::default();
+        measure.voices.push(voice);
+        track.measures.push(measure);
+    }
+    song.tracks.push(track);
+    song
+}
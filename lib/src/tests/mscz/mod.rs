@@ -0,0 +1,3 @@
+// MuseScore tests
+
+pub mod reading;
@@ -0,0 +1,282 @@
+// MuseScore reading tests
+use std::io::{Cursor, Write};
+
+use zip::write::FileOptions;
+use zip::ZipWriter;
+
+use crate::model::song::Song;
+use crate::types::enums::{BeatStatus, DirectionSign, NoteType};
+
+/// A MuseScore 3 score: a drop D guitar with a notation and a TAB staff, and a bass whose
+/// notes have pitches only.
+pub(crate) const SCORE: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<museScore version="3.02">
+  <Score>
+    <Division>480</Division>
+    <metaTag name="composer">Someone</metaTag>
+    <metaTag name="workTitle">Drop Study</metaTag>
+    <Part>
+      <Staff id="1">
+        <StaffType group="pitched"><name>stdNormal</name></StaffType>
+      </Staff>
+      <Staff id="2">
+        <StaffType group="tablature"><name>tab6StrCommon</name></StaffType>
+      </Staff>
+      <trackName>Guitar</trackName>
+      <Instrument>
+        <longName>Guitar</longName>
+        <shortName>Gtr.</shortName>
+        <trackName>Guitar</trackName>
+        <StringData>
+          <frets>22</frets>
+          <string>38</string>
+          <string>45</string>
+          <string>50</string>
+          <string>55</string>
+          <string>59</string>
+          <string>64</string>
+        </StringData>
+        <Channel>
+          <program value="27"/>
+          <controller ctrl="7" value="90"/>
+          <midiPort>0</midiPort>
+          <midiChannel>1</midiChannel>
+        </Channel>
+      </Instrument>
+    </Part>
+    <Part>
+      <Staff id="3">
+        <StaffType group="pitched"><name>stdNormal</name></StaffType>
+      </Staff>
+      <trackName>Bass</trackName>
+      <Instrument>
+        <StringData>
+          <frets>20</frets>
+          <string>28</string>
+          <string>33</string>
+          <string>38</string>
+          <string>43</string>
+        </StringData>
+        <Channel><program value="33"/></Channel>
+      </Instrument>
+    </Part>
+    <Staff id="1">
+      <VBox><Text><style>Title</style><text>Drop Study</text></Text></VBox>
+      <Measure>
+        <voice>
+          <TimeSig><sigN>3</sigN><sigD>4</sigD></TimeSig>
+          <Rest><durationType>measure</durationType><duration>3/4</duration></Rest>
+        </voice>
+      </Measure>
+    </Staff>
+    <Staff id="2">
+      <Measure>
+        <startRepeat/>
+        <voice>
+          <KeySig><accidental>2</accidental></KeySig>
+          <TimeSig><sigN>3</sigN><sigD>4</sigD></TimeSig>
+          <Tempo><tempo>1.5</tempo><text>♩ = 90</text></Tempo>
+          <RehearsalMark><text>Intro</text></RehearsalMark>
+          <Marker><subtype>segno</subtype><label>segno</label></Marker>
+          <StaffText><text>P.M.</text></StaffText>
+          <Chord>
+            <durationType>quarter</durationType>
+            <Note><pitch>38</pitch><fret>0</fret><string>5</string></Note>
+            <Note><pitch>45</pitch><fret>0</fret><string>4</string></Note>
+          </Chord>
+          <Tuplet><normalNotes>2</normalNotes><actualNotes>3</actualNotes><baseNote>eighth</baseNote></Tuplet>
+          <Chord><durationType>eighth</durationType><Note><pitch>50</pitch><fret>0</fret><string>3</string></Note></Chord>
+          <Chord><durationType>eighth</durationType><Note><pitch>52</pitch><fret>2</fret><string>3</string></Note></Chord>
+          <Chord><durationType>eighth</durationType><Note><pitch>54</pitch><fret>4</fret><string>3</string></Note></Chord>
+          <endTuplet/>
+          <Chord>
+            <durationType>quarter</durationType>
+            <Lyrics><text>la</text></Lyrics>
+            <Note>
+              <Spanner type="Tie"><Tie/><next><location><fractions>1/4</fractions></location></next></Spanner>
+              <pitch>57</pitch><fret>2</fret><string>2</string>
+            </Note>
+          </Chord>
+        </voice>
+      </Measure>
+      <Measure>
+        <endRepeat>2</endRepeat>
+        <voice>
+          <Spanner type="Volta">
+            <Volta><endings>1</endings></Volta>
+            <next><location><measures>1</measures></location></next>
+          </Spanner>
+          <Chord>
+            <durationType>half</durationType>
+            <dots>1</dots>
+            <Note>
+              <Spanner type="Tie"><prev><location><fractions>-1/4</fractions></location></prev></Spanner>
+              <pitch>57</pitch><fret>2</fret><string>2</string>
+            </Note>
+          </Chord>
+          <Spanner type="Volta"><prev><location><measures>-1</measures></location></prev></Spanner>
+        </voice>
+      </Measure>
+      <Measure>
+        <voice>
+          <Rest><durationType>measure</durationType><duration>3/4</duration></Rest>
+        </voice>
+        <voice>
+          <location><fractions>1/4</fractions></location>
+          <Chord><durationType>quarter</durationType><Note><pitch>64</pitch><fret>0</fret><string>0</string></Note></Chord>
+        </voice>
+        <Jump>
+          <text>D.S. al Coda</text>
+          <jumpTo>segno</jumpTo>
+          <playUntil>coda</playUntil>
+          <continueAt>codab</continueAt>
+        </Jump>
+      </Measure>
+    </Staff>
+    <Staff id="3">
+      <Measure>
+        <voice>
+          <Chord><durationType>quarter</durationType><Note><pitch>33</pitch></Note></Chord>
+          <Chord><durationType>half</durationType><Note><pitch>40</pitch></Note></Chord>
+        </voice>
+      </Measure>
+      <Measure>
+        <voice><Rest><durationType>measure</durationType><duration>3/4</duration></Rest></voice>
+      </Measure>
+      <Measure>
+        <voice><Rest><durationType>measure</durationType><duration>3/4</duration></Rest></voice>
+      </Measure>
+    </Staff>
+  </Score>
+</museScore>"#;
+
+fn read_score() -> Song {
+    let mut song = Song::default();
+    song.read_mscx(SCORE.as_bytes()).unwrap();
+    song
+}
+
+#[test]
+fn test_mscx_parts() {
+    let song = read_score();
+    assert_eq!(song.name, "Drop Study");
+    assert_eq!(song.author, "Someone");
+    assert_eq!(song.tracks.len(), 2);
+
+    let guitar = &song.tracks[0];
+    assert_eq!(guitar.name, "Guitar");
+    assert_eq!(guitar.short_name, "Gtr.");
+    assert_eq!(guitar.fret_count, 22);
+    assert_eq!(
+        guitar.strings,
+        vec![(1, 64), (2, 59), (3, 55), (4, 50), (5, 45), (6, 38)]
+    );
+    assert_eq!(guitar.channel_index, 1);
+    assert_eq!(song.channels[1].instrument, 27);
+    assert_eq!(song.channels[1].volume, 90);
+
+    let bass = &song.tracks[1];
+    assert_eq!(bass.strings, vec![(1, 43), (2, 38), (3, 33), (4, 28)]);
+    assert_eq!(song.channels[bass.channel_index].instrument, 33);
+    // Pitches without string data are placed on the strings
+    let notes: Vec<(i8, i16)> = bass.measures[0].voices[0]
+        .beats
+        .iter()
+        .map(|b| (b.notes[0].string, b.notes[0].value))
+        .collect();
+    assert_eq!(notes, vec![(3, 0), (2, 2)]);
+}
+
+#[test]
+fn test_mscx_headers() {
+    let song = read_score();
+    assert_eq!(song.measure_headers.len(), 3);
+    assert_eq!(song.tempo, 90);
+    let first = &song.measure_headers[0];
+    assert_eq!(first.time_signature.numerator, 3);
+    assert_eq!(first.time_signature.denominator.value, 4);
+    assert_eq!(first.key_signature.key, 2);
+    assert!(first.repeat_open);
+    assert_eq!(first.marker.as_ref().unwrap().title, "Intro");
+    assert_eq!(first.direction, Some(DirectionSign::Segno));
+    assert_eq!(first.repeat_alternative, 0);
+
+    let second = &song.measure_headers[1];
+    assert_eq!(second.start, 960 + 2880);
+    assert_eq!(second.repeat_close, 1);
+    assert_eq!(second.repeat_alternative, 1);
+    assert_eq!(second.time_signature.numerator, 3);
+
+    let third = &song.measure_headers[2];
+    assert_eq!(third.repeat_alternative, 0);
+    assert_eq!(third.direction, Some(DirectionSign::DaSegnoAlCoda));
+}
+
+#[test]
+fn test_mscx_tab_staff() {
+    let song = read_score();
+    let measures = &song.tracks[0].measures;
+    let beats = &measures[0].voices[0].beats;
+    assert_eq!(beats.len(), 5);
+    let positions: Vec<Vec<(i8, i16)>> = beats
+        .iter()
+        .map(|b| b.notes.iter().map(|n| (n.string, n.value)).collect())
+        .collect();
+    assert_eq!(
+        positions,
+        vec![
+            vec![(5, 0), (6, 0)],
+            vec![(4, 0)],
+            vec![(4, 2)],
+            vec![(4, 4)],
+            vec![(3, 2)]
+        ]
+    );
+    assert!(beats[0].notes.iter().all(|n| n.effect.palm_mute));
+    assert!(!beats[1].notes[0].effect.palm_mute);
+    assert_eq!(beats[1].duration.tuplet_enters, 3);
+    assert_eq!(beats[1].duration.tuplet_times, 2);
+    let starts: Vec<i64> = beats.iter().map(|b| b.start.unwrap()).collect();
+    assert_eq!(starts, vec![960, 1920, 2240, 2560, 2880]);
+
+    let tied = &measures[1].voices[0].beats[0];
+    assert_eq!(tied.notes[0].kind, NoteType::Tie);
+    assert!(tied.duration.dotted);
+
+    let rests = &measures[2].voices[0].beats;
+    assert!(rests.iter().all(|b| b.status == BeatStatus::Rest));
+    let second = &measures[2].voices[1].beats;
+    assert_eq!(second[0].status, BeatStatus::Rest);
+    assert_eq!(second[1].start, Some(960 + 2 * 2880 + 960));
+    assert_eq!(
+        (second[1].notes[0].string, second[1].notes[0].value),
+        (1, 0)
+    );
+
+    assert_eq!(song.lyrics.track_choice, 1);
+    assert_eq!(song.lyrics.lines[0], (0, 1, String::from("la")));
+}
+
+#[test]
+fn test_mscz_archive() {
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = FileOptions::default();
+    zip.start_file("META-INF/container.xml", options).unwrap();
+    zip.write_all(
+        br#"<?xml version="1.0" encoding="UTF-8"?>
+<container><rootfiles><rootfile full-path="Drop Study.mscx"/></rootfiles></container>"#,
+    )
+    .unwrap();
+    zip.start_file("Drop Study.mscx", options).unwrap();
+    zip.write_all(SCORE.as_bytes()).unwrap();
+    let data = zip.finish().unwrap().into_inner();
+
+    let mut song = Song::default();
+    song.read_mscz(&data).unwrap();
+    assert_eq!(song.tracks.len(), 2);
+    assert_eq!(song.measure_headers.len(), 3);
+
+    let mut song = Song::default();
+    assert!(song.read_mscz(SCORE.as_bytes()).is_err());
+    assert!(song.read_mscx(b"<score-partwise/>").is_err());
+}
@@ -0,0 +1,432 @@
+// MusicXML export tests
+use quick_xml::events::Event;
+use quick_xml::Reader;
+
+use super::super::{read_file, read_gp7, read_gpx};
+use crate::model::song::Song;
+
+/// An element of a parsed document.
+#[derive(Debug, Default)]
+pub struct Element {
+    pub name: String,
+    pub attributes: Vec<(String, String)>,
+    pub text: String,
+    pub children: Vec<Element>,
+}
+
+impl Element {
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn child(&self, name: &str) -> Option<&Element> {
+        self.children.iter().find(|c| c.name == name)
+    }
+
+    pub fn child_text(&self, name: &str) -> Option<&str> {
+        self.child(name).map(|c| c.text.as_str())
+    }
+
+    /// Every element named `name` below this one, in document order.
+    pub fn descendants<'a>(&'a self, name: &str, found: &mut Vec<&'a Element>) {
+        for child in &self.children {
+            if child.name == name {
+                found.push(child);
+            }
+            child.descendants(name, found);
+        }
+    }
+
+    pub fn all(&self, name: &str) -> Vec<&Element> {
+        let mut found = Vec::new();
+        self.descendants(name, &mut found);
+        found
+    }
+}
+
+fn element(e: &quick_xml::events::BytesStart) -> Element {
+    Element {
+        name: String::from_utf8(e.name().as_ref().to_vec()).unwrap(),
+        attributes: e
+            .attributes()
+            .map(|a| {
+                let a = a.unwrap();
+                (
+                    String::from_utf8(a.key.as_ref().to_vec()).unwrap(),
+                    a.unescape_value().unwrap().into_owned(),
+                )
+            })
+            .collect(),
+        ..Default::default()
+    }
+}
+
+/// Parse a document into a tree, panicking when it is not well-formed.
+pub fn parse(xml: &str) -> Element {
+    let mut reader = Reader::from_str(xml);
+    reader.trim_text(true);
+    let mut stack = vec![Element::default()];
+    loop {
+        match reader.read_event().unwrap() {
+            Event::Start(e) => stack.push(element(&e)),
+            Event::Empty(e) => stack.last_mut().unwrap().children.push(element(&e)),
+            Event::Text(t) => stack
+                .last_mut()
+                .unwrap()
+                .text
+                .push_str(&t.unescape().unwrap()),
+            Event::End(_) => {
+                let done = stack.pop().unwrap();
+                stack.last_mut().unwrap().children.push(done);
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    assert_eq!(stack.len(), 1, "unclosed elements");
+    stack.pop().unwrap().children.pop().unwrap()
+}
+
+pub fn read_song(name: &str) -> Song {
+    if name.ends_with(".gpx") {
+        return read_gpx(&format!("test/{}", name));
+    }
+    if name.ends_with(".gp") {
+        return read_gp7(&format!("test/{}", name));
+    }
+    let data = read_file(format!("test/{}", name));
+    let mut song = Song::default();
+    match &name[name.len() - 3..] {
+        "gp3" => song.read_gp3(&data),
+        "gp4" => song.read_gp4(&data),
+        _ => song.read_gp5(&data),
+    }
+    .unwrap();
+    song
+}
+
+fn export(name: &str) -> (Song, Element) {
+    let song = read_song(name);
+    let score = parse(&song.to_musicxml());
+    (song, score)
+}
+
+fn notes_on_staff<'a>(score: &'a Element, staff: &str) -> Vec<&'a Element> {
+    score
+        .all("note")
+        .into_iter()
+        .filter(|n| n.child_text("staff") == Some(staff))
+        .collect()
+}
+
+#[test]
+fn test_musicxml_export_structure() {
+    for name in [
+        "test.gp5",
+        "Chords.gp3",
+        "Effects.gp4",
+        "all-percussion.gp5",
+        "multivoices.gp",
+        "tuplets.gpx",
+        "testIrrTuplet.gp",
+        "dotted-tuplets.gp5",
+        "grace.gpx",
+    ] {
+        let (song, score) = export(name);
+        assert_eq!(score.name, "score-partwise");
+        assert_eq!(score.attribute("version"), Some("4.0"));
+        assert_eq!(score.all("score-part").len(), song.tracks.len(), "{}", name);
+        let divisions: u64 = score.all("divisions")[0].text.parse().unwrap();
+
+        for part in score.all("part") {
+            let measures = part.all("measure");
+            assert_eq!(measures.len(), song.measure_headers.len(), "{}", name);
+            // Walk the cursor of every measure: it never moves before the start nor past the end
+            for (measure, header) in measures.iter().zip(&song.measure_headers) {
+                let length = header.length() as u64 * divisions / 960;
+                let mut position: i64 = 0;
+                for child in &measure.children {
+                    let duration = child
+                        .child_text("duration")
+                        .map_or(0, |d| d.parse::<i64>().unwrap());
+                    match child.name.as_str() {
+                        "note" if child.child("chord").is_none() => position += duration,
+                        "backup" => position -= duration,
+                        "forward" => position += duration,
+                        _ => {}
+                    }
+                    assert!(position >= 0, "{} measure {}", name, header.number);
+                    assert!(
+                        position as u64 <= length,
+                        "{} measure {}",
+                        name,
+                        header.number
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_musicxml_export_tab_staff() {
+    let (song, score) = export("Chords.gp5");
+    let part = score.child("part").unwrap();
+    let attributes = part.all("attributes")[0];
+    assert_eq!(attributes.child_text("staves"), Some("2"));
+    let clefs = attributes.all("clef");
+    assert_eq!(clefs[1].child_text("sign"), Some("TAB"));
+    let details = attributes.child("staff-details").unwrap();
+    let strings = &song.tracks[0].strings;
+    assert_eq!(
+        details.child_text("staff-lines"),
+        Some(strings.len().to_string().as_str())
+    );
+    assert_eq!(details.all("staff-tuning").len(), strings.len());
+
+    // The TAB staff carries the string and fret of every note, in song order
+    let mut expected = Vec::new();
+    for measure in &song.tracks[0].measures {
+        for voice in &measure.voices {
+            for beat in &voice.beats {
+                for note in beat
+                    .notes
+                    .iter()
+                    .filter(|n| n.kind != crate::types::enums::NoteType::Rest)
+                {
+                    expected.push((note.string.to_string(), note.value.to_string()));
+                }
+            }
+        }
+    }
+    let actual: Vec<(String, String)> = notes_on_staff(&score, "2")
+        .iter()
+        .filter(|n| n.child("rest").is_none())
+        .map(|n| {
+            let technical = n.all("technical")[0];
+            (
+                technical.child_text("string").unwrap().to_string(),
+                technical.child_text("fret").unwrap().to_string(),
+            )
+        })
+        .collect();
+    assert!(!expected.is_empty());
+    assert_eq!(actual, expected);
+
+    // The notation staff has pitches only
+    assert!(notes_on_staff(&score, "1")
+        .iter()
+        .all(|n| n.all("fret").is_empty()));
+}
+
+#[test]
+fn test_musicxml_export_effects() {
+    let count = |name: &str, element: &str| export(name).1.all(element).len();
+    assert!(count("slur.gp4", "pull-off") > 0);
+    assert!(count("grace.gpx", "hammer-on") > 0);
+    assert!(count("legato-slide.gp5", "slide") > 0);
+    assert!(count("slide-out-up.gp5", "doit") > 0);
+    assert!(count("slide-in-below.gp5", "scoop") > 0);
+    assert!(count("Harmonics.gp5", "harmonic") > 0);
+    assert!(count("grace.gp5", "grace") > 0);
+
+    let (_, score) = export("bend.gp5");
+    let bends = score.all("bend");
+    assert!(!bends.is_empty());
+    assert!(bends
+        .iter()
+        .all(|b| b.child_text("bend-alter").unwrap().parse::<f64>().unwrap() > 0.0));
+    assert!(bends.iter().any(|b| b.child("release").is_some()));
+
+    let words = |name: &str| -> Vec<String> {
+        export(name)
+            .1
+            .all("words")
+            .iter()
+            .map(|w| w.text.clone())
+            .collect()
+    };
+    assert!(words("palm-mute.gp5").contains(&String::from("P.M.")));
+    assert!(words("let-ring.gp5").contains(&String::from("let ring")));
+}
+
+#[test]
+fn test_musicxml_export_grace() {
+    let (_, score) = export("grace.gp5");
+    let graces: Vec<(Option<&str>, Option<&str>)> = notes_on_staff(&score, "1")
+        .iter()
+        .filter_map(|n| n.child("grace"))
+        .map(|g| (g.attribute("slash"), g.attribute("steal-time-following")))
+        .collect();
+    // Before the beat slashed, on the beat taking a 32nd then a 16th of a quarter note
+    assert_eq!(
+        graces,
+        vec![
+            (Some("yes"), None),
+            (None, Some("12.5")),
+            (Some("yes"), None),
+            (None, Some("25")),
+            (Some("yes"), None),
+        ]
+    );
+
+    let (_, score) = export("grace-on-beat.gp");
+    let graces = score.all("grace");
+    assert!(!graces.is_empty());
+    assert!(graces
+        .iter()
+        .all(|g| g.attribute("slash").is_none() && g.attribute("steal-time-following").is_some()));
+}
+
+#[test]
+fn test_musicxml_export_repeats() {
+    let (song, score) = export("Repeat.gp5");
+    let part = score.child("part").unwrap();
+    let repeats = part.all("repeat");
+    let forward = repeats
+        .iter()
+        .filter(|r| r.attribute("direction") == Some("forward"))
+        .count();
+    let backward: Vec<&&Element> = repeats
+        .iter()
+        .filter(|r| r.attribute("direction") == Some("backward"))
+        .collect();
+    let opens = song
+        .measure_headers
+        .iter()
+        .filter(|h| h.repeat_open)
+        .count();
+    let closes: Vec<i8> = song
+        .measure_headers
+        .iter()
+        .filter(|h| h.repeat_close >= 0)
+        .map(|h| h.repeat_close)
+        .collect();
+    assert!(opens > 0);
+    assert_eq!(forward, opens);
+    assert_eq!(backward.len(), closes.len());
+    for (repeat, close) in backward.iter().zip(&closes) {
+        let times = (*close as i32 + 1).max(2).to_string();
+        assert_eq!(repeat.attribute("times"), Some(times.as_str()));
+    }
+
+    let (song, score) = export("volta.gp5");
+    let part = score.child("part").unwrap();
+    let starts: Vec<&str> = part
+        .all("ending")
+        .into_iter()
+        .filter(|e| e.attribute("type") == Some("start"))
+        .map(|e| e.attribute("number").unwrap())
+        .collect();
+    assert!(!starts.is_empty());
+    let first = song
+        .measure_headers
+        .iter()
+        .find(|h| h.repeat_alternative != 0)
+        .unwrap();
+    let numbers: Vec<String> = (0..8)
+        .filter(|bit| first.repeat_alternative & (1 << bit) != 0)
+        .map(|bit| (bit + 1).to_string())
+        .collect();
+    assert_eq!(starts[0], numbers.join(", "));
+}
+
+#[test]
+fn test_musicxml_export_directions() {
+    let (song, score) = export("Directions.gp5");
+    let part = score.child("part").unwrap();
+    let signs = song
+        .measure_headers
+        .iter()
+        .filter(|h| h.direction.is_some())
+        .count();
+    let sounds = part
+        .all("sound")
+        .into_iter()
+        .filter(|s| s.attribute("tempo").is_none())
+        .count();
+    assert!(signs > 0);
+    assert_eq!(sounds, signs);
+    assert!(!part.all("segno").is_empty());
+    assert!(!part.all("coda").is_empty());
+}
+
+#[test]
+fn test_musicxml_export_lyrics() {
+    let (_, score) = export("test.gp5");
+    let line = |number: &str| -> Vec<String> {
+        score
+            .all("lyric")
+            .into_iter()
+            .filter(|l| l.attribute("number") == Some(number))
+            .map(|l| l.child_text("text").unwrap().to_string())
+            .collect()
+    };
+    assert_eq!(line("1"), vec!["bla", "bla", "1"]);
+    assert_eq!(line("2"), vec!["bla", "bla", "bla", "2"]);
+    // Lyrics are written on the notation staff only
+    assert!(notes_on_staff(&score, "2")
+        .iter()
+        .all(|n| n.child("lyric").is_none()));
+
+    let (song, score) = export("Demo v5.gp5");
+    let part = &score.all("part")[song.lyrics.track_choice as usize - 1];
+    let syllables: Vec<(String, String)> = part
+        .all("lyric")
+        .iter()
+        .map(|l| {
+            (
+                l.child_text("syllabic").unwrap().to_string(),
+                l.child_text("text").unwrap().to_string(),
+            )
+        })
+        .collect();
+    let example = syllables.iter().position(|(_, t)| t == "e").unwrap();
+    assert_eq!(
+        syllables[example..example + 3],
+        [
+            (String::from("begin"), String::from("e")),
+            (String::from("middle"), String::from("xam")),
+            (String::from("end"), String::from("ple")),
+        ]
+    );
+    assert!(syllables.iter().any(|(_, t)| t == "of lyrics"));
+}
+
+#[test]
+fn test_musicxml_export_percussion() {
+    let (_, score) = export("all-percussion.gp5");
+    let part = score.child("part").unwrap();
+    let attributes = part.all("attributes")[0];
+    assert!(attributes.child("staves").is_none());
+    assert_eq!(
+        attributes.all("clef")[0].child_text("sign"),
+        Some("percussion")
+    );
+    assert!(!part.all("unpitched").is_empty());
+    assert!(part.all("pitch").is_empty());
+    assert!(part.all("fret").is_empty());
+
+    // Each note names the instrument of its MIDI key
+    let score_part = score
+        .child("part-list")
+        .unwrap()
+        .child("score-part")
+        .unwrap();
+    let instruments = score_part.all("midi-instrument");
+    let closed_hi_hat = instruments
+        .iter()
+        .find(|i| i.child_text("midi-unpitched") == Some("43"))
+        .unwrap();
+    assert_eq!(closed_hi_hat.attribute("id"), Some("P1-I43"));
+    assert_eq!(score_part.all("score-instrument").len(), instruments.len());
+    let notes = part.all("note");
+    let pitched = notes.iter().filter(|n| n.child("unpitched").is_some());
+    for note in pitched {
+        let id = note.child("instrument").and_then(|i| i.attribute("id"));
+        assert!(instruments.iter().any(|i| i.attribute("id") == id));
+    }
+}
@@ -0,0 +1,293 @@
+// MusicXML import tests
+use std::io::{Cursor, Write};
+
+use zip::write::FileOptions;
+use zip::ZipWriter;
+
+use super::export::read_song;
+use crate::model::song::Song;
+use crate::types::enums::{BeatStatus, NoteType};
+
+fn round_trip(name: &str) -> (Song, Song) {
+    let song = read_song(name);
+    let mut imported = Song::default();
+    imported
+        .read_musicxml(song.to_musicxml().as_bytes())
+        .unwrap();
+    (song, imported)
+}
+
+/// (string, fret, tied) of the notes of every measure of a track.
+fn positions(song: &Song, track: usize) -> Vec<Vec<(i8, i16, bool)>> {
+    song.tracks[track]
+        .measures
+        .iter()
+        .map(|measure| {
+            let mut notes: Vec<(i8, i16, bool)> = measure
+                .voices
+                .iter()
+                .flat_map(|v| &v.beats)
+                .filter(|b| b.status == BeatStatus::Normal)
+                .flat_map(|b| &b.notes)
+                .filter(|n| n.kind != NoteType::Rest)
+                .map(|n| (n.string, n.value, n.kind == NoteType::Tie))
+                .collect();
+            notes.sort();
+            notes
+        })
+        .collect()
+}
+
+#[test]
+fn test_musicxml_import_round_trip() {
+    for name in ["Chords.gp5", "test.gp5", "multivoices.gp", "Effects.gp4"] {
+        let (song, imported) = round_trip(name);
+        assert_eq!(imported.name, song.name, "{}", name);
+        assert_eq!(imported.tracks.len(), song.tracks.len(), "{}", name);
+        assert_eq!(
+            imported.measure_headers.len(),
+            song.measure_headers.len(),
+            "{}",
+            name
+        );
+        for (track, original) in imported.tracks.iter().zip(&song.tracks) {
+            assert_eq!(track.name, original.name, "{}", name);
+            assert_eq!(track.strings, original.strings, "{}", name);
+            assert_eq!(track.offset, original.offset, "{}", name);
+        }
+        for t in 0..song.tracks.len() {
+            assert_eq!(positions(&imported, t), positions(&song, t), "{}", name);
+        }
+        for (header, original) in imported.measure_headers.iter().zip(&song.measure_headers) {
+            assert_eq!(
+                header.time_signature.numerator, original.time_signature.numerator,
+                "{}",
+                name
+            );
+        }
+    }
+}
+
+#[test]
+fn test_musicxml_import_headers() {
+    for name in ["Repeat.gp5", "volta.gp5", "Directions.gp5"] {
+        let (song, imported) = round_trip(name);
+        for (header, original) in imported.measure_headers.iter().zip(&song.measure_headers) {
+            let measure = format!("{} measure {}", name, original.number);
+            assert_eq!(header.repeat_open, original.repeat_open, "{}", measure);
+            assert_eq!(
+                header.repeat_close,
+                original
+                    .repeat_close
+                    .max(if original.repeat_close >= 0 { 1 } else { -1 }),
+                "{}",
+                measure
+            );
+            assert_eq!(
+                header.repeat_alternative, original.repeat_alternative,
+                "{}",
+                measure
+            );
+            assert_eq!(header.direction, original.direction, "{}", measure);
+            assert_eq!(
+                header.marker.as_ref().map(|m| &m.title),
+                original.marker.as_ref().map(|m| &m.title),
+                "{}",
+                measure
+            );
+        }
+    }
+}
+
+#[test]
+fn test_musicxml_import_effects() {
+    let effect = |name: &str, has: fn(&crate::model::note::Note) -> bool| {
+        round_trip(name).1.tracks[0]
+            .measures
+            .iter()
+            .flat_map(|m| &m.voices)
+            .flat_map(|v| &v.beats)
+            .flat_map(|b| &b.notes)
+            .any(has)
+    };
+    assert!(effect("bend.gp5", |n| n.effect.bend.is_some()));
+    assert!(effect("Harmonics.gp5", |n| n.effect.harmonic.is_some()));
+    assert!(effect("slur.gp4", |n| n.effect.hammer));
+    assert!(effect("grace.gp5", |n| n.effect.grace.is_some()));
+    assert!(effect("palm-mute.gp5", |n| n.effect.palm_mute));
+    assert!(effect("let-ring.gp5", |n| n.effect.let_ring));
+    assert!(effect("slide-out-up.gp5", |n| !n.effect.slides.is_empty()));
+}
+
+#[test]
+fn test_musicxml_import_grace_timing() {
+    // Graces before the beat and on it, as effects (GP5) and as grace beats (GP7)
+    for name in ["grace.gp5", "grace-on-beat.gp"] {
+        let (song, imported) = round_trip(name);
+        let timing = |song: &Song| -> Vec<(i64, i64, u8, bool)> {
+            song.events()
+                .iter()
+                .map(|e| (e.tick, e.duration, e.pitch, e.grace))
+                .collect()
+        };
+        assert!(song.events().iter().any(|e| e.grace), "{}", name);
+        assert_eq!(timing(&imported), timing(&song), "{}", name);
+    }
+}
+
+#[test]
+fn test_musicxml_import_lyrics() {
+    let (song, imported) = round_trip("test.gp5");
+    assert_eq!(imported.lyrics.track_choice, song.lyrics.track_choice);
+    assert_eq!(imported.lyrics.lines.len(), 5);
+    let words = |text: &str| {
+        text.split_whitespace()
+            .map(String::from)
+            .collect::<Vec<_>>()
+    };
+    for (line, original) in imported.lyrics.lines.iter().zip(&song.lyrics.lines) {
+        assert_eq!(words(&line.2), words(&original.2));
+    }
+    assert_eq!(imported.lyrics.lines[0].1, song.lyrics.lines[0].1);
+
+    let (_, imported) = round_trip("Demo v5.gp5");
+    let text = imported
+        .lyrics
+        .lines
+        .iter()
+        .map(|l| l.2.as_str())
+        .collect::<Vec<_>>()
+        .join(" ");
+    assert!(text.contains("e-xam-ple"));
+    assert!(text.contains("of+lyrics"));
+}
+
+const DROP_D: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<score-partwise version="4.0">
+  <work><work-title>Drop D</work-title></work>
+  <part-list>
+    <score-part id="P1">
+      <part-name>Guitar</part-name>
+      <midi-instrument id="P1-I1">
+        <midi-channel>3</midi-channel>
+        <midi-program>30</midi-program>
+      </midi-instrument>
+    </score-part>
+  </part-list>
+  <part id="P1">
+    <measure number="1">
+      <attributes>
+        <divisions>2</divisions>
+        <time><beats>2</beats><beat-type>4</beat-type></time>
+        <clef><sign>G</sign><line>2</line><clef-octave-change>-1</clef-octave-change></clef>
+        <staff-details>
+          <staff-lines>6</staff-lines>
+          <staff-tuning line="1"><tuning-step>D</tuning-step><tuning-octave>2</tuning-octave></staff-tuning>
+          <staff-tuning line="2"><tuning-step>A</tuning-step><tuning-octave>2</tuning-octave></staff-tuning>
+          <staff-tuning line="3"><tuning-step>D</tuning-step><tuning-octave>3</tuning-octave></staff-tuning>
+          <staff-tuning line="4"><tuning-step>G</tuning-step><tuning-octave>3</tuning-octave></staff-tuning>
+          <staff-tuning line="5"><tuning-step>B</tuning-step><tuning-octave>3</tuning-octave></staff-tuning>
+          <staff-tuning line="6"><tuning-step>E</tuning-step><tuning-octave>4</tuning-octave></staff-tuning>
+        </staff-details>
+      </attributes>
+      <note><pitch><step>D</step><octave>2</octave></pitch><duration>2</duration><type>quarter</type></note>
+      <note><chord/><pitch><step>A</step><octave>2</octave></pitch><duration>2</duration><type>quarter</type></note>
+      <note><rest/><duration>1</duration><type>eighth</type></note>
+      <note><pitch><step>F</step><alter>1</alter><octave>3</octave></pitch><duration>1</duration><type>eighth</type></note>
+    </measure>
+    <measure number="2">
+      <note><pitch><step>E</step><octave>2</octave></pitch><duration>4</duration><type>half</type></note>
+    </measure>
+  </part>
+</score-partwise>"#;
+
+#[test]
+fn test_musicxml_import_staff_tuning() {
+    let mut song = Song::default();
+    song.read_musicxml(DROP_D.as_bytes()).unwrap();
+    assert_eq!(song.name, "Drop D");
+    let track = &song.tracks[0];
+    assert_eq!(track.name, "Guitar");
+    assert_eq!(
+        track.strings,
+        vec![(1, 64), (2, 59), (3, 55), (4, 50), (5, 45), (6, 38)]
+    );
+    assert_eq!(track.channel_index, 2);
+    assert_eq!(song.channels[2].instrument, 29);
+    assert_eq!(song.measure_headers.len(), 2);
+    assert_eq!(song.measure_headers[1].start, 960 + 1920);
+
+    let beats = &track.measures[0].voices[0].beats;
+    assert_eq!(beats.len(), 3);
+    let notes: Vec<(i8, i16)> = beats[0].notes.iter().map(|n| (n.string, n.value)).collect();
+    assert_eq!(notes, vec![(5, 0), (6, 0)]);
+    assert_eq!(beats[1].status, BeatStatus::Rest);
+    assert_eq!(beats[1].duration.value, 8);
+    assert_eq!(beats[2].start, Some(960 + 1440));
+    assert_eq!(beats[2].notes[0].real_value(&track.strings).unwrap(), 54);
+    let low_e = &track.measures[1].voices[0].beats[0].notes[0];
+    assert_eq!((low_e.string, low_e.value), (6, 2));
+}
+
+#[test]
+fn test_musicxml_import_compressed() {
+    let song = read_song("Chords.gp5");
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = FileOptions::default();
+    zip.start_file("META-INF/container.xml", options).unwrap();
+    zip.write_all(
+        br#"<?xml version="1.0" encoding="UTF-8"?>
+<container><rootfiles><rootfile full-path="score/Chords.musicxml" media-type="application/vnd.recordare.musicxml+xml"/></rootfiles></container>"#,
+    )
+    .unwrap();
+    zip.start_file("score/Chords.musicxml", options).unwrap();
+    zip.write_all(song.to_musicxml().as_bytes()).unwrap();
+    let data = zip.finish().unwrap().into_inner();
+
+    let mut imported = Song::default();
+    imported.read_musicxml(&data).unwrap();
+    assert_eq!(imported.measure_headers.len(), song.measure_headers.len());
+    assert_eq!(positions(&imported, 0), positions(&song, 0));
+}
+
+#[test]
+fn test_musicxml_import_errors() {
+    let mut song = Song::default();
+    assert!(song
+        .read_musicxml(b"<score-timewise version=\"4.0\"/>")
+        .is_err());
+    assert!(song.read_musicxml(b"<score-partwise><part>").is_err());
+    assert!(song.read_musicxml(b"PK\x03\x04 not an archive").is_err());
+}
+
+#[test]
+fn test_musicxml_import_percussion_round_trip() {
+    // Sharp keys share their display step with the key below: the instrument tells them apart
+    let (song, imported) = round_trip("all-percussion.gp5");
+    let keys = |song: &Song| -> Vec<Vec<i32>> {
+        let track = &song.tracks[0];
+        track
+            .measures
+            .iter()
+            .map(|measure| {
+                let mut keys: Vec<i32> = measure
+                    .voices
+                    .iter()
+                    .flat_map(|v| &v.beats)
+                    .flat_map(|b| &b.notes)
+                    .filter(|n| n.kind != NoteType::Rest)
+                    .map(|n| {
+                        let tuning = track.strings[(n.string - 1) as usize].1;
+                        i32::from(tuning) + i32::from(n.value)
+                    })
+                    .collect();
+                keys.sort();
+                keys
+            })
+            .collect()
+    };
+    assert!(imported.tracks[0].percussion_track);
+    let expected = keys(&song);
+    assert!(expected.iter().flatten().any(|k| [42, 49].contains(k)));
+    assert_eq!(keys(&imported), expected);
+}
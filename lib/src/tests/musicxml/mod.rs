@@ -0,0 +1,4 @@
+// MusicXML tests
+
+pub mod export;
+pub mod import;
@@ -0,0 +1,5 @@
+// Playback tests
+
+pub mod order;
+pub mod tempo;
+pub mod timeline;
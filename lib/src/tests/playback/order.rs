@@ -0,0 +1,76 @@
+// Playback order tests
+use super::super::{read_file, read_gp7, song_of};
+use crate::model::song::Song;
+use crate::types::enums::DirectionSign;
+
+/// A song of `count` empty measures.
+fn empty_song(count: usize) -> Song {
+    song_of(&vec![Vec::new(); count])
+}
+
+#[test]
+fn test_repeats() {
+    // The first measure is played 3 times, measures 2-3 twice
+    let song = read_gp7("test/repeats.gp");
+    assert_eq!(song.playback_order(), vec![0, 0, 0, 1, 2, 1, 2]);
+}
+
+#[test]
+fn test_alternate_endings() {
+    let song = read_gp7("test/volta.gp");
+    assert_eq!(song.playback_order(), vec![0, 1, 0, 1, 0, 2, 3]);
+
+    let mut song = Song::default();
+    song.read_gp5(&read_file(String::from("test/volta.gp5")))
+        .unwrap();
+    assert_eq!(
+        song.playback_order(),
+        vec![0, 1, 2, 3, 4, 5, 6, 0, 1, 2, 3, 4, 5, 7]
+    );
+}
+
+#[test]
+fn test_directions() {
+    // Da Capo, then Da Capo al Coda: "Da Coda" at measure 27 leads to the Coda
+    let song = read_gp7("test/directions.gp");
+    let mut expected = vec![0, 1, 0, 1, 2, 3];
+    expected.extend(0..=26);
+    expected.extend([33, 34]);
+    assert_eq!(song.playback_order(), expected);
+}
+
+#[test]
+fn test_nested_repeats() {
+    // |: 0 |: 1 :| 2 :| 3
+    let mut song = empty_song(4);
+    song.measure_headers[0].repeat_open = true;
+    song.measure_headers[1].repeat_open = true;
+    song.measure_headers[1].repeat_close = 1;
+    song.measure_headers[2].repeat_close = 1;
+    assert_eq!(song.playback_order(), vec![0, 1, 1, 2, 0, 1, 1, 2, 3]);
+
+    // |: 0 :| 1 :| 2: the second closing bar repeats from the end of the first
+    let mut song = empty_song(3);
+    song.measure_headers[0].repeat_close = 1;
+    song.measure_headers[1].repeat_close = 2;
+    assert_eq!(song.playback_order(), vec![0, 0, 1, 1, 1, 2]);
+}
+
+#[test]
+fn test_dal_segno_al_fine() {
+    // 0 |: 1 (Segno) 2 (Fine, 1st ending) :| 3 (2nd ending, D.S. al Fine) 4
+    let mut song = empty_song(5);
+    song.measure_headers[1].repeat_open = true;
+    song.measure_headers[1].direction = Some(DirectionSign::Segno);
+    song.measure_headers[2].direction = Some(DirectionSign::Fine);
+    song.measure_headers[2].repeat_alternative = 0b01;
+    song.measure_headers[2].repeat_close = 1;
+    song.measure_headers[3].repeat_alternative = 0b10;
+    song.measure_headers[3].direction = Some(DirectionSign::DaSegnoAlFine);
+    // After the jump the repeat is not played again and the last ending is taken
+    assert_eq!(song.playback_order(), vec![0, 1, 2, 1, 3, 1, 3, 4]);
+
+    // Without Segno the jump is ignored
+    song.measure_headers[1].direction = None;
+    assert_eq!(song.playback_order(), vec![0, 1, 2, 1, 3, 4]);
+}
@@ -0,0 +1,124 @@
+// Tempo map tests
+use super::super::{read_file, read_gp7};
+use crate::audio::tempo::TempoMap;
+use crate::model::beat::Beat;
+use crate::model::headers::MeasureHeader;
+use crate::model::note::Note;
+use crate::model::song::Song;
+
+/// Ticks of a 4/4 measure.
+const MEASURE: i64 = 3840;
+
+fn assert_seconds(map: &TempoMap, tick: i64, seconds: f64) {
+    assert!(
+        (map.seconds_at(tick) - seconds).abs() < 1e-9,
+        "tick {}: {} s, expected {} s",
+        tick,
+        map.seconds_at(tick),
+        seconds
+    );
+    assert_eq!(map.tick_at(seconds), tick);
+}
+
+#[test]
+fn test_tempo_changes() {
+    // 250 BPM, then 80 BPM from the third measure: set by a GPIF automation or a mix table
+    let gp7 = read_gp7("test/tempo.gp");
+    let mut gp5 = Song::default();
+    gp5.read_gp5(&read_file(String::from("test/tempo.gp5")))
+        .unwrap();
+    for song in [gp7, gp5] {
+        let map = song.tempo_map();
+        assert_seconds(&map, 0, 0.0);
+        assert_seconds(&map, MEASURE, 0.96);
+        assert_seconds(&map, 2 * MEASURE, 1.92);
+        assert_seconds(&map, 3 * MEASURE, 4.92);
+        assert_eq!(map.tempo_at(2 * MEASURE - 1), 250.0);
+        assert_eq!(map.tempo_at(2 * MEASURE), 80.0);
+        assert_eq!(map.segments().len(), 2);
+    }
+}
+
+#[test]
+fn test_gradual_tempo_change() {
+    // From 60 to 120 BPM over two measures
+    let mut song = Song {
+        tempo: 60,
+        ..Default::default()
+    };
+    for number in 1..=3 {
+        song.measure_headers.push(MeasureHeader {
+            number,
+            ..Default::default()
+        });
+    }
+    song.measure_headers[0].tempo = 60;
+    song.measure_headers[0].tempo_linear = true;
+    song.measure_headers[2].tempo = 120;
+    let map = song.tempo_map();
+    assert_eq!(map.tempo_at(MEASURE), 90.0);
+    assert_seconds(&map, 2 * MEASURE, 8.0 * 2f64.ln());
+    assert_seconds(&map, 3 * MEASURE, 8.0 * 2f64.ln() + 2.0);
+    for tick in [100, 1000, 5000, 7000] {
+        assert_eq!(map.tick_at(map.seconds_at(tick)), tick);
+    }
+
+    // Mix table change from 250 to 80 BPM over the 4 beats of the third measure
+    let mut song = Song::default();
+    song.read_gp5(&read_file(String::from("test/tempo.gp5")))
+        .unwrap();
+    let beat = &mut song.tracks[0].measures[2].voices[0].beats[0];
+    beat.effect
+        .mix_table_change
+        .as_mut()
+        .unwrap()
+        .tempo
+        .as_mut()
+        .unwrap()
+        .duration = 4;
+    let map = song.tempo_map();
+    assert_eq!(map.tempo_at(2 * MEASURE + MEASURE / 2), 165.0);
+    assert_seconds(
+        &map,
+        3 * MEASURE,
+        1.92 + 4.0 * 60.0 / 170.0 * (250f64 / 80.0).ln(),
+    );
+}
+
+#[test]
+fn test_tempo_change_after_grace_beat() {
+    // The 80 BPM mix table moved to the second beat of the third measure, after a grace beat
+    let mut song = Song::default();
+    song.read_gp5(&read_file(String::from("test/tempo.gp5")))
+        .unwrap();
+    let beats = &mut song.tracks[0].measures[2].voices[0].beats;
+    beats[1].effect.mix_table_change = beats[0].effect.mix_table_change.take();
+    let grace = Beat {
+        notes: vec![Note {
+            string: beats[0].notes[0].string,
+            ..Default::default()
+        }],
+        grace: true,
+        ..Default::default()
+    };
+    beats.insert(1, grace);
+    let map = song.tempo_map();
+    assert_eq!(map.tempo_at(2 * MEASURE + 959), 250.0);
+    assert_eq!(map.tempo_at(2 * MEASURE + 960), 80.0);
+}
+
+#[test]
+fn test_unrolled_tempo_map() {
+    // The repeat from the start plays the first measures at 250 BPM again
+    let mut song = Song::default();
+    song.read_gp5(&read_file(String::from("test/tempo.gp5")))
+        .unwrap();
+    song.measure_headers[2].repeat_close = 1;
+    let map = TempoMap::unrolled(&song);
+    assert_seconds(&map, 3 * MEASURE, 4.92);
+    assert_seconds(&map, 4 * MEASURE, 5.88);
+    assert_seconds(&map, 6 * MEASURE, 9.84);
+    assert_eq!(map.tempo_at(5 * MEASURE), 80.0);
+    // Written order ignores the repeat
+    assert_seconds(&song.tempo_map(), 4 * MEASURE, 7.92);
+}
@@ -0,0 +1,102 @@
+// Note event timeline tests
+use super::super::{read_file, read_gp7};
+use crate::model::song::Song;
+use crate::types::enums::NoteType;
+
+fn read_gp5(filename: &str) -> Song {
+    let mut song = Song::default();
+    song.read_gp5(&read_file(String::from(filename))).unwrap();
+    song
+}
+
+#[test]
+fn test_events() {
+    // 250 BPM, 80 BPM from the third measure
+    let song = read_gp5("test/tempo.gp5");
+    let events = song.events();
+    assert_eq!(events.len(), 12);
+    let first = &events[0];
+    assert_eq!(
+        (first.tick, first.duration, first.pitch, first.velocity),
+        (0, 960, 52, 95)
+    );
+    assert_eq!((first.string, first.fret), (4, 2));
+    assert_eq!(
+        (first.track, first.measure, first.voice, first.beat),
+        (0, 0, 0, 0)
+    );
+    assert!((first.duration_seconds - 0.24).abs() < 1e-9);
+    let last = events.last().unwrap();
+    assert_eq!((last.tick, last.measure, last.beat), (10560, 2, 3));
+    assert!((last.seconds - 4.17).abs() < 1e-9);
+    assert!((last.duration_seconds - 0.75).abs() < 1e-9);
+    assert!(events.windows(2).all(|e| e[0].tick <= e[1].tick));
+}
+
+#[test]
+fn test_event_pitch_and_ties() {
+    // Capo on the first fret
+    let mut song = read_gp5("test/capo-fret.gp5");
+    let events = song.events();
+    assert_eq!(events[0].pitch, 59 + 1);
+    // The notation transposition does not change the sounding pitch
+    song.tracks[0].transpose_octave = -1;
+    song.tracks[0].transpose_chromatic = 2;
+    assert_eq!(song.events()[0].pitch, 59 + 1);
+
+    // GP7 guitars are written an octave above the sounding notes: D string, fret 12
+    let song = read_gp7("test/ghost-note.gp");
+    assert_eq!(song.tracks[0].transpose_octave, -1);
+    let event = &song.events()[0];
+    assert_eq!((event.string, event.fret), (4, 12));
+    assert_eq!(event.pitch, 50 + 12);
+
+    // A tied note extends the note it continues
+    let mut song = read_gp5("test/tempo.gp5");
+    song.tracks[0].measures[0].voices[0].beats[1].notes[0].kind = NoteType::Tie;
+    let events = song.events();
+    assert_eq!(events.len(), 11);
+    assert_eq!((events[0].tick, events[0].duration), (0, 1920));
+    assert!((events[0].duration_seconds - 0.48).abs() < 1e-9);
+    assert_eq!(events[1].tick, 1920);
+}
+
+#[test]
+fn test_grace_events() {
+    // Grace effects of GP5 notes and GP7 grace beats are played the same way
+    for (song, first_beat) in [
+        (read_gp5("test/grace.gp5"), 0),
+        (read_gp7("test/grace.gp"), 1),
+    ] {
+        let events = song.events();
+        let grace = &events[0];
+        let note = &events[1];
+        assert!(grace.grace && !note.grace);
+        assert_eq!((grace.pitch, note.pitch), (61, 62));
+        assert_eq!(grace.tick + grace.duration, 0);
+        assert_eq!((note.tick, note.beat), (0, first_beat));
+
+        // On the beat: the grace note delays the note
+        let grace = events.iter().find(|e| e.grace && e.tick == 1920).unwrap();
+        let note = events.iter().find(|e| !e.grace && e.tick == 2040).unwrap();
+        assert_eq!((grace.duration, grace.pitch, note.pitch), (120, 68, 69));
+        assert_eq!(note.tick + note.duration, 2880);
+    }
+}
+
+#[test]
+fn test_played_events() {
+    // The whole song is repeated once
+    let mut song = read_gp5("test/tempo.gp5");
+    song.measure_headers[2].repeat_close = 1;
+    let events = song.played_events();
+    assert_eq!(events.len(), 24);
+    let repeated = &events[12];
+    assert_eq!(
+        (repeated.tick, repeated.measure, repeated.beat),
+        (11520, 0, 0)
+    );
+    assert!((repeated.seconds - 4.92).abs() < 1e-9);
+    assert!((repeated.duration_seconds - 0.24).abs() < 1e-9);
+    assert_eq!(song.events().len(), 12);
+}
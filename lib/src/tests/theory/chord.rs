@@ -0,0 +1,157 @@
+// Chord recognition tests
+use super::super::read_file;
+use crate::model::song::Song;
+use crate::theory::chord::{parse_chord, recognize_chord};
+use crate::types::enums::chord::{ChordAlteration, ChordExtension, ChordType};
+
+fn name(pitches: &[u8], sharp: bool) -> Option<String> {
+    recognize_chord(pitches, sharp).map(|c| c.name)
+}
+
+#[test]
+fn test_recognize_qualities() {
+    let cases: [(&[u8], &str); 14] = [
+        (&[48, 52, 55], "C"),
+        (&[57, 60, 64], "Am"),
+        (&[48, 52, 55, 58], "C7"),
+        (&[48, 52, 55, 59], "Cmaj7"),
+        (&[48, 52, 55, 57], "C6"),
+        (&[45, 48, 52, 55], "Am7"),
+        (&[48, 51, 54], "Cdim"),
+        (&[48, 51, 54, 57], "Cdim7"),
+        (&[42, 45, 48, 52], "F#m7b5"),
+        (&[48, 52, 56], "Caug"),
+        (&[48, 55, 60], "C5"),
+        (&[48, 53, 55], "Csus4"),
+        (&[48, 50, 55], "Csus2"),
+        (&[48, 53, 55, 58], "C7sus4"),
+    ];
+    for (pitches, expected) in cases {
+        assert_eq!(
+            name(pitches, true).as_deref(),
+            Some(expected),
+            "{pitches:?}"
+        );
+    }
+}
+
+#[test]
+fn test_recognize_extensions() {
+    let cases: [(&[u8], &str); 8] = [
+        (&[48, 52, 55, 58, 62], "C9"),
+        (&[48, 52, 55, 59, 62], "Cmaj9"),
+        (&[43, 46, 50, 53, 57], "Gm9"),
+        (&[48, 52, 55, 62], "Cadd9"),
+        (&[48, 52, 58, 62, 69], "C13"),
+        (&[48, 52, 58, 61], "C7b9"),
+        (&[48, 52, 58, 63], "C7#9"),
+        (&[70, 65, 61, 55, 52, 48], "C11b9"),
+    ];
+    for (pitches, expected) in cases {
+        assert_eq!(
+            name(pitches, true).as_deref(),
+            Some(expected),
+            "{pitches:?}"
+        );
+    }
+
+    let chord = recognize_chord(&[48, 52, 58, 61], true).unwrap();
+    assert_eq!(chord.kind, Some(ChordType::Seventh));
+    assert_eq!(chord.extension, Some(ChordExtension::Ninth));
+    assert_eq!(chord.ninth, Some(ChordAlteration::Diminished));
+    assert_eq!(chord.add, Some(false));
+    let chord = recognize_chord(&[48, 51, 54, 58], true).unwrap();
+    assert_eq!(chord.kind, Some(ChordType::MinorSeventh));
+    assert_eq!(chord.fifth, Some(ChordAlteration::Diminished));
+    let chord = recognize_chord(&[48, 52, 55, 62], true).unwrap();
+    assert_eq!(chord.add, Some(true));
+}
+
+#[test]
+fn test_recognize_bass_and_spelling() {
+    // First inversion, and the bass under a chord
+    assert_eq!(name(&[40, 48, 55, 64], true).as_deref(), Some("C/E"));
+    let chord = recognize_chord(&[41, 55, 58, 62, 64, 69], false).unwrap();
+    assert_eq!(chord.name, "Gm13/F");
+    assert_eq!(chord.root.unwrap().to_string(), "G");
+    assert_eq!(chord.bass.unwrap().to_string(), "F");
+
+    // Sharps or flats
+    assert_eq!(name(&[46, 50, 53], false).as_deref(), Some("Bb"));
+    assert_eq!(name(&[46, 50, 53], true).as_deref(), Some("A#"));
+    assert_eq!(name(&[42, 45, 49], false).as_deref(), Some("Gbm"));
+
+    // Not a chord
+    assert_eq!(name(&[48, 60], true), None);
+    assert_eq!(name(&[], true), None);
+}
+
+#[test]
+fn test_beat_chords() {
+    let mut song = Song::default();
+    song.read_gp5(&read_file(String::from("test/Chords.gp5")))
+        .unwrap();
+    let chord = song.recognize_chord(0, 2, 0, 0..1).unwrap();
+    assert_eq!(chord.name, "C9");
+    let chord = song.recognize_chord(0, 6, 0, 0..1).unwrap();
+    assert_eq!(chord.name, "F");
+    assert_eq!(song.recognize_chord(0, 6, 0, 9..10), None);
+
+    // Labels where the chord changes
+    for measure in &mut song.tracks[0].measures {
+        for beat in &mut measure.voices[0].beats {
+            beat.effect.chord = None;
+        }
+    }
+    assert_eq!(song.label_chords(0), 6);
+    let labels: Vec<String> = song.tracks[0]
+        .measures
+        .iter()
+        .flat_map(|m| &m.voices[0].beats)
+        .filter_map(|b| b.effect.chord.as_ref().map(|c| c.name.clone()))
+        .collect();
+    assert_eq!(labels, vec!["C", "C9", "Cadd9", "F", "E", "Gm13/F"]);
+}
+
+#[test]
+fn test_parse_chord() {
+    let chord = parse_chord("F#m7b5/A").unwrap();
+    assert_eq!(chord.name, "F#m7b5/A");
+    assert_eq!(chord.root.unwrap().to_string(), "F#");
+    assert_eq!(chord.kind, Some(ChordType::MinorSeventh));
+    assert_eq!(chord.fifth, Some(ChordAlteration::Diminished));
+    assert_eq!(chord.bass.unwrap().to_string(), "A");
+
+    let chord = parse_chord("Bb7#9").unwrap();
+    assert_eq!(chord.root.unwrap().to_string(), "Bb");
+    assert_eq!(chord.sharp, Some(false));
+    assert_eq!(chord.kind, Some(ChordType::Seventh));
+    assert_eq!(chord.extension, Some(ChordExtension::Ninth));
+    assert_eq!(chord.ninth, Some(ChordAlteration::Augmented));
+
+    let chord = parse_chord("Gadd9/B").unwrap();
+    assert_eq!(chord.kind, Some(ChordType::Major));
+    assert_eq!(chord.extension, Some(ChordExtension::Ninth));
+    assert_eq!(chord.add, Some(true));
+    assert_eq!(chord.bass.unwrap().to_string(), "B");
+
+    let kinds = [
+        ("Cmaj9", ChordType::MajorSeventh),
+        ("C-7", ChordType::MinorSeventh),
+        ("CmMaj7", ChordType::MinorMajor),
+        ("Dsus4", ChordType::SuspendedFourth),
+        ("D7sus2", ChordType::SeventhSuspendedSecond),
+        ("E6/9", ChordType::Sixth),
+        ("Cdim7", ChordType::Diminished),
+        ("C+", ChordType::Augmented),
+        ("A5", ChordType::Power),
+        ("Ebm6", ChordType::MinorSixth),
+    ];
+    for (name, kind) in kinds {
+        assert_eq!(parse_chord(name).unwrap().kind, Some(kind), "{name}");
+    }
+    assert_eq!(parse_chord("E6/9").unwrap().bass.unwrap().to_string(), "E");
+    assert_eq!(parse_chord("Xm"), None);
+    assert_eq!(parse_chord("C7q"), None);
+    assert_eq!(parse_chord(""), None);
+}
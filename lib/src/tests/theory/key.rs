@@ -0,0 +1,160 @@
+// Key detection tests
+use super::super::{read_file, song_of};
+use crate::model::key_signature::KeySignature;
+use crate::model::song::Song;
+use crate::theory::key::estimate_key;
+use crate::types::measure::Marker;
+
+/// Song of quarter notes on the low string of a guitar, one list of MIDI pitches per
+/// measure, with the marker of each measure.
+fn song_with_markers(measures: &[(&[u8], Option<&str>)]) -> Song {
+    let beats: Vec<Vec<Vec<(i8, i16)>>> = measures
+        .iter()
+        .map(|(pitches, _)| {
+            pitches
+                .iter()
+                .map(|p| vec![(6, i16::from(*p) - 40)])
+                .collect()
+        })
+        .collect();
+    let mut song = song_of(&beats);
+    for (header, (_, marker)) in song.measure_headers.iter_mut().zip(measures) {
+        header.marker = marker.map(|title| Marker {
+            title: title.to_string(),
+            ..Default::default()
+        });
+    }
+    song
+}
+
+const C_MAJOR: [u8; 8] = [48, 52, 55, 60, 53, 57, 59, 62];
+const A_MINOR: [u8; 8] = [45, 48, 52, 57, 56, 59, 50, 53];
+const E_MAJOR: [u8; 8] = [52, 56, 59, 64, 57, 61, 63, 54];
+
+#[test]
+fn test_estimate_key() {
+    // Major scale with a weight on the tonic triad
+    let mut durations = [0.0; 12];
+    for (p, weight) in [
+        (0, 3.0),
+        (2, 1.0),
+        (4, 2.0),
+        (5, 1.0),
+        (7, 2.0),
+        (9, 1.0),
+        (11, 1.0),
+    ] {
+        durations[p] = weight;
+    }
+    let key = estimate_key(&durations).unwrap();
+    assert_eq!(key.to_string(), "C major");
+    assert_eq!(key.key_signature, KeySignature::default());
+    assert!(key.correlation > 0.8);
+    assert!(key.confidence > 0.0 && key.confidence <= 1.0);
+
+    // The same profile a minor third down, with the raised seventh: F# minor
+    let mut minor = [0.0; 12];
+    for (p, weight) in [
+        (6, 3.0),
+        (8, 1.0),
+        (9, 2.0),
+        (11, 1.0),
+        (1, 2.0),
+        (2, 1.0),
+        (5, 1.0),
+    ] {
+        minor[p] = weight;
+    }
+    let key = estimate_key(&minor).unwrap();
+    assert_eq!(key.to_string(), "F# minor");
+    assert_eq!(
+        key.key_signature,
+        KeySignature {
+            key: 3,
+            is_minor: true
+        }
+    );
+
+    // Flat keys are spelled with flats
+    let flat: [f64; 12] = std::array::from_fn(|p| durations[(p + 2) % 12]);
+    let key = estimate_key(&flat).unwrap();
+    assert_eq!(key.to_string(), "Bb major");
+    assert_eq!(key.key_signature.key, -2);
+
+    // Nothing played, or every pitch class as long
+    assert_eq!(estimate_key(&[0.0; 12]), None);
+    assert_eq!(estimate_key(&[1.0; 12]), None);
+}
+
+#[test]
+fn test_song_key() {
+    let song = song_with_markers(&[(&C_MAJOR, None), (&C_MAJOR, None)]);
+    assert_eq!(song.detect_key().unwrap().to_string(), "C major");
+    let song = song_with_markers(&[(&A_MINOR, None), (&A_MINOR, None)]);
+    let key = song.detect_key().unwrap();
+    assert_eq!(key.to_string(), "A minor");
+    assert_eq!(key.key_signature.key, 0);
+
+    // Percussion is left out
+    let mut song = song_with_markers(&[(&E_MAJOR, None)]);
+    song.tracks[0].percussion_track = true;
+    assert_eq!(song.detect_key(), None);
+
+    let mut song = Song::default();
+    song.read_gp5(&read_file(String::from("test/Chords.gp5")))
+        .unwrap();
+    assert_eq!(song.detect_key().unwrap().to_string(), "C major");
+}
+
+#[test]
+fn test_section_keys() {
+    let song = song_with_markers(&[
+        (&C_MAJOR, None),
+        (&C_MAJOR, None),
+        (&E_MAJOR, Some("Chorus")),
+        (&E_MAJOR, None),
+        (&[], Some("End")),
+    ]);
+    let sections = song.detect_section_keys();
+    assert_eq!(sections.len(), 3);
+    assert_eq!(sections[0].title, None);
+    assert_eq!(sections[0].measures, 0..2);
+    assert_eq!(sections[0].key.as_ref().unwrap().to_string(), "C major");
+    assert_eq!(sections[1].title.as_deref(), Some("Chorus"));
+    assert_eq!(sections[1].measures, 2..4);
+    assert_eq!(sections[1].key.as_ref().unwrap().to_string(), "E major");
+    assert_eq!(sections[2].key, None);
+}
+
+#[test]
+fn test_apply_detected_keys() {
+    let measures: [(&[u8], Option<&str>); 4] = [
+        (&E_MAJOR, None),
+        (&E_MAJOR, None),
+        (&C_MAJOR, Some("Bridge")),
+        (&[], Some("End")),
+    ];
+    let e_major = KeySignature {
+        key: 4,
+        is_minor: false,
+    };
+
+    let mut song = song_with_markers(&measures);
+    assert_eq!(song.apply_detected_keys(true), 2);
+    let keys: Vec<i8> = song
+        .measure_headers
+        .iter()
+        .map(|h| h.key_signature.key)
+        .collect();
+    assert_eq!(keys, vec![4, 4, 0, 0]);
+    assert_eq!(song.tracks[0].measures[1].key_signature, e_major);
+    assert_eq!(song.key, e_major);
+
+    let mut song = song_with_markers(&measures);
+    assert_eq!(song.apply_detected_keys(false), 4);
+    assert!(song
+        .measure_headers
+        .iter()
+        .all(|h| h.key_signature == e_major));
+    assert_eq!(song.apply_detected_keys(false), 0);
+}
@@ -0,0 +1,5 @@
+// Music theory tests
+
+pub mod chord;
+pub mod voicing;
+pub mod key;
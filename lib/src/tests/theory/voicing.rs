@@ -0,0 +1,224 @@
+// Chord voicing tests
+use crate::model::beat::{Beat, Voice};
+use crate::model::measure::Measure;
+use crate::model::track::Track;
+use crate::theory::chord::{parse_chord, recognize_chord};
+use crate::types::chord::{Barre, Chord, PitchClass};
+use crate::types::enums::chord::ChordExtension;
+use crate::types::enums::note::Fingering;
+
+/// Sounding pitches of a voicing on a track.
+fn pitches(track: &Track, chord: &Chord) -> Vec<u8> {
+    chord
+        .strings
+        .iter()
+        .enumerate()
+        .filter(|(_, f)| **f >= 0)
+        .map(|(s, f)| (i32::from(track.strings[s].1) + track.offset + i32::from(*f)) as u8)
+        .collect()
+}
+
+fn best(track: &Track, name: &str) -> Vec<i8> {
+    track.chord_voicings(&parse_chord(name).unwrap())[0]
+        .strings
+        .clone()
+}
+
+#[test]
+fn test_open_chords() {
+    let track = Track::default();
+    assert_eq!(best(&track, "C"), vec![0, 1, 0, 2, 3, -1]);
+    assert_eq!(best(&track, "G"), vec![3, 0, 0, 0, 2, 3]);
+    assert_eq!(best(&track, "D"), vec![2, 3, 2, 0, -1, -1]);
+    assert_eq!(best(&track, "A"), vec![0, 2, 2, 2, 0, -1]);
+    assert_eq!(best(&track, "E"), vec![0, 0, 1, 2, 2, 0]);
+    assert_eq!(best(&track, "Am"), vec![0, 1, 2, 2, 0, -1]);
+    assert_eq!(best(&track, "Dm"), vec![1, 3, 2, 0, -1, -1]);
+    assert_eq!(best(&track, "G7"), vec![1, 0, 0, 0, 2, 3]);
+    assert_eq!(best(&track, "B7"), vec![2, 0, 2, 1, 2, -1]);
+    assert_eq!(best(&track, "Cmaj7"), vec![0, 0, 0, 2, 3, -1]);
+
+    let c = &track.chord_voicings(&parse_chord("C").unwrap())[0];
+    assert_eq!(c.first_fret, Some(1));
+    assert!(c.barres.is_empty());
+    assert_eq!(
+        c.fingerings,
+        vec![
+            Fingering::Open,
+            Fingering::Index,
+            Fingering::Open,
+            Fingering::Middle,
+            Fingering::Annular,
+            Fingering::Open
+        ]
+    );
+    // Root, third and fifth played
+    assert_eq!(
+        c.omissions,
+        vec![false, false, false, true, true, true, true]
+    );
+    assert_eq!(c.name, "C");
+}
+
+#[test]
+fn test_barre_chords() {
+    let track = Track::default();
+    let f = &track.chord_voicings(&parse_chord("F").unwrap())[0];
+    assert_eq!(f.strings, vec![1, 1, 2, 3, 3, 1]);
+    assert_eq!(
+        f.barres,
+        vec![Barre {
+            fret: 1,
+            start: 1,
+            end: 6
+        }]
+    );
+    assert_eq!(f.fingerings[0], Fingering::Index);
+    assert_eq!(f.fingerings[3], Fingering::Little);
+
+    let cm7 = &track.chord_voicings(&parse_chord("Cm7").unwrap())[0];
+    assert_eq!(cm7.strings, vec![3, 4, 3, 5, 3, -1]);
+    assert_eq!(cm7.barres[0].fret, 3);
+
+    // High on the neck, the diagram starts on the lowest fret
+    for voicing in track.chord_voicings(&parse_chord("A7").unwrap()) {
+        let frets = voicing.strings.iter().filter(|f| **f > 0);
+        let (low, high) = (*frets.clone().min().unwrap(), *frets.max().unwrap());
+        let first = if high > 5 { low as u8 } else { 1 };
+        assert_eq!(voicing.first_fret, Some(first));
+    }
+}
+
+#[test]
+fn test_voicings_play_the_chord() {
+    let mut track = Track::default();
+    for capo in [0, 3] {
+        track.offset = capo;
+        for name in [
+            "C", "Am", "G7", "Cmaj7", "F#m7b5/A", "Bbm", "C/E", "Dsus4", "Cdim7",
+        ] {
+            let chord = parse_chord(name).unwrap();
+            let voicings = track.chord_voicings(&chord);
+            assert!(!voicings.is_empty(), "{name}");
+            for voicing in &voicings {
+                let pitches = pitches(&track, voicing);
+                let frets = voicing.strings.iter().filter(|f| **f > 0);
+                assert!(frets.clone().max().unwrap_or(&0) - frets.min().unwrap_or(&0) < 4);
+                assert!(pitches.len() >= 3, "{name} {:?}", voicing.strings);
+                let recognized = recognize_chord(&pitches, !name.contains('b')).unwrap();
+                let value = |p: Option<PitchClass>| p.map(|p| p.value);
+                assert_eq!(value(recognized.bass), value(chord.bass.clone()), "{name}");
+                // F#m7b5/A reads as Am6
+                if !name.contains('/') {
+                    assert_eq!(value(recognized.root), value(chord.root.clone()), "{name}");
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_altered_thirteenth_voicing() {
+    // The lowered thirteenth of A7b13 is F, not the F# of A13
+    let track = Track::default();
+    let chord = parse_chord("A7b13").unwrap();
+    let voicings = track.chord_voicings(&chord);
+    assert!(!voicings.is_empty());
+    for voicing in &voicings {
+        let classes: Vec<u8> = pitches(&track, voicing).iter().map(|p| p % 12).collect();
+        assert!(classes.contains(&5), "{:?}", voicing.strings);
+        assert!(!classes.contains(&6), "{:?}", voicing.strings);
+    }
+}
+
+#[test]
+fn test_diminished_seventh_voicing() {
+    // The seventh comes from the degrees of the chord, not from its name
+    let track = Track::default();
+    let classes = |chord: &Chord| -> Vec<Vec<u8>> {
+        track
+            .chord_voicings(chord)
+            .iter()
+            .map(|v| {
+                let mut classes: Vec<u8> = pitches(&track, v).iter().map(|p| p % 12).collect();
+                classes.sort();
+                classes.dedup();
+                classes
+            })
+            .collect()
+    };
+    // C Eb Gb A, without the name
+    let mut dim7 = parse_chord("Cdim7").unwrap();
+    dim7.name = String::new();
+    assert!(classes(&dim7).iter().all(|c| c == &[0, 3, 6, 9]));
+    // B D F, whatever the name says
+    let b = parse_chord("B°").unwrap();
+    assert!(classes(&b).iter().all(|c| c == &[2, 5, 11]));
+    let mut b7 = b.clone();
+    b7.name = String::from("B° (7 frets)");
+    assert_eq!(classes(&b7), classes(&b));
+    // A diminished ninth chord read from a file has its seventh, Ab
+    let b9 = Chord {
+        extension: Some(ChordExtension::Ninth),
+        thirteenth: None,
+        ..b
+    };
+    assert!(classes(&b9).iter().all(|c| c.contains(&8)));
+}
+
+#[test]
+fn test_other_tunings() {
+    // Four string bass: a chord on three strings
+    let bass = Track {
+        strings: vec![(1, 43), (2, 38), (3, 33), (4, 28)],
+        ..Default::default()
+    };
+    let voicing = &bass.chord_voicings(&parse_chord("C5").unwrap())[0];
+    assert_eq!(voicing.strings.len(), 4);
+    assert_eq!(voicing.length, 4);
+    assert!(voicing.strings.iter().filter(|f| **f >= 0).count() >= 3);
+
+    // Chords without root have no voicing
+    assert!(bass.chord_voicings(&Chord::default()).is_empty());
+}
+
+#[test]
+fn test_add_chord_diagrams() {
+    let mut voice = Voice::default();
+    for name in ["C", "G7", "Hm"] {
+        let mut beat = Beat::default();
+        beat.effect.chord = Some(Chord {
+            name: name.to_string(),
+            ..Default::default()
+        });
+        voice.beats.push(beat);
+    }
+    let mut beat = Beat::default();
+    beat.effect.chord = Some(Chord {
+        name: String::from("Am"),
+        strings: vec![5, 5, 5, 7, 7, 5],
+        ..Default::default()
+    });
+    voice.beats.push(beat);
+    let mut measure = Measure::default();
+    measure.voices.push(voice);
+    let mut track = Track::default();
+    track.measures.push(measure);
+
+    assert_eq!(track.add_chord_diagrams(), 2);
+    let chords: Vec<&Chord> = track.measures[0].voices[0]
+        .beats
+        .iter()
+        .map(|b| b.effect.chord.as_ref().unwrap())
+        .collect();
+    assert_eq!(chords[0].strings, vec![0, 1, 0, 2, 3, -1]);
+    assert_eq!(chords[0].name, "C");
+    assert_eq!(chords[1].strings, vec![1, 0, 0, 0, 2, 3]);
+    // Unknown names and written diagrams are left alone
+    assert!(chords[2].strings.is_empty());
+    assert_eq!(chords[3].strings, vec![5, 5, 5, 7, 7, 5]);
+
+    track.percussion_track = true;
+    track.measures[0].voices[0].beats[0].effect.chord = Some(Chord::default());
+    assert_eq!(track.add_chord_diagrams(), 0);
+}
@@ -0,0 +1,180 @@
+// Score validation checks tests
+use super::super::{read_file, song_of};
+use crate::model::note::Note;
+use crate::model::song::Song;
+use crate::types::enums::NoteType;
+use crate::validate::diagnostic::{Issue, Location, Severity};
+
+/// Song of `measures` measures of four quarter notes on the open low string.
+fn quarters_song(measures: usize) -> Song {
+    song_of(&vec![vec![vec![(6, 0)]; 4]; measures])
+}
+
+fn note_at(measure: usize, beat: usize, note: usize) -> Location {
+    Location {
+        track: Some(0),
+        measure: Some(measure),
+        voice: Some(0),
+        beat: Some(beat),
+        note: Some(note),
+    }
+}
+
+#[test]
+fn test_clean_song() {
+    assert!(quarters_song(2).validate().is_empty());
+    for file in ["Chords.gp5", "Repeat.gp5", "Voices.gp5"] {
+        let song = Song::from_bytes(&read_file(String::from(file))).unwrap().0;
+        let diagnostics = song.validate();
+        assert!(diagnostics.is_empty(), "{}: {:?}", file, diagnostics);
+    }
+}
+
+#[test]
+fn test_note_issues() {
+    let mut song = quarters_song(2);
+    let notes = &mut song.tracks[0].measures[1].voices[0].beats[1].notes;
+    notes[0].value = 25;
+    notes.push(Note {
+        string: 6,
+        value: -1,
+        kind: NoteType::Normal,
+        ..Default::default()
+    });
+    notes.push(Note {
+        string: 7,
+        value: 3,
+        kind: NoteType::Normal,
+        ..Default::default()
+    });
+    let found: Vec<(Location, Issue)> = song
+        .validate()
+        .into_iter()
+        .map(|d| (d.location, d.issue))
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            (
+                note_at(1, 1, 0),
+                Issue::FretAboveNeck {
+                    fret: 25,
+                    fret_count: 24
+                }
+            ),
+            (note_at(1, 1, 1), Issue::DuplicateString { string: 6 }),
+            (note_at(1, 1, 1), Issue::NegativeFret { fret: -1 }),
+            (
+                note_at(1, 1, 2),
+                Issue::StringOutOfRange {
+                    string: 7,
+                    strings: 6
+                }
+            ),
+        ]
+    );
+
+    // Percussion notes are above the frets
+    song.tracks[0].percussion_track = true;
+    assert!(!song
+        .validate()
+        .iter()
+        .any(|d| matches!(d.issue, Issue::FretAboveNeck { .. })));
+}
+
+#[test]
+fn test_ties_and_durations() {
+    let mut song = quarters_song(2);
+    // Tied to the last note of the previous measure: fine
+    song.tracks[0].measures[1].voices[0].beats[0].notes[0].kind = NoteType::Tie;
+    assert!(song.validate().is_empty());
+
+    // Tied on a string the previous beat does not play
+    let beats = &mut song.tracks[0].measures[1].voices[0].beats;
+    beats[2].notes[0].string = 5;
+    beats[3].notes[0].string = 4;
+    beats[3].notes[0].kind = NoteType::Tie;
+    // A missing quarter note
+    song.tracks[0].measures[0].voices[0].beats.pop();
+    let diagnostics = song.validate();
+    assert_eq!(diagnostics.len(), 2);
+    assert_eq!(
+        diagnostics[0].to_string(),
+        "warning: track 1, measure 1, voice 1: beats last 2880 ticks instead of 3840"
+    );
+    assert_eq!(
+        diagnostics[1].to_string(),
+        "warning: track 1, measure 2, voice 1, beat 4, note 1: tie on string 4 without a previous note"
+    );
+
+    // Free time measures have any length
+    song.measure_headers[0].free_time = true;
+    assert_eq!(song.validate().len(), 1);
+}
+
+#[test]
+fn test_song_issues() {
+    let mut song = quarters_song(4);
+    song.tracks[0].measures.pop();
+    song.tracks[0].channel_index = 3;
+    // Repeat opened on measure 2, ending on measure 4 outside a closed repeat
+    song.measure_headers[1].repeat_open = true;
+    song.measure_headers[3].repeat_alternative = 0b101;
+    let diagnostics = song.validate();
+    let issues: Vec<&Issue> = diagnostics.iter().map(|d| &d.issue).collect();
+    assert_eq!(
+        issues,
+        vec![
+            &Issue::UnclosedRepeat,
+            &Issue::EndingWithoutRepeat { endings: 0b101 },
+            &Issue::MeasureCount {
+                headers: 4,
+                measures: 3
+            },
+            &Issue::ChannelOutOfRange {
+                index: 3,
+                channels: 1
+            },
+        ]
+    );
+    let text: Vec<String> = diagnostics.iter().map(|d| d.to_string()).collect();
+    assert_eq!(text[0], "warning: measure 2: repeat never closed");
+    assert_eq!(
+        text[1],
+        "warning: measure 4: alternate ending 1, 3 outside a repeat"
+    );
+    assert_eq!(text[2], "error: track 1: 3 measures for 4 measure headers");
+    assert_eq!(text[3], "error: track 1: channel 3 of 1 channels");
+    assert_eq!(diagnostics[0].severity, Severity::Warning);
+    assert_eq!(diagnostics[2].severity, Severity::Error);
+    assert_eq!(Location::default().to_string(), "song");
+
+    // Closing the repeat fixes both repeat issues
+    song.measure_headers[2].repeat_close = 1;
+    assert_eq!(song.validate().len(), 2);
+}
+
+#[test]
+fn test_grace_effects_are_not_grace_beats() {
+    // GP3 beats whose grace note has the fret of the note still take their time
+    let file = "gamma_ray-heading_for_tomorrow.gp3";
+    let song = Song::from_bytes(&read_file(String::from(file))).unwrap().0;
+    let beats = song
+        .tracks
+        .iter()
+        .flat_map(|t| &t.measures)
+        .flat_map(|m| &m.voices)
+        .flat_map(|v| &v.beats);
+    assert!(!beats.clone().any(|b| b.is_grace_beat()));
+    assert!(beats.flat_map(|b| &b.notes).any(|n| n
+        .effect
+        .grace
+        .as_ref()
+        .is_some_and(|g| g.fret as i16 == n.value)));
+    let durations: Vec<_> = song
+        .validate()
+        .into_iter()
+        .filter(|d| matches!(d.issue, Issue::VoiceDuration { .. }))
+        .collect();
+    assert!(durations.is_empty(), "{:?}", durations);
+}
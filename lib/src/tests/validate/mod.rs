@@ -0,0 +1,3 @@
+// Score validation tests
+
+pub mod checks;
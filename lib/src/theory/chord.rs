@@ -0,0 +1,659 @@
+// Chord recognition: root, quality, extensions and bass of a set of pitches
+use std::ops::Range;
+
+use crate::audio::timeline::pitch;
+use crate::model::song::Song;
+use crate::types::chord::{Chord, PitchClass};
+use crate::types::enums::chord::{ChordAlteration, ChordExtension, ChordType};
+use crate::types::enums::NoteType;
+
+/// Cost of a pitch the chord does not explain: such a reading is rejected.
+const UNEXPLAINED_COST: i32 = 100;
+/// Cost of a root that is not the bass, over one natural extension.
+const INVERSION_COST: i32 = 3;
+
+/// Degrees of a chord over its root, in semitones from the root.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Degrees {
+    /// 3 or 4, or 2 and 5 for the suspended chords
+    third: Option<i32>,
+    /// 7, or 6 and 8 when lowered or raised
+    fifth: Option<i32>,
+    /// 10 or 11, or 9 for a diminished seventh
+    seventh: Option<i32>,
+    /// 2, or 1 and 3 when lowered or raised
+    ninth: Option<i32>,
+    /// 5, or 6 when raised
+    eleventh: Option<i32>,
+    /// 9 (the sixth without seventh), or 8 when lowered
+    thirteenth: Option<i32>,
+}
+
+impl Degrees {
+    /// Read the intervals over a root (bit `n` set for `n` semitones), with the intervals
+    /// left unexplained.
+    fn read(intervals: u16) -> (Degrees, u16) {
+        let has = |n: i32| intervals & (1 << n) != 0;
+        let mut d = Degrees {
+            third: [4, 3, 5, 2].into_iter().find(|n| has(*n)),
+            ..Default::default()
+        };
+        d.fifth = if has(7) {
+            Some(7)
+        } else if has(6) && d.third != Some(4) {
+            Some(6)
+        } else if has(8) && d.third == Some(4) {
+            Some(8)
+        } else {
+            None
+        };
+        d.seventh = if has(10) {
+            Some(10)
+        } else if has(11) {
+            Some(11)
+        } else if d.third == Some(3) && d.fifth == Some(6) && has(9) {
+            Some(9)
+        } else {
+            None
+        };
+        d.ninth = if has(2) && d.third != Some(2) {
+            Some(2)
+        } else if has(1) {
+            Some(1)
+        } else if has(3) && d.third == Some(4) {
+            Some(3)
+        } else {
+            None
+        };
+        d.eleventh = if has(5) && d.third != Some(5) {
+            Some(5)
+        } else if has(6) && d.fifth != Some(6) {
+            Some(6)
+        } else {
+            None
+        };
+        d.thirteenth = if has(9) && d.seventh != Some(9) {
+            Some(9)
+        } else if has(8) && d.fifth != Some(8) {
+            Some(8)
+        } else {
+            None
+        };
+        let mut explained = 1u16;
+        for degree in [
+            d.third,
+            d.fifth,
+            d.seventh,
+            d.ninth,
+            d.eleventh,
+            d.thirteenth,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            explained |= 1 << degree;
+        }
+        (d, intervals & !explained)
+    }
+
+    /// Complexity of the reading: the simplest one names the chord.
+    fn cost(&self) -> i32 {
+        let mut cost = 0;
+        if self.third.is_none() && self.fifth != Some(7) {
+            cost += 2;
+        }
+        if matches!(self.third, Some(2) | Some(5)) {
+            cost += 1;
+        }
+        if self.fifth != Some(7) {
+            cost += 1;
+        }
+        for (degree, natural) in [(self.ninth, 2), (self.eleventh, 5), (self.thirteenth, 9)] {
+            match degree {
+                Some(n) if n == natural => cost += 1,
+                Some(_) => cost += 2,
+                None => {}
+            }
+        }
+        cost
+    }
+
+    fn kind(&self) -> ChordType {
+        let sixth = self.seventh.is_none() && self.thirteenth == Some(9);
+        match (self.third, self.fifth, self.seventh) {
+            (Some(4), Some(8), None) => ChordType::Augmented,
+            (Some(4), _, Some(10)) => ChordType::Seventh,
+            (Some(4), _, Some(11)) => ChordType::MajorSeventh,
+            (Some(4), _, _) if sixth => ChordType::Sixth,
+            (Some(4), _, _) => ChordType::Major,
+            (Some(3), Some(6), None) | (Some(3), Some(6), Some(9)) => ChordType::Diminished,
+            (Some(3), _, Some(10)) => ChordType::MinorSeventh,
+            (Some(3), _, Some(11)) => ChordType::MinorMajor,
+            (Some(3), _, _) if sixth => ChordType::MinorSixth,
+            (Some(3), _, _) => ChordType::Minor,
+            (Some(2), _, Some(_)) => ChordType::SeventhSuspendedSecond,
+            (Some(2), _, None) => ChordType::SuspendedSecond,
+            (Some(5), _, Some(_)) => ChordType::SeventhSuspendedFourth,
+            (Some(5), _, None) => ChordType::SuspendedFourth,
+            _ => ChordType::Power,
+        }
+    }
+
+    /// Chord symbol after the root: quality, extensions, alterations and added notes.
+    fn symbol(&self, kind: &ChordType) -> String {
+        let mut symbol = String::from(match kind {
+            ChordType::Major => "",
+            ChordType::Seventh => "7",
+            ChordType::MajorSeventh => "maj7",
+            ChordType::Sixth => "6",
+            ChordType::Minor => "m",
+            ChordType::MinorSeventh if self.fifth == Some(6) => "m7b5",
+            ChordType::MinorSeventh => "m7",
+            ChordType::MinorMajor => "mMaj7",
+            ChordType::MinorSixth => "m6",
+            ChordType::SuspendedSecond => "sus2",
+            ChordType::SuspendedFourth => "sus4",
+            ChordType::SeventhSuspendedSecond => "7sus2",
+            ChordType::SeventhSuspendedFourth => "7sus4",
+            ChordType::Diminished if self.seventh == Some(9) => "dim7",
+            ChordType::Diminished => "dim",
+            ChordType::Augmented => "aug",
+            ChordType::Power | ChordType::Unknown(_) => "5",
+        });
+        let seventh = matches!(self.seventh, Some(10) | Some(11));
+        // The highest natural extension replaces the seventh of the symbol
+        let highest = [
+            (self.thirteenth, 9, "13"),
+            (self.eleventh, 5, "11"),
+            (self.ninth, 2, "9"),
+        ]
+        .into_iter()
+        .find(|(degree, natural, _)| *degree == Some(*natural));
+        if let Some((_, _, number)) = highest.filter(|_| seventh) {
+            symbol = symbol.replacen('7', number, 1);
+        }
+        if matches!(self.fifth, Some(6)) && *kind != ChordType::Diminished && !symbol.contains("b5")
+        {
+            symbol.push_str("b5");
+        }
+        if matches!(self.fifth, Some(8)) && *kind != ChordType::Augmented {
+            symbol.push_str("#5");
+        }
+        let added = |symbol: &mut String, text: &str| {
+            if !seventh {
+                symbol.push_str("add");
+            }
+            symbol.push_str(text);
+        };
+        // With a seventh the natural extensions are in the number, without they are added
+        match self.ninth {
+            Some(2) if !seventh => added(&mut symbol, "9"),
+            Some(1) => added(&mut symbol, "b9"),
+            Some(3) => added(&mut symbol, "#9"),
+            _ => {}
+        }
+        match self.eleventh {
+            Some(5) if !seventh => added(&mut symbol, "11"),
+            Some(6) => added(&mut symbol, "#11"),
+            _ => {}
+        }
+        if self.thirteenth == Some(8) {
+            added(&mut symbol, "b13");
+        }
+        symbol
+    }
+
+    /// Extension of the chord: the highest of the ninth, eleventh and thirteenth.
+    fn extension(&self) -> ChordExtension {
+        let sixth = self.seventh.is_none() && self.thirteenth == Some(9);
+        if self.thirteenth.is_some() && !sixth {
+            ChordExtension::Thirteenth
+        } else if self.eleventh.is_some() {
+            ChordExtension::Eleventh
+        } else if self.ninth.is_some() {
+            ChordExtension::Ninth
+        } else {
+            ChordExtension::None
+        }
+    }
+}
+
+/// Alteration of a degree from its natural interval.
+fn alteration(degree: Option<i32>, natural: i32) -> ChordAlteration {
+    match degree {
+        Some(n) if n < natural => ChordAlteration::Diminished,
+        Some(n) if n > natural => ChordAlteration::Augmented,
+        _ => ChordAlteration::Perfect,
+    }
+}
+
+/// Recognize the chord of a set of MIDI pitches: the root, quality (`Chord::kind`),
+/// extension, alterations of the fifth, ninth and eleventh, added notes (`Chord::add`)
+/// and the bass, which is the lowest pitch. Every pitch class is tried as the root and
+/// the simplest reading explaining all the pitches wins, with the bass as root when two
+/// are as simple. The name is spelled with sharps or flats, with a slash bass when the
+/// bass is not the root. None for less than two pitch classes or when no reading
+/// explains them all.
+pub fn recognize_chord(pitches: &[u8], sharp: bool) -> Option<Chord> {
+    let bass = i32::from(*pitches.iter().min()?) % 12;
+    let classes: u16 = pitches.iter().fold(0, |set, p| set | 1 << (p % 12));
+    if classes.count_ones() < 2 {
+        return None;
+    }
+    let (root, degrees) = (0..12i32)
+        .filter(|root| classes & (1 << root) != 0)
+        .map(|root| {
+            let intervals = (0..12i32)
+                .filter(|p| classes & (1 << p) != 0)
+                .fold(0u16, |set, p| set | 1 << (p - root).rem_euclid(12));
+            let (degrees, unexplained) = Degrees::read(intervals);
+            let mut cost = degrees.cost() * 2 + unexplained.count_ones() as i32 * UNEXPLAINED_COST;
+            if root != bass {
+                cost += INVERSION_COST;
+            }
+            (cost, root, degrees)
+        })
+        .min_by_key(|(cost, root, _)| (*cost, *root != bass))
+        .filter(|(cost, _, _)| *cost < UNEXPLAINED_COST)
+        .map(|(_, root, degrees)| (root, degrees))?;
+
+    Some(chord_of(root, &degrees, bass, sharp))
+}
+
+/// Chord of the degrees over a root, with its bass and name.
+fn chord_of(root: i32, degrees: &Degrees, bass: i32, sharp: bool) -> Chord {
+    let kind = degrees.kind();
+    let seventh = matches!(degrees.seventh, Some(10) | Some(11));
+    let root_class = PitchClass::from_value(root, sharp);
+    let bass_class = PitchClass::from_value(bass, sharp);
+    let mut name = format!("{}{}", root_class, degrees.symbol(&kind));
+    if bass != root {
+        name.push('/');
+        name.push_str(&bass_class.to_string());
+    }
+    let fifth = match kind {
+        ChordType::Diminished | ChordType::Augmented => ChordAlteration::Perfect,
+        _ => alteration(degrees.fifth, 7),
+    };
+    let thirteenth = match kind {
+        ChordType::Diminished => degrees.seventh.map(|_| ChordAlteration::Perfect),
+        _ => degrees.thirteenth.map(|d| alteration(Some(d), 9)),
+    };
+    Chord {
+        sharp: Some(sharp),
+        root: Some(root_class),
+        kind: Some(kind),
+        extension: Some(degrees.extension()),
+        bass: Some(bass_class),
+        tonality: Some(ChordAlteration::Perfect),
+        add: Some(!seventh && (degrees.ninth.is_some() || degrees.eleventh.is_some())),
+        name,
+        fifth: Some(fifth),
+        ninth: Some(alteration(degrees.ninth, 2)),
+        eleventh: Some(alteration(degrees.eleventh, 5)),
+        thirteenth,
+        new_format: Some(true),
+        show: Some(true),
+        ..Default::default()
+    }
+}
+
+/// Pitch class of a note name at the start of a text (`C`, `F#`, `Bb`), whether it is
+/// sharp, flat or natural, and the rest of the text.
+fn parse_root(text: &str) -> Option<(i32, Option<bool>, &str)> {
+    let natural = match text.chars().next()? {
+        'C' => 0,
+        'D' => 2,
+        'E' => 4,
+        'F' => 5,
+        'G' => 7,
+        'A' => 9,
+        'B' => 11,
+        _ => return None,
+    };
+    let rest = &text[1..];
+    if let Some(rest) = rest.strip_prefix('#') {
+        Some(((natural + 1) % 12, Some(true), rest))
+    } else if let Some(rest) = rest.strip_prefix('b') {
+        Some(((natural + 11) % 12, Some(false), rest))
+    } else {
+        Some((natural, None, rest))
+    }
+}
+
+/// Number at the start of a text, and the rest of the text.
+fn parse_number(text: &str) -> (Option<u32>, &str) {
+    let end = text
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(text.len());
+    (text[..end].parse().ok(), &text[end..])
+}
+
+/// Degrees of a chord symbol after the root, None when a part is not understood.
+fn parse_symbol(symbol: &str) -> Option<Degrees> {
+    let symbol: String = symbol
+        .chars()
+        .filter(|c| !matches!(c, '(' | ')' | ',' | ' '))
+        .collect();
+    let symbol = symbol.replace("6/9", "69");
+    let mut d = Degrees {
+        third: Some(4),
+        fifth: Some(7),
+        ..Default::default()
+    };
+    let (mut major, mut diminished) = (false, false);
+    let mut rest = symbol.as_str();
+    let strip = |text: &'_ str, prefixes: &[&str]| -> Option<usize> {
+        prefixes
+            .iter()
+            .find(|p| text.starts_with(**p))
+            .map(|p| p.len())
+    };
+    while !rest.is_empty() {
+        if let Some(n) = strip(rest, &["maj", "Maj", "ma", "M", "Δ"]) {
+            major = true;
+            rest = &rest[n..];
+        } else if let Some(n) = strip(rest, &["min", "m", "-"]) {
+            d.third = Some(3);
+            rest = &rest[n..];
+        } else if let Some(n) = strip(rest, &["dim", "°", "o"]) {
+            (d.third, d.fifth, diminished) = (Some(3), Some(6), true);
+            rest = &rest[n..];
+        } else if let Some(n) = strip(rest, &["ø"]) {
+            (d.third, d.fifth, d.seventh) = (Some(3), Some(6), Some(10));
+            rest = rest[n..].strip_prefix('7').unwrap_or(&rest[n..]);
+        } else if let Some(n) = strip(rest, &["aug", "+"]) {
+            d.fifth = Some(8);
+            rest = &rest[n..];
+        } else if let Some(after) = rest.strip_prefix("sus") {
+            let (number, after) = parse_number(after);
+            d.third = match number {
+                Some(2) => Some(2),
+                Some(4) | None => Some(5),
+                _ => return None,
+            };
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix("add") {
+            let (number, after) = parse_number(after);
+            match number? {
+                2 | 9 => d.ninth = Some(2),
+                4 | 11 => d.eleventh = Some(5),
+                6 | 13 => d.thirteenth = Some(9),
+                _ => return None,
+            }
+            rest = after;
+        } else if rest.starts_with(['b', '#']) {
+            let flat = rest.starts_with('b');
+            let (number, after) = parse_number(&rest[1..]);
+            match (flat, number?) {
+                (true, 5) => d.fifth = Some(6),
+                (false, 5) => d.fifth = Some(8),
+                (true, 9) => d.ninth = Some(1),
+                (false, 9) => d.ninth = Some(3),
+                (false, 11) => d.eleventh = Some(6),
+                (true, 13) => d.thirteenth = Some(8),
+                _ => return None,
+            }
+            rest = after;
+        } else {
+            let (number, after) = parse_number(rest);
+            match number? {
+                5 => d.third = None,
+                6 => d.thirteenth = Some(9),
+                69 => (d.ninth, d.thirteenth) = (Some(2), Some(9)),
+                2 => d.ninth = Some(2),
+                4 => d.third = Some(5),
+                n @ (7 | 9 | 11 | 13) => {
+                    d.seventh = Some(if major {
+                        11
+                    } else if diminished && n == 7 {
+                        9
+                    } else {
+                        10
+                    });
+                    if n >= 9 {
+                        d.ninth.get_or_insert(2);
+                    }
+                    if n == 11 {
+                        d.eleventh.get_or_insert(5);
+                    }
+                    if n == 13 {
+                        d.thirteenth.get_or_insert(9);
+                    }
+                }
+                _ => return None,
+            }
+            rest = after;
+        }
+    }
+    Some(d)
+}
+
+/// Read a chord symbol such as `F#m7b5/A`, `Cmaj9`, `Bb7#9`, `Dsus4`, `E6/9` or
+/// `Gadd9/B`: root, quality (`Chord::kind`), extension, alterations and slash bass, the
+/// name kept as written. None when the symbol is not understood.
+pub fn parse_chord(name: &str) -> Option<Chord> {
+    let name = name.trim();
+    let (body, bass) = match name.rsplit_once('/') {
+        Some((body, bass)) => match parse_root(bass) {
+            Some((value, sharp, "")) => (body, Some((value, sharp))),
+            _ => (name, None),
+        },
+        None => (name, None),
+    };
+    let (root, root_sharp, symbol) = parse_root(body)?;
+    let degrees = parse_symbol(symbol)?;
+    let sharp = root_sharp.or(bass.and_then(|b| b.1)).unwrap_or(true);
+    let mut chord = chord_of(root, &degrees, bass.map_or(root, |b| b.0), sharp);
+    chord.name = name.to_string();
+    Some(chord)
+}
+
+/// Tones of a chord for its voicings: root, bass and the interval over the root of the
+/// root, third, fifth, seventh, ninth, eleventh and thirteenth (the order of
+/// `Chord::omissions`), with the ones a voicing may leave out.
+pub(super) struct ChordTones {
+    pub(super) root: i32,
+    pub(super) bass: i32,
+    pub(super) degrees: [Option<i32>; 7],
+    pub(super) optional: [bool; 7],
+}
+
+/// Tones of a chord from its root, quality, extension, alterations and added notes. The
+/// natural fifth of a major or minor chord and the natural ninth of a thirteenth chord
+/// are optional. None without a root.
+pub(super) fn chord_tones(chord: &Chord) -> Option<ChordTones> {
+    let root = i32::from(chord.root.as_ref()?.value).rem_euclid(12);
+    let bass = chord
+        .bass
+        .as_ref()
+        .map_or(root, |b| i32::from(b.value).rem_euclid(12));
+    let kind = chord.kind.clone().unwrap_or(ChordType::Major);
+    let extension = chord.extension.clone().unwrap_or(ChordExtension::None);
+    let altered = |alteration: &Option<ChordAlteration>, natural: i32| match alteration {
+        Some(ChordAlteration::Diminished) => natural - 1,
+        Some(ChordAlteration::Augmented) => natural + 1,
+        _ => natural,
+    };
+    let is_altered = |alteration: &Option<ChordAlteration>| {
+        matches!(
+            alteration,
+            Some(ChordAlteration::Diminished) | Some(ChordAlteration::Augmented)
+        )
+    };
+    let third = match kind {
+        ChordType::Major
+        | ChordType::Seventh
+        | ChordType::MajorSeventh
+        | ChordType::Sixth
+        | ChordType::Augmented => Some(4),
+        ChordType::Minor
+        | ChordType::MinorSeventh
+        | ChordType::MinorMajor
+        | ChordType::MinorSixth
+        | ChordType::Diminished => Some(3),
+        ChordType::SuspendedSecond | ChordType::SeventhSuspendedSecond => Some(2),
+        ChordType::SuspendedFourth | ChordType::SeventhSuspendedFourth => Some(5),
+        ChordType::Power | ChordType::Unknown(_) => None,
+    };
+    let fifth = match kind {
+        ChordType::Diminished => 6,
+        ChordType::Augmented => 8,
+        _ => altered(&chord.fifth, 7),
+    };
+    let seventh = match kind {
+        ChordType::Seventh
+        | ChordType::MinorSeventh
+        | ChordType::SeventhSuspendedSecond
+        | ChordType::SeventhSuspendedFourth => Some(10),
+        ChordType::MajorSeventh | ChordType::MinorMajor => Some(11),
+        // The diminished seventh, implied by the extensions
+        ChordType::Diminished
+            if chord.thirteenth.is_some()
+                || !matches!(extension, ChordExtension::None | ChordExtension::Unknown(_)) =>
+        {
+            Some(9)
+        }
+        _ => None,
+    };
+    // Added notes stand alone, the extensions of a seventh chord include the lower ones
+    let add = chord.add == Some(true);
+    let ninth = match extension {
+        ChordExtension::Ninth => true,
+        ChordExtension::Eleventh | ChordExtension::Thirteenth => !add || is_altered(&chord.ninth),
+        _ => false,
+    };
+    let eleventh = extension == ChordExtension::Eleventh
+        || (extension == ChordExtension::Thirteenth && is_altered(&chord.eleventh));
+    let thirteenth = kind != ChordType::Diminished
+        && (extension == ChordExtension::Thirteenth
+            || chord.thirteenth.is_some()
+            || matches!(kind, ChordType::Sixth | ChordType::MinorSixth));
+    let degrees = [
+        Some(0),
+        third,
+        Some(fifth),
+        seventh,
+        ninth.then(|| altered(&chord.ninth, 2)),
+        eleventh.then(|| altered(&chord.eleventh, 5)),
+        thirteenth.then(|| altered(&chord.thirteenth, 9)),
+    ];
+    let optional = [
+        false,
+        false,
+        fifth == 7 && matches!(third, Some(3) | Some(4)),
+        false,
+        degrees[4] == Some(2) && extension == ChordExtension::Thirteenth,
+        false,
+        false,
+    ];
+    Some(ChordTones {
+        root,
+        bass,
+        degrees,
+        optional,
+    })
+}
+
+/// Sounding pitches of the notes of some beats of a voice.
+fn beat_pitches(
+    song: &Song,
+    track: usize,
+    measure: usize,
+    voice: usize,
+    beats: Range<usize>,
+) -> Vec<u8> {
+    let Some(track) = song.tracks.get(track) else {
+        return Vec::new();
+    };
+    let Some(voice) = track
+        .measures
+        .get(measure)
+        .and_then(|m| m.voices.get(voice))
+    else {
+        return Vec::new();
+    };
+    voice
+        .beats
+        .get(beats)
+        .unwrap_or_default()
+        .iter()
+        .flat_map(|b| &b.notes)
+        .filter(|n| n.kind != NoteType::Rest && n.kind != NoteType::Dead)
+        .filter_map(|n| pitch(track, n.string, n.value))
+        .collect()
+}
+
+/// Whether the chord names of a measure are spelled with sharps, from its key signature.
+fn sharp_key(song: &Song, measure: usize) -> bool {
+    song.measure_headers
+        .get(measure)
+        .map_or(song.key.key, |h| h.key_signature.key)
+        >= 0
+}
+
+/// Recognize the chord played by a span of beats of a voice (see `recognize_chord`):
+/// one beat, or the beats of an arpeggio. Percussion tracks have no chords.
+pub fn span_chord(
+    song: &Song,
+    track: usize,
+    measure: usize,
+    voice: usize,
+    beats: Range<usize>,
+) -> Option<Chord> {
+    if song.tracks.get(track)?.percussion_track {
+        return None;
+    }
+    let pitches = beat_pitches(song, track, measure, voice, beats);
+    recognize_chord(&pitches, sharp_key(song, measure))
+}
+
+/// Label the chords of a track: in each measure, the beats of the first voice are
+/// gathered until they play three pitch classes (a strummed chord, or the notes of an
+/// arpeggio) and the chord they form is set on the first beat of the group when it has
+/// none and the chord changed. Returns the number of chords added.
+pub fn label_chords(song: &mut Song, track: usize) -> usize {
+    let Some(t) = song.tracks.get(track) else {
+        return 0;
+    };
+    if t.percussion_track {
+        return 0;
+    }
+    let mut labels: Vec<(usize, usize, Chord)> = Vec::new();
+    let mut previous: Option<String> = None;
+    for (m, measure) in t.measures.iter().enumerate() {
+        let Some(voice) = measure.voices.first() else {
+            continue;
+        };
+        let mut start = 0;
+        while start < voice.beats.len() {
+            // Beats up to three pitch classes, or to the end of the measure
+            let mut end = start + 1;
+            loop {
+                let classes: u16 = beat_pitches(song, track, m, 0, start..end)
+                    .iter()
+                    .fold(0, |set, p| set | 1 << (p % 12));
+                if classes.count_ones() >= 3 || end == voice.beats.len() {
+                    break;
+                }
+                end += 1;
+            }
+            let written = voice.beats[start].effect.chord.as_ref();
+            if let Some(chord) = written {
+                previous = Some(chord.name.clone());
+            } else if let Some(chord) = span_chord(song, track, m, 0, start..end) {
+                if previous.as_ref() != Some(&chord.name) {
+                    previous = Some(chord.name.clone());
+                    labels.push((m, start, chord));
+                }
+            }
+            start = end;
+        }
+    }
+    let count = labels.len();
+    let t = &mut song.tracks[track];
+    for (m, b, chord) in labels {
+        t.measures[m].voices[0].beats[b].effect.chord = Some(chord);
+    }
+    count
+}
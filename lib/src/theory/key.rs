@@ -0,0 +1,197 @@
+// Key detection: key and mode of a song or of its sections from the pitches played
+use std::ops::Range;
+
+use crate::audio::timeline::events;
+use crate::model::key_signature::{KeySignature, DURATION_QUARTER_TIME};
+use crate::model::song::Song;
+use crate::types::chord::PitchClass;
+
+/// Weight of each pitch class over the tonic in a major key (Krumhansl and Kessler).
+const MAJOR_PROFILE: [f64; 12] = [
+    6.35, 2.23, 3.48, 2.33, 4.38, 4.09, 2.52, 5.19, 2.39, 3.66, 2.29, 2.88,
+];
+/// Weight of each pitch class over the tonic in a minor key (Krumhansl and Kessler).
+const MINOR_PROFILE: [f64; 12] = [
+    6.33, 2.68, 3.52, 5.38, 2.60, 3.53, 2.54, 4.75, 3.98, 2.69, 3.34, 3.17,
+];
+
+/// A key found in the notes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyEstimate {
+    /// Tonic of the key
+    pub tonic: PitchClass,
+    /// Key signature of the key, with the mode in `KeySignature::is_minor`
+    pub key_signature: KeySignature,
+    /// Correlation of the pitch classes played with the profile of the key, from -1 to 1
+    pub correlation: f64,
+    /// How far the key is ahead of the next likely one, from 0 (as likely) to 1
+    pub confidence: f64,
+}
+
+impl std::fmt::Display for KeyEstimate {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let mode = if self.key_signature.is_minor {
+            "minor"
+        } else {
+            "major"
+        };
+        write!(f, "{} {}", self.tonic, mode)
+    }
+}
+
+/// A section of the song, from a marker to the next one, and its key.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SectionKey {
+    /// Title of the marker starting the section, None before the first marker
+    pub title: Option<String>,
+    /// Indexes of the measure headers of the section
+    pub measures: Range<usize>,
+    /// Key of the section, None when it plays no pitched note
+    pub key: Option<KeyEstimate>,
+}
+
+/// Pearson correlation of two profiles, NaN when one of them is flat.
+fn correlation(a: &[f64; 12], b: &[f64; 12]) -> f64 {
+    let (mean_a, mean_b) = (a.iter().sum::<f64>() / 12.0, b.iter().sum::<f64>() / 12.0);
+    let (mut covariance, mut var_a, mut var_b) = (0.0, 0.0, 0.0);
+    for (x, y) in a.iter().zip(b) {
+        covariance += (x - mean_a) * (y - mean_b);
+        var_a += (x - mean_a) * (x - mean_a);
+        var_b += (y - mean_b) * (y - mean_b);
+    }
+    covariance / (var_a * var_b).sqrt()
+}
+
+/// Key signature of a key: the one of its relative major, 6 sharps written as 6 flats.
+fn signature(tonic: i32, minor: bool) -> KeySignature {
+    let major = if minor { tonic + 3 } else { tonic };
+    // Each fifth up adds a sharp
+    let mut key = (major * 7).rem_euclid(12);
+    if key >= 6 {
+        key -= 12;
+    }
+    KeySignature {
+        key: key as i8,
+        is_minor: minor,
+    }
+}
+
+/// Estimate the key of a pitch class profile (the time each pitch class sounds, from C):
+/// the major or minor key whose Krumhansl-Kessler profile correlates best with it. The
+/// confidence compares the best correlation `r1` with the next one `r2`:
+/// `(r1 - r2) / (1 - r2)`. None when every pitch class sounds as long.
+pub fn estimate_key(durations: &[f64; 12]) -> Option<KeyEstimate> {
+    let mut scores: Vec<(f64, i32, bool)> = Vec::with_capacity(24);
+    for tonic in 0..12i32 {
+        for (minor, profile) in [(false, &MAJOR_PROFILE), (true, &MINOR_PROFILE)] {
+            let rotated: [f64; 12] =
+                std::array::from_fn(|p| profile[(p as i32 - tonic).rem_euclid(12) as usize]);
+            let r = correlation(durations, &rotated);
+            if !r.is_finite() {
+                return None;
+            }
+            scores.push((r, tonic, minor));
+        }
+    }
+    scores.sort_by(|a, b| b.0.total_cmp(&a.0));
+    let (best, tonic, minor) = scores[0];
+    let next = scores[1].0;
+    let confidence = if next < 1.0 {
+        ((best - next) / (1.0 - next)).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    let key_signature = signature(tonic, minor);
+    Some(KeyEstimate {
+        tonic: PitchClass::from_value(tonic, key_signature.key >= 0),
+        key_signature,
+        correlation: best,
+        confidence,
+    })
+}
+
+/// Time in quarter notes each pitch class sounds in each measure, the notes of the
+/// pitched tracks counted in the measure they start in.
+fn measure_durations(song: &Song) -> Vec<[f64; 12]> {
+    let mut durations = vec![[0.0; 12]; song.measure_headers.len()];
+    for event in events(song) {
+        if song.tracks[event.track].percussion_track {
+            continue;
+        }
+        if let Some(measure) = durations.get_mut(event.measure) {
+            measure[usize::from(event.pitch % 12)] +=
+                event.duration as f64 / DURATION_QUARTER_TIME as f64;
+        }
+    }
+    durations
+}
+
+/// Key of some measures from their durations.
+fn key_of(durations: &[[f64; 12]], measures: Range<usize>) -> Option<KeyEstimate> {
+    let mut total = [0.0; 12];
+    for measure in &durations[measures] {
+        for (t, d) in total.iter_mut().zip(measure) {
+            *t += d;
+        }
+    }
+    estimate_key(&total)
+}
+
+/// Key of the whole song, from the time each pitch class sounds in the pitched tracks
+/// (see `estimate_key`). None when the song plays no pitched note.
+pub fn song_key(song: &Song) -> Option<KeyEstimate> {
+    key_of(&measure_durations(song), 0..song.measure_headers.len())
+}
+
+/// Key of each section of the song: the measures before the first marker, then the
+/// measures from each marker to the next one.
+pub fn section_keys(song: &Song) -> Vec<SectionKey> {
+    let durations = measure_durations(song);
+    let headers = &song.measure_headers;
+    let mut starts: Vec<usize> = (0..headers.len())
+        .filter(|m| *m == 0 || headers[*m].marker.is_some())
+        .collect();
+    starts.push(headers.len());
+    starts
+        .windows(2)
+        .map(|w| SectionKey {
+            title: headers[w[0]].marker.as_ref().map(|m| m.title.clone()),
+            measures: w[0]..w[1],
+            key: key_of(&durations, w[0]..w[1]),
+        })
+        .collect()
+}
+
+/// Write the detected keys to the key signatures of the measure headers and of the track
+/// measures: the key of the song everywhere, or the key of each section when
+/// `per_section` (sections without pitched notes keep theirs). `Song::key` is set to the
+/// key of the song. Returns the number of measure headers whose key signature changed.
+pub fn apply_keys(song: &mut Song, per_section: bool) -> usize {
+    let Some(whole) = song_key(song) else {
+        return 0;
+    };
+    let keys: Vec<(Range<usize>, KeySignature)> = if per_section {
+        section_keys(song)
+            .into_iter()
+            .filter_map(|s| s.key.map(|k| (s.measures, k.key_signature)))
+            .collect()
+    } else {
+        vec![(0..song.measure_headers.len(), whole.key_signature.clone())]
+    };
+    let mut changed = 0;
+    for (measures, key) in keys {
+        for m in measures {
+            if song.measure_headers[m].key_signature != key {
+                song.measure_headers[m].key_signature = key.clone();
+                changed += 1;
+            }
+            for track in &mut song.tracks {
+                if let Some(measure) = track.measures.get_mut(m) {
+                    measure.key_signature = key.clone();
+                }
+            }
+        }
+    }
+    song.key = whole.key_signature;
+    changed
+}
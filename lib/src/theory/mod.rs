@@ -0,0 +1,3 @@
+pub mod chord;
+pub mod voicing;
+pub mod key;
@@ -0,0 +1,314 @@
+// Chord voicings: playable diagrams of a chord on a tuning
+use std::cmp::Reverse;
+use std::collections::HashSet;
+
+use super::chord::{chord_tones, parse_chord, ChordTones};
+use crate::edit::fingering::HAND_SPAN;
+use crate::model::track::Track;
+use crate::types::chord::{Barre, Chord};
+use crate::types::enums::note::Fingering;
+
+/// Voicings kept for a chord, the most playable ones.
+const MAX_VOICINGS: usize = 16;
+/// Highest fret of the index finger: higher voicings repeat lower ones an octave up.
+const MAX_POSITION: i32 = 12;
+/// Cost of a barre, which needs more strength than single fingers.
+const BARRE_COST: i32 = 2;
+/// Cost of a muted string between two played strings.
+const INNER_MUTE_COST: i32 = 4;
+/// Cost of a muted string under or over the played ones, for fuller voicings.
+const OUTER_MUTE_COST: i32 = 2;
+/// Cost of an optional tone left out.
+const OMISSION_COST: i32 = 2;
+
+/// A playable diagram and its cost.
+struct Voicing {
+    cost: i32,
+    frets: Vec<i8>,
+    barre: Option<Barre>,
+    fingerings: Vec<Fingering>,
+    omissions: Vec<bool>,
+}
+
+/// Search of the diagrams of a chord in a window of frets.
+struct Search<'a> {
+    tones: &'a ChordTones,
+    /// Pitch classes of the chord (bit `n` for pitch class `n`)
+    classes: u16,
+    strings: &'a [(i8, i8)],
+    capo: i32,
+    /// Frets the fingers can reach, besides the open strings
+    window: (i32, i32),
+    found: HashSet<Vec<i8>>,
+}
+
+impl Search<'_> {
+    /// Choose the fret of the strings from `index` down to the first one (the highest),
+    /// the strings below being set in `frets`. The lowest played string plays the bass.
+    fn run(&mut self, index: usize, frets: &mut Vec<i8>, bass_played: bool) {
+        if index == 0 {
+            self.found.insert(frets.clone());
+            return;
+        }
+        let string = index - 1;
+        self.run(string, frets, bass_played);
+        let (low, high) = self.window;
+        for fret in std::iter::once(0).chain(low..=high) {
+            let class = (i32::from(self.strings[string].1) + self.capo + fret).rem_euclid(12);
+            let allowed = if bass_played {
+                self.classes & (1 << class) != 0
+            } else {
+                class == self.tones.bass
+            };
+            if allowed {
+                frets[string] = fret as i8;
+                self.run(string, frets, true);
+                frets[string] = -1;
+            }
+        }
+    }
+}
+
+/// Fingers of the fretted notes, from the lowest fret and the lowest string, with or
+/// without a barre of the index finger on the lowest fret. The barre needs two strings or
+/// more on that fret and the strings between them fretted. None when four fingers cannot
+/// play the notes, or two fingers on a fret would cross a finger between them.
+fn assign(frets: &[i8], barred: bool) -> Option<(Option<Barre>, Vec<Fingering>)> {
+    let mut fingerings = vec![Fingering::Open; frets.len()];
+    let fretted: Vec<(usize, i8)> = frets
+        .iter()
+        .enumerate()
+        .filter(|(_, f)| **f > 0)
+        .map(|(s, f)| (s, *f))
+        .collect();
+    let Some(low) = fretted.iter().map(|n| n.1).min() else {
+        return Some((None, fingerings));
+    };
+    let mut barre = None;
+    let mut next = 1;
+    if barred {
+        let at_low: Vec<usize> = fretted.iter().filter(|n| n.1 == low).map(|n| n.0).collect();
+        let (first, last) = (at_low[0], at_low[at_low.len() - 1]);
+        if at_low.len() < 2 || frets[first..=last].iter().any(|f| *f < low) {
+            return None;
+        }
+        for &s in &at_low {
+            fingerings[s] = Fingering::Index;
+        }
+        barre = Some(Barre {
+            fret: low,
+            start: first as i8 + 1,
+            end: last as i8 + 1,
+        });
+        next = 2;
+    }
+    let mut rest: Vec<(usize, i8)> = fretted
+        .into_iter()
+        .filter(|n| !barred || n.1 > low)
+        .collect();
+    rest.sort_by_key(|n| (n.1, Reverse(n.0)));
+    let mut previous: Option<(usize, i8)> = None;
+    for (s, fret) in rest {
+        // Two fingers far apart on a fret, a finger on a higher fret between them
+        if previous.is_some_and(|p| {
+            p.1 == fret && p.0 - s > 2 && frets[s + 1..p.0].iter().any(|f| *f > fret)
+        }) {
+            return None;
+        }
+        previous = Some((s, fret));
+        let finger = next.max(1 + i32::from(fret - low));
+        fingerings[s] = match finger {
+            1 => Fingering::Index,
+            2 => Fingering::Middle,
+            3 => Fingering::Annular,
+            4 => Fingering::Little,
+            _ => return None,
+        };
+        next = finger + 1;
+    }
+    Some((barre, fingerings))
+}
+
+/// Check a diagram and rate how hard it is to play: the height on the neck, the stretch,
+/// the fingers, the barre, the muted strings and the tones left out. None when the bass
+/// is not the lowest pitch, a tone the chord needs is missing, less than three strings are played (unless there are
+/// fewer strings) or the diagram needs more than four fingers.
+fn rate(frets: Vec<i8>, search: &Search) -> Option<Voicing> {
+    let played: Vec<usize> = (0..frets.len()).filter(|s| frets[*s] >= 0).collect();
+    if played.len() < frets.len().min(3) {
+        return None;
+    }
+    let tones = search.tones;
+    let mut present = 0u16;
+    let mut lowest = i32::MAX;
+    for &s in &played {
+        let pitch = i32::from(search.strings[s].1) + search.capo + i32::from(frets[s]);
+        present |= 1 << (pitch - tones.root).rem_euclid(12);
+        lowest = lowest.min(pitch);
+    }
+    if lowest.rem_euclid(12) != tones.bass {
+        return None;
+    }
+    let mut omissions = vec![true; 7];
+    let mut omitted = 0;
+    for (d, degree) in tones.degrees.iter().enumerate() {
+        let Some(interval) = degree else {
+            continue;
+        };
+        if present & (1 << interval.rem_euclid(12)) != 0 {
+            omissions[d] = false;
+        } else if tones.optional[d] {
+            omitted += 1;
+        } else {
+            return None;
+        }
+    }
+    let (first, last) = (played[0], played[played.len() - 1]);
+    let inner = (first..=last).filter(|s| frets[*s] < 0).count() as i32;
+    if inner > 1 {
+        return None;
+    }
+    let outer = frets.len() as i32 - played.len() as i32 - inner;
+    let (barre, fingerings) = assign(&frets, false).or_else(|| assign(&frets, true))?;
+    let fretted: Vec<i32> = frets
+        .iter()
+        .filter(|f| **f > 0)
+        .map(|f| i32::from(*f))
+        .collect();
+    let (low, high) = (
+        fretted.iter().min().copied().unwrap_or(0),
+        fretted.iter().max().copied().unwrap_or(0),
+    );
+    let used = [
+        Fingering::Index,
+        Fingering::Middle,
+        Fingering::Annular,
+        Fingering::Little,
+    ]
+    .iter()
+    .filter(|finger| fingerings.contains(finger))
+    .count() as i32;
+    let cost = low
+        + (high - low)
+        + used
+        + if barre.is_some() { BARRE_COST } else { 0 }
+        + INNER_MUTE_COST * inner
+        + OUTER_MUTE_COST * outer
+        + OMISSION_COST * omitted;
+    Some(Voicing {
+        cost,
+        frets,
+        barre,
+        fingerings,
+        omissions,
+    })
+}
+
+/// Playable voicings of a chord on an instrument tuned to `strings` (string number from 1
+/// and MIDI pitch of the open string) with `fret_count` frets and a capo on fret `capo`,
+/// the easiest first, at most sixteen.
+///
+/// The tones come from the root, quality, extension, alterations, added notes and bass of
+/// the chord (see `parse_chord` to get them from a name); the bass is the lowest pitch.
+/// A voicing has every tone but the natural fifth of a major or minor chord and the
+/// natural ninth of a thirteenth chord, on at least three strings, with at most one muted
+/// string between played ones. Its fretted notes are within four frets for four fingers,
+/// the index finger barring the lowest fret when single fingers cannot play them.
+/// Voicings low on the neck, with little stretch, few fingers and many strings come
+/// first.
+///
+/// Each voicing is a copy of the chord with its diagram: `Chord::strings` (the fret of
+/// each string, -1 when muted), `first_fret`, `barres`, `fingerings` (`Fingering::Open`
+/// for open and muted strings) and `omissions` (root, third, fifth, seventh, ninth,
+/// eleventh and thirteenth not played). Empty when the chord has no root.
+pub fn chord_voicings(
+    chord: &Chord,
+    strings: &[(i8, i8)],
+    fret_count: u8,
+    capo: i32,
+) -> Vec<Chord> {
+    let Some(tones) = chord_tones(chord) else {
+        return Vec::new();
+    };
+    let classes = tones
+        .degrees
+        .iter()
+        .flatten()
+        .fold(0u16, |set, d| set | 1 << (tones.root + d).rem_euclid(12));
+    let max = i32::from(fret_count) - capo;
+    let mut search = Search {
+        tones: &tones,
+        classes,
+        strings,
+        capo,
+        window: (1, 0),
+        found: HashSet::new(),
+    };
+    for low in 1..=MAX_POSITION.min(max) {
+        search.window = (low, (low + HAND_SPAN - 1).min(max));
+        search.run(strings.len(), &mut vec![-1; strings.len()], false);
+    }
+    let found: Vec<Vec<i8>> = search.found.iter().cloned().collect();
+    let mut voicings: Vec<Voicing> = found.into_iter().filter_map(|f| rate(f, &search)).collect();
+    voicings.sort_by(|a, b| (a.cost, &a.frets).cmp(&(b.cost, &b.frets)));
+    voicings.truncate(MAX_VOICINGS);
+    voicings
+        .into_iter()
+        .map(|voicing| {
+            let fretted = voicing.frets.iter().filter(|f| **f > 0);
+            let (low, high) = (
+                fretted.clone().min().copied().unwrap_or(1),
+                fretted.max().copied().unwrap_or(1),
+            );
+            let mut chord = chord.clone();
+            chord.length = strings.len() as u8;
+            chord.first_fret = Some(if i32::from(high) <= HAND_SPAN + 1 {
+                1
+            } else {
+                low as u8
+            });
+            chord.strings = voicing.frets;
+            chord.barres = voicing.barre.into_iter().collect();
+            chord.fingerings = voicing.fingerings;
+            chord.omissions = voicing.omissions;
+            chord
+        })
+        .collect()
+}
+
+/// Add a diagram, the easiest voicing, to the chords of a track that have a name but no
+/// diagram. Chords without root are read from their name (see `parse_chord`). Returns
+/// the number of diagrams added. Percussion tracks have no chords.
+pub fn add_chord_diagrams(track: &mut Track) -> usize {
+    if track.percussion_track {
+        return 0;
+    }
+    let (strings, fret_count, capo) = (track.strings.clone(), track.fret_count, track.offset);
+    let mut count = 0;
+    for measure in &mut track.measures {
+        for voice in &mut measure.voices {
+            for beat in &mut voice.beats {
+                let Some(chord) = beat.effect.chord.as_mut() else {
+                    continue;
+                };
+                if chord.strings.iter().any(|f| *f >= 0) {
+                    continue;
+                }
+                let source = match chord.root {
+                    Some(_) => Some(chord.clone()),
+                    None => parse_chord(&chord.name),
+                };
+                let voicing = source.and_then(|c| {
+                    chord_voicings(&c, &strings, fret_count, capo)
+                        .into_iter()
+                        .next()
+                });
+                if let Some(voicing) = voicing {
+                    *chord = voicing;
+                    count += 1;
+                }
+            }
+        }
+    }
+    count
+}
@@ -16,6 +16,8 @@ pub struct Beat {
     pub octave: Octave,
     pub display: BeatDisplay,
     pub status: BeatStatus,
+    /// The beat holds grace notes leading to the next beat (GP6/7 grace beat)
+    pub grace: bool,
 }
 
 impl Default for Beat {
@@ -30,6 +32,7 @@ impl Default for Beat {
             octave: Octave::None,
             display: BeatDisplay::default(),
             status: BeatStatus::Normal,
+            grace: false,
         }
     }
 }
@@ -53,4 +56,9 @@ impl Beat {
         }
         false
     }
+
+    /// A beat of grace notes (GP6/7 grace beats): it takes no time in the measure.
+    pub(crate) fn is_grace_beat(&self) -> bool {
+        self.grace && !self.notes.is_empty()
+    }
 }
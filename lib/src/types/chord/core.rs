@@ -19,6 +19,9 @@ pub struct Chord {
     pub fifth: Option<ChordAlteration>,
     pub ninth: Option<ChordAlteration>,
     pub eleventh: Option<ChordAlteration>,
+    /// Alteration of the thirteenth, None without one (not stored by GP3-5). On a
+    /// diminished chord the natural thirteenth is its diminished seventh.
+    pub thirteenth: Option<ChordAlteration>,
     pub first_fret: Option<u8>,
     pub strings: Vec<i8>,
     pub barres: Vec<Barre>,
@@ -54,7 +54,6 @@ impl PitchClass {
         p
     }
 
-    #[allow(dead_code)]
     pub(crate) fn from_note(note: String) -> PitchClass {
         let mut p = PitchClass {
             note,
@@ -80,6 +79,25 @@ impl PitchClass {
         p.value = p.just + p.accidental;
         p
     }
+
+    /// The pitch class of a value (0 for C to 11 for B), spelled with sharps or flats.
+    pub(crate) fn from_value(value: i32, sharp: bool) -> PitchClass {
+        let value = value.rem_euclid(12) as usize;
+        let note = if sharp {
+            SHARP_NOTES[value]
+        } else {
+            FLAT_NOTES[value]
+        };
+        PitchClass {
+            sharp,
+            ..PitchClass::from_note(note.to_string())
+        }
+    }
+
+    /// The pitch class `semitones` higher, spelled with sharps or flats.
+    pub(crate) fn transposed(&self, semitones: i32, sharp: bool) -> PitchClass {
+        PitchClass::from_value(i32::from(self.value) + semitones, sharp)
+    }
 }
 
 impl std::fmt::Display for PitchClass {
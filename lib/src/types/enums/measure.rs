@@ -33,8 +33,9 @@ pub(crate) fn from_triplet_feel(value: &TripletFeel) -> u8 {
 /// An enumeration of available clefs
 #[allow(dead_code)]
 #[repr(u8)]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
 pub enum MeasureClef {
+    #[default]
     Treble,
     Bass,
     Tenor,
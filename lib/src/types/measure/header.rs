@@ -12,6 +12,9 @@ pub struct MeasureHeader {
     pub start: i64,
     pub time_signature: TimeSignature,
     pub tempo: i32,
+    /// The tempo changes gradually from `tempo` to the next tempo change (linear GPIF
+    /// automation)
+    pub tempo_linear: bool,
     pub marker: Option<Marker>,
     pub repeat_open: bool,
     pub repeat_alternative: u8,
@@ -33,6 +36,7 @@ impl Default for MeasureHeader {
             number: 1,
             start: DURATION_QUARTER_TIME,
             tempo: 0,
+            tempo_linear: false,
             repeat_open: false,
             repeat_alternative: 0,
             repeat_close: -1,
@@ -40,7 +40,12 @@ impl Note {
         if self.string > 0 {
             let index = (self.string as usize).saturating_sub(1);
             if index < strings.len() {
-                return Ok(self.value.to_i8().unwrap_or(0) + strings[index].1);
+                let value =
+                    i16::from(self.value.to_i8().unwrap_or(0)) + i16::from(strings[index].1);
+                return i8::try_from(value).map_err(|_| GpError::InvalidValue {
+                    context: "real note value",
+                    value: value.into(),
+                });
             }
         }
         Err(GpError::InvalidValue {
@@ -0,0 +1,176 @@
+// Checks of the score validation
+use std::collections::{HashMap, HashSet};
+
+use super::diagnostic::{Diagnostic, Issue, Location};
+use crate::model::song::Song;
+use crate::model::track::Track;
+use crate::types::enums::{BeatStatus, NoteType};
+
+/// Diagnostics of a song, gathered by the checks.
+struct Validator {
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Validator {
+    fn report(&mut self, location: Location, issue: Issue) {
+        self.diagnostics.push(Diagnostic {
+            severity: issue.severity(),
+            location,
+            issue,
+        });
+    }
+
+    /// Opening repeats without closing bar, alternate endings outside repeated sections.
+    fn repeats(&mut self, song: &Song) {
+        let headers = &song.measure_headers;
+        // Sections from an opening bar (or the start) to the next one
+        let mut starts: Vec<usize> = (0..headers.len())
+            .filter(|m| *m == 0 || headers[*m].repeat_open)
+            .collect();
+        starts.push(headers.len());
+        for section in starts.windows(2) {
+            let measures = section[0]..section[1];
+            let closed = measures.clone().any(|m| headers[m].repeat_close >= 0);
+            if headers[section[0]].repeat_open && !closed {
+                self.report(
+                    Location {
+                        measure: Some(section[0]),
+                        ..Default::default()
+                    },
+                    Issue::UnclosedRepeat,
+                );
+            }
+            if !closed {
+                for m in measures.filter(|m| headers[*m].repeat_alternative != 0) {
+                    self.report(
+                        Location {
+                            measure: Some(m),
+                            ..Default::default()
+                        },
+                        Issue::EndingWithoutRepeat {
+                            endings: headers[m].repeat_alternative,
+                        },
+                    );
+                }
+            }
+        }
+    }
+
+    /// Measure count, channel, durations of the voices and notes of a track.
+    fn track(&mut self, song: &Song, t: usize, track: &Track) {
+        let at = |measure: Option<usize>, voice: Option<usize>, beat: Option<usize>| Location {
+            track: Some(t),
+            measure,
+            voice,
+            beat,
+            note: None,
+        };
+        if track.measures.len() != song.measure_headers.len() {
+            self.report(
+                at(None, None, None),
+                Issue::MeasureCount {
+                    headers: song.measure_headers.len(),
+                    measures: track.measures.len(),
+                },
+            );
+        }
+        if track.channel_index >= song.channels.len() {
+            self.report(
+                at(None, None, None),
+                Issue::ChannelOutOfRange {
+                    index: track.channel_index,
+                    channels: song.channels.len(),
+                },
+            );
+        }
+        // Strings sounding at the end of the previous beat of each voice
+        let mut sounding: HashMap<usize, HashSet<i8>> = HashMap::new();
+        for (m, measure) in track.measures.iter().enumerate() {
+            let header = song.measure_headers.get(m);
+            for (v, voice) in measure.voices.iter().enumerate() {
+                let used = voice.beats.iter().any(|b| b.status != BeatStatus::Empty);
+                let found: i64 = voice
+                    .beats
+                    .iter()
+                    .filter(|b| !b.is_grace_beat())
+                    .map(|b| i64::from(b.duration.time()))
+                    .sum();
+                if let Some(header) = header.filter(|h| used && !h.free_time) {
+                    if found != header.length() {
+                        self.report(
+                            at(Some(m), Some(v), None),
+                            Issue::VoiceDuration {
+                                expected: header.length(),
+                                found,
+                            },
+                        );
+                    }
+                }
+                for (b, beat) in voice.beats.iter().enumerate() {
+                    if beat.status == BeatStatus::Empty {
+                        continue;
+                    }
+                    let mut strings: HashSet<i8> = HashSet::new();
+                    for (n, note) in beat.notes.iter().enumerate() {
+                        if note.kind == NoteType::Rest {
+                            continue;
+                        }
+                        let location = Location {
+                            note: Some(n),
+                            ..at(Some(m), Some(v), Some(b))
+                        };
+                        let issues = [
+                            (note.string < 1 || note.string as usize > track.strings.len())
+                                .then_some(Issue::StringOutOfRange {
+                                    string: note.string,
+                                    strings: track.strings.len(),
+                                }),
+                            (!strings.insert(note.string)).then_some(Issue::DuplicateString {
+                                string: note.string,
+                            }),
+                            (note.value < 0).then_some(Issue::NegativeFret { fret: note.value }),
+                            (!track.percussion_track && note.value > i16::from(track.fret_count))
+                                .then_some(Issue::FretAboveNeck {
+                                    fret: note.value,
+                                    fret_count: track.fret_count,
+                                }),
+                            (note.kind == NoteType::Tie
+                                && !sounding.get(&v).is_some_and(|s| s.contains(&note.string)))
+                            .then_some(Issue::TieWithoutNote {
+                                string: note.string,
+                            }),
+                        ];
+                        for issue in issues.into_iter().flatten() {
+                            self.report(location.clone(), issue);
+                        }
+                    }
+                    if !beat.is_grace_beat() {
+                        sounding.insert(v, strings);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Check a song for the problems of damaged or hand-made files, returned in the order
+/// of the song: repeats first, then each track, measure, voice, beat and note.
+///
+/// - Every track has a measure per measure header and a channel in `Song::channels`.
+/// - The beats of each used voice (grace beats left out) fill the time signature of the
+///   measure, unless it is in free time.
+/// - Notes are on a string of their track, one per string in a beat, on a fret from 0
+///   to `Track::fret_count` (percussion notes may be higher).
+/// - Tied notes follow a note on their string in the previous beat of their voice.
+/// - Opening repeat bars are closed before the next opening one, and alternate endings
+///   are in a section with a closing bar.
+pub fn validate(song: &Song) -> Vec<Diagnostic> {
+    let mut validator = Validator {
+        diagnostics: Vec::new(),
+    };
+    validator.repeats(song);
+    for (t, track) in song.tracks.iter().enumerate() {
+        validator.track(song, t, track);
+    }
+    validator.diagnostics
+}
@@ -0,0 +1,144 @@
+// Diagnostics of the score validation: what is wrong and where
+use std::fmt;
+
+/// How serious a problem is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// The score plays, but not as the author likely meant
+    Warning,
+    /// Readers, writers or players may fail or index out of bounds
+    Error,
+}
+
+/// Where a problem is: indexes from 0, None above the level of the problem. Displayed
+/// numbered from 1, e.g. `track 2, measure 37, voice 1, beat 4`, or `song`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Location {
+    /// Index of the track in `Song::tracks`
+    pub track: Option<usize>,
+    /// Index of the measure in `Song::measure_headers` and `Track::measures`
+    pub measure: Option<usize>,
+    /// Index of the voice in `Measure::voices`
+    pub voice: Option<usize>,
+    /// Index of the beat in `Voice::beats`
+    pub beat: Option<usize>,
+    /// Index of the note in `Beat::notes`
+    pub note: Option<usize>,
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let parts: Vec<String> = [
+            ("track", self.track),
+            ("measure", self.measure),
+            ("voice", self.voice),
+            ("beat", self.beat),
+            ("note", self.note),
+        ]
+        .iter()
+        .filter_map(|(name, index)| index.map(|i| format!("{} {}", name, i + 1)))
+        .collect();
+        if parts.is_empty() {
+            write!(f, "song")
+        } else {
+            write!(f, "{}", parts.join(", "))
+        }
+    }
+}
+
+/// A problem found in a score.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Issue {
+    /// The track does not have a measure for each measure header
+    MeasureCount { headers: usize, measures: usize },
+    /// The beats of the voice do not fill the time signature (lengths in ticks)
+    VoiceDuration { expected: i64, found: i64 },
+    /// The fret of the note is above the frets of the track
+    FretAboveNeck { fret: i16, fret_count: u8 },
+    /// The fret of the note is negative
+    NegativeFret { fret: i16 },
+    /// The string of the note is not one of the strings of the track
+    StringOutOfRange { string: i8, strings: usize },
+    /// Another note of the beat is on the same string
+    DuplicateString { string: i8 },
+    /// The tied note has no note on its string in the previous beat of the voice
+    TieWithoutNote { string: i8 },
+    /// The opening repeat bar has no closing bar before the next opening one
+    UnclosedRepeat,
+    /// The alternate ending (bit `n` for ending `n + 1`) is not in a repeated section
+    EndingWithoutRepeat { endings: u8 },
+    /// The channel of the track is not one of the channels of the song
+    ChannelOutOfRange { index: usize, channels: usize },
+}
+
+impl Issue {
+    /// Severity of the problem: an error when it can make a tool fail.
+    pub fn severity(&self) -> Severity {
+        match self {
+            Issue::VoiceDuration { .. }
+            | Issue::TieWithoutNote { .. }
+            | Issue::UnclosedRepeat
+            | Issue::EndingWithoutRepeat { .. } => Severity::Warning,
+            _ => Severity::Error,
+        }
+    }
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Issue::MeasureCount { headers, measures } => {
+                write!(f, "{} measures for {} measure headers", measures, headers)
+            }
+            Issue::VoiceDuration { expected, found } => {
+                write!(f, "beats last {} ticks instead of {}", found, expected)
+            }
+            Issue::FretAboveNeck { fret, fret_count } => {
+                write!(f, "fret {} above the {} frets", fret, fret_count)
+            }
+            Issue::NegativeFret { fret } => write!(f, "negative fret {}", fret),
+            Issue::StringOutOfRange { string, strings } => {
+                write!(f, "string {} of a track with {} strings", string, strings)
+            }
+            Issue::DuplicateString { string } => {
+                write!(f, "string {} already played in the beat", string)
+            }
+            Issue::TieWithoutNote { string } => {
+                write!(f, "tie on string {} without a previous note", string)
+            }
+            Issue::UnclosedRepeat => write!(f, "repeat never closed"),
+            Issue::EndingWithoutRepeat { endings } => {
+                let numbers: Vec<String> = (0..8)
+                    .filter(|n| endings & (1 << n) != 0)
+                    .map(|n| (n + 1).to_string())
+                    .collect();
+                write!(
+                    f,
+                    "alternate ending {} outside a repeat",
+                    numbers.join(", ")
+                )
+            }
+            Issue::ChannelOutOfRange { index, channels } => {
+                write!(f, "channel {} of {} channels", index, channels)
+            }
+        }
+    }
+}
+
+/// A problem of a score and where it is.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub location: Location,
+    pub issue: Issue,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        write!(f, "{}: {}: {}", severity, self.location, self.issue)
+    }
+}